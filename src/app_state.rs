@@ -6,13 +6,19 @@ use crate::{
     db::Database,
     errors::AppResult,
     repositories::{
-        MongoAgentJobRepository, MongoQuizAttemptRepository, MongoQuizRepository,
+        MongoAgentJobRepository, MongoJobScheduleRepository, MongoLlmUsageRepository,
+        MongoQuizAttemptRepository, MongoQuizRepository, MongoQuizVersionRepository,
         MongoRefreshTokenRepository, MongoSummaryDocumentRepository, MongoUserRepository,
         QuizAttemptRepository, RefreshTokenRepository, UserRepository,
     },
     services::{
-        agent_orchestrator_service::AgentOrchestrator, model_service::ModelService,
-        quiz_service::QuizService, summary_document_service::SummaryDocumentService,
+        agent_orchestrator_service::AgentOrchestrator,
+        job_service::JobService,
+        llm_usage_service::LlmUsageService,
+        model_service::ModelService,
+        orchestrator_steps::{default_registry, default_schedules},
+        quiz_service::QuizService,
+        summary_document_service::SummaryDocumentService,
         user_service::UserService,
     },
 };
@@ -21,9 +27,11 @@ use crate::{
 pub struct AppState {
     pub user_service: Arc<UserService>,
     pub quiz_service: Arc<QuizService>,
+    pub job_service: Arc<JobService>,
     pub quiz_attempt_repository: Arc<dyn QuizAttemptRepository>,
     pub summary_document_service: Arc<SummaryDocumentService>,
     pub model_service: Arc<ModelService>,
+    pub llm_usage_service: Arc<LlmUsageService>,
     pub jwt_service: Arc<JwtService>,
     pub refresh_token_repository: Arc<dyn RefreshTokenRepository>,
     pub config: Arc<Config>,
@@ -41,25 +49,56 @@ impl AppState {
 
         let agent_job_repository = Arc::new(MongoAgentJobRepository::new(&db));
         agent_job_repository.ensure_indexes().await?;
-        let agent_orchestrator = Arc::new(AgentOrchestrator::new(agent_job_repository));
+        let job_schedule_repository = Arc::new(MongoJobScheduleRepository::new(&db));
+        job_schedule_repository.ensure_indexes().await?;
+        let agent_orchestrator = Arc::new(
+            AgentOrchestrator::new(agent_job_repository)
+                .with_registry(Arc::new(default_registry()))
+                .with_schedules(job_schedule_repository)
+                .with_schedule_poll_interval(config.agent_schedule_poll_seconds)
+                .with_worker_id(config.agent_worker_id.clone())
+                .with_concurrency(config.agent_worker_concurrency)
+                .with_sweep_interval(config.agent_sweep_interval_seconds)
+                .with_lease_duration(config.agent_lease_seconds)
+                .with_shutdown_timeout(config.agent_shutdown_timeout_seconds),
+        );
+        for schedule in default_schedules() {
+            agent_orchestrator.ensure_schedule(schedule).await?;
+        }
+
+        let summary_document_repository = Arc::new(MongoSummaryDocumentRepository::new(&db));
+        summary_document_repository.ensure_indexes().await?;
+        let summary_document_service =
+            Arc::new(SummaryDocumentService::new(summary_document_repository));
 
         let quiz_repository = Arc::new(MongoQuizRepository::new(&db));
         quiz_repository.ensure_indexes().await?;
-        let quiz_service = Arc::new(QuizService::new(
-            quiz_repository,
+        let quiz_version_repository = Arc::new(MongoQuizVersionRepository::new(&db));
+        quiz_version_repository.ensure_indexes().await?;
+        let quiz_service = Arc::new(
+            QuizService::new(quiz_repository, agent_orchestrator.clone())
+                .with_generation_limits(
+                    config.quiz_generation_limits,
+                    config.admin_quiz_generation_limits,
+                )
+                .with_summary_documents(summary_document_service.clone())
+                .with_versions(quiz_version_repository),
+        );
+        let job_service = Arc::new(JobService::new(
             agent_orchestrator.clone(),
+            quiz_service.clone(),
         ));
 
         let quiz_attempt_repository_mongo = Arc::new(MongoQuizAttemptRepository::new(&db));
         quiz_attempt_repository_mongo.ensure_indexes().await?;
         let quiz_attempt_repository: Arc<dyn QuizAttemptRepository> = quiz_attempt_repository_mongo;
 
-        let summary_document_repository = Arc::new(MongoSummaryDocumentRepository::new(&db));
-        summary_document_repository.ensure_indexes().await?;
-        let summary_document_service =
-            Arc::new(SummaryDocumentService::new(summary_document_repository));
-
-        let model_service = Arc::new(ModelService::new(&config));
+        let llm_usage_repository = Arc::new(MongoLlmUsageRepository::new(&db));
+        llm_usage_repository.ensure_indexes().await?;
+        let model_service = Arc::new(
+            ModelService::new(&config).with_usage_repository(llm_usage_repository.clone()),
+        );
+        let llm_usage_service = Arc::new(LlmUsageService::new(llm_usage_repository));
 
         let refresh_token_repository_mongo = Arc::new(MongoRefreshTokenRepository::new(&db));
         refresh_token_repository_mongo.ensure_indexes().await?;
@@ -75,9 +114,11 @@ impl AppState {
         Ok(Self {
             user_service,
             quiz_service,
+            job_service,
             quiz_attempt_repository,
             summary_document_service,
             model_service,
+            llm_usage_service,
             jwt_service,
             refresh_token_repository,
             config: Arc::new(config),
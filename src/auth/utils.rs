@@ -1,7 +1,7 @@
 use async_graphql::Context;
 
 use crate::{
-    auth::Claims,
+    auth::{Claims, JwtService},
     errors::{AppError, AppResult},
     models::domain::user::UserRole,
 };
@@ -31,6 +31,25 @@ pub fn extract_claims_from_context(ctx: &Context<'_>) -> AppResult<Claims> {
         .map_err(|_| AppError::Unauthorized("Authentication required".to_string()))
 }
 
+/// Authenticate a GraphQL WebSocket connection from its `connection_init`
+/// payload. Browsers cannot set headers on WebSocket upgrades, so the token is
+/// sent as `{"Authorization": "Bearer <token>"}` (or `{"token": "<token>"}`).
+pub fn claims_from_connection_init(
+    jwt_service: &JwtService,
+    payload: &serde_json::Value,
+) -> AppResult<Claims> {
+    let token = ["Authorization", "authorization"]
+        .iter()
+        .find_map(|key| payload.get(key).and_then(|v| v.as_str()))
+        .map(|header| header.strip_prefix("Bearer ").unwrap_or(header))
+        .or_else(|| payload.get("token").and_then(|v| v.as_str()))
+        .ok_or_else(|| AppError::Unauthorized("Missing authorization token".to_string()))?;
+
+    jwt_service
+        .validate_token(token)
+        .map_err(|_| AppError::Unauthorized("Invalid or expired token".to_string()))
+}
+
 /// Check if user is the creator of the quiz or has attempted it
 pub fn can_view_quiz_results(
     user_id: &str,
@@ -100,6 +119,33 @@ mod tests {
         assert!(require_owner_or_admin(&claims, "jane").is_err());
     }
 
+    #[test]
+    fn test_claims_from_connection_init_accepts_bearer_token() {
+        let config = crate::config::Config::test_config();
+        let jwt_service = JwtService::new(&config.jwt_secret, 1, 168);
+        let user = crate::models::domain::User::new("John", "Doe", "johndoe", "john@example.com");
+        let token = jwt_service.create_token(&user).unwrap();
+
+        let payload = serde_json::json!({ "Authorization": format!("Bearer {}", token) });
+        let claims = claims_from_connection_init(&jwt_service, &payload).unwrap();
+        assert_eq!(claims.sub, "johndoe");
+
+        let payload = serde_json::json!({ "token": token });
+        assert!(claims_from_connection_init(&jwt_service, &payload).is_ok());
+    }
+
+    #[test]
+    fn test_claims_from_connection_init_rejects_missing_or_invalid_token() {
+        let config = crate::config::Config::test_config();
+        let jwt_service = JwtService::new(&config.jwt_secret, 1, 168);
+
+        let missing = serde_json::json!({});
+        assert!(claims_from_connection_init(&jwt_service, &missing).is_err());
+
+        let invalid = serde_json::json!({ "Authorization": "Bearer invalid.token.here" });
+        assert!(claims_from_connection_init(&jwt_service, &invalid).is_err());
+    }
+
     #[test]
     fn test_can_view_quiz_results_as_creator() {
         let user_id = "550e8400-e29b-41d4-a716-446655440000";
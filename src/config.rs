@@ -1,8 +1,211 @@
 use secrecy::SecretString;
+use std::collections::HashMap;
 use std::env;
+use std::str::FromStr;
 
 use crate::errors::{AppError, AppResult};
 
+const DEFAULT_LLM_MODEL: &str = "mistralai/ministral-3-3b";
+/// Context window, in tokens, assumed for a stage's models unless configured
+pub const DEFAULT_CONTEXT_LENGTH: usize = 8192;
+
+/// Wire protocol used to talk to an LLM backend
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum LlmProviderKind {
+    /// OpenAI chat completions, also served by LM Studio, vLLM and others
+    OpenAi,
+    Ollama,
+    Anthropic,
+}
+
+impl FromStr for LlmProviderKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "openai" | "openai-compatible" => Ok(LlmProviderKind::OpenAi),
+            "ollama" => Ok(LlmProviderKind::Ollama),
+            "anthropic" => Ok(LlmProviderKind::Anthropic),
+            other => Err(format!("Unknown LLM provider '{}'", other)),
+        }
+    }
+}
+
+/// A provider and model pair
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LlmModelConfig {
+    pub provider: LlmProviderKind,
+    pub model: String,
+}
+
+impl FromStr for LlmModelConfig {
+    type Err = String;
+
+    /// Parse `provider:model`. Only the first `:` separates the two, so model
+    /// tags such as `ollama:llama3.2:3b` are kept intact.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (provider, model) = s
+            .trim()
+            .split_once(':')
+            .ok_or_else(|| format!("Expected provider:model, got '{}'", s.trim()))?;
+
+        if model.trim().is_empty() {
+            return Err(format!("Missing model in '{}'", s.trim()));
+        }
+
+        Ok(Self {
+            provider: provider.parse()?,
+            model: model.trim().to_string(),
+        })
+    }
+}
+
+/// Provider and model used for one stage of the quiz pipeline, plus the
+/// models to fall back to, in order, when it is unavailable
+#[derive(Clone, Debug)]
+pub struct LlmStageConfig {
+    pub provider: LlmProviderKind,
+    pub model: String,
+    pub fallbacks: Vec<LlmModelConfig>,
+    /// Smallest context window, in tokens, of the models in the chain
+    pub context_tokens: usize,
+    /// Settings that could not be parsed and were replaced by defaults;
+    /// fatal in production
+    pub errors: Vec<String>,
+}
+
+impl LlmStageConfig {
+    /// The primary model followed by the fallbacks
+    pub fn chain(&self) -> Vec<LlmModelConfig> {
+        let primary = LlmModelConfig {
+            provider: self.provider,
+            model: self.model.clone(),
+        };
+        std::iter::once(primary)
+            .chain(self.fallbacks.iter().cloned())
+            .collect()
+    }
+
+    /// Read `LLM_<STAGE>_PROVIDER`, `LLM_<STAGE>_MODEL`, the comma separated
+    /// `provider:model` list in `LLM_<STAGE>_FALLBACKS` and
+    /// `LLM_<STAGE>_CONTEXT_TOKENS`
+    fn from_env(stage: &str) -> Self {
+        let mut errors = Vec::new();
+
+        let provider_var = format!("LLM_{}_PROVIDER", stage);
+        let provider = match env::var(&provider_var) {
+            Ok(value) => value.parse().unwrap_or_else(|e| {
+                log::warn!("{}: {}; falling back to openai", provider_var, e);
+                errors.push(format!("{}: {}", provider_var, e));
+                LlmProviderKind::OpenAi
+            }),
+            Err(_) => LlmProviderKind::OpenAi,
+        };
+
+        let fallbacks_var = format!("LLM_{}_FALLBACKS", stage);
+        let fallbacks = env::var(&fallbacks_var)
+            .unwrap_or_default()
+            .split(',')
+            .filter(|entry| !entry.trim().is_empty())
+            .filter_map(|entry| match entry.parse() {
+                Ok(fallback) => Some(fallback),
+                Err(e) => {
+                    log::warn!("{}: {}; entry ignored", fallbacks_var, e);
+                    errors.push(format!("{}: {}", fallbacks_var, e));
+                    None
+                }
+            })
+            .collect();
+
+        Self {
+            provider,
+            model: env::var(format!("LLM_{}_MODEL", stage))
+                .unwrap_or_else(|_| DEFAULT_LLM_MODEL.to_string()),
+            fallbacks,
+            context_tokens: env::var(format!("LLM_{}_CONTEXT_TOKENS", stage))
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .filter(|tokens| *tokens > 0)
+                .unwrap_or(DEFAULT_CONTEXT_LENGTH),
+            errors,
+        }
+    }
+}
+
+/// USD price per million tokens for one model
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LlmModelPrice {
+    pub prompt_per_million: f64,
+    pub completion_per_million: f64,
+}
+
+impl LlmModelPrice {
+    pub fn cost(&self, prompt_tokens: u32, completion_tokens: u32) -> f64 {
+        (prompt_tokens as f64 * self.prompt_per_million
+            + completion_tokens as f64 * self.completion_per_million)
+            / 1_000_000.0
+    }
+}
+
+/// Parse a comma separated list, dropping empty entries
+fn parse_list(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+        .collect()
+}
+
+/// Parse `model=prompt:completion` entries, e.g. `gpt-4o-mini=0.15:0.60`
+fn parse_model_prices(value: &str) -> HashMap<String, LlmModelPrice> {
+    value
+        .split(',')
+        .filter(|entry| !entry.trim().is_empty())
+        .filter_map(|entry| {
+            let parsed = entry.trim().rsplit_once('=').and_then(|(model, prices)| {
+                let (prompt, completion) = prices.split_once(':')?;
+                let price = LlmModelPrice {
+                    prompt_per_million: prompt.trim().parse().ok()?,
+                    completion_per_million: completion.trim().parse().ok()?,
+                };
+                Some((model.trim().to_string(), price))
+            });
+            if parsed.is_none() {
+                log::warn!("LLM_MODEL_PRICES: cannot parse '{}'; entry ignored", entry);
+            }
+            parsed
+        })
+        .collect()
+}
+
+/// Caps on AI quiz generation for one user; `None` means unlimited
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct GenerationLimits {
+    /// Drafts created per UTC day
+    pub daily: Option<u32>,
+    /// Drafts whose generation has not yet finished
+    pub concurrent: Option<u32>,
+}
+
+impl GenerationLimits {
+    /// Read `<PREFIX>_DAILY_LIMIT` and `<PREFIX>_CONCURRENT_LIMIT`, where 0
+    /// disables the limit
+    fn from_env(prefix: &str, default_daily: u32, default_concurrent: u32) -> Self {
+        let limit = |name: &str, default: u32| {
+            let value = env::var(format!("{}_{}", prefix, name))
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(default);
+            (value > 0).then_some(value)
+        };
+
+        Self {
+            daily: limit("DAILY_LIMIT", default_daily),
+            concurrent: limit("CONCURRENT_LIMIT", default_concurrent),
+        }
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct Config {
     pub mongo_conn_string: String,
@@ -22,7 +225,40 @@ pub struct Config {
     pub func_enums_embed_path: String,
     pub openai_api_key: SecretString,
     pub openai_base_url: String,
+    pub ollama_base_url: String,
+    pub anthropic_api_key: SecretString,
+    pub anthropic_base_url: String,
+    pub llm_summary: LlmStageConfig,
+    pub llm_questions: LlmStageConfig,
+    pub llm_validation: LlmStageConfig,
+    pub llm_circuit_failure_threshold: u32,
+    pub llm_circuit_cooldown_seconds: u64,
+    /// Keyed by model name; models without a price are recorded at zero cost
+    pub llm_model_prices: HashMap<String, LlmModelPrice>,
+    pub quiz_generation_limits: GenerationLimits,
+    pub admin_quiz_generation_limits: GenerationLimits,
+    pub web_fetch_connect_timeout_seconds: u64,
+    pub web_fetch_read_timeout_seconds: u64,
+    /// Deadline for a whole fetch, redirects and body included
+    pub web_fetch_total_timeout_seconds: u64,
+    pub web_fetch_max_body_bytes: usize,
+    pub web_fetch_max_redirects: u32,
+    /// When non-empty, only these domains and their subdomains can be fetched
+    pub web_fetch_allowed_domains: Vec<String>,
+    pub web_fetch_denied_domains: Vec<String>,
+    pub document_upload_max_bytes: usize,
+    /// Whether generated answers are checked against their summary before a
+    /// quiz is published
+    pub quiz_grounding_check: bool,
+    /// Grounding confidence, 0 to 100, below which questions need review
+    pub quiz_grounding_min_confidence: u8,
     pub cors_origins: Vec<String>,
+    pub agent_worker_concurrency: usize,
+    pub agent_sweep_interval_seconds: u64,
+    pub agent_worker_id: String,
+    pub agent_lease_seconds: u64,
+    pub agent_schedule_poll_seconds: u64,
+    pub agent_shutdown_timeout_seconds: u64,
 }
 
 impl Config {
@@ -75,12 +311,97 @@ impl Config {
             ),
             openai_base_url: env::var("OPENAI_BASE_URL")
                 .unwrap_or_else(|_| "http://localhost:1234".to_string()),
+            ollama_base_url: env::var("OLLAMA_BASE_URL")
+                .unwrap_or_else(|_| "http://localhost:11434".to_string()),
+            anthropic_api_key: SecretString::from(
+                env::var("ANTHROPIC_API_KEY").unwrap_or_default(),
+            ),
+            anthropic_base_url: env::var("ANTHROPIC_BASE_URL")
+                .unwrap_or_else(|_| "https://api.anthropic.com".to_string()),
+            llm_summary: LlmStageConfig::from_env("SUMMARY"),
+            llm_questions: LlmStageConfig::from_env("QUESTIONS"),
+            llm_validation: LlmStageConfig::from_env("VALIDATION"),
+            llm_circuit_failure_threshold: env::var("LLM_CIRCUIT_FAILURE_THRESHOLD")
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(3),
+            llm_circuit_cooldown_seconds: env::var("LLM_CIRCUIT_COOLDOWN_SECONDS")
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(60),
+            llm_model_prices: parse_model_prices(&env::var("LLM_MODEL_PRICES").unwrap_or_default()),
+            quiz_generation_limits: GenerationLimits::from_env("QUIZ_GENERATION", 10, 2),
+            admin_quiz_generation_limits: GenerationLimits::from_env("ADMIN_QUIZ_GENERATION", 0, 0),
+            web_fetch_connect_timeout_seconds: env::var("WEB_FETCH_CONNECT_TIMEOUT_SECONDS")
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(5),
+            web_fetch_read_timeout_seconds: env::var("WEB_FETCH_READ_TIMEOUT_SECONDS")
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(20),
+            web_fetch_total_timeout_seconds: env::var("WEB_FETCH_TOTAL_TIMEOUT_SECONDS")
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(60),
+            web_fetch_max_body_bytes: env::var("WEB_FETCH_MAX_BODY_BYTES")
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(5 * 1024 * 1024),
+            web_fetch_max_redirects: env::var("WEB_FETCH_MAX_REDIRECTS")
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(5),
+            web_fetch_allowed_domains: parse_list(
+                &env::var("WEB_FETCH_ALLOWED_DOMAINS").unwrap_or_default(),
+            ),
+            web_fetch_denied_domains: parse_list(
+                &env::var("WEB_FETCH_DENIED_DOMAINS").unwrap_or_default(),
+            ),
+            document_upload_max_bytes: env::var("DOCUMENT_UPLOAD_MAX_BYTES")
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(10 * 1024 * 1024),
+            quiz_grounding_check: env::var("QUIZ_GROUNDING_CHECK")
+                .map(|s| matches!(s.trim().to_lowercase().as_str(), "1" | "true" | "yes"))
+                .unwrap_or(false),
+            quiz_grounding_min_confidence: env::var("QUIZ_GROUNDING_MIN_CONFIDENCE")
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(70),
             cors_origins: env::var("CORS_ORIGINS")
                 .unwrap_or_else(|_| "http://localhost:5173,http://localhost:3000".to_string())
                 .split(',')
                 .map(|s| s.trim().to_string())
                 .filter(|s| !s.is_empty())
                 .collect(),
+            agent_worker_concurrency: env::var("AGENT_WORKER_CONCURRENCY")
+                .ok()
+                .and_then(|c| c.parse().ok())
+                .unwrap_or(4),
+            agent_sweep_interval_seconds: env::var("AGENT_SWEEP_INTERVAL_SECONDS")
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(30),
+            agent_worker_id: env::var("AGENT_WORKER_ID").unwrap_or_else(|_| {
+                let suffix = uuid::Uuid::new_v4().simple().to_string();
+                match env::var("HOSTNAME") {
+                    Ok(host) => format!("{}-{}", host, &suffix[..8]),
+                    Err(_) => suffix,
+                }
+            }),
+            agent_lease_seconds: env::var("AGENT_LEASE_SECONDS")
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(60),
+            agent_schedule_poll_seconds: env::var("AGENT_SCHEDULE_POLL_SECONDS")
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(30),
+            agent_shutdown_timeout_seconds: env::var("AGENT_SHUTDOWN_TIMEOUT_SECONDS")
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(30),
         }
     }
 
@@ -117,12 +438,47 @@ impl Config {
             ));
         }
 
-        if openai_key == "sk-default-key" {
+        let llm_stages = [&self.llm_summary, &self.llm_questions, &self.llm_validation];
+        let llm_stage_errors: Vec<&str> = llm_stages
+            .iter()
+            .flat_map(|stage| stage.errors.iter().map(String::as_str))
+            .collect();
+        if !llm_stage_errors.is_empty() {
+            return Err(AppError::ValidationError(format!(
+                "FATAL: invalid LLM stage settings: {}",
+                llm_stage_errors.join("; ")
+            )));
+        }
+
+        let llm_models: Vec<LlmModelConfig> =
+            llm_stages.iter().flat_map(|stage| stage.chain()).collect();
+
+        if openai_key == "sk-default-key"
+            && llm_models
+                .iter()
+                .any(|model| model.provider == LlmProviderKind::OpenAi)
+        {
             return Err(AppError::ValidationError(
                 "FATAL: OPENAI_API_KEY is using default value! Set OPENAI_API_KEY environment variable.".to_string(),
             ));
         }
 
+        if self.anthropic_api_key.expose_secret().is_empty()
+            && llm_models
+                .iter()
+                .any(|model| model.provider == LlmProviderKind::Anthropic)
+        {
+            return Err(AppError::ValidationError(
+                "FATAL: ANTHROPIC_API_KEY must be set when an LLM stage uses the anthropic provider.".to_string(),
+            ));
+        }
+
+        if llm_models.iter().any(|model| model.model.trim().is_empty()) {
+            return Err(AppError::ValidationError(
+                "FATAL: LLM_SUMMARY_MODEL, LLM_QUESTIONS_MODEL and LLM_VALIDATION_MODEL cannot be empty.".to_string(),
+            ));
+        }
+
         if self.func_enums_max_response_tokens == 0 {
             return Err(AppError::ValidationError(
                 "FATAL: FUNC_ENUMS_MAX_RESPONSE_TOKENS must be greater than 0.".to_string(),
@@ -163,6 +519,57 @@ impl Config {
             ));
         }
 
+        if self.agent_worker_concurrency == 0 {
+            return Err(AppError::ValidationError(
+                "FATAL: AGENT_WORKER_CONCURRENCY must be greater than 0.".to_string(),
+            ));
+        }
+
+        if self.agent_sweep_interval_seconds == 0 {
+            return Err(AppError::ValidationError(
+                "FATAL: AGENT_SWEEP_INTERVAL_SECONDS must be greater than 0.".to_string(),
+            ));
+        }
+
+        if self.agent_lease_seconds < 3 {
+            return Err(AppError::ValidationError(
+                "FATAL: AGENT_LEASE_SECONDS must be at least 3.".to_string(),
+            ));
+        }
+
+        if self.web_fetch_read_timeout_seconds == 0
+            || self.web_fetch_total_timeout_seconds == 0
+            || self.web_fetch_max_body_bytes == 0
+        {
+            return Err(AppError::ValidationError(
+                "FATAL: WEB_FETCH_READ_TIMEOUT_SECONDS, WEB_FETCH_TOTAL_TIMEOUT_SECONDS and WEB_FETCH_MAX_BODY_BYTES must be greater than 0.".to_string(),
+            ));
+        }
+
+        if self.document_upload_max_bytes == 0 {
+            return Err(AppError::ValidationError(
+                "FATAL: DOCUMENT_UPLOAD_MAX_BYTES must be greater than 0.".to_string(),
+            ));
+        }
+
+        if self.quiz_grounding_min_confidence > 100 {
+            return Err(AppError::ValidationError(
+                "FATAL: QUIZ_GROUNDING_MIN_CONFIDENCE must be between 0 and 100.".to_string(),
+            ));
+        }
+
+        if self.llm_circuit_failure_threshold == 0 {
+            return Err(AppError::ValidationError(
+                "FATAL: LLM_CIRCUIT_FAILURE_THRESHOLD must be greater than 0.".to_string(),
+            ));
+        }
+
+        if self.agent_schedule_poll_seconds == 0 {
+            return Err(AppError::ValidationError(
+                "FATAL: AGENT_SCHEDULE_POLL_SECONDS must be greater than 0.".to_string(),
+            ));
+        }
+
         Ok(())
     }
 
@@ -186,10 +593,58 @@ impl Config {
             func_enums_embed_path: "http://localhost:1234".to_string(),
             openai_api_key: SecretString::from("sk-test-key".to_string()),
             openai_base_url: "http://localhost:1234".to_string(),
+            ollama_base_url: "http://localhost:11434".to_string(),
+            anthropic_api_key: SecretString::from(String::new()),
+            anthropic_base_url: "https://api.anthropic.com".to_string(),
+            llm_summary: LlmStageConfig {
+                provider: LlmProviderKind::OpenAi,
+                model: DEFAULT_LLM_MODEL.to_string(),
+                fallbacks: Vec::new(),
+                context_tokens: DEFAULT_CONTEXT_LENGTH,
+                errors: Vec::new(),
+            },
+            llm_questions: LlmStageConfig {
+                provider: LlmProviderKind::OpenAi,
+                model: DEFAULT_LLM_MODEL.to_string(),
+                fallbacks: Vec::new(),
+                context_tokens: DEFAULT_CONTEXT_LENGTH,
+                errors: Vec::new(),
+            },
+            llm_validation: LlmStageConfig {
+                provider: LlmProviderKind::OpenAi,
+                model: DEFAULT_LLM_MODEL.to_string(),
+                fallbacks: Vec::new(),
+                context_tokens: DEFAULT_CONTEXT_LENGTH,
+                errors: Vec::new(),
+            },
+            llm_circuit_failure_threshold: 3,
+            llm_circuit_cooldown_seconds: 60,
+            llm_model_prices: HashMap::new(),
+            quiz_generation_limits: GenerationLimits {
+                daily: Some(10),
+                concurrent: Some(2),
+            },
+            admin_quiz_generation_limits: GenerationLimits::default(),
+            web_fetch_connect_timeout_seconds: 5,
+            web_fetch_read_timeout_seconds: 20,
+            web_fetch_total_timeout_seconds: 60,
+            web_fetch_max_body_bytes: 5 * 1024 * 1024,
+            web_fetch_max_redirects: 5,
+            web_fetch_allowed_domains: Vec::new(),
+            web_fetch_denied_domains: Vec::new(),
+            document_upload_max_bytes: 10 * 1024 * 1024,
+            quiz_grounding_check: false,
+            quiz_grounding_min_confidence: 70,
             cors_origins: vec![
                 "http://localhost:5173".to_string(),
                 "http://localhost:3000".to_string(),
             ],
+            agent_worker_concurrency: 4,
+            agent_sweep_interval_seconds: 30,
+            agent_worker_id: "test-worker".to_string(),
+            agent_lease_seconds: 60,
+            agent_schedule_poll_seconds: 30,
+            agent_shutdown_timeout_seconds: 30,
         }
     }
 }
@@ -216,4 +671,69 @@ mod tests {
         assert_eq!(config.mongo_db_name, "tento-test");
         assert_eq!(config.users_collection, "users");
     }
+
+    #[test]
+    fn test_llm_provider_kind_from_str() {
+        assert_eq!(
+            "OpenAI".parse::<LlmProviderKind>(),
+            Ok(LlmProviderKind::OpenAi)
+        );
+        assert_eq!(
+            " ollama ".parse::<LlmProviderKind>(),
+            Ok(LlmProviderKind::Ollama)
+        );
+        assert_eq!(
+            "anthropic".parse::<LlmProviderKind>(),
+            Ok(LlmProviderKind::Anthropic)
+        );
+        assert!("bedrock".parse::<LlmProviderKind>().is_err());
+    }
+
+    #[test]
+    fn test_llm_model_config_from_str() {
+        assert_eq!(
+            "ollama:llama3.2:3b".parse::<LlmModelConfig>(),
+            Ok(LlmModelConfig {
+                provider: LlmProviderKind::Ollama,
+                model: "llama3.2:3b".to_string(),
+            })
+        );
+        assert!("ollama".parse::<LlmModelConfig>().is_err());
+        assert!("ollama:".parse::<LlmModelConfig>().is_err());
+        assert!("bedrock:model".parse::<LlmModelConfig>().is_err());
+    }
+
+    #[test]
+    fn test_parse_model_prices() {
+        let prices = parse_model_prices("gpt-4o-mini=0.15:0.60, broken, mistralai/small=1:2");
+
+        assert_eq!(prices.len(), 2);
+        let price = prices["gpt-4o-mini"];
+        assert!((price.cost(1_000_000, 500_000) - 0.45).abs() < 1e-9);
+        assert_eq!(prices["mistralai/small"].prompt_per_million, 1.0);
+    }
+
+    #[test]
+    fn test_invalid_llm_stage_settings_fail_production_validation() {
+        // A stage name no other test reads
+        env::set_var("LLM_CONFIG_TEST_PROVIDER", "bedrock");
+        env::set_var("LLM_CONFIG_TEST_FALLBACKS", "ollama:llama3.2, gpt-4o");
+        let stage = LlmStageConfig::from_env("CONFIG_TEST");
+
+        assert_eq!(stage.provider, LlmProviderKind::OpenAi);
+        assert_eq!(stage.fallbacks.len(), 1);
+        assert_eq!(stage.errors.len(), 2);
+
+        let mut config = Config::test_config();
+        config.openai_api_key = SecretString::from("sk-test".to_string());
+        assert!(config.validate_for_production().is_ok());
+        config.llm_questions = stage;
+        match config.validate_for_production() {
+            Err(AppError::ValidationError(message)) => {
+                assert!(message.contains("LLM_CONFIG_TEST_PROVIDER"));
+                assert!(message.contains("LLM_CONFIG_TEST_FALLBACKS"));
+            }
+            other => panic!("expected a validation error, got {:?}", other),
+        }
+    }
 }
@@ -68,6 +68,42 @@ Output should be optimised for llm processing. Ahere to the following structure:
 2. Completeness: Ensuring all relevant content sections are included.
 3. Structural Integrity: Maintaining the logical and structural coherence of the content in the output.";
 
+pub const CHUNK_SUMMARY_PROMPT: &str = "You are taking notes on one part of a web page that is too long to read at once. A later step will write the full summary and quiz questions from your notes alone, so anything you leave out is lost.
+
+- Record every fact, definition, number, date, name, list item and relationship in the text that a quiz question could test.
+- Keep the original terminology and group notes under the section headings they came from.
+- Only use information in the text. Do not add an introduction, conclusion or commentary.
+- Output concise markdown bullet points.";
+
+pub const MULTI_SOURCE_QUIZ_PROMPT: &str = "The content below is made up of summaries of several sources, each under a heading giving its source id.
+
+- Spread the questions across all of the sources, in proportion to how much quizzable material each one has.
+- Write each question from a single source and set its source_id to the id in that source's heading.
+- Do not combine facts from different sources in one question.";
+
+pub const QUESTION_REPAIR_PROMPT: &str = "You are fixing a generated quiz. Some of its questions failed automated checks and must be replaced using the source content below.
+
+- Write exactly the number of replacement questions asked for, each testing a different fact from the questions being kept.
+- \"single\" questions have 2 to 6 options with exactly one correct; \"multi\" questions have 2 to 6 options with at least one correct and one incorrect; \"bool\" questions have exactly two options with one correct.
+- option_correct is the string \"true\" or \"false\". Options within a question must all be different.
+- Only use information in the source content.";
+
+pub const GROUNDING_CHECK_PROMPT: &str = "You are checking that the answers to a quiz question are supported by its source material. You will be given the source material and a question with the options marked as correct.
+
+For each correct option:
+- Set evidence to the passage of the source material that shows the option is correct, quoted word for word. Use an empty string if no passage supports it.
+- Set confidence to a score from 0 to 100 of how clearly that passage supports the option being correct. Use 0 when there is no supporting passage and low scores when the passage only partly supports it.
+
+Judge only against the source material, not your own knowledge.";
+
+pub const QUESTION_REPLACEMENT_PROMPT: &str = "You are rewriting questions in a finished quiz. The quiz creator has picked questions they want replaced; write a new question for each one using the source content below.
+
+- Write exactly the number of questions asked for. Each should test a different fact from the questions being kept and must not repeat them.
+- Follow the creator's instructions when given, for example to make the questions harder or focus on a topic.
+- \"single\" questions have 2 to 6 options with exactly one correct; \"multi\" questions have 2 to 6 options with at least one correct and one incorrect; \"bool\" questions have exactly two options with one correct.
+- option_correct is the string \"true\" or \"false\". Options within a question must all be different.
+- Only use information in the source content.";
+
 pub const STRUCTURED_QUIZ_GENERATOR_PROMPT: &str = r#"You are a structured output quiz generation agent optimized for creating high-quality, accurate quizzes based on provided content and specifications.
 
 ## PRIMARY OBJECTIVE
@@ -123,6 +159,7 @@ The top level fields are the main quiz metadata fields, including title, descrip
 ### Quiz Question fields
 - question_title: string, a short title of the question - clear, unambiguous
 - question_description: string additional context or explanation, optional
+- question_type: string, "single" for exactly one correct option, "multi" for several correct options or "bool" for a true/false question with exactly two options
 - question_options: array of Quiz Question Options, detailed below
 
 ### Quiz Question Option fields
@@ -1,5 +1,7 @@
+use chrono::{DateTime, SecondsFormat, Utc};
 use mongodb::{
-    bson::doc,
+    bson::{doc, Document},
+    error::{Error, ErrorKind, WriteFailure},
     options::{ClientOptions, ServerApi, ServerApiVersion},
     Client, Collection,
 };
@@ -62,10 +64,80 @@ impl Database {
     }
 }
 
+/// `at` exactly as serde stores a `DateTime<Utc>`, for raw updates of fields
+/// that the conditions below compare
+pub fn stored_timestamp(at: DateTime<Utc>) -> String {
+    at.to_rfc3339_opts(SecondsFormat::AutoSi, true)
+}
+
+/// Condition matching `DateTime<Utc>` values stored by serde at or after
+/// `since`, to one second's precision.
+///
+/// Those are RFC 3339 strings whose fraction is left out on whole seconds, so
+/// they don't sort chronologically within a second: `…:00.5Z` sorts before
+/// `…:00Z`. The bound is the whole second without its `Z`, which sorts before
+/// every value stored during that second and after every earlier one.
+pub fn stored_at_or_after(since: DateTime<Utc>) -> Document {
+    doc! { "$gte": since.format("%Y-%m-%dT%H:%M:%S").to_string() }
+}
+
+/// Condition matching `DateTime<Utc>` values stored by serde before the whole
+/// second of `until`; see [`stored_at_or_after`]
+pub fn stored_before(until: DateTime<Utc>) -> Document {
+    doc! { "$lt": until.format("%Y-%m-%dT%H:%M:%S").to_string() }
+}
+
+const DUPLICATE_KEY_CODE: i32 = 11000;
+
+/// Whether a write failed because it would break a unique index
+pub fn is_duplicate_key(error: &Error) -> bool {
+    matches!(
+        error.kind.as_ref(),
+        ErrorKind::Write(WriteFailure::WriteError(write_error))
+            if write_error.code == DUPLICATE_KEY_CODE
+    )
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn stored_at_or_after_bound_sorts_before_the_whole_second() {
+        let day_start = "2026-01-02T00:00:00Z".parse::<DateTime<Utc>>().unwrap();
+        let stored = |offset_millis: i64| {
+            serde_json::to_value(day_start + chrono::Duration::milliseconds(offset_millis))
+                .unwrap()
+                .as_str()
+                .unwrap()
+                .to_string()
+        };
+        let bound = stored_at_or_after(day_start);
+        let bound = bound.get_str("$gte").unwrap();
+
+        for offset in [0, 1, 500, 999, 1_000, 86_399_999] {
+            assert!(
+                stored(offset).as_str() >= bound,
+                "{} should match",
+                stored(offset)
+            );
+        }
+        for offset in [-1, -500, -1_000] {
+            assert!(
+                stored(offset).as_str() < bound,
+                "{} should not match",
+                stored(offset)
+            );
+        }
+        // stored_before matches exactly what stored_at_or_after doesn't
+        assert_eq!(stored_before(day_start).get_str("$lt"), Ok(bound));
+
+        for offset in [0, 1, 500, 86_399_999] {
+            let at = day_start + chrono::Duration::milliseconds(offset);
+            assert_eq!(stored_timestamp(at), stored(offset));
+        }
+    }
+
     #[test]
     fn test_database_structure() {
         fn assert_send_sync<T: Send + Sync>() {}
@@ -1,5 +1,6 @@
 use actix_web::{http::StatusCode, HttpResponse, ResponseError};
 use async_graphql::ErrorExtensions;
+use chrono::{DateTime, SecondsFormat, Utc};
 use serde::Serialize;
 use thiserror::Error;
 
@@ -31,6 +32,19 @@ pub enum AppError {
 
     #[error("LLM error: {0}")]
     LlmError(String),
+
+    #[error("Payload too large: {0}")]
+    PayloadTooLarge(String),
+
+    #[error("Unsupported media type: {0}")]
+    UnsupportedMediaType(String),
+
+    /// `resets_at` is when the quota next frees up, if that is known
+    #[error("Quota exceeded: {message}")]
+    QuotaExceeded {
+        message: String,
+        resets_at: Option<DateTime<Utc>>,
+    },
 }
 
 impl AppError {
@@ -45,6 +59,16 @@ impl AppError {
             AppError::BadRequest(_) => "BAD_REQUEST",
             AppError::InternalError(_) => "INTERNAL_ERROR",
             AppError::LlmError(_) => "LLM_ERROR",
+            AppError::PayloadTooLarge(_) => "PAYLOAD_TOO_LARGE",
+            AppError::UnsupportedMediaType(_) => "UNSUPPORTED_MEDIA_TYPE",
+            AppError::QuotaExceeded { .. } => "QUOTA_EXCEEDED",
+        }
+    }
+
+    fn resets_at(&self) -> Option<DateTime<Utc>> {
+        match self {
+            AppError::QuotaExceeded { resets_at, .. } => *resets_at,
+            _ => None,
         }
     }
 }
@@ -53,6 +77,8 @@ impl AppError {
 pub struct ErrorResponse {
     pub error: String,
     pub code: u16,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub resets_at: Option<DateTime<Utc>>,
 }
 
 impl ResponseError for AppError {
@@ -67,13 +93,23 @@ impl ResponseError for AppError {
             AppError::BadRequest(_) => StatusCode::BAD_REQUEST,
             AppError::InternalError(_) => StatusCode::INTERNAL_SERVER_ERROR,
             AppError::LlmError(_) => StatusCode::BAD_GATEWAY,
+            AppError::PayloadTooLarge(_) => StatusCode::PAYLOAD_TOO_LARGE,
+            AppError::UnsupportedMediaType(_) => StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            AppError::QuotaExceeded { .. } => StatusCode::TOO_MANY_REQUESTS,
         }
     }
 
     fn error_response(&self) -> HttpResponse {
-        HttpResponse::build(self.status_code()).json(ErrorResponse {
+        let mut response = HttpResponse::build(self.status_code());
+        if let Some(resets_at) = self.resets_at() {
+            let retry_after = (resets_at - Utc::now()).num_seconds().max(0);
+            response.insert_header(("Retry-After", retry_after.to_string()));
+        }
+
+        response.json(ErrorResponse {
             error: self.to_string(),
             code: self.status_code().as_u16(),
+            resets_at: self.resets_at(),
         })
     }
 }
@@ -111,6 +147,12 @@ impl ErrorExtensions for AppError {
     fn extend(&self) -> async_graphql::Error {
         async_graphql::Error::new(self.to_string()).extend_with(|_err, e| {
             e.set("code", self.error_code());
+            if let Some(resets_at) = self.resets_at() {
+                e.set(
+                    "resets_at",
+                    resets_at.to_rfc3339_opts(SecondsFormat::Secs, true),
+                );
+            }
         })
     }
 }
@@ -135,6 +177,22 @@ mod tests {
             AppError::ValidationError("test".into()).status_code(),
             StatusCode::BAD_REQUEST
         );
+        assert_eq!(
+            AppError::QuotaExceeded {
+                message: "test".into(),
+                resets_at: None,
+            }
+            .status_code(),
+            StatusCode::TOO_MANY_REQUESTS
+        );
+        assert_eq!(
+            AppError::PayloadTooLarge("test".into()).status_code(),
+            StatusCode::PAYLOAD_TOO_LARGE
+        );
+        assert_eq!(
+            AppError::UnsupportedMediaType("test".into()).status_code(),
+            StatusCode::UNSUPPORTED_MEDIA_TYPE
+        );
     }
 
     #[test]
@@ -142,4 +200,26 @@ mod tests {
         let err = AppError::NotFound("user".into());
         assert_eq!(err.to_string(), "Not found: user");
     }
+
+    #[test]
+    fn test_quota_exceeded_response_carries_reset_time() {
+        let resets_at = Utc::now() + chrono::Duration::hours(1);
+        let err = AppError::QuotaExceeded {
+            message: "Daily limit reached".into(),
+            resets_at: Some(resets_at),
+        };
+
+        let response = err.error_response();
+
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        let retry_after: i64 = response
+            .headers()
+            .get("Retry-After")
+            .expect("expected Retry-After header")
+            .to_str()
+            .unwrap()
+            .parse()
+            .unwrap();
+        assert!((3590..=3600).contains(&retry_after));
+    }
 }
@@ -1,4 +1,6 @@
-use async_graphql::{Context, Object};
+use std::collections::HashMap;
+
+use async_graphql::{Context, Json, Object, ID};
 
 use crate::{
     app_state::AppState,
@@ -9,14 +11,16 @@ use crate::{
         domain::Quiz,
         dto::{
             request::{
-                CreateUserRequestDto, SubmitQuizAttemptInput, UpdateQuizInput, UpdateUserRequestDto,
+                AddQuizQuestionInput, CreateJobScheduleRequest, CreateUserRequestDto,
+                SubmitQuizAttemptInput, UpdateQuizInput, UpdateUserRequestDto,
             },
             response::{
-                CreateUserResponse, DeleteUserResponse, QuizAttemptResponse, UpdateUserResponse,
+                CreateUserResponse, DeleteResponse, DeleteUserResponse, JobProgressResponse,
+                JobScheduleResponse, QuizAttemptResponse, UpdateUserResponse,
             },
         },
     },
-    services::quiz_attempt_service::QuizAttemptService,
+    services::{job_schedule::JobSchedule, quiz_attempt_service::QuizAttemptService},
 };
 
 pub struct MutationRoot;
@@ -73,8 +77,14 @@ impl MutationRoot {
         let user_id = claims.sub.clone();
         let quiz_id = parse_id(&input.quiz_id)?;
 
-        let quiz_dto = state.quiz_service.get_quiz(&quiz_id).await?;
-        let quiz: Quiz = quiz_dto.try_into()?;
+        // Grade against the published version so later edits to the quiz
+        // don't change how this attempt is reviewed
+        let version = state.quiz_service.current_version(&quiz_id).await?;
+        let quiz_version = version.as_ref().map(|version| version.version);
+        let quiz: Quiz = match version {
+            Some(version) => version.quiz,
+            None => state.quiz_service.get_quiz(&quiz_id).await?.try_into()?,
+        };
 
         let attempt_count = state
             .quiz_attempt_repository
@@ -93,7 +103,7 @@ impl MutationRoot {
 
         let _passed = points_earned >= quiz.required_score;
 
-        let attempt = QuizAttemptService::create_attempt(
+        let mut attempt = QuizAttemptService::create_attempt(
             &user_id,
             &quiz_id,
             points_earned,
@@ -102,6 +112,7 @@ impl MutationRoot {
             quiz.required_score,
             question_answers,
         );
+        attempt.quiz_version = quiz_version;
 
         let attempt = state.quiz_attempt_repository.create(attempt).await?;
 
@@ -120,4 +131,226 @@ impl MutationRoot {
 
         updated_quiz.try_into()
     }
+
+    async fn add_quiz_question(
+        &self,
+        ctx: &Context<'_>,
+        quiz_id: ID,
+        input: AddQuizQuestionInput,
+    ) -> AppResult<Quiz> {
+        let state = ctx.data::<AppState>()?;
+        let claims = extract_claims_from_context(ctx)?;
+
+        let existing_quiz = state.quiz_service.get_quiz(&quiz_id).await?;
+
+        require_owner_or_admin(&claims, &existing_quiz.created_by_user_id)?;
+
+        let updated_quiz = state.quiz_service.add_question(&quiz_id, input).await?;
+
+        updated_quiz.try_into()
+    }
+
+    async fn remove_quiz_question(
+        &self,
+        ctx: &Context<'_>,
+        quiz_id: ID,
+        question_id: ID,
+    ) -> AppResult<Quiz> {
+        let state = ctx.data::<AppState>()?;
+        let claims = extract_claims_from_context(ctx)?;
+
+        let existing_quiz = state.quiz_service.get_quiz(&quiz_id).await?;
+
+        require_owner_or_admin(&claims, &existing_quiz.created_by_user_id)?;
+
+        let updated_quiz = state
+            .quiz_service
+            .remove_question(&quiz_id, &question_id)
+            .await?;
+
+        updated_quiz.try_into()
+    }
+
+    /// Reorder the questions of a quiz; `question_ids` lists all of them
+    async fn reorder_quiz_questions(
+        &self,
+        ctx: &Context<'_>,
+        quiz_id: ID,
+        question_ids: Vec<ID>,
+    ) -> AppResult<Quiz> {
+        let state = ctx.data::<AppState>()?;
+        let claims = extract_claims_from_context(ctx)?;
+
+        let existing_quiz = state.quiz_service.get_quiz(&quiz_id).await?;
+
+        require_owner_or_admin(&claims, &existing_quiz.created_by_user_id)?;
+
+        let question_ids = question_ids.into_iter().map(|id| id.0).collect();
+        let updated_quiz = state
+            .quiz_service
+            .reorder_questions(&quiz_id, question_ids)
+            .await?;
+
+        updated_quiz.try_into()
+    }
+
+    /// Publish a quiz held for review after its grounding check
+    async fn approve_quiz(&self, ctx: &Context<'_>, quiz_id: ID) -> AppResult<Quiz> {
+        let state = ctx.data::<AppState>()?;
+        let claims = extract_claims_from_context(ctx)?;
+
+        let existing_quiz = state.quiz_service.get_quiz(&quiz_id).await?;
+
+        require_owner_or_admin(&claims, &existing_quiz.created_by_user_id)?;
+
+        let approved_quiz = state.quiz_service.approve_quiz(&quiz_id).await?;
+
+        approved_quiz.try_into()
+    }
+
+    /// Restore an earlier version of a quiz, publishing it as the newest one
+    async fn rollback_quiz(&self, ctx: &Context<'_>, quiz_id: ID, version: i32) -> AppResult<Quiz> {
+        let state = ctx.data::<AppState>()?;
+        let claims = extract_claims_from_context(ctx)?;
+
+        let existing_quiz = state.quiz_service.get_quiz(&quiz_id).await?;
+
+        require_owner_or_admin(&claims, &existing_quiz.created_by_user_id)?;
+
+        let restored_quiz = state
+            .quiz_service
+            .rollback_to_version(&quiz_id, version)
+            .await?;
+
+        restored_quiz.try_into()
+    }
+
+    /// Replace some questions of a quiz with newly generated ones, optionally
+    /// following the creator's instructions
+    async fn regenerate_questions(
+        &self,
+        ctx: &Context<'_>,
+        quiz_id: ID,
+        question_ids: Vec<ID>,
+        instructions: Option<String>,
+    ) -> AppResult<JobProgressResponse> {
+        let state = ctx.data::<AppState>()?;
+        let claims = extract_claims_from_context(ctx)?;
+
+        let existing_quiz = state.quiz_service.get_quiz(&quiz_id).await?;
+
+        require_owner_or_admin(&claims, &existing_quiz.created_by_user_id)?;
+
+        let question_ids = question_ids.into_iter().map(|id| id.0).collect();
+        let job_id = state
+            .quiz_service
+            .start_question_regeneration_job(&quiz_id, question_ids, instructions, &claims)
+            .await?;
+
+        state.job_service.get_job_progress(&job_id, &claims).await
+    }
+
+    /// Turn on or off a nightly check that regenerates the quiz when its web
+    /// sources change. Returns whether the check is now on.
+    async fn set_quiz_source_refresh(
+        &self,
+        ctx: &Context<'_>,
+        quiz_id: ID,
+        enabled: bool,
+    ) -> AppResult<bool> {
+        let state = ctx.data::<AppState>()?;
+        let claims = extract_claims_from_context(ctx)?;
+
+        let existing_quiz = state.quiz_service.get_quiz(&quiz_id).await?;
+
+        require_owner_or_admin(&claims, &existing_quiz.created_by_user_id)?;
+
+        state
+            .quiz_service
+            .set_source_refresh(&quiz_id, enabled)
+            .await?;
+
+        Ok(enabled)
+    }
+
+    async fn cancel_job(&self, ctx: &Context<'_>, job_id: ID) -> AppResult<JobProgressResponse> {
+        let state = ctx.data::<AppState>()?;
+        let claims = extract_claims_from_context(ctx)?;
+
+        state.job_service.cancel_job(&job_id, &claims).await
+    }
+
+    async fn pause_job(&self, ctx: &Context<'_>, job_id: ID) -> AppResult<JobProgressResponse> {
+        let state = ctx.data::<AppState>()?;
+        let claims = extract_claims_from_context(ctx)?;
+
+        state.job_service.pause_job(&job_id, &claims).await
+    }
+
+    async fn resume_job(&self, ctx: &Context<'_>, job_id: ID) -> AppResult<JobProgressResponse> {
+        let state = ctx.data::<AppState>()?;
+        let claims = extract_claims_from_context(ctx)?;
+
+        state.job_service.resume_job(&job_id, &claims).await
+    }
+
+    async fn retry_job(&self, ctx: &Context<'_>, job_id: ID) -> AppResult<JobProgressResponse> {
+        let state = ctx.data::<AppState>()?;
+        let claims = extract_claims_from_context(ctx)?;
+
+        state.job_service.retry_job(&job_id, &claims).await
+    }
+
+    async fn replay_dead_letter_job(
+        &self,
+        ctx: &Context<'_>,
+        job_id: ID,
+        results: Option<Json<HashMap<String, serde_json::Value>>>,
+    ) -> AppResult<JobProgressResponse> {
+        let state = ctx.data::<AppState>()?;
+        let claims = extract_claims_from_context(ctx)?;
+
+        state
+            .job_service
+            .replay_dead_letter(&job_id, results.map(|r| r.0), &claims)
+            .await
+    }
+
+    async fn create_job_schedule(
+        &self,
+        ctx: &Context<'_>,
+        input: CreateJobScheduleRequest,
+    ) -> AppResult<JobScheduleResponse> {
+        let state = ctx.data::<AppState>()?;
+        let claims = extract_claims_from_context(ctx)?;
+
+        let schedule = JobSchedule::try_from(input)?;
+        state.job_service.create_schedule(schedule, &claims).await
+    }
+
+    async fn set_job_schedule_enabled(
+        &self,
+        ctx: &Context<'_>,
+        name: String,
+        enabled: bool,
+    ) -> AppResult<JobScheduleResponse> {
+        let state = ctx.data::<AppState>()?;
+        let claims = extract_claims_from_context(ctx)?;
+
+        state
+            .job_service
+            .set_schedule_enabled(&name, enabled, &claims)
+            .await
+    }
+
+    async fn delete_job_schedule(
+        &self,
+        ctx: &Context<'_>,
+        name: String,
+    ) -> AppResult<DeleteResponse> {
+        let state = ctx.data::<AppState>()?;
+        let claims = extract_claims_from_context(ctx)?;
+
+        state.job_service.delete_schedule(&name, &claims).await
+    }
 }
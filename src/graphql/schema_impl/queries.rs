@@ -9,12 +9,14 @@ use crate::{
     errors::{AppError, AppResult},
     graphql::helpers::{parse_id, validate_quiz_available_for_taking},
     models::{
-        domain::Quiz,
+        domain::{Quiz, QuizVersion, QuizVersionDiff},
         dto::response::{
-            PaginatedResponseQuizAttempt, PaginatedResponseUserDto, PaginationMetadata,
-            QuizAttemptResponse, QuizAttemptReview, QuizForTaking, UserDto,
+            JobProgressResponse, JobScheduleResponse, PaginatedResponseQuizAttempt,
+            PaginatedResponseUserDto, PaginationMetadata, QuizAttemptResponse, QuizAttemptReview,
+            QuizForTaking, QuizLlmUsageReport, UserDailyLlmUsageResponse, UserDto,
         },
     },
+    services::agent_orchestrator_service::DeadLetterJob,
 };
 
 pub struct QueryRoot;
@@ -27,7 +29,14 @@ impl QueryRoot {
 
         require_owner_or_admin(&claims, &username)?;
 
-        state.user_service.get_user(&username).await
+        let mut user = state.user_service.get_user(&username).await?;
+        user.generation_quota = Some(
+            state
+                .quiz_service
+                .generation_quota(&user.id, &user.role)
+                .await?,
+        );
+        Ok(user)
     }
 
     async fn users(
@@ -99,6 +108,58 @@ impl QueryRoot {
         Ok(quiz)
     }
 
+    /// Published versions of a quiz, newest first
+    async fn quiz_versions(&self, ctx: &Context<'_>, quiz_id: ID) -> AppResult<Vec<QuizVersion>> {
+        let state = ctx.data::<AppState>()?;
+        let claims = extract_claims_from_context(ctx)?;
+
+        let quiz_id = parse_id(&quiz_id)?;
+        let quiz_dto = state.quiz_service.get_quiz(&quiz_id).await?;
+
+        require_owner_or_admin(&claims, &quiz_dto.created_by_user_id)?;
+
+        state.quiz_service.list_versions(&quiz_id).await
+    }
+
+    async fn quiz_version(
+        &self,
+        ctx: &Context<'_>,
+        quiz_id: ID,
+        version: i32,
+    ) -> AppResult<QuizVersion> {
+        let state = ctx.data::<AppState>()?;
+        let claims = extract_claims_from_context(ctx)?;
+
+        let quiz_id = parse_id(&quiz_id)?;
+        let quiz_dto = state.quiz_service.get_quiz(&quiz_id).await?;
+
+        require_owner_or_admin(&claims, &quiz_dto.created_by_user_id)?;
+
+        state.quiz_service.get_version(&quiz_id, version).await
+    }
+
+    /// What changed in a quiz between two of its versions
+    async fn quiz_version_diff(
+        &self,
+        ctx: &Context<'_>,
+        quiz_id: ID,
+        from_version: i32,
+        to_version: i32,
+    ) -> AppResult<QuizVersionDiff> {
+        let state = ctx.data::<AppState>()?;
+        let claims = extract_claims_from_context(ctx)?;
+
+        let quiz_id = parse_id(&quiz_id)?;
+        let quiz_dto = state.quiz_service.get_quiz(&quiz_id).await?;
+
+        require_owner_or_admin(&claims, &quiz_dto.created_by_user_id)?;
+
+        state
+            .quiz_service
+            .diff_versions(&quiz_id, from_version, to_version)
+            .await
+    }
+
     async fn quizzes(
         &self,
         ctx: &Context<'_>,
@@ -151,6 +212,73 @@ impl QueryRoot {
         Ok(quizzes)
     }
 
+    async fn job(&self, ctx: &Context<'_>, job_id: ID) -> AppResult<JobProgressResponse> {
+        let state = ctx.data::<AppState>()?;
+        let claims = extract_claims_from_context(ctx)?;
+
+        state.job_service.get_job_progress(&job_id, &claims).await
+    }
+
+    async fn dead_letter_jobs(
+        &self,
+        ctx: &Context<'_>,
+        include_replayed: Option<bool>,
+        offset: Option<i64>,
+        limit: Option<i64>,
+    ) -> AppResult<Vec<DeadLetterJob>> {
+        let state = ctx.data::<AppState>()?;
+        let claims = extract_claims_from_context(ctx)?;
+
+        let offset = offset.unwrap_or(0).max(0);
+        let limit = limit.unwrap_or(20).clamp(1, 100);
+
+        state
+            .job_service
+            .list_dead_letters(&claims, include_replayed.unwrap_or(false), offset, limit)
+            .await
+    }
+
+    async fn job_schedules(&self, ctx: &Context<'_>) -> AppResult<Vec<JobScheduleResponse>> {
+        let state = ctx.data::<AppState>()?;
+        let claims = extract_claims_from_context(ctx)?;
+
+        state.job_service.list_schedules(&claims).await
+    }
+
+    /// Admin: LLM tokens and cost per user per UTC day over the last `days` (default 30)
+    async fn llm_usage_by_user(
+        &self,
+        ctx: &Context<'_>,
+        days: Option<i64>,
+        user_id: Option<ID>,
+    ) -> AppResult<Vec<UserDailyLlmUsageResponse>> {
+        let state = ctx.data::<AppState>()?;
+        let claims = extract_claims_from_context(ctx)?;
+        let user_id = user_id.map(|id| parse_id(&id)).transpose()?;
+
+        state
+            .llm_usage_service
+            .usage_by_user_per_day(&claims, days, user_id.as_deref())
+            .await
+    }
+
+    /// Admin: LLM tokens and cost per quiz, with averages, over the last `days` (default 30)
+    async fn llm_usage_by_quiz(
+        &self,
+        ctx: &Context<'_>,
+        days: Option<i64>,
+        quiz_id: Option<ID>,
+    ) -> AppResult<QuizLlmUsageReport> {
+        let state = ctx.data::<AppState>()?;
+        let claims = extract_claims_from_context(ctx)?;
+        let quiz_id = quiz_id.map(|id| parse_id(&id)).transpose()?;
+
+        state
+            .llm_usage_service
+            .usage_by_quiz(&claims, days, quiz_id.as_deref())
+            .await
+    }
+
     async fn quiz_attempts(
         &self,
         ctx: &Context<'_>,
@@ -207,9 +335,21 @@ impl QueryRoot {
 
         can_view_quiz_attempt(&user_id, &attempt.user_id)?;
 
-        let quiz_dto = state.quiz_service.get_quiz(&attempt.quiz_id).await?;
-
-        let quiz: Quiz = quiz_dto.try_into()?;
+        // Review the quiz as it was when the attempt was made
+        let quiz: Quiz = match attempt.quiz_version {
+            Some(version) => {
+                state
+                    .quiz_service
+                    .get_version(&attempt.quiz_id, version)
+                    .await?
+                    .quiz
+            }
+            None => state
+                .quiz_service
+                .get_quiz(&attempt.quiz_id)
+                .await?
+                .try_into()?,
+        };
 
         let question_results = attempt
             .question_answers
@@ -0,0 +1,266 @@
+use std::{future::Future, time::Duration};
+
+use async_graphql::{Context, ErrorExtensions, Result, Subscription, ID};
+use futures::{stream, Stream};
+use tokio::{
+    sync::broadcast::{self, error::RecvError},
+    time::{self, Interval, MissedTickBehavior},
+};
+
+use crate::{
+    app_state::AppState,
+    auth::extract_claims_from_context,
+    services::{
+        agent_orchestrator_service::{AgentJob, JobStatus},
+        job_events::{JobProgressEvent, JobProgressEventKind},
+    },
+};
+
+/// How often a progress subscription reads the stored job. Events are only
+/// published on the instance running the step, so this is how subscribers on
+/// other instances see progress and learn that the job has finished.
+const JOB_PROGRESS_POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+pub struct SubscriptionRoot;
+
+#[Subscription]
+impl SubscriptionRoot {
+    /// Live progress for a quiz generation job. The first event is a snapshot
+    /// of the job; the stream ends once the quiz is ready or the job stops,
+    /// whichever instance runs it.
+    async fn quiz_generation_progress(
+        &self,
+        ctx: &Context<'_>,
+        job_id: ID,
+    ) -> Result<impl Stream<Item = JobProgressEvent>> {
+        let state = ctx.data::<AppState>()?;
+        let claims = extract_claims_from_context(ctx).map_err(|e| e.extend())?;
+
+        // Subscribe before reading the job so no event falls between the two
+        let receiver = state.agent_orchestrator.events().subscribe();
+        let job = state
+            .job_service
+            .get_job_for_user(&job_id, &claims)
+            .await
+            .map_err(|e| e.extend())?;
+
+        let snapshot = JobProgressEvent::new(&job, JobProgressEventKind::Snapshot);
+        let finished = snapshot.is_terminal();
+
+        let orchestrator = state.agent_orchestrator.clone();
+        let poll_job_id = job.job_id.clone();
+        let poll = move || {
+            let orchestrator = orchestrator.clone();
+            let job_id = poll_job_id.clone();
+            async move {
+                orchestrator
+                    .get_job(&job_id)
+                    .await
+                    .map_err(|e| log::warn!("Failed to poll job {}: {}", job_id, e))
+                    .ok()
+                    .flatten()
+            }
+        };
+
+        Ok(progress_stream(
+            receiver,
+            poll,
+            JOB_PROGRESS_POLL_INTERVAL,
+            job.job_id,
+            snapshot,
+            finished,
+        ))
+    }
+}
+
+struct ProgressStreamState<P> {
+    receiver: broadcast::Receiver<JobProgressEvent>,
+    poll: P,
+    interval: Interval,
+    job_id: String,
+    /// Status and step index of the last event sent
+    seen: (JobStatus, usize),
+    pending: Option<JobProgressEvent>,
+    finished: bool,
+}
+
+impl<P> ProgressStreamState<P> {
+    fn send(mut self, event: JobProgressEvent) -> Option<(JobProgressEvent, Self)> {
+        self.seen = (event.status, event.current_step_index);
+        self.finished = event.is_terminal();
+        Some((event, self))
+    }
+}
+
+/// Events for `job_id` from this instance's bus, plus a `StateChanged` event
+/// whenever `poll` finds the stored job has moved on from the last event sent
+fn progress_stream<P, F>(
+    receiver: broadcast::Receiver<JobProgressEvent>,
+    poll: P,
+    poll_interval: Duration,
+    job_id: String,
+    snapshot: JobProgressEvent,
+    finished: bool,
+) -> impl Stream<Item = JobProgressEvent>
+where
+    P: FnMut() -> F,
+    F: Future<Output = Option<AgentJob>>,
+{
+    let mut interval = time::interval_at(time::Instant::now() + poll_interval, poll_interval);
+    interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+    let state = ProgressStreamState {
+        receiver,
+        poll,
+        interval,
+        job_id,
+        seen: (snapshot.status, snapshot.current_step_index),
+        pending: Some(snapshot),
+        finished,
+    };
+
+    stream::unfold(state, |mut state| async move {
+        if let Some(event) = state.pending.take() {
+            return Some((event, state));
+        }
+        if state.finished {
+            return None;
+        }
+
+        loop {
+            tokio::select! {
+                received = state.receiver.recv() => match received {
+                    Ok(event) if event.job_id == state.job_id => return state.send(event),
+                    Ok(_) => continue,
+                    Err(RecvError::Lagged(skipped)) => {
+                        log::warn!(
+                            "Progress subscriber for job {} lagged and missed {} events",
+                            state.job_id,
+                            skipped
+                        );
+                    }
+                    Err(RecvError::Closed) => return None,
+                },
+                _ = state.interval.tick() => {
+                    let Some(job) = (state.poll)().await else {
+                        continue;
+                    };
+                    if (job.status, job.current_step_index) != state.seen {
+                        let event = JobProgressEvent::new(&job, JobProgressEventKind::StateChanged);
+                        return state.send(event);
+                    }
+                }
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::StreamExt;
+
+    use super::*;
+    use crate::services::{
+        agent_orchestrator_service::{AgentJob, JobStep},
+        job_events::JobEventBus,
+    };
+
+    /// A poll that never finds the job, leaving only the bus
+    fn no_poll() -> impl FnMut() -> std::future::Ready<Option<AgentJob>> {
+        || std::future::ready(None)
+    }
+
+    #[tokio::test]
+    async fn progress_stream_filters_by_job_and_ends_on_quiz_ready() {
+        let bus = JobEventBus::new();
+        let job = AgentJob::new(vec![JobStep::new("finalize_quiz")]);
+        let other = AgentJob::new(vec![JobStep::new("finalize_quiz")]);
+
+        let snapshot = JobProgressEvent::new(&job, JobProgressEventKind::Snapshot);
+        let events = progress_stream(
+            bus.subscribe(),
+            no_poll(),
+            Duration::from_secs(60),
+            job.job_id.clone(),
+            snapshot,
+            false,
+        );
+
+        bus.publish(JobProgressEvent::new(
+            &other,
+            JobProgressEventKind::StepCompleted,
+        ));
+        bus.publish(JobProgressEvent::new(
+            &job,
+            JobProgressEventKind::StepCompleted,
+        ));
+        bus.publish(JobProgressEvent::new(&job, JobProgressEventKind::QuizReady));
+        bus.publish(JobProgressEvent::new(
+            &job,
+            JobProgressEventKind::StepCompleted,
+        ));
+
+        let kinds: Vec<_> = events.map(|event| event.kind).collect().await;
+
+        assert_eq!(
+            kinds,
+            vec![
+                JobProgressEventKind::Snapshot,
+                JobProgressEventKind::StepCompleted,
+                JobProgressEventKind::QuizReady,
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn progress_stream_for_finished_job_only_yields_snapshot() {
+        let bus = JobEventBus::new();
+        let job = AgentJob::new(vec![JobStep::new("finalize_quiz")]);
+
+        let snapshot = JobProgressEvent::new(&job, JobProgressEventKind::Snapshot);
+        let events = progress_stream(
+            bus.subscribe(),
+            no_poll(),
+            Duration::from_secs(60),
+            job.job_id.clone(),
+            snapshot,
+            true,
+        );
+
+        let collected: Vec<_> = events.collect().await;
+        assert_eq!(collected.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn progress_stream_ends_when_polled_job_finished_elsewhere() {
+        let bus = JobEventBus::new();
+        let mut job = AgentJob::new(vec![JobStep::new("finalize_quiz")]);
+        job.status = JobStatus::Running;
+        let snapshot = JobProgressEvent::new(&job, JobProgressEventKind::Snapshot);
+
+        // Another instance completed the job without publishing here
+        let mut stored = job.clone();
+        stored.status = JobStatus::Completed;
+        stored.current_step_index = 1;
+        let events = progress_stream(
+            bus.subscribe(),
+            move || std::future::ready(Some(stored.clone())),
+            Duration::from_millis(10),
+            job.job_id.clone(),
+            snapshot,
+            false,
+        );
+
+        let collected: Vec<_> = time::timeout(Duration::from_secs(5), events.collect::<Vec<_>>())
+            .await
+            .expect("expected the stream to end");
+        let kinds: Vec<_> = collected.iter().map(|event| event.kind).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                JobProgressEventKind::Snapshot,
+                JobProgressEventKind::StateChanged,
+            ]
+        );
+        assert_eq!(collected[1].status, JobStatus::Completed);
+    }
+}
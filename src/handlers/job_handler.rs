@@ -0,0 +1,306 @@
+use std::sync::Arc;
+
+use actix_web::{delete, get, post, web, HttpResponse};
+
+use crate::{
+    app_state::AppState,
+    auth::AuthenticatedUser,
+    errors::AppError,
+    models::dto::{
+        request::{
+            CreateJobScheduleRequest, DeadLetterListQuery, ReplayDeadLetterRequest,
+            SetJobScheduleEnabledRequest,
+        },
+        response::JobStepRunsResponse,
+    },
+    services::job_schedule::JobSchedule,
+};
+
+#[get("/api/jobs/{job_id}")]
+async fn get_job(
+    state: web::Data<Arc<AppState>>,
+    job_id: web::Path<String>,
+    auth: AuthenticatedUser,
+) -> Result<HttpResponse, AppError> {
+    let progress = state
+        .job_service
+        .get_job_progress(&job_id.into_inner(), &auth.0)
+        .await?;
+    Ok(HttpResponse::Ok().json(progress))
+}
+
+#[post("/api/jobs/{job_id}/cancel")]
+async fn cancel_job(
+    state: web::Data<Arc<AppState>>,
+    job_id: web::Path<String>,
+    auth: AuthenticatedUser,
+) -> Result<HttpResponse, AppError> {
+    let progress = state
+        .job_service
+        .cancel_job(&job_id.into_inner(), &auth.0)
+        .await?;
+    Ok(HttpResponse::Ok().json(progress))
+}
+
+#[post("/api/jobs/{job_id}/pause")]
+async fn pause_job(
+    state: web::Data<Arc<AppState>>,
+    job_id: web::Path<String>,
+    auth: AuthenticatedUser,
+) -> Result<HttpResponse, AppError> {
+    let progress = state
+        .job_service
+        .pause_job(&job_id.into_inner(), &auth.0)
+        .await?;
+    Ok(HttpResponse::Ok().json(progress))
+}
+
+#[post("/api/jobs/{job_id}/resume")]
+async fn resume_job(
+    state: web::Data<Arc<AppState>>,
+    job_id: web::Path<String>,
+    auth: AuthenticatedUser,
+) -> Result<HttpResponse, AppError> {
+    let progress = state
+        .job_service
+        .resume_job(&job_id.into_inner(), &auth.0)
+        .await?;
+    Ok(HttpResponse::Ok().json(progress))
+}
+
+#[post("/api/jobs/{job_id}/retry")]
+async fn retry_job(
+    state: web::Data<Arc<AppState>>,
+    job_id: web::Path<String>,
+    auth: AuthenticatedUser,
+) -> Result<HttpResponse, AppError> {
+    let progress = state
+        .job_service
+        .retry_job(&job_id.into_inner(), &auth.0)
+        .await?;
+    Ok(HttpResponse::Ok().json(progress))
+}
+
+#[get("/api/jobs/{job_id}/step-runs")]
+async fn get_job_step_runs(
+    state: web::Data<Arc<AppState>>,
+    job_id: web::Path<String>,
+    auth: AuthenticatedUser,
+) -> Result<HttpResponse, AppError> {
+    let job = state
+        .job_service
+        .get_job_for_user(&job_id.into_inner(), &auth.0)
+        .await?;
+
+    Ok(HttpResponse::Ok().json(JobStepRunsResponse {
+        job_id: job.job_id,
+        step_runs: job.step_runs,
+    }))
+}
+
+#[get("/api/admin/dead-letters")]
+async fn list_dead_letters(
+    state: web::Data<Arc<AppState>>,
+    query: web::Query<DeadLetterListQuery>,
+    auth: AuthenticatedUser,
+) -> Result<HttpResponse, AppError> {
+    let query = query.into_inner();
+    let offset = query.offset.unwrap_or(0).max(0);
+    let limit = query.limit.unwrap_or(20).clamp(1, 100);
+
+    let entries = state
+        .job_service
+        .list_dead_letters(
+            &auth.0,
+            query.include_replayed.unwrap_or(false),
+            offset,
+            limit,
+        )
+        .await?;
+    Ok(HttpResponse::Ok().json(entries))
+}
+
+#[post("/api/admin/dead-letters/{job_id}/replay")]
+async fn replay_dead_letter(
+    state: web::Data<Arc<AppState>>,
+    job_id: web::Path<String>,
+    request: Option<web::Json<ReplayDeadLetterRequest>>,
+    auth: AuthenticatedUser,
+) -> Result<HttpResponse, AppError> {
+    let results = request.and_then(|r| r.into_inner().results);
+    let progress = state
+        .job_service
+        .replay_dead_letter(&job_id.into_inner(), results, &auth.0)
+        .await?;
+    Ok(HttpResponse::Ok().json(progress))
+}
+
+#[get("/api/admin/schedules")]
+async fn list_job_schedules(
+    state: web::Data<Arc<AppState>>,
+    auth: AuthenticatedUser,
+) -> Result<HttpResponse, AppError> {
+    let schedules = state.job_service.list_schedules(&auth.0).await?;
+    Ok(HttpResponse::Ok().json(schedules))
+}
+
+#[post("/api/admin/schedules")]
+async fn create_job_schedule(
+    state: web::Data<Arc<AppState>>,
+    request: web::Json<CreateJobScheduleRequest>,
+    auth: AuthenticatedUser,
+) -> Result<HttpResponse, AppError> {
+    let schedule = JobSchedule::try_from(request.into_inner())?;
+
+    let schedule = state.job_service.create_schedule(schedule, &auth.0).await?;
+    Ok(HttpResponse::Created().json(schedule))
+}
+
+#[post("/api/admin/schedules/{name}/enabled")]
+async fn set_job_schedule_enabled(
+    state: web::Data<Arc<AppState>>,
+    name: web::Path<String>,
+    request: web::Json<SetJobScheduleEnabledRequest>,
+    auth: AuthenticatedUser,
+) -> Result<HttpResponse, AppError> {
+    let schedule = state
+        .job_service
+        .set_schedule_enabled(&name.into_inner(), request.enabled, &auth.0)
+        .await?;
+    Ok(HttpResponse::Ok().json(schedule))
+}
+
+#[delete("/api/admin/schedules/{name}")]
+async fn delete_job_schedule(
+    state: web::Data<Arc<AppState>>,
+    name: web::Path<String>,
+    auth: AuthenticatedUser,
+) -> Result<HttpResponse, AppError> {
+    let response = state
+        .job_service
+        .delete_schedule(&name.into_inner(), &auth.0)
+        .await?;
+    Ok(HttpResponse::Ok().json(response))
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::{test, App};
+
+    use super::*;
+
+    #[actix_web::test]
+    async fn get_job_route_registered_for_get() {
+        let app = test::init_service(App::new().service(get_job)).await;
+
+        let req = test::TestRequest::post()
+            .uri("/api/jobs/job-1")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+
+        assert!(resp.status().is_client_error());
+    }
+
+    #[actix_web::test]
+    async fn job_action_routes_registered_for_post() {
+        let app = test::init_service(
+            App::new()
+                .service(cancel_job)
+                .service(pause_job)
+                .service(resume_job)
+                .service(retry_job),
+        )
+        .await;
+
+        for action in ["cancel", "pause", "resume", "retry"] {
+            let req = test::TestRequest::get()
+                .uri(&format!("/api/jobs/job-1/{}", action))
+                .to_request();
+            let resp = test::call_service(&app, req).await;
+
+            assert!(resp.status().is_client_error(), "{} accepted GET", action);
+        }
+    }
+
+    #[actix_web::test]
+    async fn cancel_job_without_required_app_data_returns_server_error() {
+        let app = test::init_service(App::new().service(cancel_job)).await;
+
+        let req = test::TestRequest::post()
+            .uri("/api/jobs/job-1/cancel")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+
+        assert!(resp.status().is_server_error());
+    }
+
+    #[actix_web::test]
+    async fn dead_letter_routes_registered() {
+        let app = test::init_service(
+            App::new()
+                .service(list_dead_letters)
+                .service(replay_dead_letter),
+        )
+        .await;
+
+        let req = test::TestRequest::post()
+            .uri("/api/admin/dead-letters")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert!(resp.status().is_client_error());
+
+        let req = test::TestRequest::get()
+            .uri("/api/admin/dead-letters/job-1/replay")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert!(resp.status().is_client_error());
+    }
+
+    #[actix_web::test]
+    async fn schedule_routes_registered() {
+        let app = test::init_service(
+            App::new()
+                .service(list_job_schedules)
+                .service(create_job_schedule)
+                .service(set_job_schedule_enabled)
+                .service(delete_job_schedule),
+        )
+        .await;
+
+        let req = test::TestRequest::put()
+            .uri("/api/admin/schedules")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert!(resp.status().is_client_error());
+
+        let req = test::TestRequest::get()
+            .uri("/api/admin/schedules/nightly/enabled")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert!(resp.status().is_client_error());
+    }
+
+    #[actix_web::test]
+    async fn get_job_step_runs_route_registered_for_get() {
+        let app = test::init_service(App::new().service(get_job_step_runs)).await;
+
+        let req = test::TestRequest::post()
+            .uri("/api/jobs/job-1/step-runs")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+
+        assert!(resp.status().is_client_error());
+    }
+
+    #[actix_web::test]
+    async fn get_job_step_runs_without_required_app_data_returns_server_error() {
+        let app = test::init_service(App::new().service(get_job_step_runs)).await;
+
+        let req = test::TestRequest::get()
+            .uri("/api/jobs/job-1/step-runs")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+
+        assert!(resp.status().is_server_error());
+    }
+}
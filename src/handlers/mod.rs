@@ -1,8 +1,14 @@
 pub mod auth_handler;
+pub mod job_handler;
 pub mod quiz_handler;
 pub mod user_handler;
 
-pub use quiz_handler::{create_quiz_draft, get_quiz};
+pub use job_handler::{
+    cancel_job, create_job_schedule, delete_job_schedule, get_job, get_job_step_runs,
+    list_dead_letters, list_job_schedules, pause_job, replay_dead_letter, resume_job, retry_job,
+    set_job_schedule_enabled,
+};
+pub use quiz_handler::{create_quiz_draft, create_quiz_draft_from_document, get_quiz};
 pub use user_handler::{
     create_user, delete_user, get_all_users, get_user, health_check, health_check_live,
     health_check_ready, update_user,
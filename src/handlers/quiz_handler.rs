@@ -1,12 +1,26 @@
-use std::sync::Arc;
+use std::{collections::HashMap, sync::Arc};
 
+use actix_multipart::{Field, Multipart, MultipartError};
 use actix_web::{get, post, web, HttpResponse};
+use futures::TryStreamExt;
 
 use crate::{
-    app_state::AppState, auth::AuthenticatedUser, errors::AppError,
-    models::dto::request::QuizDraftDto,
+    app_state::AppState,
+    auth::AuthenticatedUser,
+    errors::AppError,
+    models::{
+        domain::quiz::MAX_QUIZ_SOURCES,
+        dto::request::{QuizDocumentDraftDto, QuizDraftDto},
+    },
+    services::document_extractor::DocumentUpload,
 };
 
+/// Longest accepted value of a text field in an upload form
+const MAX_FORM_FIELD_BYTES: usize = 1024;
+
+/// Settings accepted as text fields of an upload form, alongside `file` and `url`
+const DRAFT_FORM_FIELDS: [&str; 4] = ["name", "question_count", "required_score", "attempt_limit"];
+
 #[get("/api/quizzes/{id}")]
 async fn get_quiz(
     state: web::Data<Arc<AppState>>,
@@ -25,11 +39,144 @@ async fn create_quiz_draft(
 ) -> Result<HttpResponse, AppError> {
     let response = state
         .quiz_service
-        .create_quiz_draft(request.into_inner(), &auth.0.sub)
+        .create_quiz_draft(request.into_inner(), &auth.0)
         .await?;
     Ok(HttpResponse::Created().json(response))
 }
 
+/// Create a quiz draft from uploaded PDF, Markdown, text or HTML files.
+/// Expects one or more `file` parts plus the draft's settings as text fields;
+/// repeated `url` fields add web pages as further sources. Any other field is
+/// rejected, and the quota is checked before anything is read.
+#[post("/api/quizzes/drafts/upload")]
+async fn create_quiz_draft_from_document(
+    state: web::Data<Arc<AppState>>,
+    auth: AuthenticatedUser,
+    mut payload: Multipart,
+) -> Result<HttpResponse, AppError> {
+    state
+        .quiz_service
+        .ensure_generation_quota(&auth.0.sub, &auth.0.role)
+        .await?;
+
+    let max_bytes = state.config.document_upload_max_bytes;
+    let mut uploads = Vec::new();
+    let mut urls = Vec::new();
+    let mut fields: HashMap<String, String> = HashMap::new();
+
+    while let Some(mut field) = payload.try_next().await.map_err(multipart_error)? {
+        let name = field.name().unwrap_or_default().to_string();
+        if !is_upload_form_field(&name) {
+            return Err(AppError::BadRequest(format!("Unexpected field '{}'", name)));
+        }
+        if (name == "file" || name == "url") && uploads.len() + urls.len() >= MAX_QUIZ_SOURCES {
+            return Err(AppError::BadRequest(format!(
+                "A quiz can have at most {} sources",
+                MAX_QUIZ_SOURCES
+            )));
+        }
+        if name == "file" {
+            let file_name = field
+                .content_disposition()
+                .and_then(|disposition| disposition.get_filename())
+                .and_then(sanitise_file_name)
+                .ok_or_else(|| AppError::BadRequest("file must have a file name".to_string()))?;
+            let content_type = field.content_type().map(|mime| mime.to_string());
+            let bytes = read_field(&mut field, max_bytes, || {
+                AppError::PayloadTooLarge(format!(
+                    "Uploaded file is larger than {} bytes",
+                    max_bytes
+                ))
+            })
+            .await?;
+
+            uploads.push(DocumentUpload {
+                file_name,
+                content_type,
+                bytes,
+            });
+        } else {
+            let bytes = read_field(&mut field, MAX_FORM_FIELD_BYTES, || {
+                AppError::BadRequest(format!("{} is too long", name))
+            })
+            .await?;
+            let value = String::from_utf8(bytes)
+                .map_err(|_| AppError::BadRequest(format!("{} must be UTF-8 text", name)))?;
+            if name == "url" {
+                urls.push(value.trim().to_string());
+            } else if fields.insert(name.clone(), value).is_some() {
+                return Err(AppError::BadRequest(format!(
+                    "{} must only be given once",
+                    name
+                )));
+            }
+        }
+    }
+
+    if uploads.is_empty() {
+        return Err(AppError::BadRequest("file is required".to_string()));
+    }
+    let request = QuizDocumentDraftDto {
+        name: fields.remove("name").unwrap_or_default(),
+        question_count: form_number(&fields, "question_count")?,
+        required_score: form_number(&fields, "required_score")?,
+        attempt_limit: form_number(&fields, "attempt_limit")?,
+        urls,
+    };
+
+    let response = state
+        .quiz_service
+        .create_document_quiz_draft(request, uploads, &auth.0)
+        .await?;
+    Ok(HttpResponse::Created().json(response))
+}
+
+fn is_upload_form_field(name: &str) -> bool {
+    name == "file" || name == "url" || DRAFT_FORM_FIELDS.contains(&name)
+}
+
+fn multipart_error(e: MultipartError) -> AppError {
+    AppError::BadRequest(format!("Invalid multipart upload: {}", e))
+}
+
+/// Read a multipart field, failing as soon as it grows past `limit`
+async fn read_field(
+    field: &mut Field,
+    limit: usize,
+    too_large: impl Fn() -> AppError,
+) -> Result<Vec<u8>, AppError> {
+    let mut bytes = Vec::new();
+    while let Some(chunk) = field.try_next().await.map_err(multipart_error)? {
+        if bytes.len() + chunk.len() > limit {
+            return Err(too_large());
+        }
+        bytes.extend_from_slice(&chunk);
+    }
+    Ok(bytes)
+}
+
+fn form_number(fields: &HashMap<String, String>, name: &str) -> Result<i16, AppError> {
+    let value = fields
+        .get(name)
+        .ok_or_else(|| AppError::BadRequest(format!("{} is required", name)))?;
+    value
+        .trim()
+        .parse()
+        .map_err(|_| AppError::BadRequest(format!("{} must be a number", name)))
+}
+
+/// Last path segment of a client supplied file name, without control characters
+fn sanitise_file_name(file_name: &str) -> Option<String> {
+    let base = file_name.rsplit(['/', '\\']).next().unwrap_or_default();
+    let cleaned: String = base
+        .chars()
+        .filter(|ch| !ch.is_control())
+        .take(255)
+        .collect();
+    let cleaned = cleaned.trim();
+    (!cleaned.is_empty() && cleaned != "." && cleaned != "..").then(|| cleaned.to_string())
+}
+
 #[cfg(test)]
 mod tests {
     use actix_web::{test, App};
@@ -84,4 +231,40 @@ mod tests {
 
         assert!(resp.status().is_server_error());
     }
+
+    #[actix_web::test]
+    async fn create_quiz_draft_from_document_route_registered_for_post() {
+        let app = test::init_service(App::new().service(create_quiz_draft_from_document)).await;
+
+        let req = test::TestRequest::get()
+            .uri("/api/quizzes/drafts/upload")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+
+        assert!(resp.status().is_client_error());
+    }
+
+    #[actix_web::test]
+    async fn upload_form_only_accepts_draft_fields() {
+        for name in ["file", "url", "name", "question_count", "attempt_limit"] {
+            assert!(is_upload_form_field(name), "{} should be accepted", name);
+        }
+        for name in ["", "status", "created_by_user_id", "files"] {
+            assert!(!is_upload_form_field(name), "{} should be rejected", name);
+        }
+    }
+
+    #[actix_web::test]
+    async fn sanitise_file_name_strips_paths_and_control_characters() {
+        assert_eq!(
+            sanitise_file_name("C:\\docs\\guide.pdf").as_deref(),
+            Some("guide.pdf")
+        );
+        assert_eq!(
+            sanitise_file_name("../../etc/notes\n.md").as_deref(),
+            Some("notes.md")
+        );
+        assert_eq!(sanitise_file_name("uploads/.."), None);
+        assert_eq!(sanitise_file_name("  "), None);
+    }
 }
@@ -27,7 +27,13 @@ async fn get_user(
 ) -> Result<HttpResponse, AppError> {
     require_owner_or_admin(&auth.0, &username)?;
 
-    let user = state.user_service.get_user(&username).await?;
+    let mut user = state.user_service.get_user(&username).await?;
+    user.generation_quota = Some(
+        state
+            .quiz_service
+            .generation_quota(&user.id, &user.role)
+            .await?,
+    );
     Ok(HttpResponse::Ok().json(user))
 }
 
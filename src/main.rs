@@ -1,7 +1,8 @@
 use actix_cors::Cors;
 use actix_web::http;
 use actix_web::{middleware::Logger, web, App, HttpMessage, HttpServer};
-use async_graphql_actix_web::{GraphQLRequest, GraphQLResponse};
+use async_graphql::Data;
+use async_graphql_actix_web::{GraphQLRequest, GraphQLResponse, GraphQLSubscription};
 use secrecy::ExposeSecret as _;
 use std::env;
 
@@ -38,6 +39,56 @@ async fn graphql_handler(
     schema.execute(request).await.into()
 }
 
+/// GraphQL subscriptions over WebSocket. The upgrade request is not behind
+/// `AuthMiddleware`; clients authenticate in the `connection_init` payload.
+async fn graphql_ws_handler(
+    schema: web::Data<graphql::Schema>,
+    jwt_service: web::Data<auth::JwtService>,
+    http_req: actix_web::HttpRequest,
+    payload: web::Payload,
+) -> actix_web::Result<actix_web::HttpResponse> {
+    let jwt_service = jwt_service.into_inner();
+
+    GraphQLSubscription::new(graphql::Schema::clone(&schema))
+        .on_connection_init(move |value| async move {
+            let claims = auth::claims_from_connection_init(&jwt_service, &value)?;
+            let mut data = Data::default();
+            data.insert(claims);
+            Ok(data)
+        })
+        .start(&http_req, payload)
+}
+
+/// Resolves on the first SIGINT or, on Unix, SIGTERM
+async fn shutdown_signal() {
+    let interrupt = async {
+        if let Err(e) = tokio::signal::ctrl_c().await {
+            log::error!("Failed to listen for SIGINT: {}", e);
+            std::future::pending::<()>().await;
+        }
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut signal) => {
+                signal.recv().await;
+            }
+            Err(e) => {
+                log::error!("Failed to listen for SIGTERM: {}", e);
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = interrupt => {}
+        _ = terminate => {}
+    }
+}
+
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
     dotenvy::from_filename(".env.local").ok();
@@ -83,6 +134,7 @@ async fn main() -> std::io::Result<()> {
 
     let schema = create_schema((*app_state).clone());
     let jwt_service = app_state.jwt_service.clone();
+    let agent_orchestrator = app_state.agent_orchestrator.clone();
 
     let host = config.web_server_host.clone();
     let port = config.web_server_port;
@@ -91,7 +143,7 @@ async fn main() -> std::io::Result<()> {
 
     let cors_origins = config.cors_origins.clone();
 
-    HttpServer::new(move || {
+    let server = HttpServer::new(move || {
         let mut cors = Cors::default()
             .allowed_methods(vec!["GET", "POST", "PUT", "DELETE", "OPTIONS"])
             .allowed_headers(vec![
@@ -121,6 +173,7 @@ async fn main() -> std::io::Result<()> {
             .service(handlers::auth_github_callback)
             .service(handlers::refresh_token)
             .service(handlers::logout)
+            .route("/graphql/ws", web::get().to(graphql_ws_handler))
             // Protected routes
             .service(
                 web::scope("")
@@ -132,10 +185,43 @@ async fn main() -> std::io::Result<()> {
                     .service(handlers::delete_user)
                     .service(handlers::get_quiz)
                     .service(handlers::create_quiz_draft)
+                    .service(handlers::create_quiz_draft_from_document)
+                    .service(handlers::get_job)
+                    .service(handlers::get_job_step_runs)
+                    .service(handlers::cancel_job)
+                    .service(handlers::pause_job)
+                    .service(handlers::resume_job)
+                    .service(handlers::retry_job)
+                    .service(handlers::list_dead_letters)
+                    .service(handlers::replay_dead_letter)
+                    .service(handlers::list_job_schedules)
+                    .service(handlers::create_job_schedule)
+                    .service(handlers::set_job_schedule_enabled)
+                    .service(handlers::delete_job_schedule)
                     .route("/graphql", web::post().to(graphql_handler)),
             )
     })
     .bind((host, port))?
-    .run()
-    .await
+    // Signals are handled below so the agent worker stops claiming jobs as
+    // soon as one arrives, not after HTTP connections have drained
+    .disable_signals()
+    .run();
+
+    let server_handle = server.handle();
+    let shutdown = actix_web::rt::spawn(async move {
+        shutdown_signal().await;
+        log::info!("Shutdown signal received; stopping HTTP server and draining agent worker");
+        let (_, stopped) = tokio::join!(server_handle.stop(true), agent_orchestrator.stop_worker());
+        if let Err(e) = stopped {
+            log::error!("Failed to stop background worker: {}", e);
+        }
+    });
+
+    server.await?;
+    // Let running job steps finish, up to the drain deadline, before exiting
+    if let Err(e) = shutdown.await {
+        log::error!("Shutdown task failed: {}", e);
+    }
+
+    Ok(())
 }
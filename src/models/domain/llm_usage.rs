@@ -0,0 +1,48 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// One LLM call, attributed to the job, quiz and user it was made for
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct LlmUsageRecord {
+    pub id: String,
+    pub job_id: Option<String>,
+    pub quiz_id: Option<String>,
+    pub user_id: Option<String>,
+    /// Pipeline stage, e.g. `Summarise`
+    pub stage: String,
+    pub provider: String,
+    pub model: String,
+    pub prompt_tokens: i64,
+    pub completion_tokens: i64,
+    pub total_tokens: i64,
+    pub latency_ms: i64,
+    pub cost_usd: f64,
+    pub success: bool,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Token totals for one user on one UTC day
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub struct UserDailyLlmUsage {
+    pub user_id: String,
+    /// `YYYY-MM-DD`
+    pub day: String,
+    pub calls: i64,
+    pub prompt_tokens: i64,
+    pub completion_tokens: i64,
+    pub total_tokens: i64,
+    pub cost_usd: f64,
+}
+
+/// Token totals for one quiz across all of its generation jobs
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub struct QuizLlmUsage {
+    pub quiz_id: String,
+    pub user_id: Option<String>,
+    pub jobs: i64,
+    pub calls: i64,
+    pub prompt_tokens: i64,
+    pub completion_tokens: i64,
+    pub total_tokens: i64,
+    pub cost_usd: f64,
+}
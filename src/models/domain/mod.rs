@@ -1,11 +1,14 @@
+pub mod llm_usage;
 pub mod quiz;
 pub mod quiz_attempt;
 pub mod quiz_question;
+pub mod quiz_version;
 pub mod refresh_token;
 pub mod summary_document;
 pub mod user;
 pub use quiz::Quiz;
 pub use quiz_attempt::QuizAttempt;
 pub use quiz_question::QuizQuestion;
+pub use quiz_version::{QuizVersion, QuizVersionDiff};
 pub use refresh_token::{hash_token, RefreshToken};
 pub use user::User;
@@ -5,6 +5,13 @@ use serde::{Deserialize, Serialize};
 
 use crate::models::domain::quiz_question::QuizQuestion;
 
+/// `QuizSource::url` prefix of documents uploaded rather than fetched from
+/// the web; the file name follows it
+pub const UPLOADED_DOCUMENT_URL_PREFIX: &str = "upload:";
+
+/// Most pages and documents a single quiz can be generated from
+pub const MAX_QUIZ_SOURCES: usize = 10;
+
 #[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize, SimpleObject, JsonSchema)]
 // #[serde(deny_unknown_fields)]
 pub struct Quiz {
@@ -19,13 +26,45 @@ pub struct Quiz {
     pub topic: Option<String>,       // Set on create - Possible tag system
     pub status: QuizStatus,
     pub questions: Option<Vec<QuizQuestion>>, // Set on create
-    pub url: String,                          // currently set on draft - subject to change
+    pub url: String,                          // First source; kept for single-source clients
+    /// Pages and documents the quiz is generated from; older quizzes only
+    /// have `url`
+    #[serde(default)]
+    pub sources: Vec<QuizSource>,
+    /// Latest published version, once the quiz has been published
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<i32>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub created_at: Option<DateTime<Utc>>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub modified_at: Option<DateTime<Utc>>,
 }
 
+/// A web page or uploaded document a quiz is generated from. Ids are short
+/// labels, unique within the quiz, that generated questions refer back to.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize, SimpleObject, JsonSchema)]
+pub struct QuizSource {
+    pub id: String,
+    pub url: String,
+}
+
+impl QuizSource {
+    /// Sources labelled `source-1`, `source-2`, ... in the given order
+    pub fn from_urls<S: AsRef<str>>(urls: &[S]) -> Vec<Self> {
+        urls.iter()
+            .enumerate()
+            .map(|(index, url)| QuizSource {
+                id: format!("source-{}", index + 1),
+                url: url.as_ref().to_string(),
+            })
+            .collect()
+    }
+
+    pub fn is_uploaded(&self) -> bool {
+        self.url.starts_with(UPLOADED_DOCUMENT_URL_PREFIX)
+    }
+}
+
 #[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize, Enum, Copy, JsonSchema)]
 #[serde(deny_unknown_fields)]
 pub enum QuizStatus {
@@ -33,6 +72,11 @@ pub enum QuizStatus {
     Pending,
     Ready,
     Complete,
+    /// Generation failed permanently; the job is in the dead-letter queue
+    Failed,
+    /// Generated, but some answers weren't supported by the source; held back
+    /// until the creator approves it
+    NeedsReview,
 }
 
 impl Quiz {
@@ -57,10 +101,31 @@ impl Quiz {
             status: QuizStatus::Draft,
             questions: None,
             url: url.to_string(),
+            sources: QuizSource::from_urls(&[url]),
+            version: None,
             created_at: Some(Utc::now()),
             modified_at: Some(Utc::now()),
         }
     }
+
+    /// Replace the draft's sources; the first becomes `url`
+    pub fn with_sources<S: AsRef<str>>(mut self, urls: &[S]) -> Self {
+        if let Some(first) = urls.first() {
+            self.url = first.as_ref().to_string();
+            self.sources = QuizSource::from_urls(urls);
+        }
+        self
+    }
+
+    /// Sources to generate from, falling back to `url` for quizzes created
+    /// before multi-source support
+    pub fn source_list(&self) -> Vec<QuizSource> {
+        if self.sources.is_empty() {
+            QuizSource::from_urls(&[&self.url])
+        } else {
+            self.sources.clone()
+        }
+    }
 }
 
 impl Quiz {
@@ -106,6 +171,25 @@ mod tests {
         assert!(quiz.modified_at.is_some());
     }
 
+    #[test]
+    fn sources_fall_back_to_url_and_are_labelled_in_order() {
+        let mut quiz = Quiz::test_quiz("Name", "user-1")
+            .with_sources(&["https://example.com/1", "upload:notes.md"]);
+
+        assert_eq!(quiz.url, "https://example.com/1");
+        assert_eq!(quiz.sources[1].id, "source-2");
+        assert!(quiz.sources[1].is_uploaded());
+
+        quiz.sources.clear();
+        assert_eq!(
+            quiz.source_list(),
+            vec![QuizSource {
+                id: "source-1".to_string(),
+                url: "https://example.com/1".to_string(),
+            }]
+        );
+    }
+
     #[test]
     fn test_quiz_with_title_sets_title_and_description() {
         let quiz = Quiz::test_quiz_with_title("Name", "user-1", "Title", "Description");
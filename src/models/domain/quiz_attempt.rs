@@ -6,6 +6,10 @@ pub struct QuizAttempt {
     pub id: String,
     pub user_id: String,
     pub quiz_id: String,
+    /// Published version of the quiz the attempt was graded against; absent
+    /// for attempts made before quizzes were versioned
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub quiz_version: Option<i32>,
     pub points_earned: i16,
     pub required_score: i16,
     pub total_possible: i16,
@@ -37,6 +41,7 @@ mod tests {
             id: "attempt-1".to_string(),
             user_id: "user-1".to_string(),
             quiz_id: "quiz-1".to_string(),
+            quiz_version: Some(1),
             points_earned,
             required_score,
             total_possible: 5,
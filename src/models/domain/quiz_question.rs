@@ -15,6 +15,12 @@ pub struct QuizQuestion {
     pub order: i16,
     pub attempt_limit: i16,
     pub topic: String,
+    /// Id of the quiz source the question was generated from
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source_id: Option<String>,
+    /// Set when the grounding check has run
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub grounding: Option<QuestionGrounding>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub created_at: Option<DateTime<Utc>>,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -29,6 +35,17 @@ pub struct QuizQuestionOption {
     pub explanation: String, // explanation for why this option is correct or incorrect
 }
 
+/// How well the correct options of a question are supported by its summary
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize, SimpleObject, JsonSchema)]
+pub struct QuestionGrounding {
+    /// Passages of the summary quoted in support of the correct options
+    pub evidence: String,
+    /// 0 to 100; the lowest score given to any correct option
+    pub confidence: u8,
+    /// Below the configured minimum confidence, so the creator should check it
+    pub needs_review: bool,
+}
+
 #[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize, Enum, Copy, JsonSchema)]
 pub enum QuizQuestionType {
     Single, // Only one correct option
@@ -91,6 +108,8 @@ mod tests {
             order: 1,
             attempt_limit: 1,
             topic: "basics".to_string(),
+            source_id: None,
+            grounding: None,
             created_at: Some(Utc::now()),
             modified_at: Some(Utc::now()),
         };
@@ -0,0 +1,248 @@
+use async_graphql::SimpleObject;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::models::domain::{quiz_question::QuizQuestion, Quiz};
+
+/// Immutable snapshot of a quiz as it was published. Attempts record the
+/// version they were graded against and are reviewed from it.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize, SimpleObject)]
+pub struct QuizVersion {
+    pub id: String,
+    pub quiz_id: String,
+    /// Numbered from 1 in the order the versions were published
+    pub version: i32,
+    pub quiz: Quiz,
+    /// Set when this version was published by rolling back to an earlier one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rolled_back_from: Option<i32>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl QuizVersion {
+    pub fn new(quiz: &Quiz, version: i32, rolled_back_from: Option<i32>) -> Self {
+        let mut snapshot = quiz.clone();
+        snapshot.version = Some(version);
+        QuizVersion {
+            id: uuid::Uuid::new_v4().to_string(),
+            quiz_id: quiz.id.clone(),
+            version,
+            quiz: snapshot,
+            rolled_back_from,
+            created_at: Utc::now(),
+        }
+    }
+
+    /// Whether `quiz` has the content of this version, ignoring its status
+    /// and timestamps
+    pub fn matches(&self, quiz: &Quiz) -> bool {
+        let snapshot = &self.quiz;
+        snapshot.title == quiz.title
+            && snapshot.description == quiz.description
+            && snapshot.topic == quiz.topic
+            && snapshot.question_count == quiz.question_count
+            && snapshot.required_score == quiz.required_score
+            && snapshot.attempt_limit == quiz.attempt_limit
+            && questions_content(snapshot) == questions_content(quiz)
+    }
+}
+
+/// Questions of `quiz` without their timestamps, which change on every save
+fn questions_content(quiz: &Quiz) -> Option<Vec<QuizQuestion>> {
+    quiz.questions.as_ref().map(|questions| {
+        questions
+            .iter()
+            .map(|question| QuizQuestion {
+                created_at: None,
+                modified_at: None,
+                ..question.clone()
+            })
+            .collect()
+    })
+}
+
+/// What changed between two versions of a quiz
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, SimpleObject)]
+pub struct QuizVersionDiff {
+    pub quiz_id: String,
+    pub from_version: i32,
+    pub to_version: i32,
+    /// Quiz fields that differ, such as `title`
+    pub changed_fields: Vec<String>,
+    pub added_questions: Vec<QuizQuestion>,
+    pub removed_questions: Vec<QuizQuestion>,
+    pub changed_questions: Vec<QuestionChange>,
+    /// Whether the questions in both versions are in a different order
+    pub reordered: bool,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, SimpleObject)]
+pub struct QuestionChange {
+    pub question_id: String,
+    /// Question fields that differ, such as `options`
+    pub changed_fields: Vec<String>,
+    pub before: QuizQuestion,
+    pub after: QuizQuestion,
+}
+
+impl QuizVersionDiff {
+    pub fn between(from: &QuizVersion, to: &QuizVersion) -> Self {
+        let (old, new) = (&from.quiz, &to.quiz);
+        let changed_fields = changed(&[
+            ("title", old.title != new.title),
+            ("description", old.description != new.description),
+            ("topic", old.topic != new.topic),
+            ("required_score", old.required_score != new.required_score),
+            ("attempt_limit", old.attempt_limit != new.attempt_limit),
+        ]);
+
+        let before = old.questions.as_deref().unwrap_or_default();
+        let after = new.questions.as_deref().unwrap_or_default();
+        let find = |questions: &[QuizQuestion], id: &str| -> Option<QuizQuestion> {
+            questions.iter().find(|q| q.id == id).cloned()
+        };
+
+        let added_questions = after
+            .iter()
+            .filter(|q| find(before, &q.id).is_none())
+            .cloned()
+            .collect();
+        let removed_questions = before
+            .iter()
+            .filter(|q| find(after, &q.id).is_none())
+            .cloned()
+            .collect();
+        let changed_questions = after
+            .iter()
+            .filter_map(|question| {
+                let previous = find(before, &question.id)?;
+                let changed_fields = changed(&[
+                    ("title", previous.title != question.title),
+                    ("description", previous.description != question.description),
+                    (
+                        "question_type",
+                        previous.question_type != question.question_type,
+                    ),
+                    ("options", previous.options != question.options),
+                ]);
+                (!changed_fields.is_empty()).then(|| QuestionChange {
+                    question_id: question.id.clone(),
+                    changed_fields,
+                    before: previous,
+                    after: question.clone(),
+                })
+            })
+            .collect();
+
+        let kept_before: Vec<&str> = before
+            .iter()
+            .filter(|q| find(after, &q.id).is_some())
+            .map(|q| q.id.as_str())
+            .collect();
+        let kept_after: Vec<&str> = after
+            .iter()
+            .filter(|q| find(before, &q.id).is_some())
+            .map(|q| q.id.as_str())
+            .collect();
+
+        QuizVersionDiff {
+            quiz_id: to.quiz_id.clone(),
+            from_version: from.version,
+            to_version: to.version,
+            changed_fields,
+            added_questions,
+            removed_questions,
+            changed_questions,
+            reordered: kept_before != kept_after,
+        }
+    }
+}
+
+fn changed(fields: &[(&str, bool)]) -> Vec<String> {
+    fields
+        .iter()
+        .filter(|(_, changed)| *changed)
+        .map(|(name, _)| name.to_string())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::domain::{quiz::QuizStatus, quiz_question::QuizQuestionType};
+
+    fn question(id: &str, title: &str) -> QuizQuestion {
+        QuizQuestion {
+            id: id.to_string(),
+            title: title.to_string(),
+            description: String::new(),
+            question_type: QuizQuestionType::Bool,
+            options: Vec::new(),
+            option_count: 2,
+            order: 0,
+            attempt_limit: 3,
+            topic: String::new(),
+            source_id: None,
+            grounding: None,
+            created_at: None,
+            modified_at: None,
+        }
+    }
+
+    fn version(number: i32, title: &str, questions: Vec<QuizQuestion>) -> QuizVersion {
+        let mut quiz = Quiz::test_quiz_with_title("Quiz", "user-1", title, "Description");
+        quiz.id = "quiz-1".to_string();
+        quiz.status = QuizStatus::Ready;
+        quiz.questions = Some(questions);
+        QuizVersion::new(&quiz, number, None)
+    }
+
+    #[test]
+    fn diff_lists_added_removed_changed_and_reordered_questions() {
+        let from = version(
+            1,
+            "Rust",
+            vec![
+                question("a", "Is Rust safe?"),
+                question("b", "Is Rust fast?"),
+                question("c", "Is Rust new?"),
+            ],
+        );
+        let to = version(
+            2,
+            "Rust basics",
+            vec![
+                question("b", "Is Rust fast?"),
+                question("a", "Is Rust memory safe?"),
+                question("d", "Is Rust compiled?"),
+            ],
+        );
+
+        let diff = QuizVersionDiff::between(&from, &to);
+
+        assert_eq!(diff.changed_fields, vec!["title"]);
+        assert_eq!(diff.added_questions[0].id, "d");
+        assert_eq!(diff.removed_questions[0].id, "c");
+        assert_eq!(diff.changed_questions.len(), 1);
+        assert_eq!(diff.changed_questions[0].question_id, "a");
+        assert_eq!(diff.changed_questions[0].changed_fields, vec!["title"]);
+        assert!(diff.reordered);
+    }
+
+    #[test]
+    fn matches_ignores_status_and_timestamps() {
+        let published = version(1, "Rust", vec![question("a", "Is Rust safe?")]);
+        let mut quiz = published.quiz.clone();
+        quiz.status = QuizStatus::Complete;
+        quiz.modified_at = Some(Utc::now());
+        quiz.version = None;
+        // Saving a quiz touches every question
+        if let Some(questions) = quiz.questions.as_mut() {
+            questions[0].modified_at = Some(Utc::now());
+        }
+
+        assert!(published.matches(&quiz));
+        quiz.questions = Some(vec![question("a", "Is Rust fast?")]);
+        assert!(!published.matches(&quiz));
+    }
+}
@@ -2,12 +2,23 @@ use async_graphql::SimpleObject;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
+use crate::models::domain::quiz::QuizSource;
+
 #[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize, SimpleObject)]
 pub struct SummaryDocument {
     pub id: String,
     pub quiz_id: String,
     pub url: String,
     pub content: String,
+    /// Id of the quiz source summarised; unset on summaries from before
+    /// quizzes had several sources
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source_id: Option<String>,
+    /// Text of the uploaded document the summary is generated from; unset
+    /// when the source is `url`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[graphql(skip)]
+    pub source: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub created_at: Option<DateTime<Utc>>,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -21,8 +32,27 @@ impl SummaryDocument {
             quiz_id: quiz_id.to_string(),
             content: content.to_string(),
             url: url.to_string(),
+            source_id: None,
+            source: None,
             created_at: Some(Utc::now()),
             modified_at: Some(Utc::now()),
         }
     }
+
+    /// Summary of one of the quiz's sources
+    pub fn for_source(quiz_id: &str, source: &QuizSource, content: &str) -> Self {
+        SummaryDocument {
+            source_id: Some(source.id.clone()),
+            ..Self::new_summary_document(&source.url, quiz_id, content)
+        }
+    }
+
+    /// Document holding uploaded source text, summarised later by the
+    /// generation pipeline
+    pub fn new_source_document(quiz_id: &str, source: &QuizSource, text: String) -> Self {
+        SummaryDocument {
+            source: Some(text),
+            ..Self::for_source(quiz_id, source, "")
+        }
+    }
 }
@@ -4,8 +4,10 @@ use serde::{Deserialize, Serialize};
 use validator::Validate;
 
 use crate::errors::AppError;
-use crate::models::domain::quiz::QuizStatus;
-use crate::models::domain::quiz_question::{QuizQuestionOption, QuizQuestionType};
+use crate::models::domain::quiz::{QuizSource, QuizStatus};
+use crate::models::domain::quiz_question::{
+    QuestionGrounding, QuizQuestionOption, QuizQuestionType,
+};
 use crate::models::domain::{Quiz, QuizQuestion};
 
 #[derive(Debug, Clone, Deserialize, Serialize, Validate, JsonSchema)]
@@ -27,6 +29,10 @@ pub struct QuizQuestionDto {
     pub order: i16,
     pub attempt_limit: i16,
     pub topic: String,
+    #[serde(default)]
+    pub source_id: Option<String>,
+    #[serde(default)]
+    pub grounding: Option<QuestionGrounding>,
     pub created_at: DateTime<Utc>,
     pub modified_at: DateTime<Utc>,
 }
@@ -44,6 +50,8 @@ impl From<QuizQuestion> for QuizQuestionDto {
             order: question.order,
             attempt_limit: question.attempt_limit,
             topic: question.topic,
+            source_id: question.source_id,
+            grounding: question.grounding,
             created_at: question.created_at.unwrap_or(now),
             modified_at: question.modified_at.unwrap_or(now),
         }
@@ -64,6 +72,8 @@ impl TryFrom<QuizQuestionDto> for QuizQuestion {
             order: dto.order,
             attempt_limit: dto.attempt_limit,
             topic: dto.topic,
+            source_id: dto.source_id,
+            grounding: dto.grounding,
             created_at: Some(dto.created_at),
             modified_at: Some(dto.modified_at),
         })
@@ -84,6 +94,10 @@ pub struct QuizDto {
     pub status: QuizStatus,
     pub questions: Vec<QuizQuestionDto>,
     pub url: String,
+    #[serde(default)]
+    pub sources: Vec<QuizSource>,
+    #[serde(default)]
+    pub version: Option<i32>,
     pub created_at: DateTime<Utc>,
     pub modified_at: DateTime<Utc>,
 }
@@ -91,6 +105,7 @@ pub struct QuizDto {
 impl From<Quiz> for QuizDto {
     fn from(quiz: Quiz) -> Self {
         let now = Utc::now();
+        let sources = quiz.source_list();
         QuizDto {
             id: quiz.id,
             name: quiz.name,
@@ -109,6 +124,8 @@ impl From<Quiz> for QuizDto {
                 .map(QuizQuestionDto::from)
                 .collect(),
             url: quiz.url,
+            sources,
+            version: quiz.version,
             created_at: quiz.created_at.unwrap_or(now),
             modified_at: quiz.modified_at.unwrap_or(now),
         }
@@ -143,6 +160,8 @@ impl TryFrom<QuizDto> for Quiz {
             status: dto.status,
             questions,
             url: dto.url,
+            sources: dto.sources,
+            version: dto.version,
             created_at: Some(dto.created_at),
             modified_at: Some(dto.modified_at),
         })
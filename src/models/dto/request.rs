@@ -1,15 +1,16 @@
 use async_graphql::InputObject;
 use serde::{Deserialize, Serialize};
-use validator::Validate;
+use validator::{Validate, ValidateUrl, ValidationError};
 
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, Timelike, Utc};
 use schemars::JsonSchema;
 
 use crate::errors::{AppError, AppResult};
-use crate::models::domain::quiz::QuizStatus;
+use crate::models::domain::quiz::{QuizStatus, MAX_QUIZ_SOURCES};
 use crate::models::domain::quiz_question::{QuizQuestionOption, QuizQuestionType};
 use crate::models::domain::summary_document::SummaryDocument;
 use crate::models::dto::quiz_dto::{QuizDto, QuizQuestionDto};
+use crate::services::job_schedule::{JobSchedule, ScheduleTrigger};
 
 #[derive(Debug, Clone, Deserialize, Validate, InputObject)]
 #[graphql(rename_fields = "snake_case")]
@@ -56,19 +57,56 @@ pub struct QuizDraftDto {
 
     #[validate(url)]
     pub url: String,
+
+    /// Further sources to generate from alongside `url`
+    #[serde(default)]
+    #[graphql(default)]
+    #[validate(custom(function = "validate_extra_source_urls"))]
+    pub sources: Vec<String>,
 }
 impl QuizDraftDto {
     pub(crate) fn from_quiz(quiz: crate::models::domain::Quiz) -> QuizDraftDto {
+        let sources = quiz
+            .sources
+            .iter()
+            .skip(1)
+            .map(|source| source.url.clone())
+            .collect();
         QuizDraftDto {
             name: quiz.name,
             question_count: quiz.question_count,
             required_score: quiz.required_score,
             attempt_limit: quiz.attempt_limit,
             url: quiz.url,
+            sources,
         }
     }
 }
 
+fn validate_extra_source_urls(urls: &[String]) -> Result<(), ValidationError> {
+    if urls.len() >= MAX_QUIZ_SOURCES {
+        return Err(ValidationError::new("too_many_sources"));
+    }
+    if !urls.iter().all(|url| url.validate_url()) {
+        return Err(ValidationError::new("url"));
+    }
+    Ok(())
+}
+
+/// Form fields of a quiz draft generated from an uploaded document
+#[derive(Debug, Clone, Deserialize, Serialize, Validate)]
+pub struct QuizDocumentDraftDto {
+    #[validate(length(min = 1, max = 100))]
+    pub name: String,
+    pub question_count: i16,
+    pub required_score: i16,
+    pub attempt_limit: i16,
+    /// Web pages to generate from alongside the uploaded files
+    #[serde(default)]
+    #[validate(custom(function = "validate_extra_source_urls"))]
+    pub urls: Vec<String>,
+}
+
 #[derive(Debug, Clone, Deserialize, Serialize, Validate, InputObject, JsonSchema)]
 pub struct GenerateQuizRequestDto {
     pub quiz_title: String,
@@ -77,12 +115,37 @@ pub struct GenerateQuizRequestDto {
     pub quiz_questions: Vec<GenerateQuizQuestionRequestDto>,
 }
 
+/// Replacements for generated questions that failed validation or that the
+/// quiz creator asked to regenerate
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
+pub struct RegeneratedQuizQuestionsDto {
+    pub quiz_questions: Vec<GenerateQuizQuestionRequestDto>,
+}
+
+/// The validation model's support for each correct option of a question
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
+pub struct GroundingJudgementDto {
+    pub options: Vec<OptionGroundingDto>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
+pub struct OptionGroundingDto {
+    pub option_text: String,
+    /// Passage quoted word for word from the summary; empty when there is none
+    pub evidence: String,
+    /// 0 to 100
+    pub confidence: i64,
+}
+
 #[derive(Debug, Clone, Deserialize, Serialize, Validate, InputObject, JsonSchema)]
 pub struct GenerateQuizQuestionRequestDto {
     pub question_title: String,
     pub question_description: String,
     pub question_type: String,
     pub question_options: Vec<GenerateQuizQuestionOptionRequestDto>,
+    /// Source the question was written from, when the quiz has several
+    #[serde(default)]
+    pub source_id: Option<String>,
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize, Validate, InputObject, JsonSchema)]
@@ -92,6 +155,27 @@ pub struct GenerateQuizQuestionOptionRequestDto {
     pub option_explanation: String, // explanation for why this option is correct or incorrect
 }
 
+/// A stored question in the shape the model writes them, for prompts
+impl From<QuizQuestionDto> for GenerateQuizQuestionRequestDto {
+    fn from(question: QuizQuestionDto) -> Self {
+        GenerateQuizQuestionRequestDto {
+            question_title: question.title,
+            question_description: question.description,
+            question_type: format!("{:?}", question.question_type).to_lowercase(),
+            question_options: question
+                .options
+                .into_iter()
+                .map(|option| GenerateQuizQuestionOptionRequestDto {
+                    option_text: option.text,
+                    option_correct: option.correct.to_string(),
+                    option_explanation: option.explanation,
+                })
+                .collect(),
+            source_id: question.source_id,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Deserialize, Serialize, Validate, InputObject, JsonSchema)]
 #[graphql(rename_fields = "snake_case")]
 pub struct QuizRequestDto {
@@ -114,6 +198,8 @@ pub struct QuizRequestDto {
 #[derive(Debug, Clone, Deserialize, Serialize, Validate, InputObject, JsonSchema)]
 pub struct QuizQuestionRequestDto {
     pub id: String,
+    #[serde(default)]
+    pub source_id: Option<String>,
     pub title: String,
     pub description: String,
     pub question_type: String,
@@ -132,6 +218,7 @@ impl From<QuizQuestionDto> for QuizQuestionRequestDto {
 
         QuizQuestionRequestDto {
             id: question.id,
+            source_id: question.source_id,
             title: question.title,
             description: question.description,
             question_type: format!("{:?}", question.question_type),
@@ -164,6 +251,8 @@ impl TryFrom<QuizQuestionRequestDto> for QuizQuestionDto {
             order: parse_i16_required(&dto.order, "order")?,
             attempt_limit: parse_i16_required(&dto.attempt_limit, "attempt_limit")?,
             topic: dto.topic,
+            source_id: dto.source_id,
+            grounding: None,
             created_at,
             modified_at,
         })
@@ -219,6 +308,8 @@ impl TryFrom<QuizRequestDto> for QuizDto {
                 .map(QuizQuestionDto::try_from)
                 .collect::<Result<Vec<_>, AppError>>()?,
             url: dto.url,
+            sources: Vec::new(),
+            version: None,
             created_at,
             modified_at,
         })
@@ -230,6 +321,8 @@ impl TryFrom<QuizRequestDto> for QuizDto {
 pub struct SummaryDocumentRequestDto {
     pub id: String,
     pub quiz_id: String,
+    #[serde(default)]
+    pub source_id: Option<String>,
     pub url: String,
     pub content: String,
     pub created_at: String,
@@ -241,6 +334,7 @@ impl From<SummaryDocument> for SummaryDocumentRequestDto {
         SummaryDocumentRequestDto {
             id: summary_document.id,
             quiz_id: summary_document.quiz_id,
+            source_id: summary_document.source_id,
             url: summary_document.url,
             content: summary_document.content,
             created_at: summary_document
@@ -264,6 +358,8 @@ impl TryFrom<SummaryDocumentRequestDto> for SummaryDocument {
             quiz_id: dto.quiz_id,
             url: dto.url,
             content: dto.content,
+            source_id: dto.source_id,
+            source: None,
             created_at: parse_optional_datetime(&dto.created_at)?,
             modified_at: parse_optional_datetime(&dto.modified_at)?,
         })
@@ -298,6 +394,8 @@ fn parse_quiz_status(value: &str) -> AppResult<QuizStatus> {
         "pending" => Ok(QuizStatus::Pending),
         "ready" => Ok(QuizStatus::Ready),
         "complete" => Ok(QuizStatus::Complete),
+        "failed" => Ok(QuizStatus::Failed),
+        "needs_review" | "needsreview" => Ok(QuizStatus::NeedsReview),
         _ => Err(AppError::ValidationError(format!(
             "Invalid status: {}",
             value
@@ -455,6 +553,31 @@ pub struct UpdateQuizQuestionInput {
     pub options: Option<Vec<UpdateQuizQuestionOptionInput>>,
 }
 
+#[derive(Debug, Clone, Deserialize, Serialize, Validate, InputObject)]
+pub struct AddQuizQuestionOptionInput {
+    #[validate(length(min = 1, max = 500))]
+    pub text: String,
+    pub correct: bool,
+    #[serde(default)]
+    #[graphql(default)]
+    pub explanation: String,
+}
+
+/// A question written by hand rather than generated
+#[derive(Debug, Clone, Deserialize, Serialize, Validate, InputObject)]
+pub struct AddQuizQuestionInput {
+    #[validate(length(min = 1, max = 500))]
+    pub title: String,
+    #[serde(default)]
+    #[graphql(default)]
+    pub description: String,
+    pub question_type: QuizQuestionType,
+    #[validate(nested)]
+    pub options: Vec<AddQuizQuestionOptionInput>,
+    /// Zero-based position to insert at; the question is appended when absent
+    pub position: Option<i16>,
+}
+
 #[derive(Debug, Clone, Deserialize, Serialize, Validate, InputObject)]
 pub struct UpdateQuizInput {
     pub id: String,
@@ -463,6 +586,74 @@ pub struct UpdateQuizInput {
     pub questions: Option<Vec<UpdateQuizQuestionInput>>,
 }
 
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct DeadLetterListQuery {
+    pub offset: Option<i64>,
+    pub limit: Option<i64>,
+    pub include_replayed: Option<bool>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ReplayDeadLetterRequest {
+    /// Replaces the job's accumulated results before the failed step reruns
+    pub results: Option<std::collections::HashMap<String, serde_json::Value>>,
+}
+
+#[derive(Debug, Clone, Deserialize, Validate, InputObject)]
+#[graphql(rename_fields = "snake_case")]
+pub struct CreateJobScheduleRequest {
+    #[validate(length(min = 1, max = 200))]
+    pub name: String,
+    pub pipeline: String,
+    /// Run every this many seconds; mutually exclusive with `daily_at`
+    pub interval_seconds: Option<u64>,
+    /// Run once a day at this UTC time, formatted `HH:MM`
+    pub daily_at: Option<String>,
+    /// Seeded into the results of every job the schedule creates
+    pub metadata: Option<async_graphql::Json<std::collections::HashMap<String, serde_json::Value>>>,
+}
+
+impl TryFrom<CreateJobScheduleRequest> for JobSchedule {
+    type Error = AppError;
+
+    fn try_from(dto: CreateJobScheduleRequest) -> Result<Self, Self::Error> {
+        dto.validate()?;
+
+        let trigger = match (dto.interval_seconds, dto.daily_at.as_deref()) {
+            (Some(seconds), None) => ScheduleTrigger::Interval { seconds },
+            (None, Some(time)) => {
+                let time = chrono::NaiveTime::parse_from_str(time, "%H:%M").map_err(|_| {
+                    AppError::ValidationError(format!(
+                        "Invalid daily_at time '{}', expected HH:MM",
+                        time
+                    ))
+                })?;
+                ScheduleTrigger::DailyAt {
+                    hour: time.hour(),
+                    minute: time.minute(),
+                }
+            }
+            _ => {
+                return Err(AppError::ValidationError(
+                    "Exactly one of interval_seconds or daily_at is required".to_string(),
+                ))
+            }
+        };
+        trigger.validate().map_err(AppError::ValidationError)?;
+
+        let mut schedule = JobSchedule::new(dto.name, dto.pipeline, trigger);
+        if let Some(metadata) = dto.metadata {
+            schedule.metadata = metadata.0;
+        }
+        Ok(schedule)
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct SetJobScheduleEnabledRequest {
+    pub enabled: bool,
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -500,4 +691,38 @@ mod tests {
         };
         assert!(request.validate().is_err());
     }
+
+    fn schedule_request(
+        interval_seconds: Option<u64>,
+        daily_at: Option<&str>,
+    ) -> CreateJobScheduleRequest {
+        CreateJobScheduleRequest {
+            name: "nightly".to_string(),
+            pipeline: "purge_completed_jobs".to_string(),
+            interval_seconds,
+            daily_at: daily_at.map(str::to_string),
+            metadata: None,
+        }
+    }
+
+    #[test]
+    fn test_schedule_request_parses_daily_time() {
+        let schedule = JobSchedule::try_from(schedule_request(None, Some("02:15"))).unwrap();
+
+        assert_eq!(
+            schedule.trigger,
+            ScheduleTrigger::DailyAt {
+                hour: 2,
+                minute: 15
+            }
+        );
+    }
+
+    #[test]
+    fn test_schedule_request_requires_exactly_one_trigger() {
+        assert!(JobSchedule::try_from(schedule_request(None, None)).is_err());
+        assert!(JobSchedule::try_from(schedule_request(Some(60), Some("02:15"))).is_err());
+        assert!(JobSchedule::try_from(schedule_request(None, Some("25:00"))).is_err());
+        assert!(JobSchedule::try_from(schedule_request(Some(0), None)).is_err());
+    }
 }
@@ -2,9 +2,16 @@ use async_graphql::SimpleObject;
 use chrono::{DateTime, Utc};
 use serde::Serialize;
 
+use crate::models::domain::llm_usage::{QuizLlmUsage, UserDailyLlmUsage};
 use crate::models::domain::quiz_attempt::QuizAttempt;
 use crate::models::domain::quiz_question::QuizQuestionType;
-use crate::models::domain::{quiz::QuizStatus, Quiz, QuizQuestion, User};
+use crate::models::domain::user::UserRole;
+use crate::models::domain::{
+    quiz::{QuizSource, QuizStatus},
+    Quiz, QuizQuestion, User,
+};
+use crate::services::agent_orchestrator_service::{AgentJob, JobStatus, StepRun};
+use crate::services::job_schedule::JobSchedule;
 
 #[derive(Debug, Clone, Serialize, SimpleObject)]
 #[graphql(rename_fields = "snake_case")]
@@ -16,6 +23,26 @@ pub struct UserDto {
     #[graphql(skip)]
     #[serde(skip_serializing_if = "Option::is_none")]
     pub created_at: Option<DateTime<Utc>>,
+    #[graphql(skip)]
+    #[serde(skip)]
+    pub role: UserRole,
+    /// Only filled in on the user's own profile lookup
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub generation_quota: Option<GenerationQuotaResponse>,
+}
+
+/// Remaining AI quiz generation allowance; `None` limits are unlimited
+#[derive(Debug, Clone, Serialize, SimpleObject)]
+#[graphql(rename_fields = "snake_case")]
+pub struct GenerationQuotaResponse {
+    pub daily_limit: Option<u32>,
+    pub used_today: i64,
+    pub remaining_today: Option<i64>,
+    pub concurrent_limit: Option<u32>,
+    pub active_generations: i64,
+    pub remaining_concurrent: Option<i64>,
+    /// Start of the next UTC day, when the daily count resets
+    pub resets_at: DateTime<Utc>,
 }
 
 impl From<User> for UserDto {
@@ -29,6 +56,8 @@ impl From<User> for UserDto {
             email: user.email,
             full_name: format!("{} {}", user.first_name, user.last_name),
             created_at: user.created_at,
+            role: user.role,
+            generation_quota: None,
         }
     }
 }
@@ -52,6 +81,9 @@ pub struct QuizResponseDto {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub questions: Option<Vec<QuizQuestion>>,
     pub url: String,
+    pub sources: Vec<QuizSource>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version: Option<i32>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub created_at: Option<DateTime<Utc>>,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -60,6 +92,7 @@ pub struct QuizResponseDto {
 
 impl From<Quiz> for QuizResponseDto {
     fn from(quiz: Quiz) -> Self {
+        let sources = quiz.source_list();
         QuizResponseDto {
             id: quiz.id,
             name: quiz.name,
@@ -73,6 +106,8 @@ impl From<Quiz> for QuizResponseDto {
             status: quiz.status,
             questions: quiz.questions,
             url: quiz.url,
+            sources,
+            version: quiz.version,
             created_at: quiz.created_at,
             modified_at: quiz.modified_at,
         }
@@ -92,6 +127,164 @@ pub struct CreateQuizDraftResponseData {
     pub job_id: String,
 }
 
+#[derive(Debug, Clone, Serialize, SimpleObject)]
+#[graphql(rename_fields = "snake_case")]
+pub struct JobProgressResponse {
+    pub job_id: String,
+    pub quiz_id: Option<String>,
+    pub status: JobStatus,
+    pub current_step_index: usize,
+    pub total_steps: usize,
+    pub current_step_name: Option<String>,
+    pub error_message: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub started_at: Option<DateTime<Utc>>,
+    pub completed_at: Option<DateTime<Utc>>,
+    pub next_attempt_at: Option<DateTime<Utc>>,
+}
+
+impl From<AgentJob> for JobProgressResponse {
+    fn from(job: AgentJob) -> Self {
+        let current_step_name = job.get_current_step().map(|step| step.name.clone());
+        let quiz_id = job.quiz_id().map(str::to_string);
+
+        Self {
+            job_id: job.job_id,
+            quiz_id,
+            status: job.status,
+            current_step_index: job.current_step_index,
+            total_steps: job.steps.len(),
+            current_step_name,
+            error_message: job.error_message,
+            created_at: job.created_at,
+            started_at: job.started_at,
+            completed_at: job.completed_at,
+            next_attempt_at: job.next_attempt_at,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, SimpleObject)]
+#[graphql(rename_fields = "snake_case")]
+pub struct JobStepRunsResponse {
+    pub job_id: String,
+    pub step_runs: Vec<StepRun>,
+}
+
+#[derive(Debug, Clone, Serialize, SimpleObject)]
+#[graphql(rename_fields = "snake_case")]
+pub struct JobScheduleResponse {
+    pub name: String,
+    pub pipeline: String,
+    /// Human-readable trigger, e.g. `every 3600s` or `daily at 03:00 UTC`
+    pub trigger: String,
+    pub metadata: std::collections::HashMap<String, serde_json::Value>,
+    pub enabled: bool,
+    pub next_run_at: DateTime<Utc>,
+    pub last_run_at: Option<DateTime<Utc>>,
+    pub last_job_id: Option<String>,
+}
+
+impl From<JobSchedule> for JobScheduleResponse {
+    fn from(schedule: JobSchedule) -> Self {
+        Self {
+            name: schedule.name,
+            pipeline: schedule.pipeline,
+            trigger: schedule.trigger.to_string(),
+            metadata: schedule.metadata,
+            enabled: schedule.enabled,
+            next_run_at: schedule.next_run_at,
+            last_run_at: schedule.last_run_at,
+            last_job_id: schedule.last_job_id,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, SimpleObject)]
+#[graphql(rename_fields = "snake_case")]
+pub struct UserDailyLlmUsageResponse {
+    pub user_id: String,
+    /// UTC day, `YYYY-MM-DD`
+    pub day: String,
+    pub calls: i64,
+    pub prompt_tokens: i64,
+    pub completion_tokens: i64,
+    pub total_tokens: i64,
+    pub cost_usd: f64,
+}
+
+impl From<UserDailyLlmUsage> for UserDailyLlmUsageResponse {
+    fn from(usage: UserDailyLlmUsage) -> Self {
+        Self {
+            user_id: usage.user_id,
+            day: usage.day,
+            calls: usage.calls,
+            prompt_tokens: usage.prompt_tokens,
+            completion_tokens: usage.completion_tokens,
+            total_tokens: usage.total_tokens,
+            cost_usd: usage.cost_usd,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, SimpleObject)]
+#[graphql(rename_fields = "snake_case")]
+pub struct QuizLlmUsageResponse {
+    pub quiz_id: String,
+    pub user_id: Option<String>,
+    pub jobs: i64,
+    pub calls: i64,
+    pub prompt_tokens: i64,
+    pub completion_tokens: i64,
+    pub total_tokens: i64,
+    pub cost_usd: f64,
+}
+
+impl From<QuizLlmUsage> for QuizLlmUsageResponse {
+    fn from(usage: QuizLlmUsage) -> Self {
+        Self {
+            quiz_id: usage.quiz_id,
+            user_id: usage.user_id,
+            jobs: usage.jobs,
+            calls: usage.calls,
+            prompt_tokens: usage.prompt_tokens,
+            completion_tokens: usage.completion_tokens,
+            total_tokens: usage.total_tokens,
+            cost_usd: usage.cost_usd,
+        }
+    }
+}
+
+/// Per-quiz LLM usage with averages across the quizzes listed
+#[derive(Debug, Clone, Serialize, SimpleObject)]
+#[graphql(rename_fields = "snake_case")]
+pub struct QuizLlmUsageReport {
+    pub quizzes: Vec<QuizLlmUsageResponse>,
+    pub total_tokens: i64,
+    pub total_cost_usd: f64,
+    pub average_tokens_per_quiz: f64,
+    pub average_cost_per_quiz: f64,
+}
+
+impl From<Vec<QuizLlmUsage>> for QuizLlmUsageReport {
+    fn from(quizzes: Vec<QuizLlmUsage>) -> Self {
+        let total_tokens: i64 = quizzes.iter().map(|quiz| quiz.total_tokens).sum();
+        let total_cost_usd: f64 = quizzes.iter().map(|quiz| quiz.cost_usd).sum();
+        let count = quizzes.len().max(1) as f64;
+
+        Self {
+            average_tokens_per_quiz: total_tokens as f64 / count,
+            average_cost_per_quiz: total_cost_usd / count,
+            total_tokens,
+            total_cost_usd,
+            quizzes: quizzes
+                .into_iter()
+                .map(QuizLlmUsageResponse::from)
+                .collect(),
+        }
+    }
+}
+
 pub type CreateUserResponse = ApiResponse<UserDto>;
 pub type UpdateUserResponse = ApiResponse<UserDto>;
 
@@ -225,6 +418,7 @@ impl QuizForTaking {
 pub struct QuizAttemptResponse {
     pub id: String,
     pub quiz_id: String,
+    pub quiz_version: Option<i32>,
     pub points_earned: i16,
     pub total_possible: i16,
     pub required_score: i16,
@@ -238,6 +432,7 @@ impl From<QuizAttempt> for QuizAttemptResponse {
         QuizAttemptResponse {
             id: attempt.id,
             quiz_id: attempt.quiz_id,
+            quiz_version: attempt.quiz_version,
             points_earned: attempt.points_earned,
             required_score: attempt.required_score,
             total_possible: attempt.total_possible,
@@ -295,4 +490,51 @@ mod tests {
         assert_eq!(dto.full_name, "John Doe");
         assert_eq!(dto.username, "johndoe");
     }
+
+    #[test]
+    fn test_quiz_llm_usage_report_averages() {
+        let usage = |quiz_id: &str, total_tokens: i64, cost_usd: f64| QuizLlmUsage {
+            quiz_id: quiz_id.to_string(),
+            user_id: Some("user-1".to_string()),
+            jobs: 1,
+            calls: 3,
+            prompt_tokens: total_tokens / 2,
+            completion_tokens: total_tokens / 2,
+            total_tokens,
+            cost_usd,
+        };
+
+        let report =
+            QuizLlmUsageReport::from(vec![usage("q1", 3000, 0.03), usage("q2", 1000, 0.01)]);
+
+        assert_eq!(report.quizzes.len(), 2);
+        assert_eq!(report.total_tokens, 4000);
+        assert_eq!(report.average_tokens_per_quiz, 2000.0);
+        assert!((report.average_cost_per_quiz - 0.02).abs() < 1e-9);
+
+        let empty = QuizLlmUsageReport::from(Vec::new());
+        assert_eq!(empty.average_tokens_per_quiz, 0.0);
+    }
+
+    #[test]
+    fn test_job_progress_from_agent_job() {
+        use crate::services::agent_orchestrator_service::JobStep;
+
+        let mut job = AgentJob::new(vec![
+            JobStep::new("fetch_webpage"),
+            JobStep::new("summarise"),
+        ]);
+        job.status = JobStatus::Running;
+        job.current_step_index = 1;
+        job.results
+            .insert("quiz_id".to_string(), serde_json::json!("quiz-1"));
+
+        let progress = JobProgressResponse::from(job);
+
+        assert_eq!(progress.quiz_id.as_deref(), Some("quiz-1"));
+        assert_eq!(progress.current_step_index, 1);
+        assert_eq!(progress.total_steps, 2);
+        assert_eq!(progress.current_step_name.as_deref(), Some("summarise"));
+        assert_eq!(progress.status, JobStatus::Running);
+    }
 }
@@ -1,9 +1,16 @@
 use async_trait::async_trait;
-use chrono::Utc;
-use mongodb::{bson::doc, options::IndexOptions, Collection, IndexModel};
-
-use crate::db::Database;
-use crate::services::agent_orchestrator_service::{AgentJob, JobStatus, JobStep};
+use chrono::{DateTime, Utc};
+use mongodb::{
+    bson::{doc, Document},
+    options::{FindOptions, IndexOptions, ReturnDocument, UpdateOptions},
+    Collection, IndexModel,
+};
+
+use crate::db::{stored_at_or_after, stored_before, stored_timestamp, Database};
+use crate::services::agent_orchestrator_service::{
+    retry_backoff, AgentJob, DeadLetterJob, JobStatus, JobStep, StepFailureKind,
+    StepFailureOutcome, StepRun, REQUESTED_BY_KEY,
+};
 
 #[async_trait]
 pub trait AgentJobRepository: Send + Sync {
@@ -11,27 +18,107 @@ pub trait AgentJobRepository: Send + Sync {
     async fn get_job(&self, job_id: &str) -> Result<Option<AgentJob>, String>;
     async fn get_job_status(&self, job_id: &str) -> Result<Option<JobStatus>, String>;
     async fn start_job(&self, job_id: &str) -> Result<(), String>;
+    /// Record the result of the current step and move on to the next one.
+    /// Returns `false`, recording nothing, if `worker_id` no longer holds the
+    /// job lease.
     async fn complete_step(
         &self,
         job_id: &str,
+        worker_id: &str,
         result: Option<serde_json::Value>,
-    ) -> Result<(), String>;
-    async fn fail_step(&self, job_id: &str, error: String) -> Result<(), String>;
+    ) -> Result<bool, String>;
+    /// Record a failed attempt of the current step by the lease holder
+    async fn fail_step(
+        &self,
+        job_id: &str,
+        worker_id: &str,
+        error: String,
+        kind: StepFailureKind,
+    ) -> Result<StepFailureOutcome, String>;
     async fn pause_job(&self, job_id: &str) -> Result<(), String>;
     async fn resume_job(&self, job_id: &str) -> Result<(), String>;
+    async fn cancel_job(&self, job_id: &str) -> Result<(), String>;
+    /// Put a failed or cancelled job back to running at its current step,
+    /// with that step's retry budget reset.
+    async fn retry_job(&self, job_id: &str) -> Result<(), String>;
     async fn list_jobs(&self, status_filter: Option<JobStatus>) -> Result<Vec<AgentJob>, String>;
+    /// Running jobs `worker_id` could acquire the lease of right now
+    async fn list_claimable_jobs(&self, worker_id: &str) -> Result<Vec<AgentJob>, String>;
     async fn delete_job(&self, job_id: &str) -> Result<(), String>;
+    /// Delete jobs that completed before `cutoff`, returning how many were
+    async fn delete_completed_before(&self, cutoff: DateTime<Utc>) -> Result<u64, String>;
     async fn save(&self, job: &AgentJob) -> Result<(), String>;
+    async fn acquire_lease(
+        &self,
+        job_id: &str,
+        worker_id: &str,
+        lease_seconds: u64,
+    ) -> Result<Option<AgentJob>, String>;
+    async fn renew_lease(
+        &self,
+        job_id: &str,
+        worker_id: &str,
+        lease_seconds: u64,
+    ) -> Result<bool, String>;
+    async fn release_lease(&self, job_id: &str, worker_id: &str) -> Result<(), String>;
+    async fn append_step_run(&self, job_id: &str, run: &StepRun) -> Result<(), String>;
+    /// Record a permanently failed job, replacing any earlier entry for it
+    async fn dead_letter_job(&self, entry: &DeadLetterJob) -> Result<(), String>;
+    async fn get_dead_letter(&self, job_id: &str) -> Result<Option<DeadLetterJob>, String>;
+    async fn list_dead_letters(
+        &self,
+        include_replayed: bool,
+        offset: i64,
+        limit: i64,
+    ) -> Result<Vec<DeadLetterJob>, String>;
+    async fn mark_dead_letter_replayed(&self, job_id: &str) -> Result<(), String>;
+    /// Jobs started by `user_id` under `REQUESTED_BY_KEY` at or after `since`
+    async fn count_requested_by_since(
+        &self,
+        user_id: &str,
+        since: DateTime<Utc>,
+    ) -> Result<i64, String>;
+    /// Jobs started by `user_id` under `REQUESTED_BY_KEY` that haven't finished
+    async fn count_active_requested_by(&self, user_id: &str) -> Result<i64, String>;
 }
 
 pub struct MongoAgentJobRepository {
     collection: Collection<AgentJob>,
+    dead_letters: Collection<DeadLetterJob>,
 }
 
 impl MongoAgentJobRepository {
     pub fn new(db: &Database) -> Self {
         let collection = db.get_collection("jobs");
-        Self { collection }
+        let dead_letters = db.get_collection("dead_letter_jobs");
+        Self {
+            collection,
+            dead_letters,
+        }
+    }
+
+    async fn find_jobs(&self, filter: Document) -> Result<Vec<AgentJob>, String> {
+        let mut cursor = self
+            .collection
+            .find(filter)
+            .await
+            .map_err(|e| format!("Failed to list jobs: {}", e))?;
+
+        let mut jobs = Vec::new();
+
+        while cursor
+            .advance()
+            .await
+            .map_err(|e| format!("Failed to iterate jobs: {}", e))?
+        {
+            jobs.push(
+                cursor
+                    .deserialize_current()
+                    .map_err(|e| format!("Failed to deserialize job: {}", e))?,
+            );
+        }
+
+        Ok(jobs)
     }
 
     pub async fn ensure_indexes(&self) -> Result<(), String> {
@@ -59,11 +146,95 @@ impl MongoAgentJobRepository {
             .await
             .map_err(|e| format!("Failed to create status index: {}", e))?;
 
+        let lease_index = IndexModel::builder()
+            .keys(doc! { "status": 1, "lease_expires_at": 1 })
+            .options(
+                IndexOptions::builder()
+                    .name("status_lease_expires_at".to_string())
+                    .build(),
+            )
+            .build();
+
+        self.collection
+            .create_index(lease_index)
+            .await
+            .map_err(|e| format!("Failed to create lease index: {}", e))?;
+
+        let requested_by_index = IndexModel::builder()
+            .keys(doc! { format!("results.{}", REQUESTED_BY_KEY): 1, "created_at": 1 })
+            .options(
+                IndexOptions::builder()
+                    .name("requested_by_created_at".to_string())
+                    .build(),
+            )
+            .build();
+
+        self.collection
+            .create_index(requested_by_index)
+            .await
+            .map_err(|e| format!("Failed to create requested_by index: {}", e))?;
+
+        let dead_letter_index = IndexModel::builder()
+            .keys(doc! { "job_id": 1 })
+            .options(
+                IndexOptions::builder()
+                    .unique(true)
+                    .name("dead_letter_job_id_unique".to_string())
+                    .build(),
+            )
+            .build();
+
+        self.dead_letters
+            .create_index(dead_letter_index)
+            .await
+            .map_err(|e| format!("Failed to create dead letter index: {}", e))?;
+
         log::info!("Successfully created indexes for jobs collection");
         Ok(())
     }
 }
 
+/// Running jobs `worker_id` may claim: nobody holds the lease, the worker
+/// already holds it, or the previous holder stopped heartbeating and it
+/// expired. Jobs backing off after a failure are not claimable until they are due.
+fn claimable_jobs_filter(worker_id: &str, now: DateTime<Utc>) -> Document {
+    doc! {
+        "status": JobStatus::Running.to_string(),
+        "$and": [
+            { "$or": [
+                { "lease_owner": null },
+                { "lease_owner": worker_id },
+                { "lease_expires_at": null },
+                { "lease_expires_at": { "$lte": now.timestamp_millis() } },
+            ] },
+            { "$or": [
+                { "next_attempt_at": null },
+                { "next_attempt_at": { "$lte": now.timestamp_millis() } },
+            ] },
+        ],
+    }
+}
+
+/// The job, as long as `worker_id` may claim it; see [`claimable_jobs_filter`]
+fn claimable_filter(job_id: &str, worker_id: &str, now: DateTime<Utc>) -> Document {
+    let mut filter = doc! { "job_id": job_id };
+    filter.extend(claimable_jobs_filter(worker_id, now));
+    filter
+}
+
+/// Jobs that completed before the whole second of `cutoff`
+fn completed_before_filter(cutoff: DateTime<Utc>) -> Document {
+    doc! {
+        "status": JobStatus::Completed.to_string(),
+        "completed_at": stored_before(cutoff),
+    }
+}
+
+/// The job, as long as `worker_id` still holds its lease
+fn held_lease_filter(job_id: &str, worker_id: &str) -> Document {
+    doc! { "job_id": job_id, "lease_owner": worker_id }
+}
+
 #[async_trait]
 impl AgentJobRepository for MongoAgentJobRepository {
     async fn create_job(&self, steps: Vec<JobStep>) -> Result<String, String> {
@@ -100,7 +271,7 @@ impl AgentJobRepository for MongoAgentJobRepository {
             return Err(format!("Job is already {}", job.status));
         }
 
-        let started_at: String = Utc::now().to_string();
+        let started_at = stored_timestamp(Utc::now());
 
         self.collection
             .update_one(
@@ -121,8 +292,9 @@ impl AgentJobRepository for MongoAgentJobRepository {
     async fn complete_step(
         &self,
         job_id: &str,
+        worker_id: &str,
         result: Option<serde_json::Value>,
-    ) -> Result<(), String> {
+    ) -> Result<bool, String> {
         let job = self
             .get_job(job_id)
             .await?
@@ -157,7 +329,7 @@ impl AgentJobRepository for MongoAgentJobRepository {
             "running"
         };
 
-        let completed_at: String = Utc::now().to_string();
+        let completed_at = stored_timestamp(Utc::now());
 
         let update_doc = if updated_job.is_complete() {
             doc! {
@@ -167,6 +339,7 @@ impl AgentJobRepository for MongoAgentJobRepository {
                         .unwrap_or(mongodb::bson::Bson::Document(doc! {})),
                     "status": new_status,
                     "completed_at": completed_at,
+                    "next_attempt_at": null,
                 }
             }
         } else {
@@ -176,19 +349,27 @@ impl AgentJobRepository for MongoAgentJobRepository {
                     "results": mongodb::bson::to_bson(&updated_job.results)
                         .unwrap_or(mongodb::bson::Bson::Document(doc! {})),
                     "status": new_status,
+                    "next_attempt_at": null,
                 }
             }
         };
 
-        self.collection
-            .update_one(doc! { "job_id": job_id }, update_doc)
+        let result = self
+            .collection
+            .update_one(held_lease_filter(job_id, worker_id), update_doc)
             .await
             .map_err(|e| format!("Failed to update job: {}", e))?;
 
-        Ok(())
+        Ok(result.matched_count > 0)
     }
 
-    async fn fail_step(&self, job_id: &str, error: String) -> Result<(), String> {
+    async fn fail_step(
+        &self,
+        job_id: &str,
+        worker_id: &str,
+        error: String,
+        kind: StepFailureKind,
+    ) -> Result<StepFailureOutcome, String> {
         let mut job = self
             .get_job(job_id)
             .await?
@@ -199,11 +380,17 @@ impl AgentJobRepository for MongoAgentJobRepository {
         }
 
         // Increment retry count for current step
-        if let Some(_current_step) = job.get_current_step() {
-            job.steps[job.current_step_index].retry_count += 1;
+        if let Some(current_step) = job.steps.get_mut(job.current_step_index) {
+            current_step.retry_count += 1;
+            if kind == StepFailureKind::Timeout {
+                current_step.timeout_count += 1;
+            }
         }
 
-        let completed_at: String = Utc::now().to_string();
+        let now = Utc::now();
+        let completed_at = stored_timestamp(now);
+        let failure_kind = kind.to_string();
+        let mut outcome = StepFailureOutcome::JobFailed;
 
         // Check if we should keep retrying or fail the entire job
         let update_doc = if let Some(current_step) = job.get_current_step() {
@@ -213,18 +400,27 @@ impl AgentJobRepository for MongoAgentJobRepository {
                     "$set": {
                         "status": "failed",
                         "error_message": &error,
+                        "last_failure_kind": &failure_kind,
                         "completed_at": &completed_at,
+                        "next_attempt_at": null,
                         "steps": mongodb::bson::to_bson(&job.steps)
                             .unwrap_or(mongodb::bson::Bson::Array(vec![])),
                     }
                 }
             } else {
-                // Keep retrying - stay in running state but increment retry count
+                // Keep retrying - stay in running state but back off before the next attempt
+                let retry_at = now
+                    + chrono::Duration::from_std(retry_backoff(current_step.retry_count))
+                        .unwrap_or_default();
+                outcome = StepFailureOutcome::RetryAt(retry_at);
+
                 doc! {
                     "$set": {
                         "steps": mongodb::bson::to_bson(&job.steps)
                             .unwrap_or(mongodb::bson::Bson::Array(vec![])),
                         "error_message": &error,
+                        "last_failure_kind": &failure_kind,
+                        "next_attempt_at": retry_at.timestamp_millis(),
                     }
                 }
             }
@@ -233,17 +429,22 @@ impl AgentJobRepository for MongoAgentJobRepository {
                 "$set": {
                     "status": "failed",
                     "error_message": &error,
+                    "last_failure_kind": &failure_kind,
                     "completed_at": &completed_at,
                 }
             }
         };
 
-        self.collection
-            .update_one(doc! { "job_id": job_id }, update_doc)
+        let result = self
+            .collection
+            .update_one(held_lease_filter(job_id, worker_id), update_doc)
             .await
             .map_err(|e| format!("Failed to update job: {}", e))?;
 
-        Ok(())
+        if result.matched_count == 0 {
+            return Ok(StepFailureOutcome::LeaseLost);
+        }
+        Ok(outcome)
     }
 
     async fn pause_job(&self, job_id: &str) -> Result<(), String> {
@@ -288,6 +489,74 @@ impl AgentJobRepository for MongoAgentJobRepository {
         Ok(())
     }
 
+    async fn cancel_job(&self, job_id: &str) -> Result<(), String> {
+        let job = self
+            .get_job(job_id)
+            .await?
+            .ok_or_else(|| format!("Job {} not found", job_id))?;
+
+        if !job.status.is_cancellable() {
+            return Err(format!("Job is already {}", job.status));
+        }
+
+        let completed_at = stored_timestamp(Utc::now());
+
+        self.collection
+            .update_one(
+                doc! { "job_id": job_id },
+                doc! {
+                    "$set": {
+                        "status": "cancelled",
+                        "completed_at": completed_at,
+                        "next_attempt_at": null,
+                    }
+                },
+            )
+            .await
+            .map_err(|e| format!("Failed to update job: {}", e))?;
+
+        Ok(())
+    }
+
+    async fn retry_job(&self, job_id: &str) -> Result<(), String> {
+        let mut job = self
+            .get_job(job_id)
+            .await?
+            .ok_or_else(|| format!("Job {} not found", job_id))?;
+
+        if !job.status.is_retryable() {
+            return Err(format!("Job is {} and cannot be retried", job.status));
+        }
+
+        if let Some(current_step) = job.steps.get_mut(job.current_step_index) {
+            current_step.retry_count = 0;
+            current_step.timeout_count = 0;
+        }
+
+        let started_at = stored_timestamp(job.started_at.unwrap_or_else(Utc::now));
+
+        self.collection
+            .update_one(
+                doc! { "job_id": job_id },
+                doc! {
+                    "$set": {
+                        "status": "running",
+                        "started_at": started_at,
+                        "completed_at": null,
+                        "error_message": null,
+                        "last_failure_kind": null,
+                        "next_attempt_at": null,
+                        "steps": mongodb::bson::to_bson(&job.steps)
+                            .unwrap_or(mongodb::bson::Bson::Array(vec![])),
+                    }
+                },
+            )
+            .await
+            .map_err(|e| format!("Failed to update job: {}", e))?;
+
+        Ok(())
+    }
+
     async fn list_jobs(&self, status_filter: Option<JobStatus>) -> Result<Vec<AgentJob>, String> {
         let filter = if let Some(status) = status_filter {
             doc! { "status": status.to_string() }
@@ -295,27 +564,12 @@ impl AgentJobRepository for MongoAgentJobRepository {
             doc! {}
         };
 
-        let mut cursor = self
-            .collection
-            .find(filter)
-            .await
-            .map_err(|e| format!("Failed to list jobs: {}", e))?;
-
-        let mut jobs = Vec::new();
+        self.find_jobs(filter).await
+    }
 
-        while cursor
-            .advance()
+    async fn list_claimable_jobs(&self, worker_id: &str) -> Result<Vec<AgentJob>, String> {
+        self.find_jobs(claimable_jobs_filter(worker_id, Utc::now()))
             .await
-            .map_err(|e| format!("Failed to iterate jobs: {}", e))?
-        {
-            jobs.push(
-                cursor
-                    .deserialize_current()
-                    .map_err(|e| format!("Failed to deserialize job: {}", e))?,
-            );
-        }
-
-        Ok(jobs)
     }
 
     async fn delete_job(&self, job_id: &str) -> Result<(), String> {
@@ -332,6 +586,16 @@ impl AgentJobRepository for MongoAgentJobRepository {
         Ok(())
     }
 
+    async fn delete_completed_before(&self, cutoff: DateTime<Utc>) -> Result<u64, String> {
+        let result = self
+            .collection
+            .delete_many(completed_before_filter(cutoff))
+            .await
+            .map_err(|e| format!("Failed to delete completed jobs: {}", e))?;
+
+        Ok(result.deleted_count)
+    }
+
     async fn save(&self, job: &AgentJob) -> Result<(), String> {
         self.collection
             .replace_one(doc! { "job_id": &job.job_id }, job)
@@ -340,4 +604,344 @@ impl AgentJobRepository for MongoAgentJobRepository {
 
         Ok(())
     }
+
+    async fn acquire_lease(
+        &self,
+        job_id: &str,
+        worker_id: &str,
+        lease_seconds: u64,
+    ) -> Result<Option<AgentJob>, String> {
+        let now = Utc::now();
+        let expires_at = now + chrono::Duration::seconds(lease_seconds as i64);
+
+        self.collection
+            .find_one_and_update(
+                claimable_filter(job_id, worker_id, now),
+                doc! {
+                    "$set": {
+                        "lease_owner": worker_id,
+                        "lease_expires_at": expires_at.timestamp_millis(),
+                        "heartbeat_at": now.timestamp_millis(),
+                    }
+                },
+            )
+            .return_document(ReturnDocument::After)
+            .await
+            .map_err(|e| format!("Failed to acquire job lease: {}", e))
+    }
+
+    async fn renew_lease(
+        &self,
+        job_id: &str,
+        worker_id: &str,
+        lease_seconds: u64,
+    ) -> Result<bool, String> {
+        let now = Utc::now();
+        let expires_at = now + chrono::Duration::seconds(lease_seconds as i64);
+
+        let result = self
+            .collection
+            .update_one(
+                held_lease_filter(job_id, worker_id),
+                doc! {
+                    "$set": {
+                        "lease_expires_at": expires_at.timestamp_millis(),
+                        "heartbeat_at": now.timestamp_millis(),
+                    }
+                },
+            )
+            .await
+            .map_err(|e| format!("Failed to renew job lease: {}", e))?;
+
+        Ok(result.matched_count > 0)
+    }
+
+    async fn release_lease(&self, job_id: &str, worker_id: &str) -> Result<(), String> {
+        self.collection
+            .update_one(
+                held_lease_filter(job_id, worker_id),
+                doc! {
+                    "$set": {
+                        "lease_owner": null,
+                        "lease_expires_at": null,
+                    }
+                },
+            )
+            .await
+            .map_err(|e| format!("Failed to release job lease: {}", e))?;
+
+        Ok(())
+    }
+
+    async fn append_step_run(&self, job_id: &str, run: &StepRun) -> Result<(), String> {
+        let run_bson = mongodb::bson::to_bson(run)
+            .map_err(|e| format!("Failed to serialize step run: {}", e))?;
+
+        self.collection
+            .update_one(
+                doc! { "job_id": job_id },
+                doc! { "$push": { "step_runs": run_bson } },
+            )
+            .await
+            .map_err(|e| format!("Failed to record step run: {}", e))?;
+
+        Ok(())
+    }
+
+    async fn dead_letter_job(&self, entry: &DeadLetterJob) -> Result<(), String> {
+        let mut fields = mongodb::bson::to_document(entry)
+            .map_err(|e| format!("Failed to serialize dead letter entry: {}", e))?;
+        // Keep the replay history if this job has been dead-lettered before
+        fields.remove("replay_count");
+        fields.insert("replayed_at", mongodb::bson::Bson::Null);
+
+        self.dead_letters
+            .update_one(
+                doc! { "job_id": &entry.job_id },
+                doc! {
+                    "$set": fields,
+                    "$setOnInsert": { "replay_count": 0 },
+                },
+            )
+            .with_options(UpdateOptions::builder().upsert(true).build())
+            .await
+            .map_err(|e| format!("Failed to dead-letter job: {}", e))?;
+
+        Ok(())
+    }
+
+    async fn get_dead_letter(&self, job_id: &str) -> Result<Option<DeadLetterJob>, String> {
+        self.dead_letters
+            .find_one(doc! { "job_id": job_id })
+            .await
+            .map_err(|e| format!("Failed to fetch dead letter entry: {}", e))
+    }
+
+    async fn list_dead_letters(
+        &self,
+        include_replayed: bool,
+        offset: i64,
+        limit: i64,
+    ) -> Result<Vec<DeadLetterJob>, String> {
+        let filter = if include_replayed {
+            doc! {}
+        } else {
+            doc! { "replayed_at": null }
+        };
+
+        let find_options = FindOptions::builder()
+            .sort(doc! { "dead_lettered_at": -1 })
+            .skip(offset.max(0) as u64)
+            .limit(limit)
+            .build();
+
+        let mut cursor = self
+            .dead_letters
+            .find(filter)
+            .with_options(find_options)
+            .await
+            .map_err(|e| format!("Failed to list dead letter entries: {}", e))?;
+
+        let mut entries = Vec::new();
+
+        while cursor
+            .advance()
+            .await
+            .map_err(|e| format!("Failed to iterate dead letter entries: {}", e))?
+        {
+            entries.push(
+                cursor
+                    .deserialize_current()
+                    .map_err(|e| format!("Failed to deserialize dead letter entry: {}", e))?,
+            );
+        }
+
+        Ok(entries)
+    }
+
+    async fn mark_dead_letter_replayed(&self, job_id: &str) -> Result<(), String> {
+        let replayed_at: String = Utc::now().to_rfc3339();
+
+        self.dead_letters
+            .update_one(
+                doc! { "job_id": job_id },
+                doc! {
+                    "$set": { "replayed_at": replayed_at },
+                    "$inc": { "replay_count": 1 },
+                },
+            )
+            .await
+            .map_err(|e| format!("Failed to mark dead letter entry replayed: {}", e))?;
+
+        Ok(())
+    }
+
+    async fn count_requested_by_since(
+        &self,
+        user_id: &str,
+        since: DateTime<Utc>,
+    ) -> Result<i64, String> {
+        let count = self
+            .collection
+            .count_documents(doc! {
+                format!("results.{}", REQUESTED_BY_KEY): user_id,
+                "created_at": stored_at_or_after(since),
+            })
+            .await
+            .map_err(|e| format!("Failed to count jobs: {}", e))?;
+        Ok(count as i64)
+    }
+
+    async fn count_active_requested_by(&self, user_id: &str) -> Result<i64, String> {
+        let active = [JobStatus::Pending, JobStatus::Running, JobStatus::Paused]
+            .iter()
+            .map(|status| status.to_string())
+            .collect::<Vec<_>>();
+        let count = self
+            .collection
+            .count_documents(doc! {
+                format!("results.{}", REQUESTED_BY_KEY): user_id,
+                "status": { "$in": active },
+            })
+            .await
+            .map_err(|e| format!("Failed to count jobs: {}", e))?;
+        Ok(count as i64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use mongodb::bson::Bson;
+
+    use super::*;
+
+    /// Evaluate a filter against a stored document, for the subset of the
+    /// query language the filters above use. Missing fields match `null`.
+    fn matches(document: &Document, filter: &Document) -> bool {
+        filter.iter().all(|(key, condition)| match key.as_str() {
+            "$and" => clauses(condition).all(|clause| matches(document, clause)),
+            "$or" => clauses(condition).any(|clause| matches(document, clause)),
+            field => {
+                let value = document.get(field).unwrap_or(&Bson::Null);
+                match condition {
+                    Bson::Document(operators) => operators.iter().all(|(operator, operand)| {
+                        let ordering = match (value, operand) {
+                            (Bson::Int64(value), Bson::Int64(operand)) => value.cmp(operand),
+                            (Bson::String(value), Bson::String(operand)) => value.cmp(operand),
+                            _ => return false,
+                        };
+                        match operator.as_str() {
+                            "$lte" => ordering.is_le(),
+                            "$lt" => ordering.is_lt(),
+                            operator => panic!("unsupported operator {}", operator),
+                        }
+                    }),
+                    condition => value == condition,
+                }
+            }
+        })
+    }
+
+    fn clauses(condition: &Bson) -> impl Iterator<Item = &Document> {
+        condition
+            .as_array()
+            .expect("expected an array of clauses")
+            .iter()
+            .map(|clause| clause.as_document().expect("expected a clause"))
+    }
+
+    fn stored(job: &AgentJob) -> Document {
+        mongodb::bson::to_document(job).expect("job should serialize")
+    }
+
+    fn running_job() -> AgentJob {
+        let mut job = AgentJob::new(vec![JobStep::new("create_quiz_draft")]);
+        job.status = JobStatus::Running;
+        job
+    }
+
+    #[test]
+    fn claimable_filter_matches_free_own_and_expired_leases() {
+        let now = Utc::now();
+        let mut job = running_job();
+        let claimable = |job: &AgentJob, worker: &str| {
+            matches(&stored(job), &claimable_filter(&job.job_id, worker, now))
+        };
+
+        assert!(claimable(&job, "worker-a"));
+
+        job.lease_owner = Some("worker-a".to_string());
+        job.lease_expires_at = Some(now + chrono::Duration::seconds(30));
+        assert!(claimable(&job, "worker-a"));
+        assert!(!claimable(&job, "worker-b"));
+
+        job.lease_expires_at = Some(now - chrono::Duration::seconds(1));
+        assert!(claimable(&job, "worker-b"));
+
+        job.next_attempt_at = Some(now + chrono::Duration::seconds(10));
+        assert!(!claimable(&job, "worker-b"));
+        assert!(!matches(
+            &stored(&job),
+            &claimable_jobs_filter("worker-b", now)
+        ));
+
+        job.next_attempt_at = Some(now);
+        assert!(claimable(&job, "worker-b"));
+        assert!(matches(
+            &stored(&job),
+            &claimable_jobs_filter("worker-b", now)
+        ));
+
+        job.next_attempt_at = None;
+        job.status = JobStatus::Paused;
+        assert!(!claimable(&job, "worker-b"));
+    }
+
+    #[test]
+    fn held_lease_filter_only_matches_the_lease_owner() {
+        let mut job = running_job();
+        job.lease_owner = Some("worker-b".to_string());
+        let held = |job: &AgentJob, worker: &str| {
+            matches(&stored(job), &held_lease_filter(&job.job_id, worker))
+        };
+
+        assert!(held(&job, "worker-b"));
+        assert!(!held(&job, "worker-a"));
+
+        job.lease_owner = None;
+        assert!(!held(&job, "worker-b"));
+    }
+
+    #[test]
+    fn completed_before_filter_keeps_jobs_completed_later_on_the_cutoff_day() {
+        let cutoff = "2026-10-10T12:00:00Z".parse::<DateTime<Utc>>().unwrap();
+        let mut job = running_job();
+        job.status = JobStatus::Completed;
+        let purged = |job: &AgentJob, completed_at: &str| {
+            let completed_at = completed_at.parse::<DateTime<Utc>>().unwrap();
+            // Completion is recorded both by saving the whole job and by raw updates
+            let mut saved = job.clone();
+            saved.completed_at = Some(completed_at);
+            let mut updated = stored(job);
+            updated.insert("completed_at", stored_timestamp(completed_at));
+
+            let filter = completed_before_filter(cutoff);
+            assert_eq!(
+                matches(&stored(&saved), &filter),
+                matches(&updated, &filter)
+            );
+            matches(&updated, &filter)
+        };
+
+        assert!(purged(&job, "2026-10-09T23:59:59.999Z"));
+        assert!(purged(&job, "2026-10-10T03:00:00.123Z"));
+        assert!(purged(&job, "2026-10-10T11:59:59.5Z"));
+        assert!(!purged(&job, "2026-10-10T12:00:00Z"));
+        assert!(!purged(&job, "2026-10-10T12:00:00.250Z"));
+        assert!(!purged(&job, "2026-10-10T18:30:00.123Z"));
+        assert!(!purged(&job, "2026-10-11T00:00:00Z"));
+
+        job.status = JobStatus::Failed;
+        assert!(!purged(&job, "2026-10-09T00:00:00Z"));
+    }
 }
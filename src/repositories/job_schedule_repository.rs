@@ -0,0 +1,280 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use mongodb::{
+    bson::doc,
+    options::{IndexOptions, UpdateOptions},
+    Collection, IndexModel,
+};
+
+use crate::db::Database;
+use crate::services::job_schedule::JobSchedule;
+
+#[async_trait]
+pub trait JobScheduleRepository: Send + Sync {
+    /// Insert a new schedule. Fails if one with the same name already exists.
+    async fn create_schedule(&self, schedule: &JobSchedule) -> Result<(), String>;
+    /// Insert the schedule only if no schedule with its name exists yet, so
+    /// built-in schedules never overwrite changes made at runtime
+    async fn ensure_schedule(&self, schedule: &JobSchedule) -> Result<(), String>;
+    async fn get_schedule(&self, name: &str) -> Result<Option<JobSchedule>, String>;
+    async fn list_schedules(&self) -> Result<Vec<JobSchedule>, String>;
+    async fn list_due_schedules(&self, now: DateTime<Utc>) -> Result<Vec<JobSchedule>, String>;
+    /// Move a due schedule on to its next run. Only succeeds if `next_run_at`
+    /// still matches `expected_run_at`, so a run is claimed by one replica.
+    async fn claim_schedule_run(
+        &self,
+        name: &str,
+        expected_run_at: DateTime<Utc>,
+        next_run_at: DateTime<Utc>,
+    ) -> Result<bool, String>;
+    async fn record_schedule_run(
+        &self,
+        name: &str,
+        job_id: &str,
+        run_at: DateTime<Utc>,
+    ) -> Result<(), String>;
+    async fn set_schedule_enabled(
+        &self,
+        name: &str,
+        enabled: bool,
+        next_run_at: DateTime<Utc>,
+    ) -> Result<(), String>;
+    async fn set_schedule_metadata(
+        &self,
+        name: &str,
+        key: &str,
+        value: serde_json::Value,
+    ) -> Result<(), String>;
+    async fn delete_schedule(&self, name: &str) -> Result<(), String>;
+}
+
+pub struct MongoJobScheduleRepository {
+    collection: Collection<JobSchedule>,
+}
+
+impl MongoJobScheduleRepository {
+    pub fn new(db: &Database) -> Self {
+        let collection = db.get_collection("job_schedules");
+        Self { collection }
+    }
+
+    pub async fn ensure_indexes(&self) -> Result<(), String> {
+        log::info!("Creating indexes for job_schedules collection");
+
+        let name_index = IndexModel::builder()
+            .keys(doc! { "name": 1 })
+            .options(
+                IndexOptions::builder()
+                    .unique(true)
+                    .name("schedule_name_unique".to_string())
+                    .build(),
+            )
+            .build();
+
+        self.collection
+            .create_index(name_index)
+            .await
+            .map_err(|e| format!("Failed to create schedule name index: {}", e))?;
+
+        let due_index = IndexModel::builder()
+            .keys(doc! { "enabled": 1, "next_run_at": 1 })
+            .options(
+                IndexOptions::builder()
+                    .name("enabled_next_run_at".to_string())
+                    .build(),
+            )
+            .build();
+
+        self.collection
+            .create_index(due_index)
+            .await
+            .map_err(|e| format!("Failed to create schedule due index: {}", e))?;
+
+        log::info!("Successfully created indexes for job_schedules collection");
+        Ok(())
+    }
+
+    async fn find_schedules(
+        &self,
+        filter: mongodb::bson::Document,
+    ) -> Result<Vec<JobSchedule>, String> {
+        let mut cursor = self
+            .collection
+            .find(filter)
+            .await
+            .map_err(|e| format!("Failed to list schedules: {}", e))?;
+
+        let mut schedules = Vec::new();
+
+        while cursor
+            .advance()
+            .await
+            .map_err(|e| format!("Failed to iterate schedules: {}", e))?
+        {
+            schedules.push(
+                cursor
+                    .deserialize_current()
+                    .map_err(|e| format!("Failed to deserialize schedule: {}", e))?,
+            );
+        }
+
+        Ok(schedules)
+    }
+}
+
+#[async_trait]
+impl JobScheduleRepository for MongoJobScheduleRepository {
+    async fn create_schedule(&self, schedule: &JobSchedule) -> Result<(), String> {
+        self.collection
+            .insert_one(schedule)
+            .await
+            .map_err(|e| format!("Failed to create schedule: {}", e))?;
+
+        Ok(())
+    }
+
+    async fn ensure_schedule(&self, schedule: &JobSchedule) -> Result<(), String> {
+        let fields = mongodb::bson::to_document(schedule)
+            .map_err(|e| format!("Failed to serialize schedule: {}", e))?;
+
+        self.collection
+            .update_one(
+                doc! { "name": &schedule.name },
+                doc! { "$setOnInsert": fields },
+            )
+            .with_options(UpdateOptions::builder().upsert(true).build())
+            .await
+            .map_err(|e| format!("Failed to ensure schedule: {}", e))?;
+
+        Ok(())
+    }
+
+    async fn get_schedule(&self, name: &str) -> Result<Option<JobSchedule>, String> {
+        self.collection
+            .find_one(doc! { "name": name })
+            .await
+            .map_err(|e| format!("Failed to fetch schedule: {}", e))
+    }
+
+    async fn list_schedules(&self) -> Result<Vec<JobSchedule>, String> {
+        self.find_schedules(doc! {}).await
+    }
+
+    async fn list_due_schedules(&self, now: DateTime<Utc>) -> Result<Vec<JobSchedule>, String> {
+        self.find_schedules(doc! {
+            "enabled": true,
+            "next_run_at": { "$lte": now.timestamp_millis() },
+        })
+        .await
+    }
+
+    async fn claim_schedule_run(
+        &self,
+        name: &str,
+        expected_run_at: DateTime<Utc>,
+        next_run_at: DateTime<Utc>,
+    ) -> Result<bool, String> {
+        let result = self
+            .collection
+            .update_one(
+                doc! {
+                    "name": name,
+                    "enabled": true,
+                    "next_run_at": expected_run_at.timestamp_millis(),
+                },
+                doc! { "$set": { "next_run_at": next_run_at.timestamp_millis() } },
+            )
+            .await
+            .map_err(|e| format!("Failed to claim schedule run: {}", e))?;
+
+        Ok(result.modified_count > 0)
+    }
+
+    async fn record_schedule_run(
+        &self,
+        name: &str,
+        job_id: &str,
+        run_at: DateTime<Utc>,
+    ) -> Result<(), String> {
+        self.collection
+            .update_one(
+                doc! { "name": name },
+                doc! {
+                    "$set": {
+                        "last_run_at": run_at.timestamp_millis(),
+                        "last_job_id": job_id,
+                    }
+                },
+            )
+            .await
+            .map_err(|e| format!("Failed to record schedule run: {}", e))?;
+
+        Ok(())
+    }
+
+    async fn set_schedule_enabled(
+        &self,
+        name: &str,
+        enabled: bool,
+        next_run_at: DateTime<Utc>,
+    ) -> Result<(), String> {
+        let result = self
+            .collection
+            .update_one(
+                doc! { "name": name },
+                doc! {
+                    "$set": {
+                        "enabled": enabled,
+                        "next_run_at": next_run_at.timestamp_millis(),
+                    }
+                },
+            )
+            .await
+            .map_err(|e| format!("Failed to update schedule: {}", e))?;
+
+        if result.matched_count == 0 {
+            return Err(format!("Schedule {} not found", name));
+        }
+
+        Ok(())
+    }
+
+    async fn set_schedule_metadata(
+        &self,
+        name: &str,
+        key: &str,
+        value: serde_json::Value,
+    ) -> Result<(), String> {
+        let value = mongodb::bson::to_bson(&value)
+            .map_err(|e| format!("Failed to serialize schedule metadata: {}", e))?;
+
+        let result = self
+            .collection
+            .update_one(
+                doc! { "name": name },
+                doc! { "$set": { format!("metadata.{}", key): value } },
+            )
+            .await
+            .map_err(|e| format!("Failed to update schedule metadata: {}", e))?;
+
+        if result.matched_count == 0 {
+            return Err(format!("Schedule {} not found", name));
+        }
+
+        Ok(())
+    }
+
+    async fn delete_schedule(&self, name: &str) -> Result<(), String> {
+        let result = self
+            .collection
+            .delete_one(doc! { "name": name })
+            .await
+            .map_err(|e| format!("Failed to delete schedule: {}", e))?;
+
+        if result.deleted_count == 0 {
+            return Err(format!("Schedule {} not found", name));
+        }
+
+        Ok(())
+    }
+}
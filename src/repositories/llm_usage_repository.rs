@@ -0,0 +1,179 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use futures::TryStreamExt;
+use mongodb::{
+    bson::{doc, Document},
+    options::IndexOptions,
+    Collection, IndexModel,
+};
+use serde::de::DeserializeOwned;
+
+use crate::{
+    db::{stored_at_or_after, Database},
+    errors::{AppError, AppResult},
+    models::domain::llm_usage::{LlmUsageRecord, QuizLlmUsage, UserDailyLlmUsage},
+};
+
+#[async_trait]
+pub trait LlmUsageRepository: Send + Sync {
+    async fn record(&self, record: &LlmUsageRecord) -> AppResult<()>;
+    /// Totals per user per UTC day for calls made at or after `since`
+    async fn usage_by_user_per_day(
+        &self,
+        since: DateTime<Utc>,
+        user_id: Option<&str>,
+    ) -> AppResult<Vec<UserDailyLlmUsage>>;
+    /// Totals per quiz for calls made at or after `since`, most expensive first
+    async fn usage_by_quiz(
+        &self,
+        since: DateTime<Utc>,
+        quiz_id: Option<&str>,
+    ) -> AppResult<Vec<QuizLlmUsage>>;
+}
+
+pub struct MongoLlmUsageRepository {
+    collection: Collection<LlmUsageRecord>,
+}
+
+impl MongoLlmUsageRepository {
+    pub fn new(db: &Database) -> Self {
+        let collection = db.get_collection("llm_usage");
+        Self { collection }
+    }
+
+    pub async fn ensure_indexes(&self) -> AppResult<()> {
+        log::info!("Creating indexes for llm_usage collection");
+
+        let user_index = IndexModel::builder()
+            .keys(doc! { "user_id": 1, "created_at": 1 })
+            .options(
+                IndexOptions::builder()
+                    .name("user_created_at".to_string())
+                    .build(),
+            )
+            .build();
+
+        let quiz_index = IndexModel::builder()
+            .keys(doc! { "quiz_id": 1, "created_at": 1 })
+            .options(
+                IndexOptions::builder()
+                    .name("quiz_created_at".to_string())
+                    .build(),
+            )
+            .build();
+
+        self.collection.create_index(user_index).await?;
+        self.collection.create_index(quiz_index).await?;
+
+        log::info!("Successfully created indexes for llm_usage collection");
+        Ok(())
+    }
+
+    fn since_filter(since: DateTime<Utc>) -> Document {
+        doc! { "created_at": stored_at_or_after(since) }
+    }
+
+    async fn aggregate<T: DeserializeOwned>(&self, pipeline: Vec<Document>) -> AppResult<Vec<T>> {
+        let documents: Vec<Document> = self
+            .collection
+            .aggregate(pipeline)
+            .await?
+            .try_collect()
+            .await?;
+
+        documents
+            .into_iter()
+            .map(|document| {
+                mongodb::bson::from_document(document).map_err(|e| {
+                    AppError::DatabaseError(format!("Failed to read LLM usage totals: {}", e))
+                })
+            })
+            .collect()
+    }
+}
+
+#[async_trait]
+impl LlmUsageRepository for MongoLlmUsageRepository {
+    async fn record(&self, record: &LlmUsageRecord) -> AppResult<()> {
+        self.collection.insert_one(record).await?;
+        Ok(())
+    }
+
+    async fn usage_by_user_per_day(
+        &self,
+        since: DateTime<Utc>,
+        user_id: Option<&str>,
+    ) -> AppResult<Vec<UserDailyLlmUsage>> {
+        let mut filter = Self::since_filter(since);
+        match user_id {
+            Some(user_id) => filter.insert("user_id", user_id),
+            None => filter.insert("user_id", doc! { "$ne": null }),
+        };
+
+        self.aggregate(vec![
+            doc! { "$match": filter },
+            doc! { "$group": {
+                "_id": {
+                    "user_id": "$user_id",
+                    "day": { "$substrBytes": ["$created_at", 0, 10] },
+                },
+                "calls": { "$sum": 1 },
+                "prompt_tokens": { "$sum": "$prompt_tokens" },
+                "completion_tokens": { "$sum": "$completion_tokens" },
+                "total_tokens": { "$sum": "$total_tokens" },
+                "cost_usd": { "$sum": "$cost_usd" },
+            } },
+            doc! { "$project": {
+                "_id": 0,
+                "user_id": "$_id.user_id",
+                "day": "$_id.day",
+                "calls": 1,
+                "prompt_tokens": 1,
+                "completion_tokens": 1,
+                "total_tokens": 1,
+                "cost_usd": 1,
+            } },
+            doc! { "$sort": { "day": -1, "total_tokens": -1 } },
+        ])
+        .await
+    }
+
+    async fn usage_by_quiz(
+        &self,
+        since: DateTime<Utc>,
+        quiz_id: Option<&str>,
+    ) -> AppResult<Vec<QuizLlmUsage>> {
+        let mut filter = Self::since_filter(since);
+        match quiz_id {
+            Some(quiz_id) => filter.insert("quiz_id", quiz_id),
+            None => filter.insert("quiz_id", doc! { "$ne": null }),
+        };
+
+        self.aggregate(vec![
+            doc! { "$match": filter },
+            doc! { "$group": {
+                "_id": "$quiz_id",
+                "user_id": { "$first": "$user_id" },
+                "job_ids": { "$addToSet": "$job_id" },
+                "calls": { "$sum": 1 },
+                "prompt_tokens": { "$sum": "$prompt_tokens" },
+                "completion_tokens": { "$sum": "$completion_tokens" },
+                "total_tokens": { "$sum": "$total_tokens" },
+                "cost_usd": { "$sum": "$cost_usd" },
+            } },
+            doc! { "$project": {
+                "_id": 0,
+                "quiz_id": "$_id",
+                "user_id": 1,
+                "jobs": { "$size": "$job_ids" },
+                "calls": 1,
+                "prompt_tokens": 1,
+                "completion_tokens": 1,
+                "total_tokens": 1,
+                "cost_usd": 1,
+            } },
+            doc! { "$sort": { "cost_usd": -1, "total_tokens": -1 } },
+        ])
+        .await
+    }
+}
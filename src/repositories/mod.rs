@@ -1,13 +1,19 @@
 pub mod agent_job_repository;
+pub mod job_schedule_repository;
+pub mod llm_usage_repository;
 pub mod quiz_attempt_repository;
 pub mod quiz_repository;
+pub mod quiz_version_repository;
 pub mod refresh_token_repository;
 pub mod summary_document_repository;
 pub mod user_repository;
 
 pub use agent_job_repository::{AgentJobRepository, MongoAgentJobRepository};
+pub use job_schedule_repository::{JobScheduleRepository, MongoJobScheduleRepository};
+pub use llm_usage_repository::{LlmUsageRepository, MongoLlmUsageRepository};
 pub use quiz_attempt_repository::{MongoQuizAttemptRepository, QuizAttemptRepository};
 pub use quiz_repository::{MongoQuizRepository, QuizRepository};
+pub use quiz_version_repository::{MongoQuizVersionRepository, QuizVersionRepository};
 pub use refresh_token_repository::{MongoRefreshTokenRepository, RefreshTokenRepository};
 pub use summary_document_repository::{MongoSummaryDocumentRepository, SummaryDocumentRepository};
 pub use user_repository::{MongoUserRepository, UserRepository};
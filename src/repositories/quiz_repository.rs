@@ -1,7 +1,16 @@
 use async_trait::async_trait;
-use mongodb::{bson::doc, options::IndexOptions, Collection, IndexModel};
-
-use crate::{db::Database, errors::AppResult, models::domain::Quiz};
+use chrono::{DateTime, Utc};
+use mongodb::{
+    bson::{doc, to_bson},
+    options::IndexOptions,
+    Collection, IndexModel,
+};
+
+use crate::{
+    db::{stored_at_or_after, Database},
+    errors::AppResult,
+    models::domain::{quiz::QuizStatus, Quiz},
+};
 
 #[async_trait]
 pub trait QuizRepository: Send + Sync {
@@ -16,6 +25,17 @@ pub trait QuizRepository: Send + Sync {
     async fn get_by_status_by_id(&self, id: &str, status: &str) -> AppResult<Option<Quiz>>;
     async fn create_quiz_draft(&self, quiz: Quiz) -> AppResult<Quiz>;
     async fn update(&self, quiz: Quiz) -> AppResult<Quiz>;
+    /// Number of quizzes the user created at or after `since`
+    async fn count_created_by_user_since(
+        &self,
+        user_id: &str,
+        since: DateTime<Utc>,
+    ) -> AppResult<i64>;
+    async fn count_by_user_with_status(
+        &self,
+        user_id: &str,
+        statuses: &[QuizStatus],
+    ) -> AppResult<i64>;
 }
 
 pub struct MongoQuizRepository {
@@ -41,7 +61,17 @@ impl MongoQuizRepository {
             )
             .build();
 
+        let user_index = IndexModel::builder()
+            .keys(doc! { "created_by_user_id": 1, "created_at": 1 })
+            .options(
+                IndexOptions::builder()
+                    .name("created_by_user_created_at".to_string())
+                    .build(),
+            )
+            .build();
+
         self.collection.create_index(id_index).await?;
+        self.collection.create_index(user_index).await?;
 
         log::info!("Successfully created indexes for quizzes collection");
         Ok(())
@@ -125,4 +155,38 @@ impl QuizRepository for MongoQuizRepository {
             .await?;
         Ok(quiz)
     }
+
+    async fn count_created_by_user_since(
+        &self,
+        user_id: &str,
+        since: DateTime<Utc>,
+    ) -> AppResult<i64> {
+        let count = self
+            .collection
+            .count_documents(doc! {
+                "created_by_user_id": user_id,
+                "created_at": stored_at_or_after(since),
+            })
+            .await?;
+        Ok(count as i64)
+    }
+
+    async fn count_by_user_with_status(
+        &self,
+        user_id: &str,
+        statuses: &[QuizStatus],
+    ) -> AppResult<i64> {
+        let statuses = statuses
+            .iter()
+            .map(to_bson)
+            .collect::<Result<Vec<_>, _>>()?;
+        let count = self
+            .collection
+            .count_documents(doc! {
+                "created_by_user_id": user_id,
+                "status": { "$in": statuses },
+            })
+            .await?;
+        Ok(count as i64)
+    }
 }
@@ -0,0 +1,104 @@
+use async_trait::async_trait;
+use futures::TryStreamExt;
+use mongodb::{bson::doc, options::IndexOptions, Collection, IndexModel};
+
+use crate::{
+    db::{is_duplicate_key, Database},
+    errors::{AppError, AppResult},
+    models::domain::QuizVersion,
+};
+
+/// Published quiz versions. Versions are never changed once stored.
+#[async_trait]
+pub trait QuizVersionRepository: Send + Sync {
+    /// Fails with `AlreadyExists` if the quiz already has a version with
+    /// the same number
+    async fn create(&self, version: QuizVersion) -> AppResult<QuizVersion>;
+    async fn find_version(&self, quiz_id: &str, version: i32) -> AppResult<Option<QuizVersion>>;
+    async fn find_latest(&self, quiz_id: &str) -> AppResult<Option<QuizVersion>>;
+    /// Versions of a quiz, newest first
+    async fn list_by_quiz(&self, quiz_id: &str) -> AppResult<Vec<QuizVersion>>;
+}
+
+pub struct MongoQuizVersionRepository {
+    collection: Collection<QuizVersion>,
+}
+
+impl MongoQuizVersionRepository {
+    pub fn new(db: &Database) -> Self {
+        let collection = db.get_collection("quiz_versions");
+        Self { collection }
+    }
+
+    pub async fn ensure_indexes(&self) -> AppResult<()> {
+        log::info!("Creating indexes for quiz_versions collection");
+
+        let id_index = IndexModel::builder()
+            .keys(doc! { "id": 1 })
+            .options(
+                IndexOptions::builder()
+                    .unique(true)
+                    .name("id_unique".to_string())
+                    .build(),
+            )
+            .build();
+
+        let version_index = IndexModel::builder()
+            .keys(doc! { "quiz_id": 1, "version": -1 })
+            .options(
+                IndexOptions::builder()
+                    .unique(true)
+                    .name("quiz_id_version_unique".to_string())
+                    .build(),
+            )
+            .build();
+
+        self.collection.create_index(id_index).await?;
+        self.collection.create_index(version_index).await?;
+
+        log::info!("Successfully created indexes for quiz_versions collection");
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl QuizVersionRepository for MongoQuizVersionRepository {
+    async fn create(&self, version: QuizVersion) -> AppResult<QuizVersion> {
+        match self.collection.insert_one(&version).await {
+            Ok(_) => Ok(version),
+            Err(e) if is_duplicate_key(&e) => Err(AppError::AlreadyExists(format!(
+                "Version {} of quiz '{}'",
+                version.version, version.quiz_id
+            ))),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    async fn find_version(&self, quiz_id: &str, version: i32) -> AppResult<Option<QuizVersion>> {
+        let version = self
+            .collection
+            .find_one(doc! { "quiz_id": quiz_id, "version": version })
+            .await?;
+        Ok(version)
+    }
+
+    async fn find_latest(&self, quiz_id: &str) -> AppResult<Option<QuizVersion>> {
+        let version = self
+            .collection
+            .find_one(doc! { "quiz_id": quiz_id })
+            .sort(doc! { "version": -1 })
+            .await?;
+        Ok(version)
+    }
+
+    async fn list_by_quiz(&self, quiz_id: &str) -> AppResult<Vec<QuizVersion>> {
+        let versions = self
+            .collection
+            .find(doc! { "quiz_id": quiz_id })
+            .sort(doc! { "version": -1 })
+            .await?
+            .try_collect()
+            .await?;
+        Ok(versions)
+    }
+}
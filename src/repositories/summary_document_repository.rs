@@ -1,4 +1,5 @@
 use async_trait::async_trait;
+use futures::TryStreamExt;
 use mongodb::{bson::doc, options::IndexOptions, Collection, IndexModel};
 
 use crate::{db::Database, errors::AppResult, models::domain::summary_document::SummaryDocument};
@@ -6,7 +7,9 @@ use crate::{db::Database, errors::AppResult, models::domain::summary_document::S
 #[async_trait]
 pub trait SummaryDocumentRepository: Send + Sync {
     async fn find_by_id(&self, id: &str) -> AppResult<Option<SummaryDocument>>;
+    async fn find_by_quiz_id(&self, quiz_id: &str) -> AppResult<Vec<SummaryDocument>>;
     async fn create(&self, document: SummaryDocument) -> AppResult<SummaryDocument>;
+    async fn update(&self, document: SummaryDocument) -> AppResult<SummaryDocument>;
 }
 
 pub struct MongoSummaryDocumentRepository {
@@ -32,7 +35,13 @@ impl MongoSummaryDocumentRepository {
             )
             .build();
 
+        let quiz_index = IndexModel::builder()
+            .keys(doc! { "quiz_id": 1 })
+            .options(IndexOptions::builder().name("quiz_id".to_string()).build())
+            .build();
+
         self.collection.create_index(id_index).await?;
+        self.collection.create_index(quiz_index).await?;
 
         log::info!("Successfully created indexes for summary_documents collection");
         Ok(())
@@ -46,8 +55,25 @@ impl SummaryDocumentRepository for MongoSummaryDocumentRepository {
         Ok(document)
     }
 
+    async fn find_by_quiz_id(&self, quiz_id: &str) -> AppResult<Vec<SummaryDocument>> {
+        let documents = self
+            .collection
+            .find(doc! { "quiz_id": quiz_id })
+            .await?
+            .try_collect()
+            .await?;
+        Ok(documents)
+    }
+
     async fn create(&self, document: SummaryDocument) -> AppResult<SummaryDocument> {
         self.collection.insert_one(&document).await?;
         Ok(document)
     }
+
+    async fn update(&self, document: SummaryDocument) -> AppResult<SummaryDocument> {
+        self.collection
+            .replace_one(doc! { "id": &document.id }, &document)
+            .await?;
+        Ok(document)
+    }
 }
@@ -1,14 +1,19 @@
+use async_graphql::{Enum, SimpleObject};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
+use std::time::Duration;
 use tokio::sync::RwLock;
 use uuid::Uuid;
 
 use crate::app_state::AppState;
-use crate::repositories::AgentJobRepository;
-use crate::services::step_executor::{JobStepType, StepHandler};
+use crate::repositories::{AgentJobRepository, JobScheduleRepository};
+use crate::services::job_dispatcher::JobDispatcher;
+use crate::services::job_events::{JobEventBus, JobProgressEvent, JobProgressEventKind};
+use crate::services::job_schedule::{JobSchedule, SCHEDULE_NAME_KEY};
+use crate::services::step_registry::StepRegistry;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Enum)]
 #[serde(rename_all = "lowercase")]
 pub enum JobStatus {
     Pending,
@@ -16,6 +21,22 @@ pub enum JobStatus {
     Completed,
     Failed,
     Paused,
+    Cancelled,
+}
+
+impl JobStatus {
+    /// Whether the job can still be cancelled
+    pub fn is_cancellable(&self) -> bool {
+        matches!(
+            self,
+            JobStatus::Pending | JobStatus::Running | JobStatus::Paused
+        )
+    }
+
+    /// Whether the job stopped before finishing and can be retried
+    pub fn is_retryable(&self) -> bool {
+        matches!(self, JobStatus::Failed | JobStatus::Cancelled)
+    }
 }
 
 impl std::fmt::Display for JobStatus {
@@ -26,10 +47,61 @@ impl std::fmt::Display for JobStatus {
             JobStatus::Completed => write!(f, "completed"),
             JobStatus::Failed => write!(f, "failed"),
             JobStatus::Paused => write!(f, "paused"),
+            JobStatus::Cancelled => write!(f, "cancelled"),
         }
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Enum)]
+#[serde(rename_all = "lowercase")]
+pub enum StepFailureKind {
+    /// The step handler returned an error
+    Error,
+    /// The step exceeded its `timeout_seconds`
+    Timeout,
+}
+
+impl std::fmt::Display for StepFailureKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StepFailureKind::Error => write!(f, "error"),
+            StepFailureKind::Timeout => write!(f, "timeout"),
+        }
+    }
+}
+
+/// Job results key recording the user who started a job that counts towards
+/// their generation quota
+pub const REQUESTED_BY_KEY: &str = "requested_by";
+
+/// What became of a failed step attempt once it was recorded
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StepFailureOutcome {
+    /// The step will be retried at this time
+    RetryAt(DateTime<Utc>),
+    /// The step ran out of retries and the job has failed
+    JobFailed,
+    /// The worker no longer held the job lease, so nothing was recorded
+    LeaseLost,
+}
+
+const RETRY_BACKOFF_BASE_SECONDS: u64 = 5;
+const RETRY_BACKOFF_MAX_SECONDS: u64 = 300;
+
+/// Delay before the given retry attempt (1-based): exponential growth from
+/// `RETRY_BACKOFF_BASE_SECONDS`, capped at `RETRY_BACKOFF_MAX_SECONDS`, with
+/// the upper half randomised so failing jobs don't retry in lockstep.
+pub fn retry_backoff(retry_count: u32) -> Duration {
+    let exponent = retry_count.saturating_sub(1).min(16);
+    let ceiling = RETRY_BACKOFF_BASE_SECONDS
+        .saturating_mul(1 << exponent)
+        .min(RETRY_BACKOFF_MAX_SECONDS);
+    let ceiling_ms = ceiling * 1000;
+    let jitter_ms = rand::random_range(0..=ceiling_ms / 2);
+
+    Duration::from_millis(ceiling_ms / 2 + jitter_ms)
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct JobStep {
     pub id: String,
@@ -38,6 +110,9 @@ pub struct JobStep {
     pub retry_count: u32,
     pub max_retries: u32,
     pub timeout_seconds: Option<u64>,
+    /// How many of the failed attempts were timeouts
+    #[serde(default)]
+    pub timeout_count: u32,
 }
 
 impl JobStep {
@@ -49,6 +124,7 @@ impl JobStep {
             retry_count: 0,
             max_retries: 3,
             timeout_seconds: None,
+            timeout_count: 0,
         }
     }
 
@@ -68,6 +144,72 @@ impl JobStep {
     }
 }
 
+const STEP_RUN_RESULT_PREVIEW_LENGTH: usize = 2000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Enum)]
+#[serde(rename_all = "lowercase")]
+pub enum StepRunOutcome {
+    Succeeded,
+    Failed,
+    TimedOut,
+}
+
+/// One execution attempt of a job step, appended to `AgentJob::step_runs`
+#[derive(Debug, Clone, Serialize, Deserialize, SimpleObject)]
+#[graphql(rename_fields = "snake_case")]
+pub struct StepRun {
+    pub step_id: String,
+    pub step_name: String,
+    pub attempt: u32,
+    pub started_at: DateTime<Utc>,
+    pub ended_at: DateTime<Utc>,
+    pub duration_ms: i64,
+    pub outcome: StepRunOutcome,
+    pub error: Option<String>,
+    /// Serialized step result, truncated to `STEP_RUN_RESULT_PREVIEW_LENGTH` characters
+    pub result_preview: Option<String>,
+}
+
+impl StepRun {
+    pub fn new(
+        step: &JobStep,
+        started_at: DateTime<Utc>,
+        ended_at: DateTime<Utc>,
+        outcome: StepRunOutcome,
+    ) -> Self {
+        Self {
+            step_id: step.id.clone(),
+            step_name: step.name.clone(),
+            attempt: step.retry_count + 1,
+            started_at,
+            ended_at,
+            duration_ms: (ended_at - started_at).num_milliseconds(),
+            outcome,
+            error: None,
+            result_preview: None,
+        }
+    }
+
+    pub fn with_error(mut self, error: impl Into<String>) -> Self {
+        self.error = Some(error.into());
+        self
+    }
+
+    pub fn with_result(mut self, result: &serde_json::Value) -> Self {
+        let serialized = result.to_string();
+        self.result_preview = Some(
+            match serialized
+                .char_indices()
+                .nth(STEP_RUN_RESULT_PREVIEW_LENGTH)
+            {
+                Some((cut, _)) => format!("{}…", &serialized[..cut]),
+                None => serialized,
+            },
+        );
+        self
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AgentJob {
     #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
@@ -82,6 +224,22 @@ pub struct AgentJob {
     pub started_at: Option<DateTime<Utc>>,
     pub completed_at: Option<DateTime<Utc>>,
     pub retries_remaining: u32,
+    /// Worker currently holding the execution lease, if any
+    #[serde(default)]
+    pub lease_owner: Option<String>,
+    /// Stored as epoch milliseconds so lease expiry can be compared in queries
+    #[serde(default, with = "chrono::serde::ts_milliseconds_option")]
+    pub lease_expires_at: Option<DateTime<Utc>>,
+    #[serde(default, with = "chrono::serde::ts_milliseconds_option")]
+    pub heartbeat_at: Option<DateTime<Utc>>,
+    /// Earliest time the current step may be retried after a failure
+    #[serde(default, with = "chrono::serde::ts_milliseconds_option")]
+    pub next_attempt_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub last_failure_kind: Option<StepFailureKind>,
+    /// Append-only history of every step attempt
+    #[serde(default)]
+    pub step_runs: Vec<StepRun>,
 }
 
 impl AgentJob {
@@ -99,6 +257,12 @@ impl AgentJob {
             started_at: None,
             completed_at: None,
             retries_remaining: 3,
+            lease_owner: None,
+            lease_expires_at: None,
+            heartbeat_at: None,
+            next_attempt_at: None,
+            last_failure_kind: None,
+            step_runs: Vec::new(),
         }
     }
 
@@ -109,12 +273,117 @@ impl AgentJob {
     pub fn is_complete(&self) -> bool {
         self.current_step_index >= self.steps.len()
     }
+
+    /// Id of the quiz this job generates, recorded in its results metadata
+    pub fn quiz_id(&self) -> Option<&str> {
+        self.results.get("quiz_id").and_then(|v| v.as_str())
+    }
+}
+
+/// Snapshot of a job that exhausted its retries, kept for inspection and replay
+#[derive(Debug, Clone, Serialize, Deserialize, SimpleObject)]
+#[graphql(rename_fields = "snake_case")]
+pub struct DeadLetterJob {
+    pub job_id: String,
+    pub quiz_id: Option<String>,
+    pub failed_step_id: Option<String>,
+    pub failed_step_name: Option<String>,
+    pub failed_step_index: usize,
+    pub error: String,
+    pub failure_kind: StepFailureKind,
+    /// Attempts made at the failed step before giving up
+    pub attempts: u32,
+    pub results: std::collections::HashMap<String, serde_json::Value>,
+    pub dead_lettered_at: DateTime<Utc>,
+    /// Set when the job is replayed; cleared if it fails permanently again
+    #[serde(default)]
+    pub replayed_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub replay_count: u32,
 }
 
-/// Orchestrator service for managing agent jobs with background worker
+impl DeadLetterJob {
+    /// Build an entry from the job as it was before its final failed attempt
+    pub fn from_failed_job(
+        job: &AgentJob,
+        error: impl Into<String>,
+        kind: StepFailureKind,
+    ) -> Self {
+        let step = job.get_current_step();
+        Self {
+            job_id: job.job_id.clone(),
+            quiz_id: job.quiz_id().map(str::to_string),
+            failed_step_id: step.map(|s| s.id.clone()),
+            failed_step_name: step.map(|s| s.name.clone()),
+            failed_step_index: job.current_step_index,
+            error: error.into(),
+            failure_kind: kind,
+            attempts: step.map(|s| s.retry_count + 1).unwrap_or(0),
+            results: job.results.clone(),
+            dead_lettered_at: Utc::now(),
+            replayed_at: None,
+            replay_count: 0,
+        }
+    }
+}
+
+const DEFAULT_WORKER_CONCURRENCY: usize = 4;
+const DEFAULT_SWEEP_INTERVAL_SECONDS: u64 = 30;
+const DEFAULT_LEASE_SECONDS: u64 = 60;
+const DEFAULT_SCHEDULE_POLL_SECONDS: u64 = 30;
+const DEFAULT_SHUTDOWN_TIMEOUT_SECONDS: u64 = 30;
+
+/// Outcome of processing a single step, used to decide whether the job goes
+/// straight back onto the dispatch queue.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum StepOutcome {
+    /// The step completed and the job has more steps to run
+    Continue,
+    /// The step failed and will be retried after the given delay
+    RetryAfter(Duration),
+    /// Something went wrong outside the step; the fallback sweep will retry it
+    RetryLater,
+    /// The job is finished, failed, leased elsewhere or no longer running
+    Done,
+}
+
+/// Aborts the wrapped task when dropped
+struct AbortOnDrop(tokio::task::JoinHandle<()>);
+
+impl Drop for AbortOnDrop {
+    fn drop(&mut self) {
+        self.0.abort();
+    }
+}
+
+/// Everything a step executor needs, cloned into each executor task
+#[derive(Clone)]
+struct ExecutorContext {
+    repository: Arc<dyn AgentJobRepository>,
+    registry: Arc<StepRegistry>,
+    events: Arc<JobEventBus>,
+    app_state: Arc<RwLock<Option<Arc<AppState>>>>,
+    worker_id: String,
+    lease_seconds: u64,
+}
+
+/// Orchestrator service for managing agent jobs with background workers
 pub struct AgentOrchestrator {
     repository: Arc<dyn AgentJobRepository>,
-    worker_handle: Arc<RwLock<Option<tokio::task::JoinHandle<()>>>>,
+    dispatcher: Arc<JobDispatcher>,
+    registry: Arc<StepRegistry>,
+    events: Arc<JobEventBus>,
+    worker_id: String,
+    worker_concurrency: usize,
+    sweep_interval: Duration,
+    lease_seconds: u64,
+    schedules: Option<Arc<dyn JobScheduleRepository>>,
+    schedule_poll_interval: Duration,
+    shutdown_timeout: Duration,
+    /// Step executors, drained on shutdown
+    worker_handles: Arc<RwLock<Vec<tokio::task::JoinHandle<()>>>>,
+    /// Sweep and scheduler loops, aborted on shutdown
+    background_handles: Arc<RwLock<Vec<tokio::task::JoinHandle<()>>>>,
     app_state: Arc<RwLock<Option<Arc<AppState>>>>,
 }
 
@@ -123,11 +392,79 @@ impl AgentOrchestrator {
     pub fn new(repository: Arc<dyn AgentJobRepository>) -> Self {
         Self {
             repository,
-            worker_handle: Arc::new(RwLock::new(None)),
+            dispatcher: Arc::new(JobDispatcher::new()),
+            registry: Arc::new(StepRegistry::new()),
+            events: Arc::new(JobEventBus::new()),
+            worker_id: Uuid::new_v4().to_string(),
+            worker_concurrency: DEFAULT_WORKER_CONCURRENCY,
+            sweep_interval: Duration::from_secs(DEFAULT_SWEEP_INTERVAL_SECONDS),
+            lease_seconds: DEFAULT_LEASE_SECONDS,
+            schedules: None,
+            schedule_poll_interval: Duration::from_secs(DEFAULT_SCHEDULE_POLL_SECONDS),
+            shutdown_timeout: Duration::from_secs(DEFAULT_SHUTDOWN_TIMEOUT_SECONDS),
+            worker_handles: Arc::new(RwLock::new(Vec::new())),
+            background_handles: Arc::new(RwLock::new(Vec::new())),
             app_state: Arc::new(RwLock::new(None)),
         }
     }
 
+    /// Step executors and pipelines this orchestrator can run
+    pub fn with_registry(mut self, registry: Arc<StepRegistry>) -> Self {
+        self.registry = registry;
+        self
+    }
+
+    /// Identifier recorded as the lease owner on jobs this instance executes
+    pub fn with_worker_id(mut self, worker_id: impl Into<String>) -> Self {
+        self.worker_id = worker_id.into();
+        self
+    }
+
+    /// Number of step executors pulling work concurrently
+    pub fn with_concurrency(mut self, worker_concurrency: usize) -> Self {
+        self.worker_concurrency = worker_concurrency.max(1);
+        self
+    }
+
+    /// Interval of the fallback sweep that re-queues running jobs
+    pub fn with_sweep_interval(mut self, seconds: u64) -> Self {
+        self.sweep_interval = Duration::from_secs(seconds.max(1));
+        self
+    }
+
+    /// How long a job lease lasts without a heartbeat before other workers may reclaim it
+    pub fn with_lease_duration(mut self, seconds: u64) -> Self {
+        self.lease_seconds = seconds.max(3);
+        self
+    }
+
+    /// Persisted schedules the worker polls for due runs
+    pub fn with_schedules(mut self, schedules: Arc<dyn JobScheduleRepository>) -> Self {
+        self.schedules = Some(schedules);
+        self
+    }
+
+    /// How often the worker checks for schedules that are due
+    pub fn with_schedule_poll_interval(mut self, seconds: u64) -> Self {
+        self.schedule_poll_interval = Duration::from_secs(seconds.max(1));
+        self
+    }
+
+    /// How long `stop_worker` waits for running steps before abandoning them
+    pub fn with_shutdown_timeout(mut self, seconds: u64) -> Self {
+        self.shutdown_timeout = Duration::from_secs(seconds);
+        self
+    }
+
+    pub fn worker_id(&self) -> &str {
+        &self.worker_id
+    }
+
+    /// Progress events published as steps complete or fail
+    pub fn events(&self) -> &Arc<JobEventBus> {
+        &self.events
+    }
+
     /// Set the app state for the orchestrator (called during app initialization)
     pub async fn set_app_state(&self, app_state: Arc<AppState>) {
         let mut state = self.app_state.write().await;
@@ -138,6 +475,12 @@ impl AgentOrchestrator {
         self.repository.create_job(steps).await
     }
 
+    /// Create a job running the steps of a registered pipeline
+    pub async fn create_pipeline_job(&self, pipeline: &str) -> Result<String, String> {
+        let steps = self.registry.build_pipeline_steps(pipeline)?;
+        self.repository.create_job(steps).await
+    }
+
     pub async fn set_job_metadata(
         &self,
         job_id: &str,
@@ -157,24 +500,49 @@ impl AgentOrchestrator {
         self.repository.get_job(job_id).await
     }
 
+    pub async fn count_jobs_requested_since(
+        &self,
+        user_id: &str,
+        since: DateTime<Utc>,
+    ) -> Result<i64, String> {
+        self.repository
+            .count_requested_by_since(user_id, since)
+            .await
+    }
+
+    pub async fn count_active_jobs_requested_by(&self, user_id: &str) -> Result<i64, String> {
+        self.repository.count_active_requested_by(user_id).await
+    }
+
     pub async fn get_job_status(&self, job_id: &str) -> Result<Option<JobStatus>, String> {
         self.repository.get_job_status(job_id).await
     }
 
     pub async fn start_job(&self, job_id: &str) -> Result<(), String> {
-        self.repository.start_job(job_id).await
+        self.repository.start_job(job_id).await?;
+        self.dispatcher.notify(job_id);
+        Ok(())
     }
 
     pub async fn complete_step(
         &self,
         job_id: &str,
         result: Option<serde_json::Value>,
-    ) -> Result<(), String> {
-        self.repository.complete_step(job_id, result).await
+    ) -> Result<bool, String> {
+        self.repository
+            .complete_step(job_id, &self.worker_id, result)
+            .await
     }
 
-    pub async fn fail_step(&self, job_id: &str, error: String) -> Result<(), String> {
-        self.repository.fail_step(job_id, error).await
+    pub async fn fail_step(
+        &self,
+        job_id: &str,
+        error: String,
+        kind: StepFailureKind,
+    ) -> Result<StepFailureOutcome, String> {
+        self.repository
+            .fail_step(job_id, &self.worker_id, error, kind)
+            .await
     }
 
     pub async fn pause_job(&self, job_id: &str) -> Result<(), String> {
@@ -182,7 +550,74 @@ impl AgentOrchestrator {
     }
 
     pub async fn resume_job(&self, job_id: &str) -> Result<(), String> {
-        self.repository.resume_job(job_id).await
+        self.repository.resume_job(job_id).await?;
+        self.dispatcher.notify(job_id);
+        Ok(())
+    }
+
+    pub async fn cancel_job(&self, job_id: &str) -> Result<(), String> {
+        self.repository.cancel_job(job_id).await?;
+
+        if let Some(job) = self.repository.get_job(job_id).await? {
+            self.events.publish(JobProgressEvent::new(
+                &job,
+                JobProgressEventKind::JobCancelled,
+            ));
+        }
+        Ok(())
+    }
+
+    /// Restart a failed or cancelled job from the step it stopped on
+    pub async fn retry_job(&self, job_id: &str) -> Result<(), String> {
+        self.repository.retry_job(job_id).await?;
+        self.repository.mark_dead_letter_replayed(job_id).await?;
+        self.dispatcher.notify(job_id);
+        Ok(())
+    }
+
+    /// Replay a dead-lettered job from its failed step, optionally replacing
+    /// the accumulated results the remaining steps will read
+    pub async fn replay_dead_letter(
+        &self,
+        job_id: &str,
+        results: Option<std::collections::HashMap<String, serde_json::Value>>,
+    ) -> Result<(), String> {
+        self.repository
+            .get_dead_letter(job_id)
+            .await?
+            .ok_or_else(|| format!("Job {} is not in the dead-letter queue", job_id))?;
+
+        let mut job = self
+            .repository
+            .get_job(job_id)
+            .await?
+            .ok_or_else(|| format!("Job {} not found", job_id))?;
+
+        if !job.status.is_retryable() {
+            return Err(format!("Job is {} and cannot be replayed", job.status));
+        }
+
+        if let Some(results) = results {
+            job.results = results;
+            self.repository.save(&job).await?;
+        }
+
+        self.retry_job(job_id).await
+    }
+
+    pub async fn get_dead_letter(&self, job_id: &str) -> Result<Option<DeadLetterJob>, String> {
+        self.repository.get_dead_letter(job_id).await
+    }
+
+    pub async fn list_dead_letters(
+        &self,
+        include_replayed: bool,
+        offset: i64,
+        limit: i64,
+    ) -> Result<Vec<DeadLetterJob>, String> {
+        self.repository
+            .list_dead_letters(include_replayed, offset, limit)
+            .await
     }
 
     pub async fn list_jobs(
@@ -196,114 +631,682 @@ impl AgentOrchestrator {
         self.repository.delete_job(job_id).await
     }
 
+    pub async fn delete_completed_jobs_before(&self, cutoff: DateTime<Utc>) -> Result<u64, String> {
+        self.repository.delete_completed_before(cutoff).await
+    }
+
+    fn schedule_repository(&self) -> Result<&Arc<dyn JobScheduleRepository>, String> {
+        self.schedules
+            .as_ref()
+            .ok_or_else(|| "Job scheduling is not configured".to_string())
+    }
+
+    /// Check the schedule's trigger is valid and its pipeline can be built
+    pub fn validate_schedule(&self, schedule: &JobSchedule) -> Result<(), String> {
+        schedule.trigger.validate()?;
+        self.registry
+            .build_pipeline_steps(&schedule.pipeline)
+            .map(|_| ())
+    }
+
+    /// Persist a new schedule. Fails if the name is already taken.
+    pub async fn create_schedule(&self, schedule: JobSchedule) -> Result<(), String> {
+        self.validate_schedule(&schedule)?;
+        self.schedule_repository()?.create_schedule(&schedule).await
+    }
+
+    /// Persist a schedule unless one with the same name already exists
+    pub async fn ensure_schedule(&self, schedule: JobSchedule) -> Result<(), String> {
+        self.validate_schedule(&schedule)?;
+        self.schedule_repository()?.ensure_schedule(&schedule).await
+    }
+
+    pub async fn get_schedule(&self, name: &str) -> Result<Option<JobSchedule>, String> {
+        self.schedule_repository()?.get_schedule(name).await
+    }
+
+    pub async fn list_schedules(&self) -> Result<Vec<JobSchedule>, String> {
+        self.schedule_repository()?.list_schedules().await
+    }
+
+    /// Enable or disable a schedule. Re-enabling counts the next run from now
+    /// rather than firing immediately for runs missed while disabled.
+    pub async fn set_schedule_enabled(&self, name: &str, enabled: bool) -> Result<(), String> {
+        let schedules = self.schedule_repository()?;
+        let schedule = schedules
+            .get_schedule(name)
+            .await?
+            .ok_or_else(|| format!("Schedule {} not found", name))?;
+
+        let next_run_at = if enabled && !schedule.enabled {
+            schedule.trigger.next_after(Utc::now())
+        } else {
+            schedule.next_run_at
+        };
+
+        schedules
+            .set_schedule_enabled(name, enabled, next_run_at)
+            .await
+    }
+
+    /// Update a metadata entry that future runs of the schedule are seeded with
+    pub async fn set_schedule_metadata(
+        &self,
+        name: &str,
+        key: &str,
+        value: serde_json::Value,
+    ) -> Result<(), String> {
+        self.schedule_repository()?
+            .set_schedule_metadata(name, key, value)
+            .await
+    }
+
+    pub async fn delete_schedule(&self, name: &str) -> Result<(), String> {
+        self.schedule_repository()?.delete_schedule(name).await
+    }
+
     pub async fn start_worker(&self) -> Result<(), String> {
-        log::info!("Starting background worker");
+        log::info!(
+            "Starting background worker {} with {} executors (sweep every {}s, lease {}s)",
+            self.worker_id,
+            self.worker_concurrency,
+            self.sweep_interval.as_secs(),
+            self.lease_seconds
+        );
 
-        let repository = self.repository.clone();
-        let app_state = self.app_state.clone();
+        let mut handles = self.worker_handles.write().await;
+        if !handles.is_empty() {
+            return Err("Background worker is already running".to_string());
+        }
+        if self.dispatcher.is_closed() {
+            return Err("Background worker has been shut down".to_string());
+        }
 
-        let worker_handle = tokio::spawn(async move {
-            loop {
-                if let Ok(jobs) = repository.list_jobs(Some(JobStatus::Running)).await {
-                    for mut job in jobs {
-                        let app_state_read = app_state.read().await;
-                        let Some(app_state_ref) = app_state_read.as_ref() else {
-                            log::warn!("App state not set for orchestrator");
-                            drop(app_state_read);
-                            continue;
-                        };
-
-                        let app_state_clone: Arc<AppState> = app_state_ref.clone();
-                        drop(app_state_read);
-
-                        if let Some(current_step) = job.get_current_step() {
-                            let step_name = current_step.name.clone();
-                            let step_id = current_step.id.clone();
-
-                            if let Some(step_type) = JobStepType::from_step_name(&step_name) {
-                                log::info!(
-                                    "Processing job {} - step {} ({}, attempt {}/{})",
-                                    job.job_id,
-                                    step_id,
-                                    step_name,
-                                    current_step.retry_count + 1,
-                                    current_step.max_retries + 1
-                                );
-
-                                // Execute the step
-                                match StepHandler::execute(
-                                    step_type,
-                                    current_step,
-                                    &job,
-                                    &app_state_clone,
-                                )
-                                .await
-                                {
-                                    Ok(result) => {
-                                        // Step succeeded - complete it
-                                        if let Err(e) = repository
-                                            .complete_step(&job.job_id, Some(result))
-                                            .await
-                                        {
-                                            log::error!("Failed to complete step: {}", e);
-                                        } else {
-                                            log::info!(
-                                                "Step {} completed for job {}",
-                                                step_name,
-                                                job.job_id
-                                            );
-                                        }
-                                    }
-                                    Err(error) => {
-                                        log::error!(
-                                            "Step {} failed for job {}: {}",
-                                            step_name,
-                                            job.job_id,
-                                            error
-                                        );
-
-                                        if let Err(e) =
-                                            repository.fail_step(&job.job_id, error).await
-                                        {
-                                            log::error!("Failed to mark step as failed: {}", e);
-                                        }
-                                    }
-                                }
-                            } else {
-                                log::error!("Unknown step type: {}", step_name);
-                                let error = format!("Unknown step type: {}", step_name);
-                                let _ = repository.fail_step(&job.job_id, error).await;
-                            }
-                        } else {
-                            log::info!(
-                                "Job {} has no more steps - marking as completed",
-                                job.job_id
-                            );
-
-                            job.status = JobStatus::Completed;
-                            job.completed_at = Some(Utc::now());
-                            if let Err(e) = repository.save(&job).await {
-                                log::error!("Failed to save completed job: {}", e);
-                            }
+        let context = ExecutorContext {
+            repository: self.repository.clone(),
+            registry: self.registry.clone(),
+            events: self.events.clone(),
+            app_state: self.app_state.clone(),
+            worker_id: self.worker_id.clone(),
+            lease_seconds: self.lease_seconds,
+        };
+
+        for executor_id in 0..self.worker_concurrency {
+            let context = context.clone();
+            let dispatcher = self.dispatcher.clone();
+
+            handles.push(tokio::spawn(async move {
+                while let Some(job_id) = dispatcher.next().await {
+                    log::debug!("Executor {} picked up job {}", executor_id, job_id);
+
+                    let outcome = Self::process_job(&context, &job_id).await;
+                    dispatcher.release(&job_id);
+
+                    match outcome {
+                        StepOutcome::Continue => dispatcher.notify(job_id),
+                        StepOutcome::RetryAfter(delay) => {
+                            let dispatcher = dispatcher.clone();
+                            tokio::spawn(async move {
+                                tokio::time::sleep(delay).await;
+                                dispatcher.notify(job_id);
+                            });
                         }
+                        StepOutcome::RetryLater | StepOutcome::Done => {}
                     }
                 }
+                log::debug!("Executor {} stopped", executor_id);
+            }));
+        }
+
+        let mut background = self.background_handles.write().await;
+        let repository = self.repository.clone();
+        let dispatcher = self.dispatcher.clone();
+        let worker_id = self.worker_id.clone();
+        let sweep_interval = self.sweep_interval;
 
-                tokio::time::sleep(tokio::time::Duration::from_secs(5)).await;
+        background.push(tokio::spawn(async move {
+            let mut interval = tokio::time::interval(sweep_interval);
+            loop {
+                interval.tick().await;
+                Self::sweep_running_jobs(&repository, &dispatcher, &worker_id).await;
             }
-        });
+        }));
 
-        let mut handle = self.worker_handle.write().await;
-        *handle = Some(worker_handle);
+        if let Some(schedules) = self.schedules.clone() {
+            let context = context.clone();
+            let dispatcher = self.dispatcher.clone();
+            let poll_interval = self.schedule_poll_interval;
+
+            background.push(tokio::spawn(async move {
+                let mut interval = tokio::time::interval(poll_interval);
+                loop {
+                    interval.tick().await;
+                    Self::run_due_schedules(&schedules, &context, &dispatcher).await;
+                }
+            }));
+        }
 
         Ok(())
     }
 
+    /// Stop taking new step work and wait up to the shutdown timeout for
+    /// running steps to finish and persist their results. Steps still running
+    /// at the deadline are abandoned and their job leases released, so another
+    /// instance can resume them straight away.
     pub async fn stop_worker(&self) -> Result<(), String> {
-        let mut handle = self.worker_handle.write().await;
-        if let Some(join_handle) = handle.take() {
+        self.dispatcher.close();
+
+        for join_handle in self.background_handles.write().await.drain(..) {
             join_handle.abort();
         }
+
+        let handles: Vec<_> = self.worker_handles.write().await.drain(..).collect();
+        if handles.is_empty() {
+            return Ok(());
+        }
+
+        let in_flight = self.dispatcher.in_flight_jobs().len();
+        log::info!(
+            "Draining {} in-flight job step(s), waiting up to {}s",
+            in_flight,
+            self.shutdown_timeout.as_secs()
+        );
+
+        let abort_handles: Vec<_> = handles.iter().map(|h| h.abort_handle()).collect();
+        let drained =
+            tokio::time::timeout(self.shutdown_timeout, futures::future::join_all(handles))
+                .await
+                .is_ok();
+
+        if drained {
+            log::info!("All in-flight job steps finished");
+            return Ok(());
+        }
+
+        for abort_handle in abort_handles {
+            abort_handle.abort();
+        }
+
+        for job_id in self.dispatcher.in_flight_jobs() {
+            log::warn!(
+                "Abandoning job {} after shutdown timeout; releasing its lease",
+                job_id
+            );
+            if let Err(e) = self
+                .repository
+                .release_lease(&job_id, &self.worker_id)
+                .await
+            {
+                log::error!("Failed to release lease for job {}: {}", job_id, e);
+            }
+            self.dispatcher.release(&job_id);
+        }
+
         Ok(())
     }
+
+    /// Fallback for missed notifications and expired leases: queue every
+    /// claimable job not already in flight on this instance
+    async fn sweep_running_jobs(
+        repository: &Arc<dyn AgentJobRepository>,
+        dispatcher: &JobDispatcher,
+        worker_id: &str,
+    ) {
+        match repository.list_claimable_jobs(worker_id).await {
+            Ok(jobs) => {
+                for job in jobs {
+                    if !dispatcher.is_in_flight(&job.job_id) {
+                        dispatcher.notify(job.job_id);
+                    }
+                }
+            }
+            Err(e) => log::error!("Failed to sweep running jobs: {}", e),
+        }
+    }
+
+    /// Enqueue a job for every due schedule whose run this instance claims
+    async fn run_due_schedules(
+        schedules: &Arc<dyn JobScheduleRepository>,
+        context: &ExecutorContext,
+        dispatcher: &JobDispatcher,
+    ) {
+        let now = Utc::now();
+        let due = match schedules.list_due_schedules(now).await {
+            Ok(due) => due,
+            Err(e) => {
+                log::error!("Failed to list due schedules: {}", e);
+                return;
+            }
+        };
+
+        for schedule in due {
+            // Runs missed while no worker was up collapse into this one
+            let next_run_at = schedule.trigger.next_after(now);
+            match schedules
+                .claim_schedule_run(&schedule.name, schedule.next_run_at, next_run_at)
+                .await
+            {
+                Ok(true) => {}
+                Ok(false) => continue,
+                Err(e) => {
+                    log::error!("Failed to claim schedule {}: {}", schedule.name, e);
+                    continue;
+                }
+            }
+
+            match Self::enqueue_scheduled_job(context, dispatcher, &schedule).await {
+                Ok(job_id) => {
+                    log::info!(
+                        "Schedule {} enqueued job {} (next run at {})",
+                        schedule.name,
+                        job_id,
+                        next_run_at
+                    );
+                    if let Err(e) = schedules
+                        .record_schedule_run(&schedule.name, &job_id, now)
+                        .await
+                    {
+                        log::error!("Failed to record run of schedule {}: {}", schedule.name, e);
+                    }
+                }
+                Err(e) => log::error!("Schedule {} failed to enqueue job: {}", schedule.name, e),
+            }
+        }
+    }
+
+    async fn enqueue_scheduled_job(
+        context: &ExecutorContext,
+        dispatcher: &JobDispatcher,
+        schedule: &JobSchedule,
+    ) -> Result<String, String> {
+        let steps = context.registry.build_pipeline_steps(&schedule.pipeline)?;
+        let job_id = context.repository.create_job(steps).await?;
+
+        let mut job = context
+            .repository
+            .get_job(&job_id)
+            .await?
+            .ok_or_else(|| format!("Job {} not found", job_id))?;
+        job.results.extend(schedule.metadata.clone());
+        job.results.insert(
+            SCHEDULE_NAME_KEY.to_string(),
+            serde_json::json!(schedule.name),
+        );
+        context.repository.save(&job).await?;
+
+        context.repository.start_job(&job_id).await?;
+        dispatcher.notify(job_id.clone());
+        Ok(job_id)
+    }
+
+    /// Claim the job lease, run its current step and persist the outcome
+    async fn process_job(context: &ExecutorContext, job_id: &str) -> StepOutcome {
+        let job = match context
+            .repository
+            .acquire_lease(job_id, &context.worker_id, context.lease_seconds)
+            .await
+        {
+            Ok(Some(job)) => job,
+            Ok(None) => {
+                log::debug!(
+                    "Job {} is not running or is leased by another worker",
+                    job_id
+                );
+                return StepOutcome::Done;
+            }
+            Err(e) => {
+                log::error!("Failed to acquire lease for job {}: {}", job_id, e);
+                return StepOutcome::RetryLater;
+            }
+        };
+
+        // Dropped with this future, so an executor aborted at shutdown stops renewing too
+        let mut heartbeat = AbortOnDrop(Self::spawn_heartbeat(context, job_id));
+        // The heartbeat only finishes once another worker has taken the lease;
+        // the step is abandoned then, since its result could no longer be recorded
+        let outcome = tokio::select! {
+            outcome = Self::run_current_step(context, job) => outcome,
+            _ = &mut heartbeat.0 => {
+                log::warn!("Cancelled running step of job {} after losing its lease", job_id);
+                StepOutcome::Done
+            }
+        };
+        drop(heartbeat);
+
+        if let Err(e) = context
+            .repository
+            .release_lease(job_id, &context.worker_id)
+            .await
+        {
+            log::error!("Failed to release lease for job {}: {}", job_id, e);
+        }
+
+        outcome
+    }
+
+    /// Periodically extend the lease while a step is executing. The task ends
+    /// when the lease turns out to be held by another worker.
+    fn spawn_heartbeat(context: &ExecutorContext, job_id: &str) -> tokio::task::JoinHandle<()> {
+        let repository = context.repository.clone();
+        let worker_id = context.worker_id.clone();
+        let lease_seconds = context.lease_seconds;
+        let job_id = job_id.to_string();
+
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(Duration::from_secs(lease_seconds / 3));
+            interval.tick().await;
+            loop {
+                interval.tick().await;
+                match repository
+                    .renew_lease(&job_id, &worker_id, lease_seconds)
+                    .await
+                {
+                    Ok(true) => {}
+                    Ok(false) => {
+                        log::warn!("Lost lease on job {} while step was running", job_id);
+                        break;
+                    }
+                    Err(e) => log::error!("Failed to renew lease for job {}: {}", job_id, e),
+                }
+            }
+        })
+    }
+
+    async fn run_current_step(context: &ExecutorContext, mut job: AgentJob) -> StepOutcome {
+        let repository = &context.repository;
+
+        let Some(app_state) = context.app_state.read().await.clone() else {
+            log::warn!("App state not set for orchestrator");
+            return StepOutcome::RetryLater;
+        };
+
+        let Some(current_step) = job.get_current_step() else {
+            log::info!(
+                "Job {} has no more steps - marking as completed",
+                job.job_id
+            );
+
+            job.status = JobStatus::Completed;
+            job.completed_at = Some(Utc::now());
+            if let Err(e) = repository.save(&job).await {
+                log::error!("Failed to save completed job: {}", e);
+            }
+            return StepOutcome::Done;
+        };
+
+        let step_name = current_step.name.clone();
+        let step_id = current_step.id.clone();
+
+        let Some(executor) = context.registry.get_step(&step_name) else {
+            log::error!("No executor registered for step: {}", step_name);
+            let error = format!("No executor registered for step: {}", step_name);
+            return Self::record_failure(context, &job, error, StepFailureKind::Error).await;
+        };
+
+        log::info!(
+            "Processing job {} - step {} ({}, attempt {}/{})",
+            job.job_id,
+            step_id,
+            step_name,
+            current_step.retry_count + 1,
+            current_step.max_retries + 1
+        );
+
+        let started_at = Utc::now();
+        let execution = executor.execute(current_step, &job, &app_state);
+        let result = match current_step.timeout_seconds {
+            Some(seconds) => tokio::time::timeout(Duration::from_secs(seconds), execution)
+                .await
+                .map_err(|_| {
+                    (
+                        format!("Step {} timed out after {}s", step_name, seconds),
+                        StepFailureKind::Timeout,
+                    )
+                })
+                .and_then(|result| result.map_err(|e| (e, StepFailureKind::Error))),
+            None => execution.await.map_err(|e| (e, StepFailureKind::Error)),
+        };
+        let ended_at = Utc::now();
+
+        let step_run = match &result {
+            Ok(value) => StepRun::new(
+                current_step,
+                started_at,
+                ended_at,
+                StepRunOutcome::Succeeded,
+            )
+            .with_result(value),
+            Err((error, StepFailureKind::Timeout)) => {
+                StepRun::new(current_step, started_at, ended_at, StepRunOutcome::TimedOut)
+                    .with_error(error.clone())
+            }
+            Err((error, StepFailureKind::Error)) => {
+                StepRun::new(current_step, started_at, ended_at, StepRunOutcome::Failed)
+                    .with_error(error.clone())
+            }
+        };
+        if let Err(e) = repository.append_step_run(&job.job_id, &step_run).await {
+            log::error!("Failed to record step run for job {}: {}", job.job_id, e);
+        }
+
+        match result {
+            Ok(result) => {
+                match repository
+                    .complete_step(&job.job_id, &context.worker_id, Some(result))
+                    .await
+                {
+                    Ok(true) => {}
+                    Ok(false) => {
+                        log::warn!(
+                            "Dropped result of step {} for job {}: lease was lost",
+                            step_name,
+                            job.job_id
+                        );
+                        return StepOutcome::Done;
+                    }
+                    Err(e) => {
+                        log::error!("Failed to complete step: {}", e);
+                        return StepOutcome::RetryLater;
+                    }
+                }
+
+                log::info!("Step {} completed for job {}", step_name, job.job_id);
+
+                let has_more_steps = job.current_step_index + 1 < job.steps.len();
+                let status = if has_more_steps {
+                    JobStatus::Running
+                } else {
+                    JobStatus::Completed
+                };
+                context.events.publish(
+                    JobProgressEvent::new(&job, JobProgressEventKind::StepCompleted)
+                        .with_status(status)
+                        .with_current_step_index(job.current_step_index + 1),
+                );
+
+                if has_more_steps {
+                    StepOutcome::Continue
+                } else {
+                    StepOutcome::Done
+                }
+            }
+            Err((error, kind)) => {
+                log::error!(
+                    "Step {} failed for job {} ({}): {}",
+                    step_name,
+                    job.job_id,
+                    kind,
+                    error
+                );
+
+                Self::record_failure(context, &job, error, kind).await
+            }
+        }
+    }
+
+    async fn record_failure(
+        context: &ExecutorContext,
+        job: &AgentJob,
+        error: String,
+        kind: StepFailureKind,
+    ) -> StepOutcome {
+        let job_id = job.job_id.as_str();
+        let event = JobProgressEvent::new(job, JobProgressEventKind::StepFailed).with_error(&error);
+        let dead_letter = DeadLetterJob::from_failed_job(job, &error, kind);
+
+        match context
+            .repository
+            .fail_step(job_id, &context.worker_id, error, kind)
+            .await
+        {
+            Ok(StepFailureOutcome::RetryAt(next_attempt_at)) => {
+                context
+                    .events
+                    .publish(event.with_next_attempt_at(Some(next_attempt_at)));
+
+                let delay = (next_attempt_at - Utc::now()).to_std().unwrap_or_default();
+                log::info!("Job {} will retry in {:.1}s", job_id, delay.as_secs_f32());
+                StepOutcome::RetryAfter(delay)
+            }
+            Ok(StepFailureOutcome::JobFailed) => {
+                context.events.publish(
+                    event
+                        .with_status(JobStatus::Failed)
+                        .with_next_attempt_at(None),
+                );
+                Self::dead_letter(context, dead_letter).await;
+                StepOutcome::Done
+            }
+            Ok(StepFailureOutcome::LeaseLost) => {
+                log::warn!("Dropped failure of job {}: lease was lost", job_id);
+                StepOutcome::Done
+            }
+            Err(e) => {
+                log::error!("Failed to mark step as failed: {}", e);
+                StepOutcome::RetryLater
+            }
+        }
+    }
+
+    /// Park a permanently failed job in the dead-letter queue and flag its quiz
+    async fn dead_letter(context: &ExecutorContext, entry: DeadLetterJob) {
+        log::warn!(
+            "Job {} failed permanently at step {:?}; moving to dead-letter queue",
+            entry.job_id,
+            entry.failed_step_name
+        );
+
+        if let Err(e) = context.repository.dead_letter_job(&entry).await {
+            log::error!("Failed to dead-letter job {}: {}", entry.job_id, e);
+        }
+
+        let Some(quiz_id) = entry.quiz_id.as_deref() else {
+            return;
+        };
+        let Some(app_state) = context.app_state.read().await.clone() else {
+            return;
+        };
+        if let Err(e) = app_state.quiz_service.mark_generation_failed(quiz_id).await {
+            log::error!("Failed to mark quiz {} as failed: {}", quiz_id, e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_unfinished_jobs_are_cancellable_and_only_stopped_jobs_retryable() {
+        assert!(JobStatus::Pending.is_cancellable());
+        assert!(JobStatus::Running.is_cancellable());
+        assert!(JobStatus::Paused.is_cancellable());
+        assert!(!JobStatus::Completed.is_cancellable());
+        assert!(!JobStatus::Cancelled.is_cancellable());
+
+        assert!(JobStatus::Failed.is_retryable());
+        assert!(JobStatus::Cancelled.is_retryable());
+        assert!(!JobStatus::Running.is_retryable());
+        assert!(!JobStatus::Completed.is_retryable());
+    }
+
+    #[test]
+    fn dead_letter_entry_captures_failed_step_and_results() {
+        let mut job = AgentJob::new(vec![
+            JobStep::new("create_quiz_draft"),
+            JobStep::new("create_summary_document"),
+        ]);
+        job.current_step_index = 1;
+        job.steps[1].retry_count = 2;
+        job.results
+            .insert("quiz_id".to_string(), serde_json::json!("quiz-1"));
+
+        let entry = DeadLetterJob::from_failed_job(&job, "fetch failed", StepFailureKind::Timeout);
+
+        assert_eq!(entry.job_id, job.job_id);
+        assert_eq!(entry.quiz_id.as_deref(), Some("quiz-1"));
+        assert_eq!(
+            entry.failed_step_name.as_deref(),
+            Some("create_summary_document")
+        );
+        assert_eq!(entry.failed_step_index, 1);
+        assert_eq!(entry.attempts, 3);
+        assert_eq!(entry.failure_kind, StepFailureKind::Timeout);
+        assert_eq!(entry.results.get("quiz_id"), job.results.get("quiz_id"));
+        assert!(entry.replayed_at.is_none());
+    }
+
+    #[test]
+    fn job_without_lease_fields_deserializes_with_defaults() {
+        let job = AgentJob::new(vec![JobStep::new("create_quiz_draft")]);
+        let mut value = serde_json::to_value(&job).expect("job should serialize");
+        let obj = value.as_object_mut().expect("job should be an object");
+        obj.remove("lease_owner");
+        obj.remove("lease_expires_at");
+        obj.remove("heartbeat_at");
+
+        let parsed: AgentJob = serde_json::from_value(value).expect("job should deserialize");
+
+        assert!(parsed.lease_owner.is_none());
+        assert!(parsed.lease_expires_at.is_none());
+        assert!(parsed.heartbeat_at.is_none());
+    }
+
+    #[test]
+    fn retry_backoff_grows_exponentially_within_jitter_bounds() {
+        for _ in 0..20 {
+            let first = retry_backoff(1);
+            let third = retry_backoff(3);
+
+            assert!(first >= Duration::from_millis(2500) && first <= Duration::from_secs(5));
+            assert!(third >= Duration::from_secs(10) && third <= Duration::from_secs(20));
+        }
+    }
+
+    #[test]
+    fn retry_backoff_is_capped() {
+        let delay = retry_backoff(30);
+
+        assert!(delay <= Duration::from_secs(RETRY_BACKOFF_MAX_SECONDS));
+        assert!(delay >= Duration::from_secs(RETRY_BACKOFF_MAX_SECONDS / 2));
+    }
+
+    #[test]
+    fn step_run_records_attempt_duration_and_truncated_result() {
+        let mut step = JobStep::new("create_quiz_questions");
+        step.retry_count = 2;
+        let started_at = Utc::now();
+        let ended_at = started_at + chrono::Duration::milliseconds(1500);
+        let result =
+            serde_json::json!({ "response": "x".repeat(STEP_RUN_RESULT_PREVIEW_LENGTH * 2) });
+
+        let run = StepRun::new(&step, started_at, ended_at, StepRunOutcome::Succeeded)
+            .with_result(&result);
+
+        assert_eq!(run.attempt, 3);
+        assert_eq!(run.duration_ms, 1500);
+        assert_eq!(run.step_name, "create_quiz_questions");
+        let preview = run.result_preview.expect("preview should be set");
+        assert_eq!(preview.chars().count(), STEP_RUN_RESULT_PREVIEW_LENGTH + 1);
+    }
 }
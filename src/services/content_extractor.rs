@@ -0,0 +1,662 @@
+use once_cell::sync::Lazy;
+use regex::Regex;
+use scraper::{node::Node, ElementRef, Html, Selector};
+
+/// Readable content of a web page, with boilerplate removed
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ExtractedPage {
+    pub title: Option<String>,
+    pub description: Option<String>,
+    pub author: Option<String>,
+    pub published_at: Option<String>,
+    pub site_name: Option<String>,
+    pub language: Option<String>,
+    pub canonical_url: Option<String>,
+    /// Main content as markdown: headings, paragraphs, lists, quotes, code and
+    /// tables
+    pub content: String,
+}
+
+impl ExtractedPage {
+    /// Title and metadata header followed by the content, ready for an LLM
+    pub fn to_markdown(&self) -> String {
+        let mut out = String::new();
+        if let Some(title) = &self.title {
+            out.push_str(&format!("# {}\n\n", title));
+        }
+
+        let metadata = [
+            ("Site", &self.site_name),
+            ("Author", &self.author),
+            ("Published", &self.published_at),
+            ("Canonical URL", &self.canonical_url),
+            ("Language", &self.language),
+            ("Description", &self.description),
+        ];
+        let mut has_metadata = false;
+        for (label, value) in metadata {
+            if let Some(value) = value {
+                out.push_str(&format!("{}: {}\n", label, value));
+                has_metadata = true;
+            }
+        }
+        if has_metadata {
+            out.push('\n');
+        }
+
+        out.push_str(&self.content);
+        out.trim_end().to_string()
+    }
+}
+
+/// Elements that never hold readable content
+const SKIPPED_TAGS: &[&str] = &[
+    "head", "script", "style", "noscript", "template", "svg", "canvas", "iframe", "object",
+    "embed", "form", "button", "input", "select", "textarea", "nav", "footer", "aside", "dialog",
+    "menu",
+];
+
+const BOILERPLATE_ROLES: &[&str] = &[
+    "navigation",
+    "banner",
+    "contentinfo",
+    "complementary",
+    "dialog",
+    "alertdialog",
+    "search",
+    "menu",
+    "menubar",
+];
+
+const BLOCK_TAGS: &[&str] = &[
+    "address",
+    "article",
+    "blockquote",
+    "details",
+    "dd",
+    "div",
+    "dl",
+    "dt",
+    "fieldset",
+    "figcaption",
+    "figure",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "header",
+    "hr",
+    "li",
+    "main",
+    "ol",
+    "p",
+    "pre",
+    "section",
+    "summary",
+    "table",
+    "ul",
+];
+
+/// class/id fragments that mark an element as boilerplate regardless of what
+/// else it says
+static ALWAYS_BOILERPLATE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(?i)cookie|consent|gdpr|newsletter|advert|sponsor|popup|modal|skip-link")
+        .expect("valid regex")
+});
+
+/// class/id fragments that usually mark boilerplate, unless [`LIKELY_CONTENT`]
+/// also matches
+static UNLIKELY_CONTENT: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r"(?i)banner|subscribe|signup|share|social|sidebar|breadcrumb|related|recommend|comment|promo|menu|navbar|masthead|footer|header|pagination|toolbar",
+    )
+    .expect("valid regex")
+});
+
+static LIKELY_CONTENT: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(?i)article|content|entry|main|post|story|body|text").expect("valid regex")
+});
+
+static MAIN_CANDIDATES: Lazy<Selector> =
+    Lazy::new(|| Selector::parse("article, main, [role=main]").expect("valid selector"));
+static PARAGRAPHS: Lazy<Selector> = Lazy::new(|| Selector::parse("p").expect("valid selector"));
+static BODY: Lazy<Selector> = Lazy::new(|| Selector::parse("body").expect("valid selector"));
+
+/// Paragraph text needed before a scored container is trusted over `<body>`
+const MIN_CANDIDATE_SCORE: usize = 140;
+/// Share of link text above which a block is treated as a link list
+const MAX_LINK_DENSITY: f64 = 0.5;
+
+/// Parse an HTML document and extract its readable content and metadata
+pub fn extract_page(html: &str) -> ExtractedPage {
+    let document = Html::parse_document(html);
+
+    let content = find_content_root(&document)
+        .map(|root| {
+            let mut blocks = Vec::new();
+            render_children(root, &mut blocks);
+            blocks.join("\n\n")
+        })
+        .unwrap_or_default();
+
+    ExtractedPage {
+        title: meta_content(&document, "property", "og:title")
+            .or_else(|| first_text(&document, "title"))
+            .or_else(|| first_text(&document, "h1")),
+        description: meta_content(&document, "name", "description")
+            .or_else(|| meta_content(&document, "property", "og:description")),
+        author: meta_content(&document, "name", "author")
+            .or_else(|| meta_content(&document, "property", "article:author")),
+        published_at: meta_content(&document, "property", "article:published_time")
+            .or_else(|| meta_content(&document, "name", "date"))
+            .or_else(|| first_attr(&document, "time[datetime]", "datetime")),
+        site_name: meta_content(&document, "property", "og:site_name"),
+        language: first_attr(&document, "html[lang]", "lang"),
+        canonical_url: first_attr(&document, "link[rel=canonical]", "href"),
+        content,
+    }
+}
+
+/// Cut `text` to at most `max_bytes` without splitting a character
+pub fn truncate_at_char_boundary(text: &mut String, max_bytes: usize) -> bool {
+    if text.len() <= max_bytes {
+        return false;
+    }
+    let mut end = max_bytes;
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    text.truncate(end);
+    true
+}
+
+/// The `<article>`/`<main>` with the most text, else the element whose
+/// paragraphs hold the most text, else `<body>`
+fn find_content_root(document: &Html) -> Option<ElementRef<'_>> {
+    let marked = document
+        .select(&MAIN_CANDIDATES)
+        .filter(|element| !is_boilerplate(element))
+        .max_by_key(|element| text_length(element));
+    if let Some(marked) = marked.filter(|element| text_length(element) > 0) {
+        return Some(marked);
+    }
+
+    // Each paragraph scores its parent fully and its grandparent by half
+    let mut scores: Vec<(ElementRef<'_>, usize)> = Vec::new();
+    for paragraph in document.select(&PARAGRAPHS) {
+        let length = text_length(&paragraph);
+        if length < 25 {
+            continue;
+        }
+        let ancestors = paragraph.ancestors().filter_map(ElementRef::wrap).take(2);
+        for (element, score) in ancestors.zip([length, length / 2]) {
+            match scores
+                .iter_mut()
+                .find(|(candidate, _)| candidate.id() == element.id())
+            {
+                Some((_, total)) => *total += score,
+                None => scores.push((element, score)),
+            }
+        }
+    }
+
+    scores
+        .into_iter()
+        .filter(|(element, _)| !is_boilerplate(element))
+        .max_by_key(|(_, score)| *score)
+        .filter(|(_, score)| *score >= MIN_CANDIDATE_SCORE)
+        .map(|(element, _)| element)
+        .or_else(|| document.select(&BODY).next())
+}
+
+fn render_children(element: ElementRef<'_>, blocks: &mut Vec<String>) {
+    let mut inline = String::new();
+
+    for child in element.children() {
+        match child.value() {
+            Node::Text(text) => push_text(&mut inline, text),
+            Node::Element(_) => {
+                let Some(child) = ElementRef::wrap(child) else {
+                    continue;
+                };
+                if is_boilerplate(&child) {
+                    continue;
+                }
+                if BLOCK_TAGS.contains(&child.value().name()) {
+                    flush_paragraph(&mut inline, blocks);
+                    render_block(child, blocks);
+                } else {
+                    push_inline(&mut inline, child);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    flush_paragraph(&mut inline, blocks);
+}
+
+fn render_block(element: ElementRef<'_>, blocks: &mut Vec<String>) {
+    let name = element.value().name();
+    match name {
+        "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => {
+            let level = name[1..].parse().unwrap_or(1);
+            let text = inline_text(element);
+            if !text.is_empty() {
+                blocks.push(format!("{} {}", "#".repeat(level), text));
+            }
+        }
+        "p" | "dt" | "dd" | "figcaption" | "summary" => {
+            let text = inline_text(element);
+            if !text.is_empty() {
+                blocks.push(if name == "dt" {
+                    format!("**{}**", text)
+                } else {
+                    text
+                });
+            }
+        }
+        "ul" | "ol" => {
+            if link_density(&element) > MAX_LINK_DENSITY {
+                return;
+            }
+            let mut lines = Vec::new();
+            render_list(element, 0, &mut lines);
+            if !lines.is_empty() {
+                blocks.push(lines.join("\n"));
+            }
+        }
+        "blockquote" => {
+            let mut quoted = Vec::new();
+            render_children(element, &mut quoted);
+            if !quoted.is_empty() {
+                let quote = quoted
+                    .join("\n\n")
+                    .lines()
+                    .map(|line| format!("> {}", line).trim_end().to_string())
+                    .collect::<Vec<_>>()
+                    .join("\n");
+                blocks.push(quote);
+            }
+        }
+        "pre" => {
+            let code: String = element.text().collect();
+            let code = code.trim_matches('\n');
+            if !code.trim().is_empty() {
+                blocks.push(format!("```\n{}\n```", code));
+            }
+        }
+        "table" => render_table(element, blocks),
+        "hr" => {}
+        "header" if !has_heading(&element) => {}
+        _ => {
+            if link_density(&element) > MAX_LINK_DENSITY {
+                return;
+            }
+            render_children(element, blocks);
+        }
+    }
+}
+
+fn render_list(list: ElementRef<'_>, depth: usize, lines: &mut Vec<String>) {
+    let ordered = list.value().name() == "ol";
+    let indent = "  ".repeat(depth);
+    let items = list
+        .children()
+        .filter_map(ElementRef::wrap)
+        .filter(|item| item.value().name() == "li" && !is_boilerplate(item));
+
+    for (index, item) in items.enumerate() {
+        let mut text = String::new();
+        let mut nested = Vec::new();
+        for child in item.children() {
+            match child.value() {
+                Node::Text(value) => push_text(&mut text, value),
+                Node::Element(_) => {
+                    let Some(child) = ElementRef::wrap(child) else {
+                        continue;
+                    };
+                    match child.value().name() {
+                        "ul" | "ol" => nested.push(child),
+                        _ if is_boilerplate(&child) => {}
+                        _ => {
+                            push_separator(&mut text);
+                            push_inline(&mut text, child);
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        let text = normalise_inline(&text);
+        if !text.is_empty() {
+            let marker = if ordered {
+                format!("{}.", index + 1)
+            } else {
+                "-".to_string()
+            };
+            lines.push(format!("{}{} {}", indent, marker, text));
+        }
+        for list in nested {
+            render_list(list, depth + 1, lines);
+        }
+    }
+}
+
+fn render_table(table: ElementRef<'_>, blocks: &mut Vec<String>) {
+    let rows: Vec<Vec<String>> = table
+        .descendants()
+        .filter_map(ElementRef::wrap)
+        .filter(|element| element.value().name() == "tr")
+        .map(|row| {
+            row.children()
+                .filter_map(ElementRef::wrap)
+                .filter(|cell| matches!(cell.value().name(), "td" | "th"))
+                .map(|cell| inline_text(cell).replace('|', "\\|"))
+                .collect::<Vec<_>>()
+        })
+        .filter(|cells| cells.iter().any(|cell| !cell.is_empty()))
+        .collect();
+
+    let Some(columns) = rows.iter().map(Vec::len).max() else {
+        return;
+    };
+
+    let mut lines = Vec::with_capacity(rows.len() + 1);
+    for (index, mut cells) in rows.into_iter().enumerate() {
+        cells.resize(columns, String::new());
+        lines.push(format!("| {} |", cells.join(" | ")));
+        if index == 0 {
+            lines.push(format!("|{}", " --- |".repeat(columns)));
+        }
+    }
+    blocks.push(lines.join("\n"));
+}
+
+/// Text of an element with nested markup flattened, whitespace collapsed
+fn inline_text(element: ElementRef<'_>) -> String {
+    let mut text = String::new();
+    push_inline_children(&mut text, element);
+    normalise_inline(&text)
+}
+
+fn push_inline_children(out: &mut String, element: ElementRef<'_>) {
+    for child in element.children() {
+        match child.value() {
+            Node::Text(text) => push_text(out, text),
+            Node::Element(_) => {
+                if let Some(child) = ElementRef::wrap(child) {
+                    if BLOCK_TAGS.contains(&child.value().name()) {
+                        push_separator(out);
+                    }
+                    push_inline(out, child);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn push_inline(out: &mut String, element: ElementRef<'_>) {
+    if is_boilerplate(&element) {
+        return;
+    }
+    match element.value().name() {
+        "br" => out.push('\n'),
+        "img" => {}
+        "code" | "kbd" | "samp" => {
+            let code: String = element.text().collect();
+            let code = code.split_whitespace().collect::<Vec<_>>().join(" ");
+            if !code.is_empty() {
+                out.push('`');
+                out.push_str(&code);
+                out.push('`');
+            }
+        }
+        _ => push_inline_children(out, element),
+    }
+}
+
+fn push_text(out: &mut String, text: &str) {
+    let mut was_space = out.ends_with([' ', '\n']);
+    for ch in text.chars() {
+        if ch.is_whitespace() {
+            if !was_space {
+                out.push(' ');
+                was_space = true;
+            }
+        } else {
+            out.push(ch);
+            was_space = false;
+        }
+    }
+}
+
+fn push_separator(out: &mut String) {
+    if !out.is_empty() && !out.ends_with([' ', '\n']) {
+        out.push(' ');
+    }
+}
+
+fn normalise_inline(text: &str) -> String {
+    text.lines()
+        .map(|line| line.split_whitespace().collect::<Vec<_>>().join(" "))
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn flush_paragraph(inline: &mut String, blocks: &mut Vec<String>) {
+    let text = normalise_inline(inline);
+    if !text.is_empty() {
+        blocks.push(text);
+    }
+    inline.clear();
+}
+
+fn is_boilerplate(element: &ElementRef<'_>) -> bool {
+    let value = element.value();
+    if SKIPPED_TAGS.contains(&value.name()) {
+        return true;
+    }
+    if value.attr("hidden").is_some() || value.attr("aria-hidden") == Some("true") {
+        return true;
+    }
+    if value
+        .attr("style")
+        .is_some_and(|style| style.replace(' ', "").contains("display:none"))
+    {
+        return true;
+    }
+    if value
+        .attr("role")
+        .is_some_and(|role| BOILERPLATE_ROLES.contains(&role))
+    {
+        return true;
+    }
+
+    // Never drop the document's own top-level containers by name alone
+    if matches!(value.name(), "html" | "body" | "article" | "main") {
+        return false;
+    }
+
+    let marker = format!(
+        "{} {}",
+        value.attr("class").unwrap_or_default(),
+        value.id().unwrap_or_default()
+    );
+    ALWAYS_BOILERPLATE.is_match(&marker)
+        || (UNLIKELY_CONTENT.is_match(&marker) && !LIKELY_CONTENT.is_match(&marker))
+}
+
+fn has_heading(element: &ElementRef<'_>) -> bool {
+    element
+        .descendants()
+        .filter_map(ElementRef::wrap)
+        .any(|e| matches!(e.value().name(), "h1" | "h2" | "h3" | "h4" | "h5" | "h6"))
+}
+
+fn text_length(element: &ElementRef<'_>) -> usize {
+    element
+        .text()
+        .map(|text| text.split_whitespace().map(str::len).sum::<usize>())
+        .sum()
+}
+
+/// Share of an element's text that sits inside links
+fn link_density(element: &ElementRef<'_>) -> f64 {
+    let total = text_length(element);
+    if total == 0 {
+        return 0.0;
+    }
+    let linked: usize = element
+        .descendants()
+        .filter_map(ElementRef::wrap)
+        .filter(|e| e.value().name() == "a")
+        .map(|link| text_length(&link))
+        .sum();
+    linked as f64 / total as f64
+}
+
+fn meta_content(document: &Html, attribute: &str, name: &str) -> Option<String> {
+    let selector = Selector::parse(&format!("meta[{}=\"{}\"]", attribute, name)).ok()?;
+    first_attr_of(document, &selector, "content")
+}
+
+fn first_attr(document: &Html, selector: &str, attribute: &str) -> Option<String> {
+    let selector = Selector::parse(selector).ok()?;
+    first_attr_of(document, &selector, attribute)
+}
+
+fn first_attr_of(document: &Html, selector: &Selector, attribute: &str) -> Option<String> {
+    document
+        .select(selector)
+        .filter_map(|element| element.value().attr(attribute))
+        .map(|value| value.split_whitespace().collect::<Vec<_>>().join(" "))
+        .find(|value| !value.is_empty())
+}
+
+fn first_text(document: &Html, selector: &str) -> Option<String> {
+    let selector = Selector::parse(selector).ok()?;
+    document
+        .select(&selector)
+        .map(inline_text)
+        .find(|text| !text.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ARTICLE: &str = r#"<!doctype html>
+        <html lang="en">
+        <head>
+            <title>Rust Ownership | Example Docs</title>
+            <meta name="description" content="How ownership works">
+            <meta name="author" content="Ferris">
+            <meta property="og:site_name" content="Example Docs">
+            <link rel="canonical" href="https://docs.example.com/ownership">
+            <style>body { color: red; }</style>
+            <script>trackVisitor();</script>
+        </head>
+        <body>
+            <nav><a href="/">Home</a> <a href="/docs">Docs</a></nav>
+            <div class="cookie-banner">We use cookies &amp; tracking. <button>Accept</button></div>
+            <main>
+                <article>
+                    <h1>Understanding &lt;Ownership&gt;</h1>
+                    <p>Each value in Rust has an <em>owner</em>.
+                       There can only be one owner at a time.</p>
+                    <h2>Rules</h2>
+                    <ul>
+                        <li>Values are dropped when the owner goes out of scope</li>
+                        <li>Ownership can be moved
+                            <ol><li>by assignment</li><li>by passing to <code>fn</code></li></ol>
+                        </li>
+                    </ul>
+                    <blockquote><p>Borrowing lets you use a value without owning it.</p></blockquote>
+                    <pre>let s = String::from("hi");
+let t = s;</pre>
+                    <table>
+                        <tr><th>Type</th><th>Copy?</th></tr>
+                        <tr><td>i32</td><td>yes</td></tr>
+                    </table>
+                    <div class="share-links"><a href="/x">Share on X</a></div>
+                </article>
+                <aside>Related posts</aside>
+            </main>
+            <footer>© 2024 Example</footer>
+        </body>
+        </html>"#;
+
+    #[test]
+    fn extracts_article_as_markdown_without_boilerplate() {
+        let page = extract_page(ARTICLE);
+
+        assert_eq!(
+            page.content,
+            "# Understanding <Ownership>\n\n\
+             Each value in Rust has an owner. There can only be one owner at a time.\n\n\
+             ## Rules\n\n\
+             - Values are dropped when the owner goes out of scope\n\
+             - Ownership can be moved\n  \
+               1. by assignment\n  \
+               2. by passing to `fn`\n\n\
+             > Borrowing lets you use a value without owning it.\n\n\
+             ```\nlet s = String::from(\"hi\");\nlet t = s;\n```\n\n\
+             | Type | Copy? |\n| --- | --- |\n| i32 | yes |"
+        );
+    }
+
+    #[test]
+    fn captures_title_and_metadata() {
+        let page = extract_page(ARTICLE);
+
+        assert_eq!(page.title.as_deref(), Some("Rust Ownership | Example Docs"));
+        assert_eq!(page.description.as_deref(), Some("How ownership works"));
+        assert_eq!(page.author.as_deref(), Some("Ferris"));
+        assert_eq!(page.site_name.as_deref(), Some("Example Docs"));
+        assert_eq!(page.language.as_deref(), Some("en"));
+        assert_eq!(
+            page.canonical_url.as_deref(),
+            Some("https://docs.example.com/ownership")
+        );
+        assert!(page
+            .to_markdown()
+            .starts_with("# Rust Ownership | Example Docs\n\nSite: Example Docs\n"));
+    }
+
+    #[test]
+    fn scores_paragraph_containers_without_semantic_markup() {
+        let html = r#"<html><body>
+            <div id="menu"><ul><li><a href="/a">A link</a></li><li><a href="/b">B link</a></li></ul></div>
+            <div class="wrapper">
+                <div class="post-body">
+                    <p>This is the first long paragraph of the story, with plenty of words in it.</p>
+                    <p>This is the second long paragraph of the story, again with plenty of words.</p>
+                </div>
+            </div>
+            <div class="footer-links"><p>Terms of service and a privacy policy for this site.</p></div>
+        </body></html>"#;
+
+        let page = extract_page(html);
+
+        assert!(page.content.starts_with("This is the first long paragraph"));
+        assert!(!page.content.contains("A link"));
+        assert!(!page.content.contains("Terms of service"));
+    }
+
+    #[test]
+    fn truncates_on_char_boundary() {
+        let mut text = "héllo".to_string();
+        assert!(truncate_at_char_boundary(&mut text, 2));
+        assert_eq!(text, "h");
+
+        let mut short = "abc".to_string();
+        assert!(!truncate_at_char_boundary(&mut short, 10));
+        assert_eq!(short, "abc");
+    }
+}
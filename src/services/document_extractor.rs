@@ -0,0 +1,298 @@
+use std::path::Path;
+
+use crate::{
+    errors::{AppError, AppResult},
+    services::content_extractor::{extract_page, ExtractedPage},
+};
+
+/// A file uploaded as a quiz source
+#[derive(Debug, Clone)]
+pub struct DocumentUpload {
+    pub file_name: String,
+    /// MIME type declared by the client; only trusted once the contents agree
+    pub content_type: Option<String>,
+    pub bytes: Vec<u8>,
+}
+
+/// File types quizzes can be generated from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DocumentFormat {
+    Pdf,
+    Markdown,
+    PlainText,
+    Html,
+}
+
+impl DocumentFormat {
+    /// Work out the format of an upload from its contents, rejecting files
+    /// whose contents don't match the type given by their name or MIME type
+    pub fn sniff(file_name: &str, content_type: Option<&str>, bytes: &[u8]) -> AppResult<Self> {
+        let claimed = Self::from_file_name(file_name)
+            .or_else(|| content_type.and_then(Self::from_mime_type))
+            .ok_or_else(|| {
+                AppError::UnsupportedMediaType(
+                    "Only PDF, Markdown, plain text and HTML files can be uploaded".to_string(),
+                )
+            })?;
+
+        let detected = if bytes.starts_with(b"%PDF-") {
+            Self::Pdf
+        } else {
+            let text = std::str::from_utf8(bytes)
+                .ok()
+                .filter(|text| !text.contains('\0'))
+                .ok_or_else(|| {
+                    AppError::UnsupportedMediaType(format!(
+                        "{} is not a PDF or UTF-8 text file",
+                        file_name
+                    ))
+                })?;
+            // Fragments only count as HTML when that's what the upload claims to be
+            if looks_like_html(text) || (claimed == Self::Html && contains_tag(text)) {
+                Self::Html
+            } else {
+                Self::PlainText
+            }
+        };
+
+        match (claimed, detected) {
+            (Self::Markdown, Self::PlainText) => Ok(Self::Markdown),
+            (claimed, detected) if claimed == detected => Ok(claimed),
+            _ => Err(AppError::UnsupportedMediaType(format!(
+                "Contents of {} do not match its {} file type",
+                file_name,
+                claimed.label()
+            ))),
+        }
+    }
+
+    fn from_file_name(file_name: &str) -> Option<Self> {
+        let extension = Path::new(file_name).extension()?.to_str()?;
+        match extension.to_ascii_lowercase().as_str() {
+            "pdf" => Some(Self::Pdf),
+            "md" | "markdown" => Some(Self::Markdown),
+            "txt" | "text" => Some(Self::PlainText),
+            "html" | "htm" => Some(Self::Html),
+            _ => None,
+        }
+    }
+
+    fn from_mime_type(content_type: &str) -> Option<Self> {
+        let essence = content_type.split(';').next()?.trim();
+        match essence.to_ascii_lowercase().as_str() {
+            "application/pdf" => Some(Self::Pdf),
+            "text/markdown" | "text/x-markdown" => Some(Self::Markdown),
+            "text/plain" => Some(Self::PlainText),
+            "text/html" => Some(Self::Html),
+            _ => None,
+        }
+    }
+
+    fn label(&self) -> &'static str {
+        match self {
+            Self::Pdf => "PDF",
+            Self::Markdown => "Markdown",
+            Self::PlainText => "plain text",
+            Self::Html => "HTML",
+        }
+    }
+}
+
+/// Whether `text` starts like a whole HTML document, after any comments and
+/// XML declaration
+fn looks_like_html(text: &str) -> bool {
+    let mut start = text.trim_start_matches('\u{feff}').trim_start();
+    loop {
+        let end_marker = if start.starts_with("<!--") {
+            "-->"
+        } else if start
+            .get(..5)
+            .is_some_and(|s| s.eq_ignore_ascii_case("<?xml"))
+        {
+            "?>"
+        } else {
+            break;
+        };
+        match start.find(end_marker) {
+            Some(end) => start = start[end + end_marker.len()..].trim_start(),
+            None => return false,
+        }
+    }
+    let prefix = start
+        .chars()
+        .take(16)
+        .collect::<String>()
+        .to_ascii_lowercase();
+    ["<!doctype html", "<html", "<head", "<body"]
+        .iter()
+        .any(|tag| prefix.starts_with(tag))
+}
+
+/// Whether `text` contains at least one opening or closing tag
+fn contains_tag(text: &str) -> bool {
+    text.match_indices('<').any(|(at, _)| {
+        let rest = &text[at + 1..];
+        rest.trim_start_matches('/')
+            .starts_with(|c: char| c.is_ascii_alphabetic())
+            && rest.contains('>')
+    })
+}
+
+/// Extract the text of an uploaded document as markdown. Documents without a
+/// title of their own are titled after the file name.
+pub async fn extract_document(
+    format: DocumentFormat,
+    file_name: &str,
+    bytes: Vec<u8>,
+) -> AppResult<ExtractedPage> {
+    let mut page = match format {
+        DocumentFormat::Pdf => {
+            // pdf-extract is CPU bound and can panic on malformed files
+            let text =
+                tokio::task::spawn_blocking(move || pdf_extract::extract_text_from_mem(&bytes))
+                    .await
+                    .map_err(|_| {
+                        AppError::ValidationError(format!("{} could not be read", file_name))
+                    })?
+                    .map_err(|e| {
+                        AppError::ValidationError(format!("{} could not be read: {}", file_name, e))
+                    })?;
+            ExtractedPage {
+                content: normalise_text(&text),
+                ..Default::default()
+            }
+        }
+        DocumentFormat::Html => extract_page(&decode_text(bytes, file_name)?),
+        DocumentFormat::Markdown | DocumentFormat::PlainText => ExtractedPage {
+            content: normalise_text(&decode_text(bytes, file_name)?),
+            ..Default::default()
+        },
+    };
+
+    if page.content.trim().is_empty() {
+        return Err(AppError::ValidationError(format!(
+            "No text could be extracted from {}",
+            file_name
+        )));
+    }
+
+    // Markdown files usually carry their own headings
+    if page.title.is_none() && format != DocumentFormat::Markdown {
+        page.title = Path::new(file_name)
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .map(str::to_string);
+    }
+    Ok(page)
+}
+
+fn decode_text(bytes: Vec<u8>, file_name: &str) -> AppResult<String> {
+    String::from_utf8(bytes)
+        .map_err(|_| AppError::UnsupportedMediaType(format!("{} is not UTF-8 text", file_name)))
+}
+
+/// Normalise line endings, drop trailing spaces and collapse runs of blank
+/// lines left behind by PDF page breaks
+fn normalise_text(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut blank_lines = 0;
+    for line in text.trim_start_matches('\u{feff}').lines() {
+        let line = line.trim_end();
+        if line.is_empty() {
+            blank_lines += 1;
+            continue;
+        }
+        if !out.is_empty() {
+            out.push_str(if blank_lines > 0 { "\n\n" } else { "\n" });
+        }
+        out.push_str(line);
+        blank_lines = 0;
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sniff_checks_contents_against_declared_type() {
+        assert_eq!(
+            DocumentFormat::sniff("notes.md", None, b"# Notes\n\nText").unwrap(),
+            DocumentFormat::Markdown
+        );
+        assert_eq!(
+            DocumentFormat::sniff(
+                "upload",
+                Some("text/html; charset=utf-8"),
+                b"<!DOCTYPE html><p>Hi</p>"
+            )
+            .unwrap(),
+            DocumentFormat::Html
+        );
+        assert_eq!(
+            DocumentFormat::sniff("paper.PDF", None, b"%PDF-1.7\n...").unwrap(),
+            DocumentFormat::Pdf
+        );
+        for (name, bytes) in [
+            (
+                "saved.html",
+                b"<!-- saved from url -->\n<!DOCTYPE html><p>Hi</p>".as_slice(),
+            ),
+            (
+                "page.xhtml.htm",
+                b"<?xml version=\"1.0\"?>\n<html xmlns=\"http://www.w3.org/1999/xhtml\"></html>",
+            ),
+            ("fragment.html", b"<div>Hi</div>"),
+            ("article.htm", b"\n  <article><p>Hi</p></article>"),
+            ("heading.html", b"<h1>Title</h1>\nBody"),
+        ] {
+            assert_eq!(
+                DocumentFormat::sniff(name, None, bytes).unwrap(),
+                DocumentFormat::Html,
+                "{} should be accepted",
+                name
+            );
+        }
+        assert_eq!(
+            DocumentFormat::sniff("notes.md", None, b"Text with <em>inline</em> HTML").unwrap(),
+            DocumentFormat::Markdown
+        );
+
+        for (name, bytes) in [
+            ("paper.pdf", b"plain text".as_slice()),
+            ("notes.txt", b"%PDF-1.4"),
+            ("notes.txt", b"<html><body>Hi</body></html>"),
+            ("notes.md", b"<!-- draft -->\n<!doctype html><p>Hi</p>"),
+            ("page.html", b"a < b, with no markup at all"),
+            ("image.png", b"\x89PNG\r\n"),
+            ("notes.txt", b"bin\0ary"),
+        ] {
+            assert!(
+                matches!(
+                    DocumentFormat::sniff(name, None, bytes),
+                    Err(AppError::UnsupportedMediaType(_))
+                ),
+                "{} should be rejected",
+                name
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn extracts_text_documents_with_file_name_title() {
+        let page = extract_document(
+            DocumentFormat::PlainText,
+            "release-notes.txt",
+            b"\xef\xbb\xbfFirst line  \r\nsecond line\r\n\r\n\r\n\r\nNext paragraph".to_vec(),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(page.title.as_deref(), Some("release-notes"));
+        assert_eq!(page.content, "First line\nsecond line\n\nNext paragraph");
+
+        let empty = extract_document(DocumentFormat::Markdown, "empty.md", b"  \n".to_vec()).await;
+        assert!(matches!(empty, Err(AppError::ValidationError(_))));
+    }
+}
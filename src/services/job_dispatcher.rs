@@ -0,0 +1,144 @@
+use std::collections::HashSet;
+use std::sync::Mutex;
+
+use tokio::sync::{mpsc, watch, Mutex as AsyncMutex};
+
+/// In-process queue of job ids that are ready for their next step.
+///
+/// Producers (`start_job`, `resume_job`, the fallback sweep) push job ids with
+/// [`JobDispatcher::notify`]; a pool of executors pulls them with
+/// [`JobDispatcher::next`]. A job id is only handed to one executor at a time.
+/// Once [`JobDispatcher::close`] is called executors stop receiving work.
+pub struct JobDispatcher {
+    sender: mpsc::UnboundedSender<String>,
+    receiver: AsyncMutex<mpsc::UnboundedReceiver<String>>,
+    in_flight: Mutex<HashSet<String>>,
+    closed: watch::Sender<bool>,
+}
+
+impl JobDispatcher {
+    pub fn new() -> Self {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        Self {
+            sender,
+            receiver: AsyncMutex::new(receiver),
+            in_flight: Mutex::new(HashSet::new()),
+            closed: watch::Sender::new(false),
+        }
+    }
+
+    /// Wake an executor for the given job
+    pub fn notify(&self, job_id: impl Into<String>) {
+        if self.sender.send(job_id.into()).is_err() {
+            log::warn!("Job dispatcher channel closed; notification dropped");
+        }
+    }
+
+    /// Wait for the next job id that is not already being processed.
+    ///
+    /// The returned id is marked in flight until [`JobDispatcher::release`] is called.
+    /// Returns `None` once the dispatcher is closed, even if notifications are queued.
+    pub async fn next(&self) -> Option<String> {
+        let mut closed = self.closed.subscribe();
+        loop {
+            let job_id = tokio::select! {
+                biased;
+                _ = closed.wait_for(|closed| *closed) => return None,
+                job_id = async { self.receiver.lock().await.recv().await } => job_id?,
+            };
+            if self.try_claim(&job_id) {
+                return Some(job_id);
+            }
+            log::debug!("Job {} already in flight, skipping notification", job_id);
+        }
+    }
+
+    /// Stop handing out work. Executors waiting in [`JobDispatcher::next`] return `None`.
+    pub fn close(&self) {
+        self.closed.send_replace(true);
+    }
+
+    pub fn is_closed(&self) -> bool {
+        *self.closed.borrow()
+    }
+
+    /// Job ids currently claimed by an executor
+    pub fn in_flight_jobs(&self) -> Vec<String> {
+        self.lock_in_flight().iter().cloned().collect()
+    }
+
+    pub fn release(&self, job_id: &str) {
+        self.lock_in_flight().remove(job_id);
+    }
+
+    pub fn is_in_flight(&self, job_id: &str) -> bool {
+        self.lock_in_flight().contains(job_id)
+    }
+
+    fn try_claim(&self, job_id: &str) -> bool {
+        self.lock_in_flight().insert(job_id.to_string())
+    }
+
+    fn lock_in_flight(&self) -> std::sync::MutexGuard<'_, HashSet<String>> {
+        self.in_flight
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl Default for JobDispatcher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn next_returns_notified_job_and_marks_it_in_flight() {
+        let dispatcher = JobDispatcher::new();
+        dispatcher.notify("job-1");
+
+        let job_id = dispatcher.next().await.expect("expected a job id");
+
+        assert_eq!(job_id, "job-1");
+        assert!(dispatcher.is_in_flight("job-1"));
+
+        dispatcher.release("job-1");
+        assert!(!dispatcher.is_in_flight("job-1"));
+    }
+
+    #[tokio::test]
+    async fn next_skips_duplicate_notifications_for_in_flight_job() {
+        let dispatcher = JobDispatcher::new();
+        dispatcher.notify("job-1");
+        dispatcher.notify("job-1");
+        dispatcher.notify("job-2");
+
+        let first = dispatcher.next().await.expect("expected first job");
+        let second = dispatcher.next().await.expect("expected second job");
+
+        assert_eq!(first, "job-1");
+        assert_eq!(second, "job-2");
+    }
+
+    #[tokio::test]
+    async fn close_wakes_waiting_executor_and_drops_queued_work() {
+        let dispatcher = std::sync::Arc::new(JobDispatcher::new());
+
+        let waiting = tokio::spawn({
+            let dispatcher = dispatcher.clone();
+            async move { dispatcher.next().await }
+        });
+        tokio::task::yield_now().await;
+        dispatcher.close();
+
+        assert_eq!(waiting.await.expect("executor task panicked"), None);
+
+        dispatcher.notify("job-1");
+        assert!(dispatcher.is_closed());
+        assert_eq!(dispatcher.next().await, None);
+    }
+}
@@ -0,0 +1,167 @@
+use async_graphql::{Enum, SimpleObject};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use tokio::sync::broadcast;
+
+use crate::models::domain::quiz::QuizStatus;
+use crate::services::agent_orchestrator_service::{AgentJob, JobStatus};
+
+const JOB_EVENT_CHANNEL_CAPACITY: usize = 256;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Enum)]
+#[serde(rename_all = "snake_case")]
+pub enum JobProgressEventKind {
+    /// Current state of the job at the moment a subscriber attached
+    Snapshot,
+    StepCompleted,
+    StepFailed,
+    JobCancelled,
+    /// The quiz was finalized and is ready to take
+    QuizReady,
+    /// The stored job moved on without an event reaching this instance, as
+    /// when another instance ran the step
+    StateChanged,
+}
+
+#[derive(Debug, Clone, Serialize, SimpleObject)]
+#[graphql(rename_fields = "snake_case")]
+pub struct JobProgressEvent {
+    pub job_id: String,
+    pub kind: JobProgressEventKind,
+    pub status: JobStatus,
+    pub quiz_id: Option<String>,
+    pub quiz_status: Option<QuizStatus>,
+    pub step_id: Option<String>,
+    pub step_name: Option<String>,
+    pub current_step_index: usize,
+    pub total_steps: usize,
+    pub error: Option<String>,
+    pub next_attempt_at: Option<DateTime<Utc>>,
+    pub emitted_at: DateTime<Utc>,
+}
+
+impl JobProgressEvent {
+    /// Build an event from the job as it was when the step started
+    pub fn new(job: &AgentJob, kind: JobProgressEventKind) -> Self {
+        let step = job.get_current_step();
+        Self {
+            job_id: job.job_id.clone(),
+            kind,
+            status: job.status,
+            quiz_id: job.quiz_id().map(str::to_string),
+            quiz_status: None,
+            step_id: step.map(|s| s.id.clone()),
+            step_name: step.map(|s| s.name.clone()),
+            current_step_index: job.current_step_index,
+            total_steps: job.steps.len(),
+            error: job.error_message.clone(),
+            next_attempt_at: job.next_attempt_at,
+            emitted_at: Utc::now(),
+        }
+    }
+
+    pub fn with_status(mut self, status: JobStatus) -> Self {
+        self.status = status;
+        self
+    }
+
+    pub fn with_current_step_index(mut self, index: usize) -> Self {
+        self.current_step_index = index;
+        self
+    }
+
+    pub fn with_quiz_status(mut self, quiz_status: QuizStatus) -> Self {
+        self.quiz_status = Some(quiz_status);
+        self
+    }
+
+    pub fn with_error(mut self, error: impl Into<String>) -> Self {
+        self.error = Some(error.into());
+        self
+    }
+
+    pub fn with_next_attempt_at(mut self, next_attempt_at: Option<DateTime<Utc>>) -> Self {
+        self.next_attempt_at = next_attempt_at;
+        self
+    }
+
+    /// Whether no further events will be published for this job
+    pub fn is_terminal(&self) -> bool {
+        self.kind == JobProgressEventKind::QuizReady
+            || matches!(
+                self.status,
+                JobStatus::Completed | JobStatus::Failed | JobStatus::Cancelled
+            )
+    }
+}
+
+/// Fan-out of job progress events to live subscribers.
+///
+/// Events are not persisted and only reach subscribers on the instance that
+/// ran the step; a subscriber only sees events published after it subscribed,
+/// and a slow subscriber may miss events once the channel is full. Progress
+/// subscriptions also poll the stored job to cover what they miss.
+pub struct JobEventBus {
+    sender: broadcast::Sender<JobProgressEvent>,
+}
+
+impl JobEventBus {
+    pub fn new() -> Self {
+        let (sender, _) = broadcast::channel(JOB_EVENT_CHANNEL_CAPACITY);
+        Self { sender }
+    }
+
+    pub fn publish(&self, event: JobProgressEvent) {
+        // An error only means nobody is listening right now
+        let _ = self.sender.send(event);
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<JobProgressEvent> {
+        self.sender.subscribe()
+    }
+}
+
+impl Default for JobEventBus {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::agent_orchestrator_service::JobStep;
+
+    #[tokio::test]
+    async fn subscribers_receive_published_events() {
+        let bus = JobEventBus::new();
+        let mut receiver = bus.subscribe();
+        let job = AgentJob::new(vec![JobStep::new("create_quiz_draft")]);
+
+        bus.publish(JobProgressEvent::new(
+            &job,
+            JobProgressEventKind::StepCompleted,
+        ));
+
+        let event = receiver.recv().await.expect("expected an event");
+        assert_eq!(event.job_id, job.job_id);
+        assert_eq!(event.kind, JobProgressEventKind::StepCompleted);
+        assert_eq!(event.step_name.as_deref(), Some("create_quiz_draft"));
+    }
+
+    #[test]
+    fn quiz_ready_and_finished_jobs_are_terminal() {
+        let mut job = AgentJob::new(vec![JobStep::new("finalize_quiz")]);
+        job.status = JobStatus::Running;
+
+        let step_completed = JobProgressEvent::new(&job, JobProgressEventKind::StepCompleted);
+        assert!(!step_completed.is_terminal());
+
+        let quiz_ready = JobProgressEvent::new(&job, JobProgressEventKind::QuizReady);
+        assert!(quiz_ready.is_terminal());
+
+        let failed = JobProgressEvent::new(&job, JobProgressEventKind::StepFailed)
+            .with_status(JobStatus::Failed);
+        assert!(failed.is_terminal());
+    }
+}
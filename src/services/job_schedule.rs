@@ -0,0 +1,177 @@
+use chrono::{DateTime, Duration, NaiveTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// When a schedule fires. Times are UTC.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ScheduleTrigger {
+    /// Every `seconds`, measured from the previous run
+    Interval { seconds: u64 },
+    /// Once a day at a fixed time, equivalent to the cron expression `M H * * *`
+    DailyAt { hour: u32, minute: u32 },
+}
+
+impl ScheduleTrigger {
+    pub fn validate(&self) -> Result<(), String> {
+        match *self {
+            ScheduleTrigger::Interval { seconds: 0 } => {
+                Err("Schedule interval must be at least one second".to_string())
+            }
+            ScheduleTrigger::DailyAt { hour, minute } if hour > 23 || minute > 59 => Err(format!(
+                "Invalid daily schedule time {:02}:{:02}",
+                hour, minute
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    /// First time strictly after `after` at which the schedule should fire
+    pub fn next_after(&self, after: DateTime<Utc>) -> DateTime<Utc> {
+        match *self {
+            ScheduleTrigger::Interval { seconds } => after + Duration::seconds(seconds as i64),
+            ScheduleTrigger::DailyAt { hour, minute } => {
+                let time = NaiveTime::from_hms_opt(hour, minute, 0).unwrap_or(NaiveTime::MIN);
+                let today = after.date_naive().and_time(time).and_utc();
+                if today > after {
+                    today
+                } else {
+                    today + Duration::days(1)
+                }
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for ScheduleTrigger {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ScheduleTrigger::Interval { seconds } => write!(f, "every {}s", seconds),
+            ScheduleTrigger::DailyAt { hour, minute } => {
+                write!(f, "daily at {:02}:{:02} UTC", hour, minute)
+            }
+        }
+    }
+}
+
+/// Job results key holding the name of the schedule that enqueued the job
+pub const SCHEDULE_NAME_KEY: &str = "schedule_name";
+
+/// A persisted schedule that enqueues a pipeline job each time it fires
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JobSchedule {
+    /// Unique key, e.g. `purge_expired_refresh_tokens` or `regenerate_quiz:<quiz_id>`
+    pub name: String,
+    pub pipeline: String,
+    pub trigger: ScheduleTrigger,
+    /// Seeded into the results of every job the schedule creates
+    #[serde(default)]
+    pub metadata: HashMap<String, serde_json::Value>,
+    pub enabled: bool,
+    /// Stored as epoch milliseconds so due schedules can be queried
+    #[serde(with = "chrono::serde::ts_milliseconds")]
+    pub next_run_at: DateTime<Utc>,
+    #[serde(default, with = "chrono::serde::ts_milliseconds_option")]
+    pub last_run_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub last_job_id: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl JobSchedule {
+    pub fn new(
+        name: impl Into<String>,
+        pipeline: impl Into<String>,
+        trigger: ScheduleTrigger,
+    ) -> Self {
+        let now = Utc::now();
+        Self {
+            name: name.into(),
+            pipeline: pipeline.into(),
+            trigger,
+            metadata: HashMap::new(),
+            enabled: true,
+            next_run_at: trigger.next_after(now),
+            last_run_at: None,
+            last_job_id: None,
+            created_at: now,
+        }
+    }
+
+    pub fn with_metadata(mut self, key: impl Into<String>, value: serde_json::Value) -> Self {
+        self.metadata.insert(key.into(), value);
+        self
+    }
+
+    pub fn is_due(&self, now: DateTime<Utc>) -> bool {
+        self.enabled && self.next_run_at <= now
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn interval_trigger_adds_interval() {
+        let now = Utc.with_ymd_and_hms(2026, 1, 1, 12, 0, 0).unwrap();
+        let trigger = ScheduleTrigger::Interval { seconds: 3600 };
+
+        assert_eq!(
+            trigger.next_after(now),
+            Utc.with_ymd_and_hms(2026, 1, 1, 13, 0, 0).unwrap()
+        );
+    }
+
+    #[test]
+    fn daily_trigger_fires_later_today_or_tomorrow() {
+        let trigger = ScheduleTrigger::DailyAt {
+            hour: 3,
+            minute: 30,
+        };
+
+        let before = Utc.with_ymd_and_hms(2026, 1, 1, 1, 0, 0).unwrap();
+        assert_eq!(
+            trigger.next_after(before),
+            Utc.with_ymd_and_hms(2026, 1, 1, 3, 30, 0).unwrap()
+        );
+
+        let exactly = Utc.with_ymd_and_hms(2026, 1, 1, 3, 30, 0).unwrap();
+        assert_eq!(
+            trigger.next_after(exactly),
+            Utc.with_ymd_and_hms(2026, 1, 2, 3, 30, 0).unwrap()
+        );
+    }
+
+    #[test]
+    fn validate_rejects_zero_interval_and_out_of_range_times() {
+        assert!(ScheduleTrigger::Interval { seconds: 0 }.validate().is_err());
+        assert!(ScheduleTrigger::DailyAt {
+            hour: 24,
+            minute: 0
+        }
+        .validate()
+        .is_err());
+        assert!(ScheduleTrigger::DailyAt {
+            hour: 23,
+            minute: 59
+        }
+        .validate()
+        .is_ok());
+    }
+
+    #[test]
+    fn disabled_schedule_is_never_due() {
+        let mut schedule = JobSchedule::new(
+            "purge",
+            "purge_expired_refresh_tokens",
+            ScheduleTrigger::Interval { seconds: 60 },
+        );
+        let later = schedule.next_run_at + Duration::seconds(1);
+
+        assert!(schedule.is_due(later));
+        schedule.enabled = false;
+        assert!(!schedule.is_due(later));
+    }
+}
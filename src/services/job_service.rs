@@ -0,0 +1,302 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::{
+    auth::{require_admin, Claims},
+    errors::{AppError, AppResult},
+    models::{
+        domain::user::UserRole,
+        dto::response::{DeleteResponse, JobProgressResponse, JobScheduleResponse},
+    },
+    services::{
+        agent_orchestrator_service::{AgentJob, AgentOrchestrator, DeadLetterJob, JobStatus},
+        job_schedule::JobSchedule,
+        quiz_service::QuizService,
+    },
+};
+
+/// User-facing access to agent jobs, scoped to the quiz each job generates
+pub struct JobService {
+    orchestrator: Arc<AgentOrchestrator>,
+    quiz_service: Arc<QuizService>,
+}
+
+impl JobService {
+    pub fn new(orchestrator: Arc<AgentOrchestrator>, quiz_service: Arc<QuizService>) -> Self {
+        Self {
+            orchestrator,
+            quiz_service,
+        }
+    }
+
+    /// Fetch a job, allowing only admins or the owner of the job's quiz
+    pub async fn get_job_for_user(&self, job_id: &str, claims: &Claims) -> AppResult<AgentJob> {
+        let job = self.fetch_job(job_id).await?;
+
+        if claims.role == UserRole::Admin {
+            return Ok(job);
+        }
+
+        let quiz_id = job
+            .quiz_id()
+            .ok_or_else(|| AppError::Forbidden("Only admins can access this job".to_string()))?;
+
+        let quiz = self.quiz_service.get_quiz(quiz_id).await?;
+        if quiz.created_by_user_id != claims.sub {
+            return Err(AppError::Forbidden(
+                "You can only access jobs for quizzes you created".to_string(),
+            ));
+        }
+
+        Ok(job)
+    }
+
+    pub async fn get_job_progress(
+        &self,
+        job_id: &str,
+        claims: &Claims,
+    ) -> AppResult<JobProgressResponse> {
+        let job = self.get_job_for_user(job_id, claims).await?;
+        Ok(JobProgressResponse::from(job))
+    }
+
+    pub async fn cancel_job(
+        &self,
+        job_id: &str,
+        claims: &Claims,
+    ) -> AppResult<JobProgressResponse> {
+        let job = self.get_job_for_user(job_id, claims).await?;
+
+        if !job.status.is_cancellable() {
+            return Err(AppError::BadRequest(format!(
+                "Job is already {} and cannot be cancelled",
+                job.status
+            )));
+        }
+
+        self.orchestrator
+            .cancel_job(job_id)
+            .await
+            .map_err(AppError::InternalError)?;
+
+        self.progress(job_id).await
+    }
+
+    pub async fn pause_job(&self, job_id: &str, claims: &Claims) -> AppResult<JobProgressResponse> {
+        let job = self.get_job_for_user(job_id, claims).await?;
+
+        if job.status != JobStatus::Running {
+            return Err(AppError::BadRequest(format!(
+                "Only running jobs can be paused; job is {}",
+                job.status
+            )));
+        }
+
+        self.orchestrator
+            .pause_job(job_id)
+            .await
+            .map_err(AppError::InternalError)?;
+
+        self.progress(job_id).await
+    }
+
+    pub async fn resume_job(
+        &self,
+        job_id: &str,
+        claims: &Claims,
+    ) -> AppResult<JobProgressResponse> {
+        let job = self.get_job_for_user(job_id, claims).await?;
+
+        if job.status != JobStatus::Paused {
+            return Err(AppError::BadRequest(format!(
+                "Only paused jobs can be resumed; job is {}",
+                job.status
+            )));
+        }
+
+        self.orchestrator
+            .resume_job(job_id)
+            .await
+            .map_err(AppError::InternalError)?;
+
+        self.progress(job_id).await
+    }
+
+    pub async fn retry_job(&self, job_id: &str, claims: &Claims) -> AppResult<JobProgressResponse> {
+        let job = self.get_job_for_user(job_id, claims).await?;
+
+        if !job.status.is_retryable() {
+            return Err(AppError::BadRequest(format!(
+                "Only failed or cancelled jobs can be retried; job is {}",
+                job.status
+            )));
+        }
+
+        self.orchestrator
+            .retry_job(job_id)
+            .await
+            .map_err(AppError::InternalError)?;
+        self.reset_quiz(&job).await?;
+
+        self.progress(job_id).await
+    }
+
+    /// Admin view of jobs that exhausted their retries
+    pub async fn list_dead_letters(
+        &self,
+        claims: &Claims,
+        include_replayed: bool,
+        offset: i64,
+        limit: i64,
+    ) -> AppResult<Vec<DeadLetterJob>> {
+        require_admin(claims)?;
+
+        self.orchestrator
+            .list_dead_letters(include_replayed, offset, limit)
+            .await
+            .map_err(AppError::InternalError)
+    }
+
+    /// Admin replay of a dead-lettered job from its failed step. When
+    /// `results` is given it replaces the job's accumulated results.
+    pub async fn replay_dead_letter(
+        &self,
+        job_id: &str,
+        results: Option<HashMap<String, serde_json::Value>>,
+        claims: &Claims,
+    ) -> AppResult<JobProgressResponse> {
+        require_admin(claims)?;
+
+        self.orchestrator
+            .get_dead_letter(job_id)
+            .await
+            .map_err(AppError::InternalError)?
+            .ok_or_else(|| {
+                AppError::NotFound(format!("Job '{}' is not in the dead-letter queue", job_id))
+            })?;
+
+        let job = self.fetch_job(job_id).await?;
+        if !job.status.is_retryable() {
+            return Err(AppError::BadRequest(format!(
+                "Job is {} and cannot be replayed",
+                job.status
+            )));
+        }
+
+        self.orchestrator
+            .replay_dead_letter(job_id, results)
+            .await
+            .map_err(AppError::InternalError)?;
+        self.reset_quiz(&job).await?;
+
+        self.progress(job_id).await
+    }
+
+    /// Admin view of every persisted job schedule
+    pub async fn list_schedules(&self, claims: &Claims) -> AppResult<Vec<JobScheduleResponse>> {
+        require_admin(claims)?;
+
+        let schedules = self
+            .orchestrator
+            .list_schedules()
+            .await
+            .map_err(AppError::InternalError)?;
+        Ok(schedules
+            .into_iter()
+            .map(JobScheduleResponse::from)
+            .collect())
+    }
+
+    pub async fn create_schedule(
+        &self,
+        schedule: JobSchedule,
+        claims: &Claims,
+    ) -> AppResult<JobScheduleResponse> {
+        require_admin(claims)?;
+
+        self.orchestrator
+            .validate_schedule(&schedule)
+            .map_err(AppError::ValidationError)?;
+
+        if self
+            .orchestrator
+            .get_schedule(&schedule.name)
+            .await
+            .map_err(AppError::InternalError)?
+            .is_some()
+        {
+            return Err(AppError::BadRequest(format!(
+                "Schedule '{}' already exists",
+                schedule.name
+            )));
+        }
+
+        let name = schedule.name.clone();
+        self.orchestrator
+            .create_schedule(schedule)
+            .await
+            .map_err(AppError::InternalError)?;
+
+        self.schedule(&name).await
+    }
+
+    pub async fn set_schedule_enabled(
+        &self,
+        name: &str,
+        enabled: bool,
+        claims: &Claims,
+    ) -> AppResult<JobScheduleResponse> {
+        require_admin(claims)?;
+        self.schedule(name).await?;
+
+        self.orchestrator
+            .set_schedule_enabled(name, enabled)
+            .await
+            .map_err(AppError::InternalError)?;
+
+        self.schedule(name).await
+    }
+
+    pub async fn delete_schedule(&self, name: &str, claims: &Claims) -> AppResult<DeleteResponse> {
+        require_admin(claims)?;
+        self.schedule(name).await?;
+
+        self.orchestrator
+            .delete_schedule(name)
+            .await
+            .map_err(AppError::InternalError)?;
+
+        Ok(DeleteResponse {
+            message: format!("Schedule '{}' deleted", name),
+        })
+    }
+
+    async fn schedule(&self, name: &str) -> AppResult<JobScheduleResponse> {
+        self.orchestrator
+            .get_schedule(name)
+            .await
+            .map_err(AppError::InternalError)?
+            .map(JobScheduleResponse::from)
+            .ok_or_else(|| AppError::NotFound(format!("Schedule '{}' not found", name)))
+    }
+
+    async fn reset_quiz(&self, job: &AgentJob) -> AppResult<()> {
+        match job.quiz_id() {
+            Some(quiz_id) => self.quiz_service.reset_failed_generation(quiz_id).await,
+            None => Ok(()),
+        }
+    }
+
+    async fn progress(&self, job_id: &str) -> AppResult<JobProgressResponse> {
+        let job = self.fetch_job(job_id).await?;
+        Ok(JobProgressResponse::from(job))
+    }
+
+    async fn fetch_job(&self, job_id: &str) -> AppResult<AgentJob> {
+        self.orchestrator
+            .get_job(job_id)
+            .await
+            .map_err(AppError::InternalError)?
+            .ok_or_else(|| AppError::NotFound(format!("Job with id '{}' not found", job_id)))
+    }
+}
@@ -0,0 +1,269 @@
+use async_trait::async_trait;
+use secrecy::{ExposeSecret, SecretString};
+use serde_json::{json, Value};
+
+use super::{LlmMessage, LlmProvider, LlmRequest, LlmResponse, LlmRole, LlmToolCall, LlmUsage};
+use crate::errors::{AppError, AppResult};
+
+const ANTHROPIC_VERSION: &str = "2023-06-01";
+/// The Messages API requires `max_tokens` on every request
+const DEFAULT_MAX_TOKENS: u32 = 4096;
+/// Forced tool used to get schema-conforming JSON back when no other tools are offered
+const STRUCTURED_OUTPUT_TOOL: &str = "structured_output";
+
+/// Anthropic-style Messages API (`/v1/messages`)
+pub struct AnthropicProvider {
+    client: reqwest::Client,
+    base_url: String,
+    api_key: SecretString,
+}
+
+impl AnthropicProvider {
+    pub fn new(base_url: &str, api_key: &SecretString) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            base_url: base_url.trim_end_matches('/').to_string(),
+            api_key: api_key.clone(),
+        }
+    }
+
+    fn request_body(request: &LlmRequest) -> Value {
+        let mut system: Vec<String> = request
+            .messages
+            .iter()
+            .filter(|m| m.role == LlmRole::System)
+            .filter_map(|m| m.content.clone())
+            .collect();
+
+        let mut tools: Vec<Value> = request
+            .tools
+            .iter()
+            .map(|tool| {
+                json!({
+                    "name": tool.name,
+                    "description": tool.description,
+                    "input_schema": tool.parameters,
+                })
+            })
+            .collect();
+
+        let mut tool_choice = None;
+        if let Some(schema) = &request.response_schema {
+            if tools.is_empty() {
+                tools.push(json!({
+                    "name": STRUCTURED_OUTPUT_TOOL,
+                    "description": format!("Return the {} as structured data", schema.name),
+                    "input_schema": schema.schema,
+                }));
+                tool_choice = Some(json!({ "type": "tool", "name": STRUCTURED_OUTPUT_TOOL }));
+            } else {
+                system.push(format!(
+                    "When you have finished using tools, reply with only a JSON object matching this schema:\n{}",
+                    schema.schema
+                ));
+            }
+        }
+
+        let mut body = json!({
+            "model": request.model,
+            "max_tokens": request.max_tokens.unwrap_or(DEFAULT_MAX_TOKENS),
+            "messages": Self::messages(&request.messages),
+        });
+        if !system.is_empty() {
+            body["system"] = json!(system.join("\n\n"));
+        }
+        if !tools.is_empty() {
+            body["tools"] = json!(tools);
+        }
+        if let Some(tool_choice) = tool_choice {
+            body["tool_choice"] = tool_choice;
+        }
+
+        body
+    }
+
+    /// Convert to content-block messages, merging consecutive messages from the
+    /// same side since the API expects user and assistant turns to alternate
+    fn messages(messages: &[LlmMessage]) -> Vec<Value> {
+        let mut converted: Vec<(&str, Vec<Value>)> = Vec::new();
+
+        for message in messages {
+            let (role, blocks) = match message.role {
+                LlmRole::System => continue,
+                LlmRole::User => ("user", Self::text_blocks(message)),
+                LlmRole::Tool => (
+                    "user",
+                    vec![json!({
+                        "type": "tool_result",
+                        "tool_use_id": message.tool_call_id,
+                        "content": message.content.clone().unwrap_or_default(),
+                    })],
+                ),
+                LlmRole::Assistant => {
+                    let mut blocks = Self::text_blocks(message);
+                    blocks.extend(message.tool_calls.iter().map(|call| {
+                        let input: Value =
+                            serde_json::from_str(&call.arguments).unwrap_or_else(|_| json!({}));
+                        json!({ "type": "tool_use", "id": call.id, "name": call.name, "input": input })
+                    }));
+                    ("assistant", blocks)
+                }
+            };
+
+            match converted.last_mut() {
+                Some((last_role, last_blocks)) if *last_role == role => last_blocks.extend(blocks),
+                _ => converted.push((role, blocks)),
+            }
+        }
+
+        converted
+            .into_iter()
+            .map(|(role, content)| json!({ "role": role, "content": content }))
+            .collect()
+    }
+
+    fn text_blocks(message: &LlmMessage) -> Vec<Value> {
+        message
+            .content
+            .iter()
+            .filter(|text| !text.is_empty())
+            .map(|text| json!({ "type": "text", "text": text }))
+            .collect()
+    }
+
+    fn parse_response(response: &Value) -> AppResult<LlmResponse> {
+        let blocks = response["content"]
+            .as_array()
+            .ok_or_else(|| AppError::LlmError("No response from LLM".to_string()))?;
+
+        let mut text = String::new();
+        let mut tool_calls = Vec::new();
+
+        for block in blocks {
+            match block["type"].as_str() {
+                Some("text") => text.push_str(block["text"].as_str().unwrap_or_default()),
+                Some("tool_use") if block["name"] == STRUCTURED_OUTPUT_TOOL => {
+                    text.push_str(&block["input"].to_string());
+                }
+                Some("tool_use") => tool_calls.push(LlmToolCall {
+                    id: block["id"].as_str().unwrap_or_default().to_string(),
+                    name: block["name"].as_str().unwrap_or_default().to_string(),
+                    arguments: block["input"].to_string(),
+                }),
+                _ => {}
+            }
+        }
+
+        Ok(LlmResponse {
+            content: (!text.is_empty()).then_some(text),
+            tool_calls,
+            usage: LlmUsage::from_fields(&response["usage"], "input_tokens", "output_tokens"),
+        })
+    }
+}
+
+#[async_trait]
+impl LlmProvider for AnthropicProvider {
+    fn name(&self) -> &'static str {
+        "anthropic"
+    }
+
+    async fn complete(&self, request: LlmRequest) -> AppResult<LlmResponse> {
+        let response = self
+            .client
+            .post(format!("{}/v1/messages", self.base_url))
+            .header("x-api-key", self.api_key.expose_secret())
+            .header("anthropic-version", ANTHROPIC_VERSION)
+            .json(&Self::request_body(&request))
+            .send()
+            .await
+            .map_err(|e| AppError::LlmError(format!("Anthropic request failed: {}", e)))?;
+
+        let status = response.status();
+        let body: Value = response
+            .json()
+            .await
+            .map_err(|e| AppError::LlmError(format!("Invalid Anthropic response: {}", e)))?;
+
+        if !status.is_success() {
+            return Err(AppError::LlmError(format!(
+                "Anthropic returned {}: {}",
+                status, body["error"]["message"]
+            )));
+        }
+
+        Self::parse_response(&body)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::llm_providers::LlmTool;
+
+    #[test]
+    fn request_body_lifts_system_and_merges_tool_results() {
+        let request = LlmRequest::new(
+            "claude-model",
+            vec![
+                LlmMessage::system("first"),
+                LlmMessage::system("second"),
+                LlmMessage::user("https://example.com"),
+                LlmMessage::assistant_tool_calls(vec![
+                    LlmToolCall {
+                        id: "toolu_1".to_string(),
+                        name: "fetch_webpage".to_string(),
+                        arguments: "{\"url\":\"a\"}".to_string(),
+                    },
+                    LlmToolCall {
+                        id: "toolu_2".to_string(),
+                        name: "fetch_webpage".to_string(),
+                        arguments: "{\"url\":\"b\"}".to_string(),
+                    },
+                ]),
+                LlmMessage::tool_result("toolu_1", "page a"),
+                LlmMessage::tool_result("toolu_2", "page b"),
+            ],
+        )
+        .with_tools(vec![LlmTool {
+            name: "fetch_webpage".to_string(),
+            description: "Fetch a page".to_string(),
+            parameters: json!({ "type": "object" }),
+        }]);
+
+        let body = AnthropicProvider::request_body(&request);
+
+        assert_eq!(body["system"], "first\n\nsecond");
+        assert_eq!(body["max_tokens"], DEFAULT_MAX_TOKENS);
+        let messages = body["messages"].as_array().unwrap();
+        assert_eq!(messages.len(), 3);
+        assert_eq!(messages[1]["content"][1]["input"]["url"], "b");
+        assert_eq!(messages[2]["role"], "user");
+        assert_eq!(messages[2]["content"].as_array().unwrap().len(), 2);
+        assert_eq!(messages[2]["content"][1]["tool_use_id"], "toolu_2");
+    }
+
+    #[test]
+    fn structured_output_without_tools_forces_schema_tool() {
+        let request = LlmRequest::new("claude-model", vec![LlmMessage::user("summary")])
+            .with_response_schema("quiz", json!({ "type": "object" }));
+
+        let body = AnthropicProvider::request_body(&request);
+        assert_eq!(body["tool_choice"]["name"], STRUCTURED_OUTPUT_TOOL);
+
+        let response = json!({
+            "content": [{
+                "type": "tool_use",
+                "id": "toolu_1",
+                "name": STRUCTURED_OUTPUT_TOOL,
+                "input": { "quiz_title": "Rust" }
+            }],
+            "usage": { "input_tokens": 10, "output_tokens": 5 }
+        });
+        let parsed = AnthropicProvider::parse_response(&response).unwrap();
+
+        assert_eq!(parsed.content.as_deref(), Some("{\"quiz_title\":\"Rust\"}"));
+        assert!(parsed.tool_calls.is_empty());
+        assert_eq!(parsed.usage.completion_tokens, 5);
+    }
+}
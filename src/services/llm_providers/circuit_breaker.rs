@@ -0,0 +1,113 @@
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Default)]
+struct BreakerState {
+    consecutive_failures: u32,
+    open_until: Option<Instant>,
+}
+
+/// Tracks consecutive failures of one LLM endpoint.
+///
+/// After `failure_threshold` failures in a row the breaker opens and
+/// [`CircuitBreaker::allow_request`] returns `false` for `cooldown`. Once the
+/// cool-down has passed a single trial request is let through: success closes
+/// the breaker, another failure opens it again for a full cool-down.
+pub struct CircuitBreaker {
+    failure_threshold: u32,
+    cooldown: Duration,
+    state: Mutex<BreakerState>,
+}
+
+impl CircuitBreaker {
+    pub fn new(failure_threshold: u32, cooldown: Duration) -> Self {
+        Self {
+            failure_threshold: failure_threshold.max(1),
+            cooldown,
+            state: Mutex::new(BreakerState::default()),
+        }
+    }
+
+    /// Whether a request should be sent to the endpoint now
+    pub fn allow_request(&self) -> bool {
+        let mut state = self.lock_state();
+        match state.open_until {
+            Some(open_until) if Instant::now() < open_until => false,
+            Some(_) => {
+                // Half-open: hold the breaker open while the trial request runs
+                state.open_until = Some(Instant::now() + self.cooldown);
+                true
+            }
+            None => true,
+        }
+    }
+
+    pub fn record_success(&self) {
+        let mut state = self.lock_state();
+        state.consecutive_failures = 0;
+        state.open_until = None;
+    }
+
+    pub fn record_failure(&self) {
+        let mut state = self.lock_state();
+        state.consecutive_failures = state.consecutive_failures.saturating_add(1);
+        if state.consecutive_failures >= self.failure_threshold {
+            state.open_until = Some(Instant::now() + self.cooldown);
+        }
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.lock_state()
+            .open_until
+            .is_some_and(|open_until| Instant::now() < open_until)
+    }
+
+    fn lock_state(&self) -> std::sync::MutexGuard<'_, BreakerState> {
+        self.state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn opens_after_threshold_consecutive_failures() {
+        let breaker = CircuitBreaker::new(2, Duration::from_secs(60));
+
+        breaker.record_failure();
+        assert!(breaker.allow_request());
+
+        breaker.record_failure();
+        assert!(breaker.is_open());
+        assert!(!breaker.allow_request());
+    }
+
+    #[test]
+    fn success_resets_failure_count() {
+        let breaker = CircuitBreaker::new(2, Duration::from_secs(60));
+
+        breaker.record_failure();
+        breaker.record_success();
+        breaker.record_failure();
+
+        assert!(!breaker.is_open());
+    }
+
+    #[test]
+    fn allows_single_trial_request_after_cooldown() {
+        let breaker = CircuitBreaker::new(1, Duration::from_millis(20));
+        breaker.record_failure();
+        assert!(!breaker.allow_request());
+
+        std::thread::sleep(Duration::from_millis(30));
+
+        assert!(breaker.allow_request());
+        assert!(!breaker.allow_request());
+
+        breaker.record_success();
+        assert!(breaker.allow_request());
+    }
+}
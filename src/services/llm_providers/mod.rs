@@ -0,0 +1,200 @@
+pub mod anthropic;
+pub mod circuit_breaker;
+pub mod ollama;
+pub mod openai;
+pub mod structured_output;
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use serde_json::Value;
+
+use crate::{
+    config::{Config, LlmProviderKind},
+    errors::AppResult,
+};
+
+pub use anthropic::AnthropicProvider;
+pub use circuit_breaker::CircuitBreaker;
+pub use ollama::OllamaProvider;
+pub use openai::OpenAiCompatibleProvider;
+pub use structured_output::StructuredOutputError;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LlmRole {
+    System,
+    User,
+    Assistant,
+    Tool,
+}
+
+/// A provider-neutral chat message
+#[derive(Debug, Clone, PartialEq)]
+pub struct LlmMessage {
+    pub role: LlmRole,
+    pub content: Option<String>,
+    /// Tool calls requested by an assistant message
+    pub tool_calls: Vec<LlmToolCall>,
+    /// Id of the call a tool message answers
+    pub tool_call_id: Option<String>,
+}
+
+impl LlmMessage {
+    fn new(role: LlmRole, content: impl Into<String>) -> Self {
+        Self {
+            role,
+            content: Some(content.into()),
+            tool_calls: Vec::new(),
+            tool_call_id: None,
+        }
+    }
+
+    pub fn system(content: impl Into<String>) -> Self {
+        Self::new(LlmRole::System, content)
+    }
+
+    pub fn user(content: impl Into<String>) -> Self {
+        Self::new(LlmRole::User, content)
+    }
+
+    pub fn assistant(content: impl Into<String>) -> Self {
+        Self::new(LlmRole::Assistant, content)
+    }
+
+    pub fn assistant_tool_calls(tool_calls: Vec<LlmToolCall>) -> Self {
+        Self {
+            role: LlmRole::Assistant,
+            content: None,
+            tool_calls,
+            tool_call_id: None,
+        }
+    }
+
+    pub fn tool_result(tool_call_id: impl Into<String>, content: impl Into<String>) -> Self {
+        Self {
+            tool_call_id: Some(tool_call_id.into()),
+            ..Self::new(LlmRole::Tool, content)
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LlmToolCall {
+    pub id: String,
+    pub name: String,
+    /// JSON-encoded arguments
+    pub arguments: String,
+}
+
+/// A function the model may call
+#[derive(Debug, Clone)]
+pub struct LlmTool {
+    pub name: String,
+    pub description: String,
+    /// JSON schema of the arguments
+    pub parameters: Value,
+}
+
+/// JSON schema the response content must conform to
+#[derive(Debug, Clone)]
+pub struct LlmResponseSchema {
+    pub name: String,
+    pub schema: Value,
+}
+
+#[derive(Debug, Clone)]
+pub struct LlmRequest {
+    pub model: String,
+    pub messages: Vec<LlmMessage>,
+    pub max_tokens: Option<u32>,
+    pub response_schema: Option<LlmResponseSchema>,
+    pub tools: Vec<LlmTool>,
+}
+
+impl LlmRequest {
+    pub fn new(model: impl Into<String>, messages: Vec<LlmMessage>) -> Self {
+        Self {
+            model: model.into(),
+            messages,
+            max_tokens: None,
+            response_schema: None,
+            tools: Vec::new(),
+        }
+    }
+
+    pub fn with_max_tokens(mut self, max_tokens: u32) -> Self {
+        self.max_tokens = Some(max_tokens);
+        self
+    }
+
+    pub fn with_response_schema(mut self, name: impl Into<String>, schema: Value) -> Self {
+        self.response_schema = Some(LlmResponseSchema {
+            name: name.into(),
+            schema,
+        });
+        self
+    }
+
+    pub fn with_tools(mut self, tools: Vec<LlmTool>) -> Self {
+        self.tools = tools;
+        self
+    }
+}
+
+/// Tokens billed for one call, as reported by the provider
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LlmUsage {
+    pub prompt_tokens: u32,
+    pub completion_tokens: u32,
+}
+
+impl LlmUsage {
+    /// Read two token counters from a provider's usage block, treating missing ones as zero
+    fn from_fields(usage: &Value, prompt_field: &str, completion_field: &str) -> Self {
+        let count = |field: &str| {
+            usage[field]
+                .as_u64()
+                .and_then(|n| u32::try_from(n).ok())
+                .unwrap_or_default()
+        };
+        Self {
+            prompt_tokens: count(prompt_field),
+            completion_tokens: count(completion_field),
+        }
+    }
+
+    pub fn total_tokens(&self) -> u32 {
+        self.prompt_tokens.saturating_add(self.completion_tokens)
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LlmResponse {
+    pub content: Option<String>,
+    pub tool_calls: Vec<LlmToolCall>,
+    pub usage: LlmUsage,
+}
+
+/// A chat completion backend. Implementations translate [`LlmRequest`] into
+/// their own wire format, so callers never depend on a particular API.
+#[async_trait]
+pub trait LlmProvider: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    async fn complete(&self, request: LlmRequest) -> AppResult<LlmResponse>;
+}
+
+/// Build the provider for the given kind from the connection settings in `Config`
+pub fn build_provider(kind: LlmProviderKind, config: &Config) -> Arc<dyn LlmProvider> {
+    match kind {
+        LlmProviderKind::OpenAi => Arc::new(OpenAiCompatibleProvider::new(
+            &config.openai_base_url,
+            &config.openai_api_key,
+        )),
+        LlmProviderKind::Ollama => Arc::new(OllamaProvider::new(&config.ollama_base_url)),
+        LlmProviderKind::Anthropic => Arc::new(AnthropicProvider::new(
+            &config.anthropic_base_url,
+            &config.anthropic_api_key,
+        )),
+    }
+}
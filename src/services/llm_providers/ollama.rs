@@ -0,0 +1,196 @@
+use async_trait::async_trait;
+use serde_json::{json, Value};
+
+use super::{LlmMessage, LlmProvider, LlmRequest, LlmResponse, LlmRole, LlmToolCall, LlmUsage};
+use crate::errors::{AppError, AppResult};
+
+/// Ollama's native `/api/chat` endpoint
+pub struct OllamaProvider {
+    client: reqwest::Client,
+    base_url: String,
+}
+
+impl OllamaProvider {
+    pub fn new(base_url: &str) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            base_url: base_url.trim_end_matches('/').to_string(),
+        }
+    }
+
+    fn request_body(request: &LlmRequest) -> Value {
+        let mut body = json!({
+            "model": request.model,
+            "messages": request.messages.iter().map(Self::message).collect::<Vec<_>>(),
+            "stream": false,
+        });
+
+        if let Some(max_tokens) = request.max_tokens {
+            body["options"] = json!({ "num_predict": max_tokens });
+        }
+        if let Some(schema) = &request.response_schema {
+            body["format"] = schema.schema.clone();
+        }
+        if !request.tools.is_empty() {
+            body["tools"] = request
+                .tools
+                .iter()
+                .map(|tool| {
+                    json!({
+                        "type": "function",
+                        "function": {
+                            "name": tool.name,
+                            "description": tool.description,
+                            "parameters": tool.parameters,
+                        }
+                    })
+                })
+                .collect();
+        }
+
+        body
+    }
+
+    fn message(message: &LlmMessage) -> Value {
+        let role = match message.role {
+            LlmRole::System => "system",
+            LlmRole::User => "user",
+            LlmRole::Assistant => "assistant",
+            LlmRole::Tool => "tool",
+        };
+        let mut value = json!({
+            "role": role,
+            "content": message.content.clone().unwrap_or_default(),
+        });
+
+        // Ollama takes tool arguments as objects rather than JSON strings
+        if !message.tool_calls.is_empty() {
+            value["tool_calls"] = message
+                .tool_calls
+                .iter()
+                .map(|call| {
+                    let arguments: Value =
+                        serde_json::from_str(&call.arguments).unwrap_or_else(|_| json!({}));
+                    json!({ "function": { "name": call.name, "arguments": arguments } })
+                })
+                .collect();
+        }
+
+        value
+    }
+
+    fn parse_response(response: &Value) -> AppResult<LlmResponse> {
+        let message = response
+            .get("message")
+            .ok_or_else(|| AppError::LlmError("No response from LLM".to_string()))?;
+
+        // Ollama does not assign call ids, so number them for the tool messages
+        let tool_calls = message["tool_calls"]
+            .as_array()
+            .map(|calls| {
+                calls
+                    .iter()
+                    .enumerate()
+                    .map(|(index, call)| LlmToolCall {
+                        id: format!("call_{}", index),
+                        name: call["function"]["name"]
+                            .as_str()
+                            .unwrap_or_default()
+                            .to_string(),
+                        arguments: call["function"]["arguments"].to_string(),
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        Ok(LlmResponse {
+            content: message["content"]
+                .as_str()
+                .filter(|content| !content.is_empty())
+                .map(str::to_string),
+            tool_calls,
+            usage: LlmUsage::from_fields(response, "prompt_eval_count", "eval_count"),
+        })
+    }
+}
+
+#[async_trait]
+impl LlmProvider for OllamaProvider {
+    fn name(&self) -> &'static str {
+        "ollama"
+    }
+
+    async fn complete(&self, request: LlmRequest) -> AppResult<LlmResponse> {
+        let response = self
+            .client
+            .post(format!("{}/api/chat", self.base_url))
+            .json(&Self::request_body(&request))
+            .send()
+            .await
+            .map_err(|e| AppError::LlmError(format!("Ollama request failed: {}", e)))?;
+
+        let status = response.status();
+        let body: Value = response
+            .json()
+            .await
+            .map_err(|e| AppError::LlmError(format!("Invalid Ollama response: {}", e)))?;
+
+        if !status.is_success() {
+            return Err(AppError::LlmError(format!(
+                "Ollama returned {}: {}",
+                status, body["error"]
+            )));
+        }
+
+        Self::parse_response(&body)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn request_body_uses_native_format_and_object_arguments() {
+        let request = LlmRequest::new(
+            "llama3.2",
+            vec![LlmMessage::assistant_tool_calls(vec![LlmToolCall {
+                id: "call_0".to_string(),
+                name: "fetch_webpage".to_string(),
+                arguments: "{\"url\":\"https://example.com\"}".to_string(),
+            }])],
+        )
+        .with_max_tokens(256)
+        .with_response_schema("quiz", json!({ "type": "object" }));
+
+        let body = OllamaProvider::request_body(&request);
+
+        assert_eq!(body["stream"], false);
+        assert_eq!(body["options"]["num_predict"], 256);
+        assert_eq!(body["format"]["type"], "object");
+        assert_eq!(
+            body["messages"][0]["tool_calls"][0]["function"]["arguments"]["url"],
+            "https://example.com"
+        );
+    }
+
+    #[test]
+    fn parse_response_numbers_tool_calls() {
+        let response = json!({
+            "message": {
+                "role": "assistant",
+                "content": "",
+                "tool_calls": [{ "function": { "name": "fetch_webpage", "arguments": { "url": "u" } } }]
+            },
+            "prompt_eval_count": 42,
+            "eval_count": 7
+        });
+
+        let parsed = OllamaProvider::parse_response(&response).unwrap();
+
+        assert_eq!(parsed.content, None);
+        assert_eq!(parsed.tool_calls[0].id, "call_0");
+        assert_eq!(parsed.tool_calls[0].arguments, "{\"url\":\"u\"}");
+        assert_eq!(parsed.usage.total_tokens(), 49);
+    }
+}
@@ -0,0 +1,207 @@
+use async_openai::{config::OpenAIConfig, Client};
+use async_trait::async_trait;
+use secrecy::{ExposeSecret, SecretString};
+use serde_json::{json, Value};
+
+use super::{LlmMessage, LlmProvider, LlmRequest, LlmResponse, LlmRole, LlmToolCall, LlmUsage};
+use crate::errors::{AppError, AppResult};
+
+/// Any server speaking the OpenAI chat completions API (OpenAI, LM Studio, vLLM, ...)
+pub struct OpenAiCompatibleProvider {
+    client: Client<OpenAIConfig>,
+}
+
+impl OpenAiCompatibleProvider {
+    pub fn new(base_url: &str, api_key: &SecretString) -> Self {
+        let openai_config = OpenAIConfig::new()
+            .with_api_key(api_key.expose_secret())
+            .with_api_base(base_url);
+
+        Self {
+            client: Client::with_config(openai_config),
+        }
+    }
+
+    fn request_body(request: &LlmRequest) -> Value {
+        let mut body = json!({
+            "model": request.model,
+            "messages": request.messages.iter().map(Self::message).collect::<Vec<_>>(),
+            "store": false,
+        });
+
+        if let Some(max_tokens) = request.max_tokens {
+            body["max_tokens"] = json!(max_tokens);
+        }
+        if let Some(schema) = &request.response_schema {
+            body["response_format"] = json!({
+                "type": "json_schema",
+                "json_schema": {
+                    "name": schema.name,
+                    "schema": schema.schema,
+                    "strict": true,
+                }
+            });
+        }
+        if !request.tools.is_empty() {
+            body["tools"] = request
+                .tools
+                .iter()
+                .map(|tool| {
+                    json!({
+                        "type": "function",
+                        "function": {
+                            "name": tool.name,
+                            "description": tool.description,
+                            "parameters": tool.parameters,
+                            "strict": true,
+                        }
+                    })
+                })
+                .collect();
+            body["tool_choice"] = json!("auto");
+        }
+
+        body
+    }
+
+    fn message(message: &LlmMessage) -> Value {
+        let role = match message.role {
+            LlmRole::System => "system",
+            LlmRole::User => "user",
+            LlmRole::Assistant => "assistant",
+            LlmRole::Tool => "tool",
+        };
+        let mut value = json!({ "role": role, "content": message.content });
+
+        if !message.tool_calls.is_empty() {
+            value["tool_calls"] = message
+                .tool_calls
+                .iter()
+                .map(|call| {
+                    json!({
+                        "id": call.id,
+                        "type": "function",
+                        "function": { "name": call.name, "arguments": call.arguments },
+                    })
+                })
+                .collect();
+        }
+        if let Some(tool_call_id) = &message.tool_call_id {
+            value["tool_call_id"] = json!(tool_call_id);
+        }
+
+        value
+    }
+
+    fn parse_response(response: &Value) -> AppResult<LlmResponse> {
+        let message = response
+            .pointer("/choices/0/message")
+            .ok_or_else(|| AppError::LlmError("No response from LLM".to_string()))?;
+
+        let tool_calls = message["tool_calls"]
+            .as_array()
+            .map(|calls| {
+                calls
+                    .iter()
+                    .map(|call| LlmToolCall {
+                        id: call["id"].as_str().unwrap_or_default().to_string(),
+                        name: call["function"]["name"]
+                            .as_str()
+                            .unwrap_or_default()
+                            .to_string(),
+                        arguments: call["function"]["arguments"]
+                            .as_str()
+                            .unwrap_or("{}")
+                            .to_string(),
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        Ok(LlmResponse {
+            content: message["content"].as_str().map(str::to_string),
+            tool_calls,
+            usage: LlmUsage::from_fields(&response["usage"], "prompt_tokens", "completion_tokens"),
+        })
+    }
+}
+
+#[async_trait]
+impl LlmProvider for OpenAiCompatibleProvider {
+    fn name(&self) -> &'static str {
+        "openai"
+    }
+
+    async fn complete(&self, request: LlmRequest) -> AppResult<LlmResponse> {
+        let response: Value = self
+            .client
+            .chat()
+            .create_byot(Self::request_body(&request))
+            .await?;
+
+        Self::parse_response(&response)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::llm_providers::LlmTool;
+
+    #[test]
+    fn request_body_includes_schema_tools_and_tool_messages() {
+        let request = LlmRequest::new(
+            "small-model",
+            vec![
+                LlmMessage::system("be brief"),
+                LlmMessage::assistant_tool_calls(vec![LlmToolCall {
+                    id: "call-1".to_string(),
+                    name: "fetch_webpage".to_string(),
+                    arguments: "{}".to_string(),
+                }]),
+                LlmMessage::tool_result("call-1", "page text"),
+            ],
+        )
+        .with_max_tokens(100)
+        .with_response_schema("quiz", json!({ "type": "object" }))
+        .with_tools(vec![LlmTool {
+            name: "fetch_webpage".to_string(),
+            description: "Fetch a page".to_string(),
+            parameters: json!({ "type": "object" }),
+        }]);
+
+        let body = OpenAiCompatibleProvider::request_body(&request);
+
+        assert_eq!(body["model"], "small-model");
+        assert_eq!(body["max_tokens"], 100);
+        assert_eq!(body["response_format"]["json_schema"]["name"], "quiz");
+        assert_eq!(body["tools"][0]["function"]["name"], "fetch_webpage");
+        assert_eq!(body["messages"][1]["tool_calls"][0]["id"], "call-1");
+        assert_eq!(body["messages"][2]["tool_call_id"], "call-1");
+    }
+
+    #[test]
+    fn parse_response_reads_content_and_tool_calls() {
+        let response = json!({
+            "choices": [{
+                "message": {
+                    "content": null,
+                    "tool_calls": [{
+                        "id": "call-1",
+                        "type": "function",
+                        "function": { "name": "fetch_webpage", "arguments": "{\"url\":\"u\"}" }
+                    }]
+                }
+            }],
+            "usage": { "prompt_tokens": 120, "completion_tokens": 30, "total_tokens": 150 }
+        });
+
+        let parsed = OpenAiCompatibleProvider::parse_response(&response).unwrap();
+
+        assert_eq!(parsed.content, None);
+        assert_eq!(parsed.tool_calls[0].name, "fetch_webpage");
+        assert_eq!(parsed.tool_calls[0].arguments, "{\"url\":\"u\"}");
+        assert_eq!(parsed.usage.prompt_tokens, 120);
+        assert_eq!(parsed.usage.completion_tokens, 30);
+    }
+}
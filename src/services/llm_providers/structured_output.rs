@@ -0,0 +1,335 @@
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use thiserror::Error;
+
+use crate::errors::AppError;
+
+/// Failure to get a schema-conforming value out of the model
+#[derive(Debug, Error)]
+pub enum StructuredOutputError {
+    #[error(transparent)]
+    Llm(#[from] AppError),
+    #[error("Failed to build response schema: {0}")]
+    Schema(#[from] serde_json::Error),
+    #[error("LLM returned no content")]
+    Empty,
+    #[error("Tool loop exceeded maximum attempts")]
+    ToolLoopExceeded,
+    #[error("Tool call failed: {0}")]
+    Tool(String),
+    #[error("LLM response did not match the {schema} schema after {attempts} attempts: {}", errors.join("; "))]
+    Invalid {
+        schema: String,
+        attempts: u32,
+        errors: Vec<String>,
+    },
+}
+
+/// Parse a model response into `T`, leniently extracting the JSON first.
+///
+/// Returns the problems found so they can be sent back to the model.
+pub fn parse_structured<T: DeserializeOwned>(
+    content: &str,
+    schema: &Value,
+) -> Result<T, Vec<String>> {
+    let value = extract_json(content).ok_or_else(|| {
+        vec!["Response is not valid JSON; it may be truncated or wrapped in prose".to_string()]
+    })?;
+
+    let errors = validate_against_schema(&value, schema);
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+
+    serde_json::from_value(value).map_err(|e| vec![e.to_string()])
+}
+
+/// Message asking the model to fix its previous response
+pub fn repair_prompt(errors: &[String]) -> String {
+    format!(
+        "Your previous response did not match the required JSON schema:\n- {}\n\
+         Reply with only the corrected JSON object.",
+        errors.join("\n- ")
+    )
+}
+
+/// Find the JSON value in a response: the whole text, the body of a code
+/// fence, or the first balanced object
+pub fn extract_json(content: &str) -> Option<Value> {
+    let trimmed = content.trim();
+    if let Ok(value) = serde_json::from_str(trimmed) {
+        return Some(value);
+    }
+
+    if let Some(fenced) = strip_code_fence(trimmed) {
+        if let Ok(value) = serde_json::from_str(fenced) {
+            return Some(value);
+        }
+    }
+
+    first_json_object(trimmed).and_then(|object| serde_json::from_str(object).ok())
+}
+
+fn strip_code_fence(content: &str) -> Option<&str> {
+    let start = content.find("```")?;
+    let after_fence = &content[start + 3..];
+    // Skip the language tag, if any
+    let body_start = after_fence.find('\n')? + 1;
+    let body = &after_fence[body_start..];
+    let end = body.find("```").unwrap_or(body.len());
+    Some(body[..end].trim())
+}
+
+fn first_json_object(content: &str) -> Option<&str> {
+    let start = content.find('{')?;
+    let mut depth = 0usize;
+    let mut in_string = false;
+    let mut escaped = false;
+
+    for (offset, ch) in content[start..].char_indices() {
+        if in_string {
+            match ch {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+
+        match ch {
+            '"' => in_string = true,
+            '{' => depth += 1,
+            '}' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(&content[start..start + offset + 1]);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    None
+}
+
+/// Check `value` against the subset of JSON schema that `schemars` emits for
+/// our DTOs. `$ref`s are expected to be inlined already.
+pub fn validate_against_schema(value: &Value, schema: &Value) -> Vec<String> {
+    let mut errors = Vec::new();
+    validate_at("$", value, schema, &mut errors);
+    errors
+}
+
+fn validate_at(path: &str, value: &Value, schema: &Value, errors: &mut Vec<String>) {
+    let Some(schema) = schema.as_object() else {
+        // `true` or an empty schema accepts anything
+        return;
+    };
+
+    if let Some(expected) = schema.get("type") {
+        let matches = match expected {
+            Value::String(name) => type_matches(value, name),
+            Value::Array(names) => names
+                .iter()
+                .filter_map(Value::as_str)
+                .any(|name| type_matches(value, name)),
+            _ => true,
+        };
+        if !matches {
+            errors.push(format!(
+                "{}: expected {}, found {}",
+                path,
+                expected,
+                type_name(value)
+            ));
+            return;
+        }
+    }
+
+    if let Some(allowed) = schema.get("enum").and_then(Value::as_array) {
+        if !allowed.contains(value) {
+            errors.push(format!(
+                "{}: {} is not one of {}",
+                path,
+                value,
+                Value::Array(allowed.clone())
+            ));
+        }
+    }
+    if let Some(expected) = schema.get("const") {
+        if expected != value {
+            errors.push(format!("{}: expected {}, found {}", path, expected, value));
+        }
+    }
+
+    for keyword in ["anyOf", "oneOf"] {
+        if let Some(options) = schema.get(keyword).and_then(Value::as_array) {
+            let matched = options
+                .iter()
+                .any(|option| validate_against_schema(value, option).is_empty());
+            if !matched {
+                errors.push(format!("{}: does not match any allowed shape", path));
+            }
+        }
+    }
+    if let Some(all) = schema.get("allOf").and_then(Value::as_array) {
+        for option in all {
+            validate_at(path, value, option, errors);
+        }
+    }
+
+    match value {
+        Value::Object(object) => {
+            if let Some(required) = schema.get("required").and_then(Value::as_array) {
+                for field in required.iter().filter_map(Value::as_str) {
+                    if !object.contains_key(field) {
+                        errors.push(format!("{}: missing required field '{}'", path, field));
+                    }
+                }
+            }
+
+            let properties = schema.get("properties").and_then(Value::as_object);
+            for (key, field_value) in object {
+                let field_path = format!("{}.{}", path, key);
+                match properties.and_then(|properties| properties.get(key)) {
+                    Some(field_schema) => {
+                        validate_at(&field_path, field_value, field_schema, errors)
+                    }
+                    None if schema.get("additionalProperties") == Some(&Value::Bool(false)) => {
+                        errors.push(format!("{}: unexpected field", field_path));
+                    }
+                    None => {}
+                }
+            }
+        }
+        Value::Array(items) => {
+            if let Some(min) = schema.get("minItems").and_then(Value::as_u64) {
+                if (items.len() as u64) < min {
+                    errors.push(format!("{}: expected at least {} items", path, min));
+                }
+            }
+            if let Some(max) = schema.get("maxItems").and_then(Value::as_u64) {
+                if (items.len() as u64) > max {
+                    errors.push(format!("{}: expected at most {} items", path, max));
+                }
+            }
+            if let Some(item_schema) = schema.get("items") {
+                for (index, item) in items.iter().enumerate() {
+                    validate_at(&format!("{}[{}]", path, index), item, item_schema, errors);
+                }
+            }
+        }
+        Value::Number(number) => {
+            let number = number.as_f64().unwrap_or_default();
+            if let Some(min) = schema.get("minimum").and_then(Value::as_f64) {
+                if number < min {
+                    errors.push(format!("{}: {} is below the minimum {}", path, number, min));
+                }
+            }
+            if let Some(max) = schema.get("maximum").and_then(Value::as_f64) {
+                if number > max {
+                    errors.push(format!("{}: {} is above the maximum {}", path, number, max));
+                }
+            }
+        }
+        Value::String(text) => {
+            if let Some(min) = schema.get("minLength").and_then(Value::as_u64) {
+                if (text.chars().count() as u64) < min {
+                    errors.push(format!("{}: expected at least {} characters", path, min));
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+fn type_matches(value: &Value, name: &str) -> bool {
+    match name {
+        "object" => value.is_object(),
+        "array" => value.is_array(),
+        "string" => value.is_string(),
+        "boolean" => value.is_boolean(),
+        "null" => value.is_null(),
+        "number" => value.is_number(),
+        "integer" => value.is_i64() || value.is_u64(),
+        _ => true,
+    }
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+    use serde_json::json;
+
+    fn schema() -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "title": { "type": "string" },
+                "count": { "type": "integer", "minimum": 1 },
+                "tags": { "type": "array", "items": { "type": "string" } },
+                "note": { "type": ["string", "null"] }
+            },
+            "required": ["title", "count"],
+            "additionalProperties": false
+        })
+    }
+
+    #[derive(Debug, Deserialize)]
+    struct Sample {
+        title: String,
+        count: i32,
+    }
+
+    #[test]
+    fn extract_json_handles_fences_and_surrounding_prose() {
+        let fenced = "```json\n{\"title\": \"a\"}\n```";
+        assert_eq!(extract_json(fenced), Some(json!({ "title": "a" })));
+
+        let prose = "Sure! Here it is: {\"title\": \"a } b\", \"n\": {\"x\": 1}} Hope that helps.";
+        assert_eq!(
+            extract_json(prose),
+            Some(json!({ "title": "a } b", "n": { "x": 1 } }))
+        );
+
+        assert_eq!(extract_json("{\"title\": \"trunc"), None);
+    }
+
+    #[test]
+    fn validate_reports_paths_of_each_problem() {
+        let value = json!({ "count": 0, "tags": ["a", 2], "extra": true, "note": null });
+
+        let errors = validate_against_schema(&value, &schema());
+
+        assert!(errors.contains(&"$: missing required field 'title'".to_string()));
+        assert!(errors.contains(&"$.count: 0 is below the minimum 1".to_string()));
+        assert!(errors.contains(&"$.tags[1]: expected \"string\", found number".to_string()));
+        assert!(errors.contains(&"$.extra: unexpected field".to_string()));
+        assert_eq!(errors.len(), 4);
+    }
+
+    #[test]
+    fn parse_structured_returns_value_or_errors() {
+        let sample: Sample =
+            parse_structured("```\n{\"title\": \"t\", \"count\": 2}\n```", &schema()).unwrap();
+        assert_eq!(sample.title, "t");
+        assert_eq!(sample.count, 2);
+
+        let errors =
+            parse_structured::<Sample>("{\"title\": 3, \"count\": 2}", &schema()).unwrap_err();
+        assert_eq!(errors, vec!["$.title: expected \"string\", found number"]);
+    }
+}
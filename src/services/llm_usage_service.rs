@@ -0,0 +1,61 @@
+use std::sync::Arc;
+
+use chrono::{Duration, Utc};
+
+use crate::{
+    auth::{require_admin, Claims},
+    errors::AppResult,
+    models::dto::response::{QuizLlmUsageReport, UserDailyLlmUsageResponse},
+    repositories::LlmUsageRepository,
+};
+
+const DEFAULT_USAGE_DAYS: i64 = 30;
+const MAX_USAGE_DAYS: i64 = 365;
+
+/// Admin reporting over recorded LLM token usage
+pub struct LlmUsageService {
+    repository: Arc<dyn LlmUsageRepository>,
+}
+
+impl LlmUsageService {
+    pub fn new(repository: Arc<dyn LlmUsageRepository>) -> Self {
+        Self { repository }
+    }
+
+    pub async fn usage_by_user_per_day(
+        &self,
+        claims: &Claims,
+        days: Option<i64>,
+        user_id: Option<&str>,
+    ) -> AppResult<Vec<UserDailyLlmUsageResponse>> {
+        require_admin(claims)?;
+
+        let usage = self
+            .repository
+            .usage_by_user_per_day(Self::since(days), user_id)
+            .await?;
+
+        Ok(usage.into_iter().map(Into::into).collect())
+    }
+
+    pub async fn usage_by_quiz(
+        &self,
+        claims: &Claims,
+        days: Option<i64>,
+        quiz_id: Option<&str>,
+    ) -> AppResult<QuizLlmUsageReport> {
+        require_admin(claims)?;
+
+        let usage = self
+            .repository
+            .usage_by_quiz(Self::since(days), quiz_id)
+            .await?;
+
+        Ok(usage.into())
+    }
+
+    fn since(days: Option<i64>) -> chrono::DateTime<Utc> {
+        let days = days.unwrap_or(DEFAULT_USAGE_DAYS).clamp(1, MAX_USAGE_DAYS);
+        Utc::now() - Duration::days(days)
+    }
+}
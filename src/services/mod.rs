@@ -1,8 +1,20 @@
 pub mod agent_orchestrator_service;
+pub mod content_extractor;
+pub mod document_extractor;
+pub mod job_dispatcher;
+pub mod job_events;
+pub mod job_schedule;
+pub mod job_service;
+pub mod llm_providers;
+pub mod llm_usage_service;
 pub mod model_service;
 pub mod orchestrator_steps;
+pub mod question_validator;
 pub mod quiz_attempt_service;
 pub mod quiz_service;
 pub mod step_executor;
+pub mod step_registry;
 pub mod summary_document_service;
+pub mod text_chunker;
 pub mod user_service;
+pub mod web_fetcher;
@@ -1,43 +1,142 @@
+use std::collections::HashMap;
 use std::error::Error;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use chrono::Utc;
+use futures::{StreamExt, TryStreamExt};
+use uuid::Uuid;
 
-use async_openai::{
-    config::OpenAIConfig,
-    types::chat::{
-        ChatCompletionMessageToolCalls, ChatCompletionRequestAssistantMessage,
-        ChatCompletionRequestMessage, ChatCompletionRequestSystemMessage,
-        ChatCompletionRequestToolMessage, ChatCompletionRequestUserMessage,
-        ChatCompletionRequestUserMessageArgs, ChatCompletionTool, ChatCompletionToolChoiceOption,
-        ChatCompletionTools, CreateChatCompletionRequestArgs, FunctionCall, FunctionObject,
-        ResponseFormat, ResponseFormatJsonSchema, ToolChoiceOptions,
-    },
-    Client,
-};
 use schemars::{schema_for, JsonSchema};
-use secrecy::ExposeSecret;
 use serde::de::DeserializeOwned;
 use serde::Deserialize;
 use serde_json::{json, Value};
 
 use crate::{
-    config::Config,
+    config::{Config, LlmModelPrice, LlmProviderKind, LlmStageConfig},
     constants::{
         prompts::QUIZ_GENERATOR_PROMPT,
-        quiz_prompt::{STRUCTURED_QUIZ_GENERATOR_PROMPT, URL_EXTRACTION_PROMPT},
+        quiz_prompt::{
+            CHUNK_SUMMARY_PROMPT, GROUNDING_CHECK_PROMPT, MULTI_SOURCE_QUIZ_PROMPT,
+            QUESTION_REPAIR_PROMPT, QUESTION_REPLACEMENT_PROMPT, STRUCTURED_QUIZ_GENERATOR_PROMPT,
+            URL_EXTRACTION_PROMPT,
+        },
         WEBSITE_SUMMARISER_PROMPT,
     },
     errors::{AppError, AppResult},
-    models::dto::request::{GenerateQuizRequestDto, QuizRequestDto, SummaryDocumentRequestDto},
+    models::{
+        domain::llm_usage::LlmUsageRecord,
+        dto::request::{
+            GenerateQuizQuestionRequestDto, GenerateQuizRequestDto, GroundingJudgementDto,
+            QuizRequestDto, RegeneratedQuizQuestionsDto, SummaryDocumentRequestDto,
+        },
+    },
+    repositories::LlmUsageRepository,
+    services::llm_providers::{
+        build_provider,
+        structured_output::{parse_structured, repair_prompt},
+        CircuitBreaker, LlmMessage, LlmProvider, LlmRequest, LlmResponse, LlmTool, LlmToolCall,
+        StructuredOutputError,
+    },
+    services::{
+        content_extractor::{extract_page, truncate_at_char_boundary, ExtractedPage},
+        question_validator::{parse_correct, RejectedQuestion},
+        text_chunker::{chunk_markdown, estimate_tokens, TextChunk},
+        web_fetcher::{FetchedPage, WebFetcher},
+    },
 };
 
+/// Quiz pipeline stage an LLM call is made for. Each stage is configured with
+/// its own provider and model.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LlmStage {
+    Summarise,
+    GenerateQuestions,
+    Validate,
+}
+
+/// Who an LLM call is made for, so its token usage can be attributed
+#[derive(Debug, Clone, Default)]
+pub struct LlmCallContext {
+    pub job_id: Option<String>,
+    pub quiz_id: Option<String>,
+    pub user_id: Option<String>,
+}
+
+impl LlmCallContext {
+    pub fn for_job(job_id: &str, quiz_id: &str, user_id: &str) -> Self {
+        Self {
+            job_id: Some(job_id.to_string()),
+            quiz_id: Some(quiz_id.to_string()),
+            user_id: Some(user_id.to_string()),
+        }
+    }
+}
+
+/// A model response along with the `provider/model` that produced it
+#[derive(Debug, Clone)]
+pub struct LlmOutput<T> {
+    pub value: T,
+    pub model: String,
+}
+
+/// Summary of a source page and the page sections it covers
+#[derive(Debug, Clone)]
+pub struct SourceSummary {
+    pub content: String,
+    pub sections_covered: Vec<String>,
+    /// Sections left out because the page was longer than the summariser
+    /// will read
+    pub sections_skipped: Vec<String>,
+    /// Chunks the page was summarised in; 1 when it fit in a single call
+    pub chunks: usize,
+}
+
+/// One provider connection, shared by every stage that uses it
+struct LlmEndpoint {
+    provider: Arc<dyn LlmProvider>,
+    breaker: CircuitBreaker,
+}
+
+struct StageModel {
+    endpoint: Arc<LlmEndpoint>,
+    model: String,
+}
+
+impl StageModel {
+    fn label(&self) -> String {
+        format!("{}/{}", self.endpoint.provider.name(), self.model)
+    }
+}
+
+/// Each stage holds its primary model followed by its fallbacks
 pub struct ModelService {
-    client: Client<OpenAIConfig>,
+    summarise: Vec<StageModel>,
+    generate_questions: Vec<StageModel>,
+    validate: Vec<StageModel>,
+    prices: HashMap<String, LlmModelPrice>,
+    usage_repository: Option<Arc<dyn LlmUsageRepository>>,
+    web_fetcher: WebFetcher,
+    summary_context_tokens: usize,
 }
 
 const TOOL_MAX_ATTEMPTS: u32 = 12;
 const TOOL_MAX_CONTENT_LENGTH: usize = 20000;
+/// Summary stage context kept back for the system prompt and the response
+/// when sizing source chunks
+const SUMMARY_PROMPT_RESERVE_TOKENS: usize = 2560;
+const SUMMARY_RESPONSE_RESERVE_TOKENS: usize = 2048;
+const SUMMARY_MIN_CHUNK_TOKENS: usize = 512;
+/// Chunks beyond this are left out of the summary and reported as skipped
+const SUMMARY_MAX_CHUNKS: usize = 24;
+/// Chunk summaries requested at once
+const SUMMARY_CHUNK_CONCURRENCY: usize = 4;
+/// Times chunk notes are condensed before the final summary; notes still too
+/// long after that are cut off
+const SUMMARY_MAX_MERGE_ROUNDS: usize = 2;
 const STRUCTURED_OUTPUT_MAX_TOKENS: u32 = 12288;
-const DEFAULT_MODEL: &str = "mistralai/ministral-3-3b";
-const DEFAULT_CONTEXT_LENGTH: u32 = 16384; // Context window size for the default model
+/// Correction turns allowed after the first invalid structured response
+const STRUCTURED_OUTPUT_REPAIR_ATTEMPTS: u32 = 2;
 
 #[derive(Debug, Deserialize)]
 struct FetchWebpageArgs {
@@ -52,77 +151,395 @@ struct OpenSimpleBrowserArgs {
 
 impl ModelService {
     pub fn new(config: &Config) -> Self {
-        let openai_config = OpenAIConfig::new()
-            .with_api_key(config.openai_api_key.expose_secret())
-            .with_api_base(&config.openai_base_url);
+        let mut endpoints: HashMap<LlmProviderKind, Arc<LlmEndpoint>> = HashMap::new();
+        let mut stage_models = |stage: &LlmStageConfig| -> Vec<StageModel> {
+            stage
+                .chain()
+                .into_iter()
+                .map(|candidate| StageModel {
+                    endpoint: endpoints
+                        .entry(candidate.provider)
+                        .or_insert_with(|| {
+                            Arc::new(LlmEndpoint {
+                                provider: build_provider(candidate.provider, config),
+                                breaker: CircuitBreaker::new(
+                                    config.llm_circuit_failure_threshold,
+                                    Duration::from_secs(config.llm_circuit_cooldown_seconds),
+                                ),
+                            })
+                        })
+                        .clone(),
+                    model: candidate.model,
+                })
+                .collect()
+        };
 
-        let client = Client::with_config(openai_config);
-        // let summariser_client = Client::with_config(openai_config);
+        Self {
+            summarise: stage_models(&config.llm_summary),
+            generate_questions: stage_models(&config.llm_questions),
+            validate: stage_models(&config.llm_validation),
+            prices: config.llm_model_prices.clone(),
+            usage_repository: None,
+            web_fetcher: WebFetcher::new(config),
+            summary_context_tokens: config.llm_summary.context_tokens,
+        }
+    }
 
-        Self { client }
+    /// Persist the token usage of every call
+    pub fn with_usage_repository(mut self, repository: Arc<dyn LlmUsageRepository>) -> Self {
+        self.usage_repository = Some(repository);
+        self
     }
 
-    pub async fn chat_completion(&self, prompt: &str, model: &str) -> AppResult<String> {
-        let message = ChatCompletionRequestUserMessageArgs::default()
-            .content(prompt)
-            .build()
-            .map_err(|e| AppError::InternalError(format!("Failed to build chat message: {}", e)))?;
+    fn stage(&self, stage: LlmStage) -> &[StageModel] {
+        match stage {
+            LlmStage::Summarise => &self.summarise,
+            LlmStage::GenerateQuestions => &self.generate_questions,
+            LlmStage::Validate => &self.validate,
+        }
+    }
 
-        let request = CreateChatCompletionRequestArgs::default()
-            .model(model)
-            .messages(vec![ChatCompletionRequestMessage::User(message)])
-            .build()
-            .map_err(|e| AppError::InternalError(format!("Failed to build chat request: {}", e)))?;
+    /// Send a request down the model chain configured for `stage`.
+    ///
+    /// Models whose endpoint has an open circuit are skipped. A failed call
+    /// counts against the endpoint's breaker and moves on to the next model.
+    async fn complete(
+        &self,
+        context: &LlmCallContext,
+        stage: LlmStage,
+        messages: Vec<LlmMessage>,
+        configure: impl Fn(LlmRequest) -> LlmRequest,
+    ) -> AppResult<LlmOutput<LlmResponse>> {
+        let mut last_error = None;
+
+        for candidate in self.stage(stage) {
+            if !candidate.endpoint.breaker.allow_request() {
+                log::debug!("Skipping {}: circuit open", candidate.label());
+                continue;
+            }
 
-        let response = self
-            .client
-            .chat()
-            .create(request)
-            .await
-            .map_err(|e| AppError::InternalError(format!("LLM request failed: {}", e)))?;
+            let request = configure(LlmRequest::new(candidate.model.clone(), messages.clone()));
+            let started = Instant::now();
+            let result = candidate.endpoint.provider.complete(request).await;
+            self.record_usage(context, stage, candidate, &result, started.elapsed())
+                .await;
+
+            match result {
+                Ok(response) => {
+                    candidate.endpoint.breaker.record_success();
+                    return Ok(LlmOutput {
+                        value: response,
+                        model: candidate.label(),
+                    });
+                }
+                Err(e) => {
+                    log::warn!("LLM call to {} failed: {}", candidate.label(), e);
+                    candidate.endpoint.breaker.record_failure();
+                    last_error = Some(e);
+                }
+            }
+        }
 
-        let content = response
-            .choices
-            .first()
-            .and_then(|choice| choice.message.content.clone())
-            .ok_or_else(|| AppError::InternalError("No response from LLM".to_string()))?;
+        Err(match last_error {
+            Some(e) => AppError::LlmError(format!(
+                "All models for the {:?} stage failed; last error: {}",
+                stage, e
+            )),
+            None => AppError::LlmError(format!(
+                "All models for the {:?} stage are unavailable (circuit open)",
+                stage
+            )),
+        })
+    }
 
-        Ok(content)
+    async fn record_usage(
+        &self,
+        context: &LlmCallContext,
+        stage: LlmStage,
+        candidate: &StageModel,
+        result: &AppResult<LlmResponse>,
+        latency: Duration,
+    ) {
+        let Some(repository) = &self.usage_repository else {
+            return;
+        };
+
+        let usage = result
+            .as_ref()
+            .map(|response| response.usage)
+            .unwrap_or_default();
+        let cost_usd = self
+            .prices
+            .get(&candidate.model)
+            .map(|price| price.cost(usage.prompt_tokens, usage.completion_tokens))
+            .unwrap_or_default();
+
+        let record = LlmUsageRecord {
+            id: Uuid::new_v4().to_string(),
+            job_id: context.job_id.clone(),
+            quiz_id: context.quiz_id.clone(),
+            user_id: context.user_id.clone(),
+            stage: format!("{:?}", stage),
+            provider: candidate.endpoint.provider.name().to_string(),
+            model: candidate.model.clone(),
+            prompt_tokens: usage.prompt_tokens.into(),
+            completion_tokens: usage.completion_tokens.into(),
+            total_tokens: usage.total_tokens().into(),
+            latency_ms: i64::try_from(latency.as_millis()).unwrap_or(i64::MAX),
+            cost_usd,
+            success: result.is_ok(),
+            created_at: Utc::now(),
+        };
+
+        // Accounting must never fail the call it describes
+        if let Err(e) = repository.record(&record).await {
+            log::warn!(
+                "Failed to record LLM usage for {}: {}",
+                candidate.label(),
+                e
+            );
+        }
+    }
+
+    async fn complete_text(
+        &self,
+        context: &LlmCallContext,
+        stage: LlmStage,
+        messages: Vec<LlmMessage>,
+    ) -> AppResult<LlmOutput<String>> {
+        let output = self
+            .complete(context, stage, messages, |request| request)
+            .await?;
+        let content = output
+            .value
+            .content
+            .ok_or_else(|| AppError::LlmError("No response from LLM".to_string()))?;
+
+        Ok(LlmOutput {
+            value: content,
+            model: output.model,
+        })
+    }
+
+    pub async fn chat_completion(
+        &self,
+        context: &LlmCallContext,
+        stage: LlmStage,
+        prompt: &str,
+    ) -> AppResult<String> {
+        Ok(self
+            .complete_text(context, stage, vec![LlmMessage::user(prompt)])
+            .await?
+            .value)
+    }
+
+    /// Fetch a source page and summarise it for quiz generation
+    pub async fn website_summariser(
+        &self,
+        context: &LlmCallContext,
+        url_string: &str,
+        question_count: Option<i16>,
+    ) -> AppResult<LlmOutput<SourceSummary>> {
+        let page = self.fetch_page(url_string).await?;
+        self.summarise_page(context, url_string, &page, question_count)
+            .await
     }
 
-    pub async fn website_summariser(&self, url_string: &str, question_count: Option<i16>) -> AppResult<String> {
-        let mut user_message = format!("URL: {}", url_string);
+    /// Summarise an extracted page.
+    ///
+    /// Pages that fit the summary stage's context are summarised in one call.
+    /// Longer pages are split into section-aligned chunks that are summarised
+    /// separately (map) and then merged into one summary (reduce).
+    pub async fn summarise_page(
+        &self,
+        context: &LlmCallContext,
+        url_string: &str,
+        page: &ExtractedPage,
+        question_count: Option<i16>,
+    ) -> AppResult<LlmOutput<SourceSummary>> {
+        let mut request = format!("URL: {}", url_string);
         if let Some(count) = question_count {
-            user_message.push_str(&format!("\nQuestion Count: {}", count));
-        }
-        
-        let response: Value = self
-            .client
-            .chat()
-            .create_byot(json!({
-                "messages": [
-                    {
-                        "role": "system",
-                        "content": URL_EXTRACTION_PROMPT
-                    },
-                    {
-                        "role": "user",
-                        "content": user_message
-                    }
+            request.push_str(&format!("\nQuestion Count: {}", count));
+        }
+
+        let chunk_tokens = self.summary_chunk_tokens();
+        let untitled_section = page.title.as_deref().unwrap_or("Introduction");
+        let mut chunks = chunk_markdown(&page.to_markdown(), chunk_tokens, untitled_section);
+
+        if chunks.len() <= 1 {
+            let chunk = chunks.pop().unwrap_or(TextChunk {
+                sections: Vec::new(),
+                text: String::new(),
+            });
+            let output = self
+                .complete_text(
+                    context,
+                    LlmStage::Summarise,
+                    vec![
+                        LlmMessage::system(URL_EXTRACTION_PROMPT),
+                        LlmMessage::user(format!("{}\n\nPage content:\n{}", request, chunk.text)),
+                    ],
+                )
+                .await?;
+            log::debug!(
+                "website_summariser content length: {} ({})",
+                output.value.len(),
+                output.model
+            );
+
+            return Ok(LlmOutput {
+                value: SourceSummary {
+                    content: output.value,
+                    sections_covered: chunk.sections,
+                    sections_skipped: Vec::new(),
+                    chunks: 1,
+                },
+                model: output.model,
+            });
+        }
+
+        let skipped = chunks.split_off(chunks.len().min(SUMMARY_MAX_CHUNKS));
+        let sections_covered = distinct_sections(&chunks, &[]);
+        let sections_skipped = distinct_sections(&skipped, &sections_covered);
+        if !skipped.is_empty() {
+            log::warn!(
+                "{} is too long to summarise fully; skipping {} chunks",
+                url_string,
+                skipped.len()
+            );
+        }
+
+        let total = chunks.len();
+        // Futures are built up front; a closure held by the stream across the
+        // await is not Send for every lifetime the step executor needs
+        let requests: Vec<_> = chunks
+            .iter()
+            .enumerate()
+            .map(|(index, chunk)| {
+                let heading = format!(
+                    "Part {} of {} (sections: {})",
+                    index + 1,
+                    total,
+                    chunk.sections.join(", ")
+                );
+                let prompt = format!("URL: {}\n{}\n\n{}", url_string, heading, chunk.text);
+                async move {
+                    let notes = self.summarise_chunk(context, prompt).await?;
+                    Ok::<_, AppError>(format!("### {}\n\n{}", heading, notes))
+                }
+            })
+            .collect();
+        let notes = futures::stream::iter(requests)
+            .buffered(SUMMARY_CHUNK_CONCURRENCY)
+            .try_collect::<Vec<_>>()
+            .await?;
+
+        let notes = self
+            .condense_notes(context, url_string, notes.join("\n\n"), chunk_tokens)
+            .await?;
+
+        let output = self
+            .complete_text(
+                context,
+                LlmStage::Summarise,
+                vec![
+                    LlmMessage::system(URL_EXTRACTION_PROMPT),
+                    LlmMessage::user(format!(
+                        "{}\n\nThe page was too long to read at once. These are notes on each \
+                         part of it, in page order. Write the summary from them.\n\n{}",
+                        request, notes
+                    )),
                 ],
-                "model": DEFAULT_MODEL,
-                "store": false
-            }))
+            )
             .await?;
+        log::debug!(
+            "website_summariser merged {} chunks into {} chars ({})",
+            total,
+            output.value.len(),
+            output.model
+        );
+
+        Ok(LlmOutput {
+            value: SourceSummary {
+                content: output.value,
+                sections_covered,
+                sections_skipped,
+                chunks: total,
+            },
+            model: output.model,
+        })
+    }
 
-        let content = response["choices"][0]["message"]["content"].to_string();
-        log::debug!("website_summariser content length: {}", content.len());
+    /// Summarise groups of chunk notes again until they fit one call
+    async fn condense_notes(
+        &self,
+        context: &LlmCallContext,
+        url_string: &str,
+        mut notes: String,
+        chunk_tokens: usize,
+    ) -> AppResult<String> {
+        for _ in 0..SUMMARY_MAX_MERGE_ROUNDS {
+            if estimate_tokens(&notes) <= chunk_tokens {
+                return Ok(notes);
+            }
 
-        Ok(content)
+            let groups = chunk_markdown(&notes, chunk_tokens, "Notes");
+            let requests: Vec<_> = groups
+                .iter()
+                .map(|group| {
+                    self.summarise_chunk(
+                        context,
+                        format!(
+                            "URL: {}\nNotes on consecutive parts of the page (sections: {}). \
+                             Condense them without losing facts.\n\n{}",
+                            url_string,
+                            group.sections.join(", "),
+                            group.text
+                        ),
+                    )
+                })
+                .collect();
+            let condensed = futures::stream::iter(requests)
+                .buffered(SUMMARY_CHUNK_CONCURRENCY)
+                .try_collect::<Vec<_>>()
+                .await?;
+            notes = condensed.join("\n\n");
+        }
+
+        if truncate_at_char_boundary(&mut notes, chunk_tokens * 4) {
+            log::warn!(
+                "Notes for {} still too long after merging; truncated",
+                url_string
+            );
+            notes.push_str("\n[TRUNCATED]");
+        }
+        Ok(notes)
+    }
+
+    async fn summarise_chunk(&self, context: &LlmCallContext, prompt: String) -> AppResult<String> {
+        let output = self
+            .complete_text(
+                context,
+                LlmStage::Summarise,
+                vec![
+                    LlmMessage::system(CHUNK_SUMMARY_PROMPT),
+                    LlmMessage::user(prompt),
+                ],
+            )
+            .await?;
+        Ok(output.value.trim().to_string())
+    }
+
+    /// Source tokens that fit in one summary call
+    fn summary_chunk_tokens(&self) -> usize {
+        self.summary_context_tokens
+            .saturating_sub(SUMMARY_PROMPT_RESERVE_TOKENS + SUMMARY_RESPONSE_RESERVE_TOKENS)
+            .max(SUMMARY_MIN_CHUNK_TOKENS)
     }
 
     pub async fn quiz_generator(
         &self,
+        context: &LlmCallContext,
         quiz: QuizRequestDto,
         summary_document: SummaryDocumentRequestDto,
     ) -> AppResult<String> {
@@ -131,30 +548,19 @@ impl ModelService {
         let summary_json = serde_json::to_string(&summary_document).map_err(|e| {
             AppError::InternalError(format!("Failed to serialize summary document: {}", e))
         })?;
-        let response: Value = self
-            .client
-            .chat()
-            .create_byot(json!({
-                "messages": [
-                    {
-                        "role": "system",
-                        "content": QUIZ_GENERATOR_PROMPT
-                    },
-                    {
-                        "role": "user",
-                        "content": quiz_json
-                    },
-                    {
-                        "role": "user",
-                        "content": summary_json
-                    }
-                ],
-                "model": DEFAULT_MODEL,
-                "store": false
-            }))
-            .await?;
 
-        let content = response["choices"][0]["message"]["content"].to_string();
+        let content = self
+            .complete_text(
+                context,
+                LlmStage::GenerateQuestions,
+                vec![
+                    LlmMessage::system(QUIZ_GENERATOR_PROMPT),
+                    LlmMessage::user(quiz_json),
+                    LlmMessage::user(summary_json),
+                ],
+            )
+            .await?
+            .value;
         log::debug!("quiz_generator content length: {}", content.len());
 
         Ok(content)
@@ -162,207 +568,351 @@ impl ModelService {
 
     pub async fn structured_quiz_generator(
         &self,
+        context: &LlmCallContext,
         _quiz: QuizRequestDto,
-        summary_document: SummaryDocumentRequestDto,
-    ) -> AppResult<GenerateQuizRequestDto> {
-        match self
-            .structured_output::<GenerateQuizRequestDto>(vec![
-                ChatCompletionRequestSystemMessage::from(
-                    "Tool calls are disabled for structured output. Do not call tools.",
-                )
-                .into(),
-                ChatCompletionRequestSystemMessage::from(STRUCTURED_QUIZ_GENERATOR_PROMPT).into(),
-                ChatCompletionRequestUserMessage::from(summary_document.content).into(),
-            ])
+        summary_documents: Vec<SummaryDocumentRequestDto>,
+    ) -> AppResult<LlmOutput<GenerateQuizRequestDto>> {
+        let mut messages = vec![
+            LlmMessage::system("Tool calls are disabled for structured output. Do not call tools."),
+            LlmMessage::system(STRUCTURED_QUIZ_GENERATOR_PROMPT),
+        ];
+        messages.extend(Self::source_content_messages(&summary_documents));
+
+        self.structured_output::<GenerateQuizRequestDto>(
+            context,
+            LlmStage::GenerateQuestions,
+            messages,
+        )
+        .await
+        .map_err(|e| AppError::LlmError(format!("Failed to generate structured quiz: {}", e)))
+    }
+
+    /// Ask the validation model for `count` questions to replace ones that
+    /// failed validation, telling it why they failed
+    pub async fn regenerate_questions(
+        &self,
+        context: &LlmCallContext,
+        summary_documents: &[SummaryDocumentRequestDto],
+        kept: &[GenerateQuizQuestionRequestDto],
+        rejected: &[RejectedQuestion],
+        count: usize,
+    ) -> AppResult<LlmOutput<RegeneratedQuizQuestionsDto>> {
+        let mut messages = vec![
+            LlmMessage::system("Tool calls are disabled for structured output. Do not call tools."),
+            LlmMessage::system(QUESTION_REPAIR_PROMPT),
+        ];
+        messages.extend(Self::source_content_messages(summary_documents));
+
+        let mut request = format!("Write {} replacement questions.", count);
+        if !rejected.is_empty() {
+            request.push_str("\n\nRejected questions:");
+            for question in rejected {
+                request.push_str(&format!(
+                    "\n- {:?}: {}",
+                    question.title,
+                    question.problems.join("; ")
+                ));
+            }
+        }
+        if !kept.is_empty() {
+            request.push_str("\n\nQuestions being kept, which must not be repeated:");
+            for question in kept {
+                request.push_str(&format!("\n- {}", question.question_title));
+            }
+        }
+        messages.push(LlmMessage::user(request));
+
+        self.structured_output::<RegeneratedQuizQuestionsDto>(context, LlmStage::Validate, messages)
             .await
-        {
-            Ok(Some(generated_quiz)) => Ok(generated_quiz),
-            Ok(None) => Err(AppError::InternalError(
-                "LLM did not return a valid quiz".to_string(),
-            )),
-            Err(e) => Err(AppError::InternalError(format!(
-                "Failed to generate structured quiz: {}",
-                e
-            ))),
+            .map_err(|e| AppError::LlmError(format!("Failed to regenerate questions: {}", e)))
+    }
+
+    /// Ask the question model for `count` questions to replace ones the quiz
+    /// creator picked, passing on their instructions
+    pub async fn replace_questions(
+        &self,
+        context: &LlmCallContext,
+        summary_documents: &[SummaryDocumentRequestDto],
+        kept: &[GenerateQuizQuestionRequestDto],
+        replaced: &[GenerateQuizQuestionRequestDto],
+        count: usize,
+        instructions: Option<&str>,
+    ) -> AppResult<LlmOutput<RegeneratedQuizQuestionsDto>> {
+        let mut messages = vec![
+            LlmMessage::system("Tool calls are disabled for structured output. Do not call tools."),
+            LlmMessage::system(QUESTION_REPLACEMENT_PROMPT),
+        ];
+        messages.extend(Self::source_content_messages(summary_documents));
+
+        let mut request = format!("Write {} new questions.", count);
+        if let Some(instructions) = instructions {
+            request.push_str(&format!(
+                "\n\nInstructions from the quiz creator: {}",
+                instructions
+            ));
+        }
+        request.push_str(&format!(
+            "\n\nQuestions being replaced:\n{}",
+            serde_json::to_string_pretty(replaced).unwrap_or_default()
+        ));
+        if !kept.is_empty() {
+            request.push_str("\n\nQuestions being kept, which must not be repeated:");
+            for question in kept {
+                request.push_str(&format!("\n- {}", question.question_title));
+            }
         }
+        messages.push(LlmMessage::user(request));
+
+        self.structured_output::<RegeneratedQuizQuestionsDto>(
+            context,
+            LlmStage::GenerateQuestions,
+            messages,
+        )
+        .await
+        .map_err(|e| AppError::LlmError(format!("Failed to replace questions: {}", e)))
+    }
 
-        // Ok(response)
+    /// Ask the validation model to quote the passage of `source` supporting
+    /// each correct option of `question`, with a confidence score
+    pub async fn check_grounding(
+        &self,
+        context: &LlmCallContext,
+        source: &str,
+        question: &GenerateQuizQuestionRequestDto,
+    ) -> AppResult<LlmOutput<GroundingJudgementDto>> {
+        let correct: Vec<String> = question
+            .question_options
+            .iter()
+            .filter(|option| parse_correct(&option.option_correct) == Some(true))
+            .map(|option| format!("- {}", option.option_text))
+            .collect();
+        let prompt = format!(
+            "# Source material\n\n{}\n\n# Question\n\n{}\n{}\n\nCorrect options:\n{}",
+            source,
+            question.question_title,
+            question.question_description,
+            correct.join("\n")
+        );
+
+        self.structured_output::<GroundingJudgementDto>(
+            context,
+            LlmStage::Validate,
+            vec![
+                LlmMessage::system(
+                    "Tool calls are disabled for structured output. Do not call tools.",
+                ),
+                LlmMessage::system(GROUNDING_CHECK_PROMPT),
+                LlmMessage::user(prompt),
+            ],
+        )
+        .await
+        .map_err(|e| AppError::LlmError(format!("Failed to check answer grounding: {}", e)))
+    }
+
+    /// The summaries to write questions from; several sources are labelled
+    /// with their ids so questions can be attributed to them
+    fn source_content_messages(summary_documents: &[SummaryDocumentRequestDto]) -> Vec<LlmMessage> {
+        if let [summary_document] = summary_documents {
+            return vec![LlmMessage::user(summary_document.content.clone())];
+        }
+
+        let sections: Vec<String> = summary_documents
+            .iter()
+            .map(|summary| {
+                format!(
+                    "## Source {} ({})\n\n{}",
+                    summary.source_id.as_deref().unwrap_or(&summary.id),
+                    summary.url,
+                    summary.content
+                )
+            })
+            .collect();
+        vec![
+            LlmMessage::system(MULTI_SOURCE_QUIZ_PROMPT),
+            LlmMessage::user(sections.join("\n\n")),
+        ]
     }
 
     pub async fn structured_summary_document(
         &self,
+        context: &LlmCallContext,
         url_string: &str,
-    ) -> AppResult<SummaryDocumentRequestDto> {
+    ) -> AppResult<LlmOutput<SummaryDocumentRequestDto>> {
         let tools = vec![
-            Self::build_fetch_webpage_tool()?,
-            Self::build_open_simple_browser_tool()?,
+            Self::build_fetch_webpage_tool(),
+            Self::build_open_simple_browser_tool(),
         ];
-        match self
-            .structured_output_with_tools::<SummaryDocumentRequestDto>(
-                vec![
-                    ChatCompletionRequestSystemMessage::from(WEBSITE_SUMMARISER_PROMPT).into(),
-                    ChatCompletionRequestUserMessage::from(url_string).into(),
-                ],
-                tools,
-            )
-            .await
-        {
-            Ok(Some(summary_document)) => Ok(summary_document),
-            Ok(None) => Err(AppError::InternalError(
-                "LLM did not return a valid summary document".to_string(),
-            )),
-            Err(e) => Err(AppError::InternalError(format!(
+        self.structured_output_with_tools::<SummaryDocumentRequestDto>(
+            context,
+            LlmStage::Summarise,
+            vec![
+                LlmMessage::system(WEBSITE_SUMMARISER_PROMPT),
+                LlmMessage::user(url_string),
+            ],
+            tools,
+        )
+        .await
+        .map_err(|e| {
+            AppError::LlmError(format!(
                 "Failed to generate structured summary document: {}",
                 e
-            ))),
-        }
+            ))
+        })
     }
 
+    /// Ask the model for a `T`, repairing invalid responses.
+    ///
+    /// Responses are parsed leniently and checked against the schema; any
+    /// problems are sent back to the model for up to
+    /// `STRUCTURED_OUTPUT_REPAIR_ATTEMPTS` correction turns.
     pub async fn structured_output<T: serde::Serialize + DeserializeOwned + JsonSchema>(
         &self,
-        messages: Vec<ChatCompletionRequestMessage>,
-    ) -> Result<Option<T>, Box<dyn Error>> {
-        let schema = schema_for!(T);
-        let mut schema_value = serde_json::to_value(&schema)?;
-
-        // Inline all $defs to avoid $ref issues with LM Studio's outlines processor
-        let defs = if let Some(obj) = schema_value.get("$defs") {
-            obj.clone()
-        } else {
-            Value::Object(Default::default())
-        };
+        context: &LlmCallContext,
+        stage: LlmStage,
+        mut messages: Vec<LlmMessage>,
+    ) -> Result<LlmOutput<T>, StructuredOutputError> {
+        let (name, schema) = Self::response_schema::<T>()?;
+        let mut repairs = 0;
 
-        Self::inline_schema_refs(&mut schema_value, &defs);
-
-        if let Some(obj) = schema_value.as_object_mut() {
-            obj.remove("$defs");
-        }
-
-        let response_format = ResponseFormat::JsonSchema {
-            json_schema: ResponseFormatJsonSchema {
-                description: None,
-                name: "reasoning".into(),
-                schema: Some(schema_value),
-                strict: Some(true),
-            },
-        };
-
-        let request = CreateChatCompletionRequestArgs::default()
-            .max_tokens(STRUCTURED_OUTPUT_MAX_TOKENS)
-            .model(DEFAULT_MODEL)
-            .messages(messages)
-            .response_format(response_format)
-            .build()?;
-
-        let response = self.client.chat().create(request).await?;
-
-        for choice in response.choices {
-            if let Some(content) = choice.message.content {
-                return Ok(Some(serde_json::from_str::<T>(&content)?));
+        loop {
+            let output = self
+                .complete(context, stage, messages.clone(), |request| {
+                    request
+                        .with_max_tokens(STRUCTURED_OUTPUT_MAX_TOKENS)
+                        .with_response_schema(name.clone(), schema.clone())
+                })
+                .await?;
+            let content = output.value.content.ok_or(StructuredOutputError::Empty)?;
+
+            if let Some(value) =
+                Self::parse_or_request_repair(&name, &schema, content, &mut messages, &mut repairs)?
+            {
+                return Ok(LlmOutput {
+                    value,
+                    model: output.model,
+                });
             }
         }
-
-        Ok(None)
     }
 
     pub async fn structured_output_with_tools<
         T: serde::Serialize + DeserializeOwned + JsonSchema,
     >(
         &self,
-        mut messages: Vec<ChatCompletionRequestMessage>,
-        tools: Vec<ChatCompletionTools>,
-    ) -> Result<Option<T>, Box<dyn Error>> {
+        context: &LlmCallContext,
+        stage: LlmStage,
+        mut messages: Vec<LlmMessage>,
+        tools: Vec<LlmTool>,
+    ) -> Result<LlmOutput<T>, StructuredOutputError> {
+        let (name, schema) = Self::response_schema::<T>()?;
         let mut attempts = 0;
+        let mut repairs = 0;
 
         loop {
             attempts += 1;
             if attempts > TOOL_MAX_ATTEMPTS {
-                return Err("Tool loop exceeded maximum attempts".into());
+                return Err(StructuredOutputError::ToolLoopExceeded);
             }
 
-            let schema = schema_for!(T);
-            let mut schema_value = serde_json::to_value(&schema)?;
-
-            let defs = if let Some(obj) = schema_value.get("$defs") {
-                obj.clone()
-            } else {
-                Value::Object(Default::default())
-            };
-
-            Self::inline_schema_refs(&mut schema_value, &defs);
-
-            if let Some(obj) = schema_value.as_object_mut() {
-                obj.remove("$defs");
-            }
-
-            let response_format = ResponseFormat::JsonSchema {
-                json_schema: ResponseFormatJsonSchema {
-                    description: None,
-                    name: "math_reasoning".into(),
-                    schema: Some(schema_value),
-                    strict: Some(true),
-                },
-            };
-
-            let request = CreateChatCompletionRequestArgs::default()
-                .max_tokens(STRUCTURED_OUTPUT_MAX_TOKENS)
-                .model(DEFAULT_MODEL)
-                .messages(messages.clone())
-                .response_format(response_format)
-                .tools(tools.clone())
-                .tool_choice(ChatCompletionToolChoiceOption::Mode(
-                    ToolChoiceOptions::Auto,
-                ))
-                .build()?;
-
-            let response = self.client.chat().create(request).await?;
-            let choice = response
-                .choices
-                .into_iter()
-                .next()
-                .ok_or("No response from LLM")?;
-
-            if let Some(tool_calls) = choice.message.tool_calls {
-                let assistant = ChatCompletionRequestAssistantMessage {
-                    content: None,
-                    tool_calls: Some(tool_calls.clone()),
-                    ..Default::default()
-                };
-                messages.push(assistant.into());
-
-                for tool_call in tool_calls {
-                    match tool_call {
-                        ChatCompletionMessageToolCalls::Function(call) => {
-                            let tool_output = self.execute_tool_call(&call.function).await?;
-                            let tool_message = ChatCompletionRequestToolMessage {
-                                content: tool_output.into(),
-                                tool_call_id: call.id,
-                            };
-                            messages.push(tool_message.into());
-                        }
-                        ChatCompletionMessageToolCalls::Custom(call) => {
-                            let tool_message = ChatCompletionRequestToolMessage {
-                                content: format!(
-                                    "Unsupported custom tool: {}",
-                                    call.custom_tool.name
-                                )
-                                .into(),
-                                tool_call_id: call.id,
-                            };
-                            messages.push(tool_message.into());
-                        }
-                    }
+            let output = self
+                .complete(context, stage, messages.clone(), |request| {
+                    request
+                        .with_max_tokens(STRUCTURED_OUTPUT_MAX_TOKENS)
+                        .with_response_schema(name.clone(), schema.clone())
+                        .with_tools(tools.clone())
+                })
+                .await?;
+            let response = output.value;
+
+            if !response.tool_calls.is_empty() {
+                messages.push(LlmMessage::assistant_tool_calls(
+                    response.tool_calls.clone(),
+                ));
+
+                for tool_call in response.tool_calls {
+                    let tool_output = self
+                        .execute_tool_call(&tool_call)
+                        .await
+                        .map_err(|e| StructuredOutputError::Tool(e.to_string()))?;
+                    messages.push(LlmMessage::tool_result(tool_call.id, tool_output));
                 }
 
                 continue;
             }
 
-            if let Some(content) = choice.message.content {
-                return Ok(Some(serde_json::from_str::<T>(&content)?));
+            let content = response.content.ok_or(StructuredOutputError::Empty)?;
+            if let Some(value) =
+                Self::parse_or_request_repair(&name, &schema, content, &mut messages, &mut repairs)?
+            {
+                return Ok(LlmOutput {
+                    value,
+                    model: output.model,
+                });
             }
+        }
+    }
 
-            return Ok(None);
+    /// Parse `content` as a `T`. When it is invalid, append the response and a
+    /// correction request to `messages` and return `None` so the caller asks
+    /// again, until the repair budget runs out.
+    fn parse_or_request_repair<T: DeserializeOwned>(
+        name: &str,
+        schema: &Value,
+        content: String,
+        messages: &mut Vec<LlmMessage>,
+        repairs: &mut u32,
+    ) -> Result<Option<T>, StructuredOutputError> {
+        let errors = match parse_structured::<T>(&content, schema) {
+            Ok(value) => return Ok(Some(value)),
+            Err(errors) => errors,
+        };
+
+        if *repairs >= STRUCTURED_OUTPUT_REPAIR_ATTEMPTS {
+            return Err(StructuredOutputError::Invalid {
+                schema: name.to_string(),
+                attempts: *repairs + 1,
+                errors,
+            });
         }
+
+        *repairs += 1;
+        log::warn!(
+            "Invalid {} response, requesting repair {}/{}: {}",
+            name,
+            repairs,
+            STRUCTURED_OUTPUT_REPAIR_ATTEMPTS,
+            errors.join("; ")
+        );
+        messages.push(LlmMessage::assistant(content));
+        messages.push(LlmMessage::user(repair_prompt(&errors)));
+
+        Ok(None)
+    }
+
+    /// JSON schema for `T` with every `$ref` inlined, named after the schema title
+    fn response_schema<T: JsonSchema>() -> Result<(String, Value), serde_json::Error> {
+        let schema = schema_for!(T);
+        let mut schema_value = serde_json::to_value(&schema)?;
+
+        // Inline all $defs to avoid $ref issues with LM Studio's outlines processor
+        let defs = if let Some(obj) = schema_value.get("$defs") {
+            obj.clone()
+        } else {
+            Value::Object(Default::default())
+        };
+
+        Self::inline_schema_refs(&mut schema_value, &defs);
+
+        if let Some(obj) = schema_value.as_object_mut() {
+            obj.remove("$defs");
+        }
+
+        let name = schema_value["title"]
+            .as_str()
+            .unwrap_or("response")
+            .to_string();
+
+        Ok((name, schema_value))
     }
 
     fn inline_schema_refs(schema: &mut Value, defs: &Value) {
@@ -400,55 +950,63 @@ impl ModelService {
         }
     }
 
-    fn build_fetch_webpage_tool() -> AppResult<ChatCompletionTools> {
-        let parameters = json!({
-            "type": "object",
-            "properties": {
-                "url": {"type": "string"},
-                "query": {"type": "string"}
-            },
-            "required": ["url", "query"]
-        });
-
-        let function = FunctionObject {
+    fn build_fetch_webpage_tool() -> LlmTool {
+        LlmTool {
             name: "fetch_webpage".to_string(),
-            description: Some(
+            description:
                 "Fetch the text content of a URL and return the relevant content for the query"
                     .to_string(),
-            ),
-            parameters: Some(parameters),
-            strict: Some(true),
-        };
+            parameters: json!({
+                "type": "object",
+                "properties": {
+                    "url": {"type": "string"},
+                    "query": {"type": "string"}
+                },
+                "required": ["url", "query"]
+            }),
+        }
+    }
 
-        Ok(ChatCompletionTools::Function(ChatCompletionTool {
-            function,
-        }))
+    fn build_open_simple_browser_tool() -> LlmTool {
+        LlmTool {
+            name: "open_simple_browser".to_string(),
+            description: "Preview a URL and return a short, plain-text snapshot of the page"
+                .to_string(),
+            parameters: json!({
+                "type": "object",
+                "properties": {
+                    "url": {"type": "string"}
+                },
+                "required": ["url"]
+            }),
+        }
     }
 
-    fn build_open_simple_browser_tool() -> AppResult<ChatCompletionTools> {
-        let parameters = json!({
-            "type": "object",
-            "properties": {
-                "url": {"type": "string"}
-            },
-            "required": ["url"]
-        });
+    /// Fetch a page and extract its readable content and metadata
+    pub async fn fetch_page(&self, url: &str) -> AppResult<ExtractedPage> {
+        let page = self
+            .web_fetcher
+            .fetch(url)
+            .await
+            .map_err(|e| AppError::InternalError(format!("Failed to fetch {}: {}", url, e)))?;
 
-        let function = FunctionObject {
-            name: "open_simple_browser".to_string(),
-            description: Some(
-                "Preview a URL and return a short, plain-text snapshot of the page".to_string(),
-            ),
-            parameters: Some(parameters),
-            strict: Some(true),
-        };
+        if !page.status.is_success() {
+            return Err(AppError::InternalError(format!(
+                "Failed to fetch {}: status {}",
+                url, page.status
+            )));
+        }
 
-        Ok(ChatCompletionTools::Function(ChatCompletionTool {
-            function,
-        }))
+        Ok(extract_fetched_page(&page))
     }
 
-    async fn execute_tool_call(&self, function: &FunctionCall) -> Result<String, Box<dyn Error>> {
+    /// Fetch a page and return its readable content as markdown, without
+    /// truncation
+    pub async fn fetch_page_text(&self, url: &str) -> AppResult<String> {
+        Ok(self.fetch_page(url).await?.to_markdown())
+    }
+
+    async fn execute_tool_call(&self, function: &LlmToolCall) -> Result<String, Box<dyn Error>> {
         match function.name.as_str() {
             "fetch_webpage" => {
                 let args: FetchWebpageArgs = serde_json::from_str(&function.arguments)?;
@@ -477,25 +1035,25 @@ impl ModelService {
     }
 
     async fn fetch_webpage(&self, url: &str, query: &str) -> Result<String, Box<dyn Error>> {
-        let response = reqwest::Client::new()
-            .get(url)
-            .header("User-Agent", "tento-server/1.0")
-            .send()
-            .await?;
+        let page = match self.web_fetcher.fetch(url).await {
+            Ok(page) => page,
+            // Tell the model rather than failing the call, so it can carry on
+            // without this page
+            Err(e) => {
+                log::warn!("fetch_webpage refused or failed for {}: {}", url, e);
+                return Ok(format!("Failed to fetch URL: {}", e));
+            }
+        };
 
-        let status = response.status();
-        let mut body = response.text().await.unwrap_or_default();
-        body = strip_html_tags(&body);
-        body = collapse_whitespace(&body);
-        if body.len() > TOOL_MAX_CONTENT_LENGTH {
-            body.truncate(TOOL_MAX_CONTENT_LENGTH);
+        let mut body = extract_fetched_page(&page).to_markdown();
+        if truncate_at_char_boundary(&mut body, TOOL_MAX_CONTENT_LENGTH) || page.truncated {
             body.push_str("\n[TRUNCATED]");
         }
 
-        if !status.is_success() {
+        if !page.status.is_success() {
             return Ok(format!(
                 "Failed to fetch URL. Status: {}. Body (truncated): {}",
-                status, body
+                page.status, body
             ));
         }
 
@@ -503,43 +1061,446 @@ impl ModelService {
     }
 }
 
-fn strip_html_tags(input: &str) -> String {
-    let mut out = String::with_capacity(input.len());
-    let mut in_tag = false;
-    for ch in input.chars() {
-        match ch {
-            '<' => in_tag = true,
-            '>' => in_tag = false,
-            _ => {
-                if !in_tag {
-                    out.push(ch);
-                }
-            }
+/// Section headings of `chunks` in order, without repeats or any in `exclude`
+fn distinct_sections(chunks: &[TextChunk], exclude: &[String]) -> Vec<String> {
+    let mut sections: Vec<String> = Vec::new();
+    for section in chunks.iter().flat_map(|chunk| &chunk.sections) {
+        if !sections.contains(section) && !exclude.contains(section) {
+            sections.push(section.clone());
         }
     }
-    out
+    sections
 }
 
-fn collapse_whitespace(input: &str) -> String {
-    let mut out = String::with_capacity(input.len());
-    let mut was_space = false;
-    for ch in input.chars() {
-        if ch.is_whitespace() {
-            if !was_space {
-                out.push(' ');
-                was_space = true;
-            }
-        } else {
-            was_space = false;
-            out.push(ch);
+/// Run HTML through the content extractor; other text is used as it is
+fn extract_fetched_page(page: &FetchedPage) -> ExtractedPage {
+    let is_html = match &page.content_type {
+        Some(content_type) => content_type.contains("html"),
+        None => page.body.trim_start().starts_with('<'),
+    };
+
+    if is_html {
+        extract_page(&page.body)
+    } else {
+        ExtractedPage {
+            content: page.body.trim().to_string(),
+            ..ExtractedPage::default()
         }
     }
-    out.trim().to_string()
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::services::llm_providers::LlmUsage;
+    use async_trait::async_trait;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    struct FakeProvider {
+        fail: bool,
+        calls: AtomicU32,
+    }
+
+    #[async_trait]
+    impl LlmProvider for FakeProvider {
+        fn name(&self) -> &'static str {
+            if self.fail {
+                "primary"
+            } else {
+                "fallback"
+            }
+        }
+
+        async fn complete(&self, request: LlmRequest) -> AppResult<LlmResponse> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            if self.fail {
+                return Err(AppError::LlmError("connection refused".to_string()));
+            }
+            Ok(LlmResponse {
+                content: Some(format!("answer from {}", request.model)),
+                tool_calls: Vec::new(),
+                usage: LlmUsage {
+                    prompt_tokens: 1_000,
+                    completion_tokens: 500,
+                },
+            })
+        }
+    }
+
+    #[derive(Default)]
+    struct RecordingUsageRepository {
+        records: std::sync::Mutex<Vec<LlmUsageRecord>>,
+    }
+
+    #[async_trait]
+    impl LlmUsageRepository for RecordingUsageRepository {
+        async fn record(&self, record: &LlmUsageRecord) -> AppResult<()> {
+            self.records.lock().unwrap().push(record.clone());
+            Ok(())
+        }
+
+        async fn usage_by_user_per_day(
+            &self,
+            _since: chrono::DateTime<Utc>,
+            _user_id: Option<&str>,
+        ) -> AppResult<Vec<crate::models::domain::llm_usage::UserDailyLlmUsage>> {
+            Ok(Vec::new())
+        }
+
+        async fn usage_by_quiz(
+            &self,
+            _since: chrono::DateTime<Utc>,
+            _quiz_id: Option<&str>,
+        ) -> AppResult<Vec<crate::models::domain::llm_usage::QuizLlmUsage>> {
+            Ok(Vec::new())
+        }
+    }
+
+    fn stage_model(provider: Arc<dyn LlmProvider>, model: &str) -> StageModel {
+        StageModel {
+            endpoint: Arc::new(LlmEndpoint {
+                provider,
+                breaker: CircuitBreaker::new(1, Duration::from_secs(60)),
+            }),
+            model: model.to_string(),
+        }
+    }
+
+    fn service_with(
+        summarise: Vec<StageModel>,
+        generate_questions: Vec<StageModel>,
+    ) -> ModelService {
+        ModelService {
+            summarise,
+            generate_questions,
+            validate: Vec::new(),
+            prices: HashMap::new(),
+            usage_repository: None,
+            web_fetcher: WebFetcher::new(&Config::test_config()),
+            summary_context_tokens: crate::config::DEFAULT_CONTEXT_LENGTH,
+        }
+    }
+
+    #[tokio::test]
+    async fn falls_back_and_skips_endpoint_with_open_circuit() {
+        let primary = Arc::new(FakeProvider {
+            fail: true,
+            calls: AtomicU32::new(0),
+        });
+        let fallback = Arc::new(FakeProvider {
+            fail: false,
+            calls: AtomicU32::new(0),
+        });
+        let service = service_with(
+            Vec::new(),
+            vec![
+                stage_model(primary.clone(), "local-model"),
+                stage_model(fallback.clone(), "backup-model"),
+            ],
+        );
+
+        for _ in 0..2 {
+            let output = service
+                .complete_text(
+                    &LlmCallContext::default(),
+                    LlmStage::GenerateQuestions,
+                    vec![LlmMessage::user("hi")],
+                )
+                .await
+                .expect("fallback should answer");
+            assert_eq!(output.value, "answer from backup-model");
+            assert_eq!(output.model, "fallback/backup-model");
+        }
+
+        assert_eq!(primary.calls.load(Ordering::SeqCst), 1);
+        assert_eq!(fallback.calls.load(Ordering::SeqCst), 2);
+    }
+
+    /// Replies with the queued responses in order and records the last request
+    struct ScriptedProvider {
+        responses: std::sync::Mutex<Vec<&'static str>>,
+        last_request: std::sync::Mutex<Option<LlmRequest>>,
+    }
+
+    #[async_trait]
+    impl LlmProvider for ScriptedProvider {
+        fn name(&self) -> &'static str {
+            "scripted"
+        }
+
+        async fn complete(&self, request: LlmRequest) -> AppResult<LlmResponse> {
+            *self.last_request.lock().unwrap() = Some(request);
+            Ok(LlmResponse {
+                content: Some(self.responses.lock().unwrap().remove(0).to_string()),
+                ..Default::default()
+            })
+        }
+    }
+
+    #[derive(Debug, serde::Serialize, Deserialize, JsonSchema)]
+    struct Answer {
+        title: String,
+        count: i32,
+    }
+
+    fn scripted_service(responses: Vec<&'static str>) -> (ModelService, Arc<ScriptedProvider>) {
+        let provider = Arc::new(ScriptedProvider {
+            responses: std::sync::Mutex::new(responses),
+            last_request: std::sync::Mutex::new(None),
+        });
+        let service = service_with(Vec::new(), vec![stage_model(provider.clone(), "model")]);
+        (service, provider)
+    }
+
+    #[tokio::test]
+    async fn structured_output_repairs_invalid_response() {
+        let (service, provider) = scripted_service(vec![
+            "```json\n{\"title\": \"Rust\"}\n```",
+            "Here you go: {\"title\": \"Rust\", \"count\": 3}",
+        ]);
+
+        let output = service
+            .structured_output::<Answer>(
+                &LlmCallContext::default(),
+                LlmStage::GenerateQuestions,
+                vec![LlmMessage::user("q")],
+            )
+            .await
+            .expect("repaired response should parse");
+
+        assert_eq!(output.value.title, "Rust");
+        assert_eq!(output.value.count, 3);
+
+        let last_request = provider.last_request.lock().unwrap().clone().unwrap();
+        let repair = last_request.messages.last().unwrap();
+        assert!(repair
+            .content
+            .as_deref()
+            .unwrap()
+            .contains("missing required field 'count'"));
+    }
+
+    #[tokio::test]
+    async fn quiz_generator_labels_each_source_when_there_are_several() {
+        let (service, provider) = scripted_service(vec![
+            r#"{"quiz_title": "T", "quiz_description": "D", "quiz_topic": "X", "quiz_questions": [
+                {"question_title": "Q", "question_description": "", "question_type": "single",
+                 "question_options": [], "source_id": "source-2"}]}"#,
+        ]);
+        let summary = |source_id: &str, url: &str, content: &str| SummaryDocumentRequestDto {
+            id: format!("summary-{}", source_id),
+            quiz_id: "quiz-1".to_string(),
+            source_id: Some(source_id.to_string()),
+            url: url.to_string(),
+            content: content.to_string(),
+            created_at: String::new(),
+            modified_at: String::new(),
+        };
+        let quiz = QuizRequestDto::from(crate::models::dto::quiz_dto::QuizDto::from(
+            crate::models::domain::Quiz::test_quiz("Quiz", "user-1"),
+        ));
+
+        let output = service
+            .structured_quiz_generator(
+                &LlmCallContext::default(),
+                quiz,
+                vec![
+                    summary("source-1", "https://example.com", "Page notes"),
+                    summary("source-2", "upload:notes.md", "Upload notes"),
+                ],
+            )
+            .await
+            .expect("quiz");
+
+        assert_eq!(
+            output.value.quiz_questions[0].source_id.as_deref(),
+            Some("source-2")
+        );
+        let request = provider.last_request.lock().unwrap().clone().unwrap();
+        assert!(request
+            .messages
+            .iter()
+            .any(|m| m.content.as_deref() == Some(MULTI_SOURCE_QUIZ_PROMPT)));
+        let content = request.messages.last().unwrap().content.clone().unwrap();
+        assert!(content.contains("## Source source-1 (https://example.com)\n\nPage notes"));
+        assert!(content.contains("## Source source-2 (upload:notes.md)\n\nUpload notes"));
+    }
+
+    #[tokio::test]
+    async fn regenerates_questions_with_the_validation_model() {
+        let (mut service, provider) = scripted_service(vec![r#"{"quiz_questions": []}"#]);
+        service.validate = std::mem::take(&mut service.generate_questions);
+        let summary = SummaryDocumentRequestDto {
+            id: "summary-1".to_string(),
+            quiz_id: "quiz-1".to_string(),
+            source_id: None,
+            url: "https://example.com".to_string(),
+            content: "Notes".to_string(),
+            created_at: String::new(),
+            modified_at: String::new(),
+        };
+
+        service
+            .regenerate_questions(
+                &LlmCallContext::default(),
+                &[summary],
+                &[],
+                &[RejectedQuestion {
+                    title: "Capital?".to_string(),
+                    problems: vec!["needs exactly 1 correct option, found 2".to_string()],
+                }],
+                2,
+            )
+            .await
+            .expect("replacements");
+
+        let request = provider.last_request.lock().unwrap().clone().unwrap();
+        let prompt = request.messages.last().unwrap().content.clone().unwrap();
+        assert!(prompt.starts_with("Write 2 replacement questions."));
+        assert!(prompt.contains("- \"Capital?\": needs exactly 1 correct option, found 2"));
+    }
+
+    #[tokio::test]
+    async fn replace_questions_passes_on_creator_instructions() {
+        let (service, provider) = scripted_service(vec![r#"{"quiz_questions": []}"#]);
+        let summary = SummaryDocumentRequestDto {
+            id: "summary-1".to_string(),
+            quiz_id: "quiz-1".to_string(),
+            source_id: None,
+            url: "https://example.com".to_string(),
+            content: "Notes".to_string(),
+            created_at: String::new(),
+            modified_at: String::new(),
+        };
+        let question = |title: &str| GenerateQuizQuestionRequestDto {
+            question_title: title.to_string(),
+            question_description: String::new(),
+            question_type: "bool".to_string(),
+            question_options: Vec::new(),
+            source_id: None,
+        };
+
+        service
+            .replace_questions(
+                &LlmCallContext::default(),
+                &[summary],
+                &[question("Is Rust fast?")],
+                &[question("Is Rust new?")],
+                1,
+                Some("make it harder"),
+            )
+            .await
+            .expect("replacements");
+
+        let request = provider.last_request.lock().unwrap().clone().unwrap();
+        let prompt = request.messages.last().unwrap().content.clone().unwrap();
+        assert!(prompt.starts_with("Write 1 new questions."));
+        assert!(prompt.contains("Instructions from the quiz creator: make it harder"));
+        assert!(prompt.contains("\"question_title\": \"Is Rust new?\""));
+        assert!(prompt.ends_with("must not be repeated:\n- Is Rust fast?"));
+    }
+
+    #[tokio::test]
+    async fn structured_output_gives_up_after_repair_budget() {
+        let (service, _) = scripted_service(vec!["not json"; 3]);
+
+        let err = service
+            .structured_output::<Answer>(
+                &LlmCallContext::default(),
+                LlmStage::GenerateQuestions,
+                vec![LlmMessage::user("q")],
+            )
+            .await
+            .unwrap_err();
+
+        match err {
+            StructuredOutputError::Invalid { attempts, .. } => {
+                assert_eq!(attempts, STRUCTURED_OUTPUT_REPAIR_ATTEMPTS + 1)
+            }
+            other => panic!("expected Invalid, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn reports_failure_when_every_model_fails() {
+        let service = service_with(
+            vec![stage_model(
+                Arc::new(FakeProvider {
+                    fail: true,
+                    calls: AtomicU32::new(0),
+                }),
+                "local-model",
+            )],
+            Vec::new(),
+        );
+
+        let first = service
+            .chat_completion(&LlmCallContext::default(), LlmStage::Summarise, "hi")
+            .await
+            .unwrap_err();
+        assert!(first.to_string().contains("connection refused"));
+
+        let second = service
+            .chat_completion(&LlmCallContext::default(), LlmStage::Summarise, "hi")
+            .await
+            .unwrap_err();
+        assert!(second.to_string().contains("circuit open"));
+    }
+
+    #[tokio::test]
+    async fn records_usage_for_each_call_with_cost() {
+        let repository = Arc::new(RecordingUsageRepository::default());
+        let mut service = service_with(
+            Vec::new(),
+            vec![
+                stage_model(
+                    Arc::new(FakeProvider {
+                        fail: true,
+                        calls: AtomicU32::new(0),
+                    }),
+                    "local-model",
+                ),
+                stage_model(
+                    Arc::new(FakeProvider {
+                        fail: false,
+                        calls: AtomicU32::new(0),
+                    }),
+                    "paid-model",
+                ),
+            ],
+        )
+        .with_usage_repository(repository.clone());
+        service.prices.insert(
+            "paid-model".to_string(),
+            LlmModelPrice {
+                prompt_per_million: 2.0,
+                completion_per_million: 10.0,
+            },
+        );
+
+        service
+            .chat_completion(
+                &LlmCallContext::for_job("job-1", "quiz-1", "user-1"),
+                LlmStage::GenerateQuestions,
+                "hi",
+            )
+            .await
+            .expect("fallback should answer");
+
+        let records = repository.records.lock().unwrap();
+        assert_eq!(records.len(), 2);
+        assert!(!records[0].success);
+        assert_eq!(records[0].total_tokens, 0);
+
+        let paid = &records[1];
+        assert!(paid.success);
+        assert_eq!(paid.model, "paid-model");
+        assert_eq!(paid.stage, "GenerateQuestions");
+        assert_eq!(paid.job_id.as_deref(), Some("job-1"));
+        assert_eq!(paid.user_id.as_deref(), Some("user-1"));
+        assert_eq!(paid.total_tokens, 1_500);
+        assert!((paid.cost_usd - 0.007).abs() < 1e-9);
+    }
 
     #[test]
     fn test_model_service_creation() {
@@ -548,4 +1509,73 @@ mod tests {
 
         assert!(std::mem::size_of_val(&service) > 0);
     }
+
+    /// Answers chunk prompts with notes and anything else with a summary,
+    /// recording every user prompt
+    #[derive(Default)]
+    struct SummaryProvider {
+        prompts: std::sync::Mutex<Vec<String>>,
+    }
+
+    #[async_trait]
+    impl LlmProvider for SummaryProvider {
+        fn name(&self) -> &'static str {
+            "summary"
+        }
+
+        async fn complete(&self, request: LlmRequest) -> AppResult<LlmResponse> {
+            let is_chunk = request.messages[0].content.as_deref() == Some(CHUNK_SUMMARY_PROMPT);
+            let prompt = request.messages[1].content.clone().unwrap_or_default();
+            self.prompts.lock().unwrap().push(prompt);
+            Ok(LlmResponse {
+                content: Some(
+                    if is_chunk {
+                        "- a note"
+                    } else {
+                        "final summary"
+                    }
+                    .to_string(),
+                ),
+                ..Default::default()
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn summarises_long_pages_in_chunks_and_merges_notes() {
+        let provider = Arc::new(SummaryProvider::default());
+        let mut service = service_with(vec![stage_model(provider.clone(), "model")], Vec::new());
+        service.summary_context_tokens = 0;
+        let section = |title: &str| format!("## {}\n\n{}", title, "lorem ipsum ".repeat(150));
+        let page = ExtractedPage {
+            title: Some("Guide".to_string()),
+            content: [section("One"), section("Two"), section("Three")].join("\n\n"),
+            ..Default::default()
+        };
+
+        let summary = service
+            .summarise_page(
+                &LlmCallContext::default(),
+                "https://example.com",
+                &page,
+                Some(5),
+            )
+            .await
+            .expect("summary");
+
+        assert_eq!(summary.value.content, "final summary");
+        assert_eq!(summary.value.chunks, 3);
+        assert_eq!(
+            summary.value.sections_covered,
+            vec!["Guide", "One", "Two", "Three"]
+        );
+        assert!(summary.value.sections_skipped.is_empty());
+
+        let prompts = provider.prompts.lock().unwrap();
+        assert_eq!(prompts.len(), 4);
+        let merge = prompts.last().unwrap();
+        assert!(merge.contains("Question Count: 5"));
+        assert!(merge.contains("### Part 1 of 3 (sections: Guide, One)"));
+        assert!(merge.contains("### Part 3 of 3 (sections: Three)"));
+    }
 }
@@ -0,0 +1,48 @@
+use crate::services::{
+    job_schedule::{JobSchedule, ScheduleTrigger},
+    step_executor::{PurgeCompletedJobsStep, PurgeExpiredRefreshTokensStep},
+    step_registry::{PipelineDefinition, StepDefinition},
+};
+
+const PURGE_TIMEOUT: u64 = 120;
+const PURGE_RETRIES: u32 = 2;
+
+pub const PURGE_EXPIRED_REFRESH_TOKENS_PIPELINE: &str = "purge_expired_refresh_tokens";
+pub const PURGE_COMPLETED_JOBS_PIPELINE: &str = "purge_completed_jobs";
+
+pub fn purge_expired_refresh_tokens_pipeline() -> PipelineDefinition {
+    PipelineDefinition::new(PURGE_EXPIRED_REFRESH_TOKENS_PIPELINE).with_step(
+        StepDefinition::new(PurgeExpiredRefreshTokensStep::NAME)
+            .with_description("Delete refresh tokens that have expired")
+            .with_max_retries(PURGE_RETRIES)
+            .with_timeout(PURGE_TIMEOUT),
+    )
+}
+
+pub fn purge_completed_jobs_pipeline() -> PipelineDefinition {
+    PipelineDefinition::new(PURGE_COMPLETED_JOBS_PIPELINE).with_step(
+        StepDefinition::new(PurgeCompletedJobsStep::NAME)
+            .with_description("Delete completed jobs older than the retention period")
+            .with_max_retries(PURGE_RETRIES)
+            .with_timeout(PURGE_TIMEOUT),
+    )
+}
+
+/// Housekeeping schedules created on startup if they don't already exist
+pub fn default_schedules() -> Vec<JobSchedule> {
+    vec![
+        JobSchedule::new(
+            PURGE_EXPIRED_REFRESH_TOKENS_PIPELINE,
+            PURGE_EXPIRED_REFRESH_TOKENS_PIPELINE,
+            ScheduleTrigger::DailyAt { hour: 3, minute: 0 },
+        ),
+        JobSchedule::new(
+            PURGE_COMPLETED_JOBS_PIPELINE,
+            PURGE_COMPLETED_JOBS_PIPELINE,
+            ScheduleTrigger::DailyAt {
+                hour: 3,
+                minute: 30,
+            },
+        ),
+    ]
+}
@@ -1,3 +1,81 @@
+pub mod maintenance_steps;
 pub mod quiz_steps;
 
-pub use quiz_steps::create_quiz_generation_steps;
+pub use maintenance_steps::{
+    default_schedules, PURGE_COMPLETED_JOBS_PIPELINE, PURGE_EXPIRED_REFRESH_TOKENS_PIPELINE,
+};
+pub use quiz_steps::{
+    create_quiz_generation_steps, quiz_source_refresh_schedule, QUIZ_GENERATION_PIPELINE,
+    QUIZ_QUESTION_REGENERATION_PIPELINE, QUIZ_SOURCE_REFRESH_PIPELINE,
+};
+
+use crate::services::{
+    step_executor::{
+        CheckQuestionGroundingStep, CreateQuizDraftStep, CreateQuizQuestionsStep,
+        CreateSummaryDocumentStep, FinalizeQuizStep, GenerateReplacementQuestionsStep,
+        PurgeCompletedJobsStep, PurgeExpiredRefreshTokensStep, RefreshQuizFromSourceStep,
+        ReplaceQuizQuestionsStep, ValidateQuizQuestionsStep,
+    },
+    step_registry::StepRegistry,
+};
+
+/// Registry with every built-in step executor and pipeline
+pub fn default_registry() -> StepRegistry {
+    let mut registry = StepRegistry::new();
+
+    registry
+        .register_step(CreateQuizDraftStep)
+        .register_step(CreateSummaryDocumentStep)
+        .register_step(CreateQuizQuestionsStep)
+        .register_step(ValidateQuizQuestionsStep)
+        .register_step(CheckQuestionGroundingStep)
+        .register_step(FinalizeQuizStep)
+        .register_step(RefreshQuizFromSourceStep)
+        .register_step(GenerateReplacementQuestionsStep)
+        .register_step(ReplaceQuizQuestionsStep)
+        .register_step(PurgeExpiredRefreshTokensStep)
+        .register_step(PurgeCompletedJobsStep)
+        .register_pipeline(quiz_steps::quiz_generation_pipeline())
+        .register_pipeline(quiz_steps::quiz_source_refresh_pipeline())
+        .register_pipeline(quiz_steps::quiz_question_regeneration_pipeline())
+        .register_pipeline(maintenance_steps::purge_expired_refresh_tokens_pipeline())
+        .register_pipeline(maintenance_steps::purge_completed_jobs_pipeline());
+
+    registry
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_registry_can_build_quiz_generation_pipeline() {
+        let registry = default_registry();
+
+        let steps = registry
+            .build_pipeline_steps(QUIZ_GENERATION_PIPELINE)
+            .expect("quiz generation pipeline should be buildable");
+
+        assert_eq!(steps.len(), 6);
+    }
+
+    #[test]
+    fn default_schedules_use_registered_pipelines() {
+        let registry = default_registry();
+
+        for schedule in default_schedules() {
+            assert!(schedule.trigger.validate().is_ok());
+            assert!(
+                registry.build_pipeline_steps(&schedule.pipeline).is_ok(),
+                "{} has no buildable pipeline",
+                schedule.name
+            );
+        }
+        assert!(registry
+            .build_pipeline_steps(QUIZ_SOURCE_REFRESH_PIPELINE)
+            .is_ok());
+        assert!(registry
+            .build_pipeline_steps(QUIZ_QUESTION_REGENERATION_PIPELINE)
+            .is_ok());
+    }
+}
@@ -1,45 +1,132 @@
-use crate::services::agent_orchestrator_service::JobStep;
+use crate::services::{
+    agent_orchestrator_service::JobStep,
+    job_schedule::{JobSchedule, ScheduleTrigger},
+    step_executor::{
+        CheckQuestionGroundingStep, CreateQuizDraftStep, CreateQuizQuestionsStep,
+        CreateSummaryDocumentStep, FinalizeQuizStep, GenerateReplacementQuestionsStep,
+        RefreshQuizFromSourceStep, ReplaceQuizQuestionsStep, ValidateQuizQuestionsStep,
+    },
+    step_registry::{PipelineDefinition, StepDefinition},
+};
 
 const DRAFT_CREATION_TIMEOUT: u64 = 10;
-const SUMMARY_FETCH_TIMEOUT: u64 = 60;
+// Quizzes can have several sources, each summarised in chunks
+const SUMMARY_FETCH_TIMEOUT: u64 = 180;
 const QUIZ_GENERATION_TIMEOUT: u64 = 120;
+// Up to two rounds of regenerating rejected questions
+const QUESTION_VALIDATION_TIMEOUT: u64 = 240;
+const GROUNDING_CHECK_TIMEOUT: u64 = 180;
 const FINALIZATION_TIMEOUT: u64 = 15;
+const SOURCE_REFRESH_TIMEOUT: u64 = 60;
+// One attempt and up to two repair rounds
+const QUESTION_REGENERATION_TIMEOUT: u64 = 240;
 
 const DEFAULT_RETRIES: u32 = 3;
 const FINALIZATION_RETRIES: u32 = 2;
 
+/// UTC hour quiz source refreshes run at
+const SOURCE_REFRESH_HOUR: u32 = 2;
+
+pub const QUIZ_GENERATION_PIPELINE: &str = "quiz_generation";
+pub const QUIZ_SOURCE_REFRESH_PIPELINE: &str = "quiz_source_refresh";
+pub const QUIZ_QUESTION_REGENERATION_PIPELINE: &str = "quiz_question_regeneration";
+
+pub fn quiz_generation_pipeline() -> PipelineDefinition {
+    PipelineDefinition::new(QUIZ_GENERATION_PIPELINE)
+        .with_step(create_draft_step())
+        .with_step(create_summary_document_step())
+        .with_step(create_quiz_questions_step())
+        .with_step(validate_quiz_questions_step())
+        .with_step(check_question_grounding_step())
+        .with_step(finalize_quiz_step())
+}
+
 pub fn create_quiz_generation_steps() -> Vec<JobStep> {
-    vec![
-        create_draft_step(),
-        create_summary_document_step(),
-        create_quiz_questions_step(),
-        finalize_quiz_step(),
-    ]
+    quiz_generation_pipeline().build_steps()
 }
 
-fn create_draft_step() -> JobStep {
-    JobStep::new("create_quiz_draft")
+pub fn quiz_source_refresh_pipeline() -> PipelineDefinition {
+    PipelineDefinition::new(QUIZ_SOURCE_REFRESH_PIPELINE).with_step(
+        StepDefinition::new(RefreshQuizFromSourceStep::NAME)
+            .with_description(
+                "Regenerate the quiz if its source page has changed since the last check",
+            )
+            .with_max_retries(DEFAULT_RETRIES)
+            .with_timeout(SOURCE_REFRESH_TIMEOUT),
+    )
+}
+
+/// Replaces questions the quiz creator picked on an existing quiz
+pub fn quiz_question_regeneration_pipeline() -> PipelineDefinition {
+    PipelineDefinition::new(QUIZ_QUESTION_REGENERATION_PIPELINE)
+        .with_step(
+            StepDefinition::new(GenerateReplacementQuestionsStep::NAME)
+                .with_description("Generate replacements for the picked questions from the stored summaries via model service")
+                .with_max_retries(DEFAULT_RETRIES)
+                .with_timeout(QUESTION_REGENERATION_TIMEOUT),
+        )
+        .with_step(check_question_grounding_step())
+        .with_step(
+            StepDefinition::new(ReplaceQuizQuestionsStep::NAME)
+                .with_description("Swap the replacement questions into the quiz, keeping their order")
+                .with_max_retries(FINALIZATION_RETRIES)
+                .with_timeout(FINALIZATION_TIMEOUT),
+        )
+}
+
+/// Nightly check that regenerates a quiz when its source URL changes, turned
+/// on by the quiz's creator
+pub fn quiz_source_refresh_schedule(quiz_id: &str) -> JobSchedule {
+    JobSchedule::new(
+        format!("{}:{}", QUIZ_SOURCE_REFRESH_PIPELINE, quiz_id),
+        QUIZ_SOURCE_REFRESH_PIPELINE,
+        ScheduleTrigger::DailyAt {
+            hour: SOURCE_REFRESH_HOUR,
+            minute: 0,
+        },
+    )
+    .with_metadata("quiz_id", serde_json::json!(quiz_id))
+}
+
+fn create_draft_step() -> StepDefinition {
+    StepDefinition::new(CreateQuizDraftStep::NAME)
         .with_description("Create new Quiz with draft status and add to database")
         .with_max_retries(DEFAULT_RETRIES)
         .with_timeout(DRAFT_CREATION_TIMEOUT)
 }
 
-fn create_summary_document_step() -> JobStep {
-    JobStep::new("create_summary_document")
-        .with_description("Create summary document from provided URL via model service")
+fn create_summary_document_step() -> StepDefinition {
+    StepDefinition::new(CreateSummaryDocumentStep::NAME)
+        .with_description("Create a summary document for each quiz source via model service")
         .with_max_retries(DEFAULT_RETRIES)
         .with_timeout(SUMMARY_FETCH_TIMEOUT)
 }
 
-fn create_quiz_questions_step() -> JobStep {
-    JobStep::new("create_quiz_questions")
+fn create_quiz_questions_step() -> StepDefinition {
+    StepDefinition::new(CreateQuizQuestionsStep::NAME)
         .with_description("Generate quiz questions and fields via model service call")
         .with_max_retries(DEFAULT_RETRIES)
         .with_timeout(QUIZ_GENERATION_TIMEOUT)
 }
 
-fn finalize_quiz_step() -> JobStep {
-    JobStep::new("finalize_quiz")
+fn validate_quiz_questions_step() -> StepDefinition {
+    StepDefinition::new(ValidateQuizQuestionsStep::NAME)
+        .with_description(
+            "Check generated questions and regenerate invalid or missing ones via model service",
+        )
+        .with_max_retries(DEFAULT_RETRIES)
+        .with_timeout(QUESTION_VALIDATION_TIMEOUT)
+}
+
+fn check_question_grounding_step() -> StepDefinition {
+    StepDefinition::new(CheckQuestionGroundingStep::NAME)
+        .with_description("Check each correct answer is supported by the summary, when enabled")
+        .with_max_retries(DEFAULT_RETRIES)
+        .with_timeout(GROUNDING_CHECK_TIMEOUT)
+}
+
+fn finalize_quiz_step() -> StepDefinition {
+    StepDefinition::new(FinalizeQuizStep::NAME)
         .with_description("Deserialize quiz JSON, update database with complete quiz model, and change status to active")
         .with_max_retries(FINALIZATION_RETRIES)
         .with_timeout(FINALIZATION_TIMEOUT)
@@ -60,6 +147,8 @@ mod tests {
                 "create_quiz_draft",
                 "create_summary_document",
                 "create_quiz_questions",
+                "validate_quiz_questions",
+                "check_question_grounding",
                 "finalize_quiz"
             ]
         );
@@ -78,8 +167,14 @@ mod tests {
         assert_eq!(steps[2].max_retries, DEFAULT_RETRIES);
         assert_eq!(steps[2].timeout_seconds, Some(QUIZ_GENERATION_TIMEOUT));
 
-        assert_eq!(steps[3].max_retries, FINALIZATION_RETRIES);
-        assert_eq!(steps[3].timeout_seconds, Some(FINALIZATION_TIMEOUT));
+        assert_eq!(steps[3].max_retries, DEFAULT_RETRIES);
+        assert_eq!(steps[3].timeout_seconds, Some(QUESTION_VALIDATION_TIMEOUT));
+
+        assert_eq!(steps[4].max_retries, DEFAULT_RETRIES);
+        assert_eq!(steps[4].timeout_seconds, Some(GROUNDING_CHECK_TIMEOUT));
+
+        assert_eq!(steps[5].max_retries, FINALIZATION_RETRIES);
+        assert_eq!(steps[5].timeout_seconds, Some(FINALIZATION_TIMEOUT));
     }
 
     #[test]
@@ -96,4 +191,36 @@ mod tests {
             .iter()
             .all(|step| step.description.as_ref().is_some_and(|d| !d.is_empty())));
     }
+
+    #[test]
+    fn quiz_question_regeneration_pipeline_checks_grounding_before_replacing() {
+        let steps = quiz_question_regeneration_pipeline().build_steps();
+
+        let names: Vec<&str> = steps.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(
+            names,
+            vec![
+                "generate_replacement_questions",
+                "check_question_grounding",
+                "replace_quiz_questions"
+            ]
+        );
+        assert_eq!(
+            steps[0].timeout_seconds,
+            Some(QUESTION_REGENERATION_TIMEOUT)
+        );
+        assert_eq!(steps[2].max_retries, FINALIZATION_RETRIES);
+    }
+
+    #[test]
+    fn quiz_source_refresh_schedule_is_keyed_by_quiz() {
+        let schedule = quiz_source_refresh_schedule("quiz-1");
+
+        assert_eq!(schedule.name, "quiz_source_refresh:quiz-1");
+        assert_eq!(schedule.pipeline, QUIZ_SOURCE_REFRESH_PIPELINE);
+        assert_eq!(
+            schedule.metadata.get("quiz_id"),
+            Some(&serde_json::json!("quiz-1"))
+        );
+    }
 }
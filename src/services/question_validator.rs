@@ -0,0 +1,404 @@
+use std::collections::HashSet;
+
+use serde::Serialize;
+
+use crate::models::{
+    domain::{
+        quiz_question::{QuestionGrounding, QuizQuestionType},
+        QuizQuestion,
+    },
+    dto::{
+        quiz_dto::QuizQuestionDto,
+        request::{GenerateQuizQuestionRequestDto, GroundingJudgementDto},
+    },
+};
+
+const MIN_OPTIONS: usize = 2;
+const MAX_OPTIONS: usize = 6;
+
+/// A generated question that failed validation, and why
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct RejectedQuestion {
+    pub title: String,
+    pub problems: Vec<String>,
+}
+
+/// Outcome of checking generated questions against the requested quiz
+#[derive(Debug, Clone, Default)]
+pub struct QuestionReview {
+    /// Questions that passed, in order, up to the requested count
+    pub valid: Vec<GenerateQuizQuestionRequestDto>,
+    pub rejected: Vec<RejectedQuestion>,
+    /// Questions dropped because they repeat an earlier one
+    pub duplicates: usize,
+    /// How many more valid questions are needed to reach the requested count
+    pub missing: usize,
+}
+
+impl QuestionReview {
+    pub fn is_complete(&self) -> bool {
+        self.missing == 0
+    }
+}
+
+pub fn parse_question_type(value: &str) -> Option<QuizQuestionType> {
+    match value.trim().to_lowercase().as_str() {
+        "single" => Some(QuizQuestionType::Single),
+        "multi" => Some(QuizQuestionType::Multi),
+        "bool" | "boolean" => Some(QuizQuestionType::Bool),
+        _ => None,
+    }
+}
+
+pub fn parse_correct(value: &str) -> Option<bool> {
+    match value.trim().to_lowercase().as_str() {
+        "true" => Some(true),
+        "false" => Some(false),
+        _ => None,
+    }
+}
+
+/// Check each question against the rules for its type, drop repeats and
+/// count the result against `expected` questions. Surplus valid questions are
+/// left out.
+pub fn review_questions(
+    questions: Vec<GenerateQuizQuestionRequestDto>,
+    expected: usize,
+) -> QuestionReview {
+    review_against(questions, HashSet::new(), expected)
+}
+
+/// Like [`review_questions`], but also treats questions repeating one of the
+/// `kept` titles as duplicates
+pub fn review_replacements(
+    questions: Vec<GenerateQuizQuestionRequestDto>,
+    kept: &[&str],
+    expected: usize,
+) -> QuestionReview {
+    let seen = kept.iter().map(|title| normalise(title)).collect();
+    review_against(questions, seen, expected)
+}
+
+fn review_against(
+    questions: Vec<GenerateQuizQuestionRequestDto>,
+    mut seen: HashSet<String>,
+    expected: usize,
+) -> QuestionReview {
+    let mut review = QuestionReview::default();
+
+    for question in questions {
+        let problems = question_problems(&question);
+        if !problems.is_empty() {
+            review.rejected.push(RejectedQuestion {
+                title: question.question_title,
+                problems,
+            });
+        } else if !seen.insert(normalise(&question.question_title)) {
+            review.duplicates += 1;
+        } else if review.valid.len() < expected {
+            review.valid.push(question);
+        }
+    }
+
+    review.missing = expected.saturating_sub(review.valid.len());
+    review
+}
+
+/// Problems with a stored question, by the same rules as generated ones
+pub fn quiz_question_problems(question: &QuizQuestion) -> Vec<String> {
+    question_problems(&QuizQuestionDto::from(question.clone()).into())
+}
+
+fn question_problems(question: &GenerateQuizQuestionRequestDto) -> Vec<String> {
+    let mut problems = Vec::new();
+    if question.question_title.trim().is_empty() {
+        problems.push("question_title is empty".to_string());
+    }
+
+    let options = &question.question_options;
+    let mut correct = 0;
+    let mut texts = HashSet::new();
+    for (index, option) in options.iter().enumerate() {
+        let text = normalise(&option.option_text);
+        if text.is_empty() {
+            problems.push(format!("option {} has no text", index + 1));
+        } else if !texts.insert(text) {
+            problems.push(format!("option {} repeats an earlier option", index + 1));
+        }
+        match parse_correct(&option.option_correct) {
+            Some(true) => correct += 1,
+            Some(false) => {}
+            None => problems.push(format!(
+                "option {} option_correct must be \"true\" or \"false\", not {:?}",
+                index + 1,
+                option.option_correct
+            )),
+        }
+    }
+
+    let Some(question_type) = parse_question_type(&question.question_type) else {
+        problems.push(format!(
+            "question_type must be \"single\", \"multi\" or \"bool\", not {:?}",
+            question.question_type
+        ));
+        return problems;
+    };
+    match question_type {
+        QuizQuestionType::Bool => {
+            if options.len() != 2 {
+                problems.push(format!(
+                    "bool questions need exactly 2 options, found {}",
+                    options.len()
+                ));
+            }
+        }
+        QuizQuestionType::Single | QuizQuestionType::Multi => {
+            if !(MIN_OPTIONS..=MAX_OPTIONS).contains(&options.len()) {
+                problems.push(format!(
+                    "questions need {} to {} options, found {}",
+                    MIN_OPTIONS,
+                    MAX_OPTIONS,
+                    options.len()
+                ));
+            }
+        }
+    }
+    match question_type {
+        QuizQuestionType::Single | QuizQuestionType::Bool if correct != 1 => {
+            problems.push(format!("needs exactly 1 correct option, found {}", correct))
+        }
+        QuizQuestionType::Multi if correct == 0 || correct == options.len() => {
+            problems.push("needs both correct and incorrect options".to_string())
+        }
+        _ => {}
+    }
+
+    problems
+}
+
+/// Grounding of a question from the model's judgement of its correct options.
+///
+/// Options the model skipped, and evidence that isn't actually in `source`,
+/// score zero. The question scores as its weakest correct option.
+pub fn question_grounding(
+    question: &GenerateQuizQuestionRequestDto,
+    judgement: &GroundingJudgementDto,
+    source: &str,
+    min_confidence: u8,
+) -> QuestionGrounding {
+    let source = normalise(source);
+    let mut evidence: Vec<&str> = Vec::new();
+    let mut confidence = 100;
+
+    for option in &question.question_options {
+        if parse_correct(&option.option_correct) != Some(true) {
+            continue;
+        }
+        let text = normalise(&option.option_text);
+        let judged = judgement
+            .options
+            .iter()
+            .find(|judged| normalise(&judged.option_text) == text);
+        let score = match judged {
+            Some(judged) if !judged.evidence.trim().is_empty() => {
+                if !evidence.contains(&judged.evidence.trim()) {
+                    evidence.push(judged.evidence.trim());
+                }
+                if source.contains(&normalise(&judged.evidence)) {
+                    judged.confidence.clamp(0, 100) as u8
+                } else {
+                    0
+                }
+            }
+            _ => 0,
+        };
+        confidence = confidence.min(score);
+    }
+
+    QuestionGrounding {
+        evidence: evidence.join("\n"),
+        confidence,
+        needs_review: confidence < min_confidence,
+    }
+}
+
+/// Grounding for a question that couldn't be checked
+pub fn unchecked_grounding() -> QuestionGrounding {
+    QuestionGrounding {
+        evidence: String::new(),
+        confidence: 0,
+        needs_review: true,
+    }
+}
+
+/// Lowercase with whitespace collapsed, for spotting repeats and quotes
+fn normalise(text: &str) -> String {
+    text.split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::dto::request::{GenerateQuizQuestionOptionRequestDto, OptionGroundingDto};
+
+    fn question(
+        title: &str,
+        question_type: &str,
+        options: &[(&str, &str)],
+    ) -> GenerateQuizQuestionRequestDto {
+        GenerateQuizQuestionRequestDto {
+            question_title: title.to_string(),
+            question_description: String::new(),
+            question_type: question_type.to_string(),
+            question_options: options
+                .iter()
+                .map(|(text, correct)| GenerateQuizQuestionOptionRequestDto {
+                    option_text: text.to_string(),
+                    option_correct: correct.to_string(),
+                    option_explanation: String::new(),
+                })
+                .collect(),
+            source_id: None,
+        }
+    }
+
+    #[test]
+    fn rejects_questions_that_break_their_type_rules() {
+        let review = review_questions(
+            vec![
+                question("Two answers", "single", &[("A", "true"), ("B", "TRUE")]),
+                question("No answer", "single", &[("A", "false"), ("B", "false")]),
+                question(
+                    "Four way bool",
+                    "bool",
+                    &[
+                        ("A", "true"),
+                        ("B", "false"),
+                        ("C", "false"),
+                        ("D", "false"),
+                    ],
+                ),
+                question("All correct", "multi", &[("A", "true"), ("B", "true")]),
+                question(
+                    "Repeated",
+                    "single",
+                    &[("Paris", "true"), (" paris ", "false")],
+                ),
+                question("Essay", "essay", &[("A", "true"), ("B", "false")]),
+                question("Unsure", "single", &[("A", "yes"), ("B", "false")]),
+                question(
+                    "Fine",
+                    "Multi",
+                    &[("A", "true"), ("B", "true"), ("C", "false")],
+                ),
+            ],
+            3,
+        );
+
+        let rejected: Vec<&str> = review.rejected.iter().map(|r| r.title.as_str()).collect();
+        assert_eq!(
+            rejected,
+            vec![
+                "Two answers",
+                "No answer",
+                "Four way bool",
+                "All correct",
+                "Repeated",
+                "Essay",
+                "Unsure"
+            ]
+        );
+        assert!(review.rejected[4].problems[0].contains("repeats"));
+        assert_eq!(review.valid.len(), 1);
+        assert_eq!(review.missing, 2);
+    }
+
+    #[test]
+    fn drops_duplicates_and_surplus_questions() {
+        let options = [("True", "true"), ("False", "false")];
+        let review = review_questions(
+            vec![
+                question("Is Rust safe?", "bool", &options),
+                question("is  rust SAFE?", "bool", &options),
+                question("Is C safe?", "bool", &options),
+                question("Is Go safe?", "bool", &options),
+            ],
+            2,
+        );
+
+        assert_eq!(review.duplicates, 1);
+        assert!(review.is_complete());
+        let titles: Vec<&str> = review
+            .valid
+            .iter()
+            .map(|q| q.question_title.as_str())
+            .collect();
+        assert_eq!(titles, vec!["Is Rust safe?", "Is C safe?"]);
+    }
+
+    #[test]
+    fn replacements_must_not_repeat_kept_questions() {
+        let options = [("True", "true"), ("False", "false")];
+        let review = review_replacements(
+            vec![
+                question("Is rust safe?", "bool", &options),
+                question("Is Go safe?", "bool", &options),
+            ],
+            &["Is Rust safe?"],
+            2,
+        );
+
+        assert_eq!(review.duplicates, 1);
+        assert_eq!(review.valid[0].question_title, "Is Go safe?");
+        assert_eq!(review.missing, 1);
+    }
+
+    #[test]
+    fn grounding_scores_the_weakest_correct_option_and_checks_quotes() {
+        let question = question(
+            "Which are primary colours?",
+            "multi",
+            &[("Red", "true"), ("Blue", "true"), ("Green", "false")],
+        );
+        let judgement = |blue_evidence: &str| GroundingJudgementDto {
+            options: vec![
+                OptionGroundingDto {
+                    option_text: "red".to_string(),
+                    evidence: "Red is a  primary colour.".to_string(),
+                    confidence: 95,
+                },
+                OptionGroundingDto {
+                    option_text: "Blue".to_string(),
+                    evidence: blue_evidence.to_string(),
+                    confidence: 80,
+                },
+            ],
+        };
+        let source = "Red is a primary colour.\nBlue is a primary colour too.";
+
+        let grounded = question_grounding(
+            &question,
+            &judgement("Blue is a primary colour too."),
+            source,
+            70,
+        );
+        assert_eq!(grounded.confidence, 80);
+        assert!(!grounded.needs_review);
+        assert_eq!(
+            grounded.evidence,
+            "Red is a  primary colour.\nBlue is a primary colour too."
+        );
+
+        let invented = question_grounding(
+            &question,
+            &judgement("Blue is the colour of the sky."),
+            source,
+            70,
+        );
+        assert_eq!(invented.confidence, 0);
+        assert!(invented.needs_review);
+    }
+}
@@ -132,6 +132,7 @@ impl QuizAttemptService {
             id: uuid::Uuid::new_v4().to_string(),
             user_id: user_id.to_string(),
             quiz_id: quiz_id.to_string(),
+            quiz_version: None,
             points_earned,
             required_score,
             total_possible,
@@ -181,6 +182,8 @@ mod tests {
             order: 1,
             attempt_limit: 1,
             topic: "test-topic".to_string(),
+            source_id: None,
+            grounding: None,
             created_at: None,
             modified_at: None,
         }
@@ -200,6 +203,8 @@ mod tests {
             status: QuizStatus::Ready,
             questions: Some(questions),
             url: "https://example.com".to_string(),
+            sources: Vec::new(),
+            version: None,
             created_at: None,
             modified_at: None,
         }
@@ -337,6 +342,8 @@ mod tests {
             status: QuizStatus::Ready,
             questions: None,
             url: "https://example.com".to_string(),
+            sources: Vec::new(),
+            version: None,
             created_at: None,
             modified_at: None,
         };
@@ -1,26 +1,66 @@
+use chrono::{Duration, NaiveTime, SecondsFormat, Utc};
+use std::collections::HashSet;
 use std::sync::Arc;
 use validator::Validate;
 
 use crate::{
+    auth::Claims,
+    config::GenerationLimits,
     errors::{AppError, AppResult},
     models::{
-        domain::{Quiz, QuizQuestion},
+        domain::{
+            quiz::{QuizStatus, MAX_QUIZ_SOURCES, UPLOADED_DOCUMENT_URL_PREFIX},
+            quiz_question::QuizQuestionOption,
+            user::UserRole,
+            Quiz, QuizQuestion, QuizVersion, QuizVersionDiff,
+        },
         dto::{
             quiz_dto::QuizDto,
-            request::{QuizDraftDto, UpdateQuizInput},
-            response::{CreateQuizDraftResponse, CreateQuizDraftResponseData, QuizResponseDto},
+            request::{
+                AddQuizQuestionInput, QuizDocumentDraftDto, QuizDraftDto, UpdateQuizInput,
+                UpdateQuizQuestionInput, UpdateQuizQuestionOptionInput,
+            },
+            response::{
+                CreateQuizDraftResponse, CreateQuizDraftResponseData, GenerationQuotaResponse,
+                QuizResponseDto,
+            },
         },
     },
-    repositories::QuizRepository,
+    repositories::{QuizRepository, QuizVersionRepository},
     services::{
-        agent_orchestrator_service::AgentOrchestrator,
-        orchestrator_steps::create_quiz_generation_steps,
+        agent_orchestrator_service::{AgentOrchestrator, REQUESTED_BY_KEY},
+        document_extractor::{extract_document, DocumentFormat, DocumentUpload},
+        orchestrator_steps::{
+            quiz_source_refresh_schedule, QUIZ_GENERATION_PIPELINE,
+            QUIZ_QUESTION_REGENERATION_PIPELINE,
+        },
+        question_validator::quiz_question_problems,
+        step_executor::{QUESTION_IDS_KEY, REGENERATION_INSTRUCTIONS_KEY},
+        summary_document_service::SummaryDocumentService,
     },
 };
 
+/// Quiz statuses that count towards the concurrent generation limit
+const GENERATING_STATUSES: [QuizStatus; 2] = [QuizStatus::Draft, QuizStatus::Pending];
+
+/// Quiz statuses whose content is published to quiz takers
+const PUBLISHED_STATUSES: [QuizStatus; 2] = [QuizStatus::Ready, QuizStatus::Complete];
+
+/// Tries at taking the next version number when concurrent publishes race
+const VERSION_PUBLISH_ATTEMPTS: u32 = 3;
+
+/// Longest instructions a creator can give when regenerating questions
+const MAX_REGENERATION_INSTRUCTIONS_CHARS: usize = 500;
+
 pub struct QuizService {
     repository: Arc<dyn QuizRepository>,
     orchestrator: Arc<AgentOrchestrator>,
+    generation_limits: GenerationLimits,
+    admin_generation_limits: GenerationLimits,
+    /// Where uploaded source documents are stored; required for document drafts
+    summary_documents: Option<Arc<SummaryDocumentService>>,
+    /// Snapshots of published quizzes; quizzes aren't versioned without it
+    versions: Option<Arc<dyn QuizVersionRepository>>,
 }
 
 impl QuizService {
@@ -28,9 +68,36 @@ impl QuizService {
         Self {
             repository,
             orchestrator,
+            generation_limits: GenerationLimits::default(),
+            admin_generation_limits: GenerationLimits::default(),
+            summary_documents: None,
+            versions: None,
         }
     }
 
+    pub fn with_versions(mut self, versions: Arc<dyn QuizVersionRepository>) -> Self {
+        self.versions = Some(versions);
+        self
+    }
+
+    pub fn with_summary_documents(
+        mut self,
+        summary_documents: Arc<SummaryDocumentService>,
+    ) -> Self {
+        self.summary_documents = Some(summary_documents);
+        self
+    }
+
+    pub fn with_generation_limits(
+        mut self,
+        limits: GenerationLimits,
+        admin_limits: GenerationLimits,
+    ) -> Self {
+        self.generation_limits = limits;
+        self.admin_generation_limits = admin_limits;
+        self
+    }
+
     pub async fn get_quiz(&self, id: &str) -> AppResult<QuizDto> {
         let quiz = self
             .repository
@@ -75,36 +142,210 @@ impl QuizService {
     pub async fn create_quiz_draft(
         &self,
         request: QuizDraftDto,
-        user_id: &str,
+        claims: &Claims,
     ) -> AppResult<CreateQuizDraftResponse> {
         request.validate()?;
+        self.ensure_generation_quota(&claims.sub, &claims.role)
+            .await?;
 
+        let urls: Vec<&String> = std::iter::once(&request.url)
+            .chain(&request.sources)
+            .collect();
         let quiz = Quiz::new_draft(
             &request.name,
-            user_id,
+            &claims.sub,
             request.question_count,
             request.required_score,
             request.attempt_limit,
             &request.url,
-        );
+        )
+        .with_sources(&urls);
+
+        let created_quiz = self.repository.create_quiz_draft(quiz).await?;
+
+        let job_id = self.start_generation_job(&created_quiz.id).await?;
+
+        Ok(CreateQuizDraftResponse {
+            data: CreateQuizDraftResponseData {
+                quiz: QuizResponseDto::from(created_quiz),
+                job_id,
+            },
+            message: "Draft created successfully and processing started".to_string(),
+        })
+    }
+
+    /// Create a quiz draft from uploaded documents, and optionally web pages,
+    /// and start generating it. The text extracted from each upload is stored
+    /// as the source of that upload's summary document, so generation doesn't
+    /// fetch it.
+    pub async fn create_document_quiz_draft(
+        &self,
+        request: QuizDocumentDraftDto,
+        uploads: Vec<DocumentUpload>,
+        claims: &Claims,
+    ) -> AppResult<CreateQuizDraftResponse> {
+        request.validate()?;
+        if uploads.is_empty() {
+            return Err(AppError::ValidationError(
+                "At least one document must be uploaded".to_string(),
+            ));
+        }
+        if request.urls.len() + uploads.len() > MAX_QUIZ_SOURCES {
+            return Err(AppError::ValidationError(format!(
+                "A quiz can have at most {} sources",
+                MAX_QUIZ_SOURCES
+            )));
+        }
+        let summary_documents = self.summary_documents.as_ref().ok_or_else(|| {
+            AppError::InternalError("Document uploads are not configured".to_string())
+        })?;
+        self.ensure_generation_quota(&claims.sub, &claims.role)
+            .await?;
+
+        let mut pages = Vec::with_capacity(uploads.len());
+        for upload in uploads {
+            let format = DocumentFormat::sniff(
+                &upload.file_name,
+                upload.content_type.as_deref(),
+                &upload.bytes,
+            )?;
+            let page = extract_document(format, &upload.file_name, upload.bytes).await?;
+            pages.push((upload.file_name, page));
+        }
 
+        let sources: Vec<String> = request
+            .urls
+            .iter()
+            .cloned()
+            .chain(
+                pages
+                    .iter()
+                    .map(|(file_name, _)| format!("{}{}", UPLOADED_DOCUMENT_URL_PREFIX, file_name)),
+            )
+            .collect();
+        let quiz = Quiz::new_draft(
+            &request.name,
+            &claims.sub,
+            request.question_count,
+            request.required_score,
+            request.attempt_limit,
+            &sources[0],
+        )
+        .with_sources(&sources);
         let created_quiz = self.repository.create_quiz_draft(quiz).await?;
 
-        let steps = create_quiz_generation_steps();
+        let uploaded_sources = created_quiz.sources.iter().skip(request.urls.len());
+        for (source, (_, page)) in uploaded_sources.zip(&pages) {
+            summary_documents
+                .create_source_document(&created_quiz.id, source, page.to_markdown())
+                .await?;
+        }
+
+        let job_id = self.start_generation_job(&created_quiz.id).await?;
+
+        Ok(CreateQuizDraftResponse {
+            data: CreateQuizDraftResponseData {
+                quiz: QuizResponseDto::from(created_quiz),
+                job_id,
+            },
+            message: "Draft created successfully and processing started".to_string(),
+        })
+    }
+
+    /// How much of the user's AI generation allowance is left
+    pub async fn generation_quota(
+        &self,
+        user_id: &str,
+        role: &UserRole,
+    ) -> AppResult<GenerationQuotaResponse> {
+        let limits = self.limits_for(role);
+        let day_start = Utc::now().date_naive().and_time(NaiveTime::MIN).and_utc();
+
+        let count_error =
+            |e: String| AppError::InternalError(format!("Failed to count generation jobs: {}", e));
+
+        // New quizzes, plus jobs regenerating questions on existing ones
+        let used_today = self
+            .repository
+            .count_created_by_user_since(user_id, day_start)
+            .await?
+            + self
+                .orchestrator
+                .count_jobs_requested_since(user_id, day_start)
+                .await
+                .map_err(count_error)?;
+        let active_generations = self
+            .repository
+            .count_by_user_with_status(user_id, &GENERATING_STATUSES)
+            .await?
+            + self
+                .orchestrator
+                .count_active_jobs_requested_by(user_id)
+                .await
+                .map_err(count_error)?;
+
+        let remaining = |limit: Option<u32>, used: i64| limit.map(|l| (l as i64 - used).max(0));
+
+        Ok(GenerationQuotaResponse {
+            daily_limit: limits.daily,
+            used_today,
+            remaining_today: remaining(limits.daily, used_today),
+            concurrent_limit: limits.concurrent,
+            active_generations,
+            remaining_concurrent: remaining(limits.concurrent, active_generations),
+            resets_at: day_start + Duration::days(1),
+        })
+    }
+
+    /// Fails with `QuotaExceeded` if the user can't start another generation
+    pub async fn ensure_generation_quota(&self, user_id: &str, role: &UserRole) -> AppResult<()> {
+        if self.limits_for(role) == GenerationLimits::default() {
+            return Ok(());
+        }
+
+        let quota = self.generation_quota(user_id, role).await?;
+
+        if quota.remaining_today == Some(0) {
+            return Err(AppError::QuotaExceeded {
+                message: format!(
+                    "Daily limit of {} quiz generations reached; resets at {}",
+                    quota.daily_limit.unwrap_or_default(),
+                    quota.resets_at.to_rfc3339_opts(SecondsFormat::Secs, true)
+                ),
+                resets_at: Some(quota.resets_at),
+            });
+        }
+        if quota.remaining_concurrent == Some(0) {
+            return Err(AppError::QuotaExceeded {
+                message: format!(
+                    "{} quiz generations already in progress; wait for one to finish",
+                    quota.active_generations
+                ),
+                resets_at: None,
+            });
+        }
+
+        Ok(())
+    }
+
+    fn limits_for(&self, role: &UserRole) -> GenerationLimits {
+        match role {
+            UserRole::Admin => self.admin_generation_limits,
+            UserRole::User => self.generation_limits,
+        }
+    }
 
+    /// Start a quiz generation job for an existing quiz, returning its job id
+    pub async fn start_generation_job(&self, quiz_id: &str) -> AppResult<String> {
         let job_id = self
             .orchestrator
-            .create_job(steps)
+            .create_pipeline_job(QUIZ_GENERATION_PIPELINE)
             .await
             .map_err(|e| AppError::InternalError(format!("Job creation failed: {}", e)))?;
 
         // Store quiz metadata in job
         self.orchestrator
-            .set_job_metadata(
-                &job_id,
-                "quiz_id",
-                serde_json::json!(created_quiz.id.to_string()),
-            )
+            .set_job_metadata(&job_id, "quiz_id", serde_json::json!(quiz_id))
             .await
             .map_err(|e| AppError::InternalError(format!("Failed to set job metadata: {}", e)))?;
 
@@ -113,13 +354,132 @@ impl QuizService {
             .await
             .map_err(|e| AppError::InternalError(format!("Job startup failed: {}", e)))?;
 
-        Ok(CreateQuizDraftResponse {
-            data: CreateQuizDraftResponseData {
-                quiz: QuizResponseDto::from(created_quiz),
-                job_id,
-            },
-            message: "Draft created successfully and processing started".to_string(),
-        })
+        Ok(job_id)
+    }
+
+    /// Start a job replacing some questions of a generated quiz, returning
+    /// its job id. The job counts towards the requester's generation quota.
+    pub async fn start_question_regeneration_job(
+        &self,
+        quiz_id: &str,
+        question_ids: Vec<String>,
+        instructions: Option<String>,
+        claims: &Claims,
+    ) -> AppResult<String> {
+        let quiz =
+            self.repository.find_by_id(quiz_id).await?.ok_or_else(|| {
+                AppError::NotFound(format!("Quiz with id '{}' not found", quiz_id))
+            })?;
+
+        if !matches!(quiz.status, QuizStatus::Ready | QuizStatus::NeedsReview) {
+            return Err(AppError::ValidationError(
+                "Questions can only be regenerated once the quiz has been generated".to_string(),
+            ));
+        }
+        if question_ids.is_empty() {
+            return Err(AppError::ValidationError(
+                "Pick at least one question to regenerate".to_string(),
+            ));
+        }
+        let questions = quiz.questions.as_deref().unwrap_or_default();
+        if let Some(missing) = question_ids
+            .iter()
+            .find(|id| !questions.iter().any(|q| &q.id == *id))
+        {
+            return Err(AppError::NotFound(format!(
+                "Question with id '{}' not found",
+                missing
+            )));
+        }
+        let instructions = instructions
+            .map(|instructions| instructions.trim().to_string())
+            .filter(|instructions| !instructions.is_empty());
+        if instructions.as_ref().is_some_and(|instructions| {
+            instructions.chars().count() > MAX_REGENERATION_INSTRUCTIONS_CHARS
+        }) {
+            return Err(AppError::ValidationError(format!(
+                "Instructions can be at most {} characters",
+                MAX_REGENERATION_INSTRUCTIONS_CHARS
+            )));
+        }
+        self.ensure_generation_quota(&claims.sub, &claims.role)
+            .await?;
+
+        let job_id = self
+            .orchestrator
+            .create_pipeline_job(QUIZ_QUESTION_REGENERATION_PIPELINE)
+            .await
+            .map_err(|e| AppError::InternalError(format!("Job creation failed: {}", e)))?;
+
+        let mut metadata = vec![
+            ("quiz_id", serde_json::json!(quiz_id)),
+            (QUESTION_IDS_KEY, serde_json::json!(question_ids)),
+            (REQUESTED_BY_KEY, serde_json::json!(claims.sub)),
+        ];
+        if let Some(instructions) = instructions {
+            metadata.push((
+                REGENERATION_INSTRUCTIONS_KEY,
+                serde_json::json!(instructions),
+            ));
+        }
+        for (key, value) in metadata {
+            self.orchestrator
+                .set_job_metadata(&job_id, key, value)
+                .await
+                .map_err(|e| {
+                    AppError::InternalError(format!("Failed to set job metadata: {}", e))
+                })?;
+        }
+
+        self.orchestrator
+            .start_job(&job_id)
+            .await
+            .map_err(|e| AppError::InternalError(format!("Job startup failed: {}", e)))?;
+
+        Ok(job_id)
+    }
+
+    /// Turn the nightly check that regenerates a quiz when its web sources
+    /// change on or off
+    pub async fn set_source_refresh(&self, quiz_id: &str, enabled: bool) -> AppResult<()> {
+        let quiz =
+            self.repository.find_by_id(quiz_id).await?.ok_or_else(|| {
+                AppError::NotFound(format!("Quiz with id '{}' not found", quiz_id))
+            })?;
+        let schedule_error =
+            |e: String| AppError::InternalError(format!("Failed to update source refresh: {}", e));
+        let schedule = quiz_source_refresh_schedule(quiz_id);
+
+        if !enabled {
+            let existing = self
+                .orchestrator
+                .get_schedule(&schedule.name)
+                .await
+                .map_err(schedule_error)?;
+            if existing.is_some() {
+                self.orchestrator
+                    .set_schedule_enabled(&schedule.name, false)
+                    .await
+                    .map_err(schedule_error)?;
+            }
+            return Ok(());
+        }
+
+        if quiz.sources.iter().all(|source| source.is_uploaded()) {
+            return Err(AppError::ValidationError(
+                "Only quizzes with web sources can be refreshed".to_string(),
+            ));
+        }
+        let name = schedule.name.clone();
+        self.orchestrator
+            .ensure_schedule(schedule)
+            .await
+            .map_err(schedule_error)?;
+        // A schedule turned off earlier is kept with its source hash
+        self.orchestrator
+            .set_schedule_enabled(&name, true)
+            .await
+            .map_err(schedule_error)
     }
 
     pub async fn update_quiz(&self, quiz: QuizDto) -> AppResult<QuizDto> {
@@ -132,8 +492,7 @@ impl QuizService {
             quiz.modified_at = Some(now);
         }
 
-        let updated_quiz = self.repository.update(quiz).await?;
-        Ok(QuizDto::from(updated_quiz))
+        self.save(quiz).await
     }
 
     pub async fn update_quiz_partial(&self, input: UpdateQuizInput) -> AppResult<QuizDto> {
@@ -160,52 +519,364 @@ impl QuizService {
 
         quiz.modified_at = Some(now);
 
+        self.save(quiz).await
+    }
+
+    /// Add a hand-written question to a quiz, at `position` or at the end
+    pub async fn add_question(
+        &self,
+        quiz_id: &str,
+        input: AddQuizQuestionInput,
+    ) -> AppResult<QuizDto> {
+        input.validate()?;
+        let mut quiz = self.editable_quiz(quiz_id).await?;
+
+        let now = Utc::now();
+        let question = QuizQuestion {
+            id: uuid::Uuid::new_v4().to_string(),
+            title: input.title,
+            description: input.description,
+            question_type: input.question_type,
+            option_count: input.options.len() as i16,
+            options: input
+                .options
+                .into_iter()
+                .map(|option| QuizQuestionOption {
+                    id: uuid::Uuid::new_v4().to_string(),
+                    text: option.text,
+                    correct: option.correct,
+                    explanation: option.explanation,
+                })
+                .collect(),
+            order: 0,
+            attempt_limit: quiz.attempt_limit,
+            topic: quiz.topic.clone().unwrap_or_default(),
+            source_id: None,
+            grounding: None,
+            created_at: Some(now),
+            modified_at: Some(now),
+        };
+        check_question(&question)?;
+
+        let questions = quiz.questions.get_or_insert_with(Vec::new);
+        let position = input.position.map_or(questions.len(), |position| {
+            (position.max(0) as usize).min(questions.len())
+        });
+        questions.insert(position, question);
+
+        self.save_questions(quiz).await
+    }
+
+    pub async fn remove_question(&self, quiz_id: &str, question_id: &str) -> AppResult<QuizDto> {
+        let mut quiz = self.editable_quiz(quiz_id).await?;
+
+        let questions = quiz.questions.get_or_insert_with(Vec::new);
+        let index = questions
+            .iter()
+            .position(|q| q.id == question_id)
+            .ok_or_else(|| {
+                AppError::NotFound(format!("Question with id '{}' not found", question_id))
+            })?;
+        if questions.len() == 1 {
+            return Err(AppError::ValidationError(
+                "A quiz needs at least one question".to_string(),
+            ));
+        }
+        questions.remove(index);
+
+        self.save_questions(quiz).await
+    }
+
+    /// Put the questions of a quiz in the order of `question_ids`, which must
+    /// list each of them once
+    pub async fn reorder_questions(
+        &self,
+        quiz_id: &str,
+        question_ids: Vec<String>,
+    ) -> AppResult<QuizDto> {
+        let mut quiz = self.editable_quiz(quiz_id).await?;
+
+        let mut questions = quiz.questions.take().unwrap_or_default();
+        let unique: HashSet<&String> = question_ids.iter().collect();
+        if unique.len() != question_ids.len() || question_ids.len() != questions.len() {
+            return Err(AppError::ValidationError(
+                "List every question of the quiz exactly once, in the new order".to_string(),
+            ));
+        }
+
+        let mut reordered = Vec::with_capacity(questions.len());
+        for id in &question_ids {
+            let index = questions.iter().position(|q| &q.id == id).ok_or_else(|| {
+                AppError::NotFound(format!("Question with id '{}' not found", id))
+            })?;
+            reordered.push(questions.swap_remove(index));
+        }
+        quiz.questions = Some(reordered);
+
+        self.save_questions(quiz).await
+    }
+
+    /// Fetch a quiz whose questions can be changed by hand
+    async fn editable_quiz(&self, id: &str) -> AppResult<Quiz> {
+        let quiz = self
+            .repository
+            .find_by_id(id)
+            .await?
+            .ok_or_else(|| AppError::NotFound(format!("Quiz with id '{}' not found", id)))?;
+
+        // The generation job would overwrite the changes
+        if GENERATING_STATUSES.contains(&quiz.status) {
+            return Err(AppError::ValidationError(
+                "Questions can't be changed while the quiz is being generated".to_string(),
+            ));
+        }
+        Ok(quiz)
+    }
+
+    /// Number the questions of `quiz` in their current order, match its
+    /// question count to them and store it
+    async fn save_questions(&self, mut quiz: Quiz) -> AppResult<QuizDto> {
+        let questions = quiz.questions.get_or_insert_with(Vec::new);
+        for (index, question) in questions.iter_mut().enumerate() {
+            question.order = index as i16;
+        }
+        quiz.question_count = questions.len() as i16;
+        quiz.modified_at = Some(Utc::now());
+
+        self.save(quiz).await
+    }
+
+    /// Store `quiz`, publishing a new version of it if it is published and
+    /// its content changed
+    async fn save(&self, mut quiz: Quiz) -> AppResult<QuizDto> {
+        self.publish_version(&mut quiz, None).await?;
+        let updated_quiz = self.repository.update(quiz).await?;
+        Ok(QuizDto::from(updated_quiz))
+    }
+
+    /// Snapshot a published quiz as a new version unless the latest version
+    /// already has its content, and point the quiz at that version. Returns
+    /// the version matching the quiz. A version number taken by a concurrent
+    /// publish is retried with the next one.
+    async fn publish_version(
+        &self,
+        quiz: &mut Quiz,
+        rolled_back_from: Option<i32>,
+    ) -> AppResult<Option<QuizVersion>> {
+        let Some(versions) = &self.versions else {
+            return Ok(None);
+        };
+        if !PUBLISHED_STATUSES.contains(&quiz.status) {
+            return Ok(None);
+        }
+
+        let mut attempts = 0;
+        let version = loop {
+            attempts += 1;
+            let latest = versions.find_latest(&quiz.id).await?;
+            match latest {
+                Some(latest) if latest.matches(quiz) => break latest,
+                latest => {
+                    let number = latest.map_or(1, |latest| latest.version + 1);
+                    match versions
+                        .create(QuizVersion::new(quiz, number, rolled_back_from))
+                        .await
+                    {
+                        Err(AppError::AlreadyExists(_)) if attempts < VERSION_PUBLISH_ATTEMPTS => {
+                            continue
+                        }
+                        result => break result?,
+                    }
+                }
+            }
+        };
+        quiz.version = Some(version.version);
+        Ok(Some(version))
+    }
+
+    fn versions(&self) -> AppResult<&Arc<dyn QuizVersionRepository>> {
+        self.versions
+            .as_ref()
+            .ok_or_else(|| AppError::InternalError("Quiz versioning is not configured".to_string()))
+    }
+
+    /// The version new attempts at a quiz are graded against, or `None` while
+    /// the quiz isn't published. Quizzes published before versioning get
+    /// their first version here.
+    pub async fn current_version(&self, quiz_id: &str) -> AppResult<Option<QuizVersion>> {
+        let mut quiz =
+            self.repository.find_by_id(quiz_id).await?.ok_or_else(|| {
+                AppError::NotFound(format!("Quiz with id '{}' not found", quiz_id))
+            })?;
+
+        let stored_version = quiz.version;
+        let version = self.publish_version(&mut quiz, None).await?;
+        if quiz.version != stored_version {
+            self.repository.update(quiz).await?;
+        }
+        Ok(version)
+    }
+
+    pub async fn get_version(&self, quiz_id: &str, version: i32) -> AppResult<QuizVersion> {
+        self.versions()?
+            .find_version(quiz_id, version)
+            .await?
+            .ok_or_else(|| {
+                AppError::NotFound(format!(
+                    "Version {} of quiz '{}' not found",
+                    version, quiz_id
+                ))
+            })
+    }
+
+    /// Published versions of a quiz, newest first
+    pub async fn list_versions(&self, quiz_id: &str) -> AppResult<Vec<QuizVersion>> {
+        self.versions()?.list_by_quiz(quiz_id).await
+    }
+
+    pub async fn diff_versions(
+        &self,
+        quiz_id: &str,
+        from_version: i32,
+        to_version: i32,
+    ) -> AppResult<QuizVersionDiff> {
+        let from = self.get_version(quiz_id, from_version).await?;
+        let to = self.get_version(quiz_id, to_version).await?;
+        Ok(QuizVersionDiff::between(&from, &to))
+    }
+
+    /// Restore the content of an earlier version and publish it as the newest
+    /// version. Earlier versions and the attempts taken on them are kept.
+    pub async fn rollback_to_version(&self, quiz_id: &str, version: i32) -> AppResult<QuizDto> {
+        let snapshot = self.get_version(quiz_id, version).await?.quiz;
+        let mut quiz = self.editable_quiz(quiz_id).await?;
+
+        quiz.title = snapshot.title;
+        quiz.description = snapshot.description;
+        quiz.topic = snapshot.topic;
+        quiz.question_count = snapshot.question_count;
+        quiz.required_score = snapshot.required_score;
+        quiz.attempt_limit = snapshot.attempt_limit;
+        quiz.questions = snapshot.questions;
+        quiz.status = snapshot.status;
+        quiz.modified_at = Some(Utc::now());
+
+        self.publish_version(&mut quiz, Some(version)).await?;
         let updated_quiz = self.repository.update(quiz).await?;
         Ok(QuizDto::from(updated_quiz))
     }
+
+    /// Flag a quiz whose generation job failed permanently. Quizzes that were
+    /// already generated are left alone when a later job on them fails.
+    pub async fn mark_generation_failed(&self, id: &str) -> AppResult<()> {
+        let mut quiz = self
+            .repository
+            .find_by_id(id)
+            .await?
+            .ok_or_else(|| AppError::NotFound(format!("Quiz with id '{}' not found", id)))?;
+
+        if !GENERATING_STATUSES.contains(&quiz.status) {
+            return Ok(());
+        }
+
+        quiz.status = QuizStatus::Failed;
+        quiz.modified_at = Some(chrono::Utc::now());
+
+        self.repository.update(quiz).await?;
+        Ok(())
+    }
+
+    /// Put a failed quiz back into draft when its generation job is retried
+    pub async fn reset_failed_generation(&self, id: &str) -> AppResult<()> {
+        let mut quiz = self
+            .repository
+            .find_by_id(id)
+            .await?
+            .ok_or_else(|| AppError::NotFound(format!("Quiz with id '{}' not found", id)))?;
+
+        if quiz.status != QuizStatus::Failed {
+            return Ok(());
+        }
+
+        quiz.status = QuizStatus::Draft;
+        quiz.modified_at = Some(chrono::Utc::now());
+
+        self.repository.update(quiz).await?;
+        Ok(())
+    }
+
+    /// Publish a quiz held back because the grounding check flagged answers
+    pub async fn approve_quiz(&self, id: &str) -> AppResult<QuizDto> {
+        let mut quiz = self
+            .repository
+            .find_by_id(id)
+            .await?
+            .ok_or_else(|| AppError::NotFound(format!("Quiz with id '{}' not found", id)))?;
+
+        if quiz.status != QuizStatus::NeedsReview {
+            return Err(AppError::ValidationError(
+                "Only quizzes waiting for review can be approved".to_string(),
+            ));
+        }
+
+        quiz.status = QuizStatus::Ready;
+        quiz.modified_at = Some(chrono::Utc::now());
+
+        self.save(quiz).await
+    }
 }
 
+/// Apply `updates` to the matching questions, leaving the others unchanged
 fn merge_questions(
     existing: &Quiz,
-    updates: Vec<crate::models::dto::request::UpdateQuizQuestionInput>,
+    updates: Vec<UpdateQuizQuestionInput>,
 ) -> AppResult<Vec<QuizQuestion>> {
-    let existing_questions = existing.questions.as_ref().ok_or_else(|| {
+    let mut result = existing.questions.clone().ok_or_else(|| {
         AppError::ValidationError(
             "Cannot update questions on quiz without existing questions".to_string(),
         )
     })?;
 
-    let mut result = Vec::new();
     for update in updates {
-        if let Some(existing_question) = existing_questions.iter().find(|q| q.id == update.id) {
-            let mut merged = existing_question.clone();
-            if let Some(title) = update.title {
-                merged.title = title;
-            }
-            if let Some(description) = update.description {
-                merged.description = description;
-            }
-            if let Some(options_input) = update.options {
-                let merged_options = merge_options(existing_question, options_input)?;
-                merged.options = merged_options;
-            }
-            merged.modified_at = Some(chrono::Utc::now());
-            result.push(merged);
-        } else {
+        let Some(merged) = result.iter_mut().find(|q| q.id == update.id) else {
             return Err(AppError::NotFound(format!(
                 "Question with id '{}' not found",
                 update.id
             )));
+        };
+        if let Some(title) = update.title {
+            merged.title = title;
+        }
+        if let Some(description) = update.description {
+            merged.description = description;
+        }
+        if let Some(options_input) = update.options {
+            merged.options = merge_options(merged, options_input)?;
+            merged.option_count = merged.options.len() as i16;
         }
+        merged.modified_at = Some(chrono::Utc::now());
+        check_question(merged)?;
     }
 
     Ok(result)
 }
 
+fn check_question(question: &QuizQuestion) -> AppResult<()> {
+    let problems = quiz_question_problems(question);
+    if problems.is_empty() {
+        return Ok(());
+    }
+    Err(AppError::ValidationError(format!(
+        "Question {:?} is invalid: {}",
+        question.title,
+        problems.join("; ")
+    )))
+}
+
 fn merge_options(
     existing_question: &QuizQuestion,
-    updates: Vec<crate::models::dto::request::UpdateQuizQuestionOptionInput>,
-) -> AppResult<Vec<crate::models::domain::quiz_question::QuizQuestionOption>> {
+    updates: Vec<UpdateQuizQuestionOptionInput>,
+) -> AppResult<Vec<QuizQuestionOption>> {
     let mut result = Vec::new();
     for update in updates {
         if let Some(existing_option) = existing_question.options.iter().find(|o| o.id == update.id)
@@ -240,9 +911,21 @@ mod tests {
     use std::collections::HashMap;
 
     use crate::{
-        models::dto::request::QuizDraftDto,
-        repositories::AgentJobRepository,
-        services::agent_orchestrator_service::{AgentJob, JobStatus, JobStep},
+        models::{
+            domain::{
+                quiz::QuizSource, quiz_question::QuizQuestionType,
+                summary_document::SummaryDocument,
+            },
+            dto::request::{AddQuizQuestionOptionInput, QuizDraftDto},
+        },
+        repositories::{AgentJobRepository, SummaryDocumentRepository},
+        services::{
+            agent_orchestrator_service::{
+                AgentJob, DeadLetterJob, JobStatus, JobStep, StepFailureKind, StepFailureOutcome,
+                StepRun,
+            },
+            orchestrator_steps::default_registry,
+        },
     };
 
     use super::*;
@@ -258,6 +941,8 @@ mod tests {
             async fn get_by_status_by_id(&self, id: &str, status: &str) -> AppResult<Option<Quiz>>;
             async fn create_quiz_draft(&self, quiz: Quiz) -> AppResult<Quiz>;
             async fn update(&self, quiz: Quiz) -> AppResult<Quiz>;
+            async fn count_created_by_user_since(&self, user_id: &str, since: chrono::DateTime<Utc>) -> AppResult<i64>;
+            async fn count_by_user_with_status(&self, user_id: &str, statuses: &[QuizStatus]) -> AppResult<i64>;
         }
     }
 
@@ -270,18 +955,57 @@ mod tests {
             async fn get_job(&self, job_id: &str) -> Result<Option<AgentJob>, String>;
             async fn get_job_status(&self, job_id: &str) -> Result<Option<JobStatus>, String>;
             async fn start_job(&self, job_id: &str) -> Result<(), String>;
-            async fn complete_step(&self, job_id: &str, result: Option<serde_json::Value>) -> Result<(), String>;
-            async fn fail_step(&self, job_id: &str, error: String) -> Result<(), String>;
+            async fn complete_step(&self, job_id: &str, worker_id: &str, result: Option<serde_json::Value>) -> Result<bool, String>;
+            async fn fail_step(&self, job_id: &str, worker_id: &str, error: String, kind: StepFailureKind) -> Result<StepFailureOutcome, String>;
             async fn pause_job(&self, job_id: &str) -> Result<(), String>;
             async fn resume_job(&self, job_id: &str) -> Result<(), String>;
+            async fn cancel_job(&self, job_id: &str) -> Result<(), String>;
+            async fn retry_job(&self, job_id: &str) -> Result<(), String>;
             async fn list_jobs(&self, status_filter: Option<JobStatus>) -> Result<Vec<AgentJob>, String>;
+            async fn list_claimable_jobs(&self, worker_id: &str) -> Result<Vec<AgentJob>, String>;
             async fn delete_job(&self, job_id: &str) -> Result<(), String>;
             async fn save(&self, job: &AgentJob) -> Result<(), String>;
+            async fn acquire_lease(&self, job_id: &str, worker_id: &str, lease_seconds: u64) -> Result<Option<AgentJob>, String>;
+            async fn renew_lease(&self, job_id: &str, worker_id: &str, lease_seconds: u64) -> Result<bool, String>;
+            async fn release_lease(&self, job_id: &str, worker_id: &str) -> Result<(), String>;
+            async fn append_step_run(&self, job_id: &str, run: &StepRun) -> Result<(), String>;
+            async fn dead_letter_job(&self, entry: &DeadLetterJob) -> Result<(), String>;
+            async fn get_dead_letter(&self, job_id: &str) -> Result<Option<DeadLetterJob>, String>;
+            async fn list_dead_letters(&self, include_replayed: bool, offset: i64, limit: i64) -> Result<Vec<DeadLetterJob>, String>;
+            async fn mark_dead_letter_replayed(&self, job_id: &str) -> Result<(), String>;
+            async fn delete_completed_before(&self, cutoff: chrono::DateTime<Utc>) -> Result<u64, String>;
+            async fn count_requested_by_since(&self, user_id: &str, since: chrono::DateTime<Utc>) -> Result<i64, String>;
+            async fn count_active_requested_by(&self, user_id: &str) -> Result<i64, String>;
+        }
+    }
+
+    mock! {
+        pub SummaryRepo {}
+
+        #[async_trait]
+        impl SummaryDocumentRepository for SummaryRepo {
+            async fn find_by_id(&self, id: &str) -> AppResult<Option<SummaryDocument>>;
+            async fn find_by_quiz_id(&self, quiz_id: &str) -> AppResult<Vec<SummaryDocument>>;
+            async fn create(&self, document: SummaryDocument) -> AppResult<SummaryDocument>;
+            async fn update(&self, document: SummaryDocument) -> AppResult<SummaryDocument>;
+        }
+    }
+
+    mock! {
+        pub QuizVersionRepo {}
+
+        #[async_trait]
+        impl QuizVersionRepository for QuizVersionRepo {
+            async fn create(&self, version: QuizVersion) -> AppResult<QuizVersion>;
+            async fn find_version(&self, quiz_id: &str, version: i32) -> AppResult<Option<QuizVersion>>;
+            async fn find_latest(&self, quiz_id: &str) -> AppResult<Option<QuizVersion>>;
+            async fn list_by_quiz(&self, quiz_id: &str) -> AppResult<Vec<QuizVersion>>;
         }
     }
 
     fn create_service(mock_repo: MockQuizRepo, mock_job_repo: MockAgentJobRepo) -> QuizService {
-        let orchestrator = AgentOrchestrator::new(Arc::new(mock_job_repo));
+        let orchestrator = AgentOrchestrator::new(Arc::new(mock_job_repo))
+            .with_registry(Arc::new(default_registry()));
         QuizService::new(Arc::new(mock_repo), Arc::new(orchestrator))
     }
 
@@ -289,7 +1013,65 @@ mod tests {
         Quiz::new_draft(name, created_by_user_id, 5, 70, 3, "https://example.com")
     }
 
-    #[tokio::test]
+    fn make_question(id: &str) -> QuizQuestion {
+        let option = |text: &str, correct: bool| QuizQuestionOption {
+            id: format!("{}-{}", id, text.to_lowercase()),
+            text: text.to_string(),
+            correct,
+            explanation: String::new(),
+        };
+        QuizQuestion {
+            id: id.to_string(),
+            title: format!("Question {}", id),
+            description: String::new(),
+            question_type: QuizQuestionType::Bool,
+            options: vec![option("True", true), option("False", false)],
+            option_count: 2,
+            order: 0,
+            attempt_limit: 3,
+            topic: String::new(),
+            source_id: None,
+            grounding: None,
+            created_at: None,
+            modified_at: None,
+        }
+    }
+
+    fn make_ready_quiz(question_ids: &[&str]) -> Quiz {
+        let mut quiz = make_test_quiz("Quiz", "user-1");
+        quiz.status = QuizStatus::Ready;
+        quiz.questions = Some(question_ids.iter().map(|id| make_question(id)).collect());
+        quiz.question_count = question_ids.len() as i16;
+        quiz
+    }
+
+    fn question_ids(quiz: &QuizDto) -> Vec<&str> {
+        quiz.questions.iter().map(|q| q.id.as_str()).collect()
+    }
+
+    fn make_claims(user_id: &str, role: UserRole) -> Claims {
+        Claims {
+            sub: user_id.to_string(),
+            username: user_id.to_string(),
+            email: format!("{}@example.com", user_id),
+            role,
+            exp: 0,
+            iat: 0,
+        }
+    }
+
+    fn make_draft_request() -> QuizDraftDto {
+        QuizDraftDto {
+            name: "Draft Quiz".to_string(),
+            question_count: 8,
+            required_score: 75,
+            attempt_limit: 3,
+            url: "https://example.com/learning".to_string(),
+            sources: Vec::new(),
+        }
+    }
+
+    #[tokio::test]
     async fn get_quiz_returns_not_found_for_invalid_id() {
         let mut mock_repo = MockQuizRepo::new();
         let mock_job_repo = MockAgentJobRepo::new();
@@ -364,7 +1146,7 @@ mod tests {
         let mut mock_repo = MockQuizRepo::new();
         let mut mock_job_repo = MockAgentJobRepo::new();
 
-        mock_repo.expect_create_quiz_draft().returning(|quiz| Ok(quiz));
+        mock_repo.expect_create_quiz_draft().returning(Ok);
 
         mock_job_repo.expect_create_job().returning(|steps| {
             assert!(!steps.is_empty());
@@ -385,6 +1167,12 @@ mod tests {
                 started_at: None,
                 completed_at: None,
                 retries_remaining: 3,
+                lease_owner: None,
+                lease_expires_at: None,
+                heartbeat_at: None,
+                next_attempt_at: None,
+                last_failure_kind: None,
+                step_runs: Vec::new(),
             }))
         });
 
@@ -401,22 +1189,772 @@ mod tests {
 
         let service = create_service(mock_repo, mock_job_repo);
 
-        let request = QuizDraftDto {
-            name: "Draft Quiz".to_string(),
-            question_count: 8,
-            required_score: 75,
-            attempt_limit: 3,
-            url: "https://example.com/learning".to_string(),
-        };
-
         let result = service
-            .create_quiz_draft(request, "user-abc")
+            .create_quiz_draft(
+                make_draft_request(),
+                &make_claims("user-abc", UserRole::User),
+            )
             .await
             .expect("expected draft creation to succeed");
 
         assert_eq!(result.data.job_id, "job-123");
         assert_eq!(result.data.quiz.name, "Draft Quiz");
         assert_eq!(result.data.quiz.created_by_user_id, "user-abc");
-        assert_eq!(result.message, "Draft created successfully and processing started");
+        assert_eq!(
+            result.message,
+            "Draft created successfully and processing started"
+        );
+    }
+
+    #[tokio::test]
+    async fn create_document_quiz_draft_stores_each_upload_as_a_source() {
+        let mut mock_repo = MockQuizRepo::new();
+        let mut mock_job_repo = MockAgentJobRepo::new();
+        let mut mock_summary_repo = MockSummaryRepo::new();
+
+        mock_repo.expect_create_quiz_draft().returning(|quiz| {
+            let urls: Vec<&str> = quiz.sources.iter().map(|s| s.url.as_str()).collect();
+            assert_eq!(
+                urls,
+                vec![
+                    "https://example.com/policy",
+                    "upload:handbook.md",
+                    "upload:faq.txt"
+                ]
+            );
+            Ok(quiz)
+        });
+        mock_summary_repo
+            .expect_create()
+            .times(2)
+            .returning(|document| {
+                assert!(document.content.is_empty());
+                if document.url == "upload:handbook.md" {
+                    assert_eq!(document.source_id.as_deref(), Some("source-2"));
+                    assert_eq!(document.source.as_deref(), Some("# Handbook\n\nBe kind."));
+                } else {
+                    assert_eq!(document.source_id.as_deref(), Some("source-3"));
+                }
+                Ok(document)
+            });
+        mock_job_repo.expect_create_job().returning(|steps| {
+            assert_eq!(steps[1].name, "create_summary_document");
+            Ok("job-9".to_string())
+        });
+        mock_job_repo.expect_get_job().returning(|job_id| {
+            let mut job = AgentJob::new(Vec::new());
+            job.job_id = job_id.to_string();
+            Ok(Some(job))
+        });
+        let saved_keys = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let keys = saved_keys.clone();
+        mock_job_repo.expect_save().returning(move |job| {
+            keys.lock().unwrap().extend(job.results.keys().cloned());
+            Ok(())
+        });
+        mock_job_repo.expect_start_job().returning(|_| Ok(()));
+
+        let summary_documents = Arc::new(SummaryDocumentService::new(Arc::new(mock_summary_repo)));
+        let service =
+            create_service(mock_repo, mock_job_repo).with_summary_documents(summary_documents);
+
+        let result = service
+            .create_document_quiz_draft(
+                QuizDocumentDraftDto {
+                    name: "Handbook".to_string(),
+                    question_count: 5,
+                    required_score: 70,
+                    attempt_limit: 2,
+                    urls: vec!["https://example.com/policy".to_string()],
+                },
+                vec![
+                    DocumentUpload {
+                        file_name: "handbook.md".to_string(),
+                        content_type: Some("text/markdown".to_string()),
+                        bytes: b"# Handbook\r\n\r\nBe kind.\r\n".to_vec(),
+                    },
+                    DocumentUpload {
+                        file_name: "faq.txt".to_string(),
+                        content_type: None,
+                        bytes: b"Questions and answers".to_vec(),
+                    },
+                ],
+                &make_claims("user-1", UserRole::User),
+            )
+            .await
+            .expect("expected document draft creation to succeed");
+
+        assert_eq!(result.data.job_id, "job-9");
+        let saved_keys = saved_keys.lock().unwrap();
+        assert!(saved_keys.contains(&"quiz_id".to_string()));
+    }
+
+    #[tokio::test]
+    async fn create_document_quiz_draft_rejects_disguised_files() {
+        let mut mock_repo = MockQuizRepo::new();
+        mock_repo.expect_create_quiz_draft().never();
+        let summary_documents = Arc::new(SummaryDocumentService::new(Arc::new(
+            MockSummaryRepo::new(),
+        )));
+        let service = create_service(mock_repo, MockAgentJobRepo::new())
+            .with_summary_documents(summary_documents);
+
+        let result = service
+            .create_document_quiz_draft(
+                QuizDocumentDraftDto {
+                    name: "Report".to_string(),
+                    question_count: 5,
+                    required_score: 70,
+                    attempt_limit: 2,
+                    urls: Vec::new(),
+                },
+                vec![DocumentUpload {
+                    file_name: "report.pdf".to_string(),
+                    content_type: Some("application/pdf".to_string()),
+                    bytes: b"MZ\x90\x00 not a pdf".to_vec(),
+                }],
+                &make_claims("user-1", UserRole::User),
+            )
+            .await;
+
+        assert!(matches!(result, Err(AppError::UnsupportedMediaType(_))));
+    }
+
+    #[tokio::test]
+    async fn create_quiz_draft_rejects_user_over_daily_limit() {
+        let mut mock_repo = MockQuizRepo::new();
+        let mut mock_job_repo = MockAgentJobRepo::new();
+
+        mock_repo
+            .expect_count_created_by_user_since()
+            .returning(|_, _| Ok(2));
+        mock_repo
+            .expect_count_by_user_with_status()
+            .returning(|_, _| Ok(0));
+        // A regeneration job counts towards the same limit
+        mock_job_repo
+            .expect_count_requested_by_since()
+            .returning(|_, _| Ok(1));
+        mock_job_repo
+            .expect_count_active_requested_by()
+            .returning(|_| Ok(0));
+        mock_repo.expect_create_quiz_draft().never();
+
+        let limits = GenerationLimits {
+            daily: Some(3),
+            concurrent: Some(2),
+        };
+        let service = create_service(mock_repo, mock_job_repo)
+            .with_generation_limits(limits, GenerationLimits::default());
+
+        let result = service
+            .create_quiz_draft(make_draft_request(), &make_claims("user-1", UserRole::User))
+            .await;
+
+        match result.expect_err("expected quota error") {
+            AppError::QuotaExceeded { message, resets_at } => {
+                let resets_at = resets_at.expect("daily quota should carry a reset time");
+                assert!(resets_at > Utc::now());
+                assert!(resets_at - Utc::now() <= Duration::days(1));
+                assert!(message.contains("Daily limit of 3"));
+            }
+            other => panic!("expected QuotaExceeded, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn create_quiz_draft_rejects_user_over_concurrent_limit() {
+        let mut mock_repo = MockQuizRepo::new();
+        let mut mock_job_repo = MockAgentJobRepo::new();
+
+        mock_repo
+            .expect_count_created_by_user_since()
+            .returning(|_, _| Ok(1));
+        mock_repo
+            .expect_count_by_user_with_status()
+            .returning(|_, statuses| {
+                assert_eq!(statuses, GENERATING_STATUSES);
+                Ok(2)
+            });
+        mock_job_repo
+            .expect_count_requested_by_since()
+            .returning(|_, _| Ok(0));
+        mock_job_repo
+            .expect_count_active_requested_by()
+            .returning(|_| Ok(0));
+        mock_repo.expect_create_quiz_draft().never();
+
+        let limits = GenerationLimits {
+            daily: Some(10),
+            concurrent: Some(2),
+        };
+        let service = create_service(mock_repo, mock_job_repo)
+            .with_generation_limits(limits, GenerationLimits::default());
+
+        let result = service
+            .create_quiz_draft(make_draft_request(), &make_claims("user-1", UserRole::User))
+            .await;
+
+        assert!(matches!(
+            result,
+            Err(AppError::QuotaExceeded {
+                resets_at: None,
+                ..
+            })
+        ));
+    }
+
+    #[tokio::test]
+    async fn generation_quota_uses_admin_override() {
+        let mut mock_repo = MockQuizRepo::new();
+        let mut mock_job_repo = MockAgentJobRepo::new();
+
+        mock_repo
+            .expect_count_created_by_user_since()
+            .returning(|_, _| Ok(12));
+        mock_repo
+            .expect_count_by_user_with_status()
+            .returning(|_, _| Ok(0));
+        mock_job_repo
+            .expect_count_requested_by_since()
+            .returning(|_, _| Ok(1));
+        mock_job_repo
+            .expect_count_active_requested_by()
+            .returning(|_| Ok(1));
+
+        let limits = GenerationLimits {
+            daily: Some(10),
+            concurrent: Some(2),
+        };
+        let admin_limits = GenerationLimits {
+            daily: None,
+            concurrent: Some(5),
+        };
+        let service =
+            create_service(mock_repo, mock_job_repo).with_generation_limits(limits, admin_limits);
+
+        let user_quota = service
+            .generation_quota("user-1", &UserRole::User)
+            .await
+            .expect("expected quota");
+        assert_eq!(user_quota.remaining_today, Some(0));
+        assert_eq!(user_quota.remaining_concurrent, Some(1));
+
+        let admin_quota = service
+            .generation_quota("admin-1", &UserRole::Admin)
+            .await
+            .expect("expected quota");
+        assert_eq!(admin_quota.daily_limit, None);
+        assert_eq!(admin_quota.remaining_today, None);
+        assert_eq!(admin_quota.used_today, 13);
+        assert_eq!(admin_quota.remaining_concurrent, Some(4));
+    }
+
+    #[tokio::test]
+    async fn mark_generation_failed_sets_failed_status() {
+        let mut mock_repo = MockQuizRepo::new();
+        let mock_job_repo = MockAgentJobRepo::new();
+
+        mock_repo
+            .expect_find_by_id()
+            .returning(|_| Ok(Some(make_test_quiz("Quiz", "user-1"))));
+        mock_repo.expect_update().times(1).returning(|quiz| {
+            assert_eq!(quiz.status, QuizStatus::Failed);
+            Ok(quiz)
+        });
+
+        let service = create_service(mock_repo, mock_job_repo);
+
+        service
+            .mark_generation_failed("quiz-1")
+            .await
+            .expect("expected quiz to be marked failed");
+    }
+
+    #[tokio::test]
+    async fn mark_generation_failed_leaves_generated_quizzes_alone() {
+        let mut mock_repo = MockQuizRepo::new();
+        let mock_job_repo = MockAgentJobRepo::new();
+
+        mock_repo.expect_find_by_id().returning(|_| {
+            let mut quiz = make_test_quiz("Quiz", "user-1");
+            quiz.status = QuizStatus::Ready;
+            Ok(Some(quiz))
+        });
+        mock_repo.expect_update().never();
+
+        let service = create_service(mock_repo, mock_job_repo);
+
+        service
+            .mark_generation_failed("quiz-1")
+            .await
+            .expect("expected ready quiz to be left alone");
+    }
+
+    #[tokio::test]
+    async fn start_question_regeneration_job_checks_quiz_and_questions() {
+        let mut mock_repo = MockQuizRepo::new();
+        let mock_job_repo = MockAgentJobRepo::new();
+
+        mock_repo.expect_find_by_id().returning(|id| match id {
+            "draft-quiz" => Ok(Some(make_test_quiz("Quiz", "user-1"))),
+            _ => Ok(Some(make_ready_quiz(&["question-1"]))),
+        });
+
+        let service = create_service(mock_repo, mock_job_repo);
+        let claims = make_claims("user-1", UserRole::User);
+        let ids = |ids: &[&str]| ids.iter().map(|id| id.to_string()).collect::<Vec<_>>();
+
+        assert!(matches!(
+            service
+                .start_question_regeneration_job("draft-quiz", ids(&["question-1"]), None, &claims)
+                .await,
+            Err(AppError::ValidationError(_))
+        ));
+        assert!(matches!(
+            service
+                .start_question_regeneration_job("quiz-1", ids(&[]), None, &claims)
+                .await,
+            Err(AppError::ValidationError(_))
+        ));
+        assert!(matches!(
+            service
+                .start_question_regeneration_job(
+                    "quiz-1",
+                    ids(&["question-1", "other"]),
+                    None,
+                    &claims
+                )
+                .await,
+            Err(AppError::NotFound(_))
+        ));
+        assert!(matches!(
+            service
+                .start_question_regeneration_job(
+                    "quiz-1",
+                    ids(&["question-1"]),
+                    Some("x".repeat(MAX_REGENERATION_INSTRUCTIONS_CHARS + 1)),
+                    &claims
+                )
+                .await,
+            Err(AppError::ValidationError(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn start_question_regeneration_job_rejects_user_over_quota() {
+        let mut mock_repo = MockQuizRepo::new();
+        let mut mock_job_repo = MockAgentJobRepo::new();
+
+        mock_repo
+            .expect_find_by_id()
+            .returning(|_| Ok(Some(make_ready_quiz(&["question-1"]))));
+        mock_repo
+            .expect_count_created_by_user_since()
+            .returning(|_, _| Ok(0));
+        mock_repo
+            .expect_count_by_user_with_status()
+            .returning(|_, _| Ok(0));
+        mock_job_repo
+            .expect_count_requested_by_since()
+            .returning(|user_id, _| {
+                assert_eq!(user_id, "user-1");
+                Ok(1)
+            });
+        mock_job_repo
+            .expect_count_active_requested_by()
+            .returning(|_| Ok(1));
+        mock_job_repo.expect_create_job().never();
+
+        let limits = GenerationLimits {
+            daily: Some(10),
+            concurrent: Some(1),
+        };
+        let service = create_service(mock_repo, mock_job_repo)
+            .with_generation_limits(limits, GenerationLimits::default());
+
+        let result = service
+            .start_question_regeneration_job(
+                "quiz-1",
+                vec!["question-1".to_string()],
+                None,
+                &make_claims("user-1", UserRole::User),
+            )
+            .await;
+
+        assert!(matches!(result, Err(AppError::QuotaExceeded { .. })));
+    }
+
+    #[tokio::test]
+    async fn set_source_refresh_requires_web_sources() {
+        let mut mock_repo = MockQuizRepo::new();
+
+        mock_repo.expect_find_by_id().returning(|_| {
+            let mut quiz = make_ready_quiz(&["question-1"]);
+            quiz.sources =
+                QuizSource::from_urls(&[format!("{}handbook.pdf", UPLOADED_DOCUMENT_URL_PREFIX)]);
+            Ok(Some(quiz))
+        });
+
+        let service = create_service(mock_repo, MockAgentJobRepo::new());
+
+        assert!(matches!(
+            service.set_source_refresh("quiz-1", true).await,
+            Err(AppError::ValidationError(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn add_question_inserts_at_position_and_renumbers() {
+        let mut mock_repo = MockQuizRepo::new();
+        let mock_job_repo = MockAgentJobRepo::new();
+
+        mock_repo
+            .expect_find_by_id()
+            .returning(|_| Ok(Some(make_ready_quiz(&["a", "b"]))));
+        mock_repo.expect_update().times(1).returning(Ok);
+
+        let service = create_service(mock_repo, mock_job_repo);
+        let input = |correct: [bool; 3]| AddQuizQuestionInput {
+            title: "Which is a colour?".to_string(),
+            description: String::new(),
+            question_type: QuizQuestionType::Single,
+            options: ["Red", "Seven", "Tuesday"]
+                .iter()
+                .zip(correct)
+                .map(|(text, correct)| AddQuizQuestionOptionInput {
+                    text: text.to_string(),
+                    correct,
+                    explanation: String::new(),
+                })
+                .collect(),
+            position: Some(1),
+        };
+
+        let quiz = service
+            .add_question("quiz-1", input([true, false, false]))
+            .await
+            .expect("expected question to be added");
+
+        assert_eq!(quiz.question_count, 3);
+        assert_eq!(quiz.questions[0].id, "a");
+        assert_eq!(quiz.questions[1].title, "Which is a colour?");
+        assert_eq!(quiz.questions[1].option_count, 3);
+        assert_eq!(quiz.questions[2].id, "b");
+        let orders: Vec<i16> = quiz.questions.iter().map(|q| q.order).collect();
+        assert_eq!(orders, vec![0, 1, 2]);
+
+        assert!(matches!(
+            service
+                .add_question("quiz-1", input([true, true, false]))
+                .await,
+            Err(AppError::ValidationError(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn remove_and_reorder_questions_renumber_and_update_count() {
+        let mut mock_repo = MockQuizRepo::new();
+        let mock_job_repo = MockAgentJobRepo::new();
+
+        mock_repo.expect_find_by_id().returning(|id| match id {
+            "draft-quiz" => Ok(Some(make_test_quiz("Quiz", "user-1"))),
+            "single-question-quiz" => Ok(Some(make_ready_quiz(&["a"]))),
+            _ => Ok(Some(make_ready_quiz(&["a", "b", "c"]))),
+        });
+        mock_repo.expect_update().times(2).returning(Ok);
+
+        let service = create_service(mock_repo, mock_job_repo);
+        let ids = |ids: &[&str]| ids.iter().map(|id| id.to_string()).collect::<Vec<_>>();
+
+        let removed = service
+            .remove_question("quiz-1", "a")
+            .await
+            .expect("expected question to be removed");
+        assert_eq!(question_ids(&removed), vec!["b", "c"]);
+        assert_eq!(removed.question_count, 2);
+        assert_eq!(removed.questions[1].order, 1);
+
+        let reordered = service
+            .reorder_questions("quiz-1", ids(&["c", "a", "b"]))
+            .await
+            .expect("expected questions to be reordered");
+        assert_eq!(question_ids(&reordered), vec!["c", "a", "b"]);
+        let orders: Vec<i16> = reordered.questions.iter().map(|q| q.order).collect();
+        assert_eq!(orders, vec![0, 1, 2]);
+
+        for result in [
+            service.reorder_questions("quiz-1", ids(&["c", "a"])).await,
+            service
+                .reorder_questions("quiz-1", ids(&["c", "a", "a"]))
+                .await,
+            service.remove_question("single-question-quiz", "a").await,
+            service.remove_question("draft-quiz", "a").await,
+        ] {
+            assert!(matches!(result, Err(AppError::ValidationError(_))));
+        }
+        assert!(matches!(
+            service.remove_question("quiz-1", "missing").await,
+            Err(AppError::NotFound(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn update_quiz_partial_keeps_questions_not_in_the_update() {
+        let mut mock_repo = MockQuizRepo::new();
+        let mock_job_repo = MockAgentJobRepo::new();
+
+        mock_repo
+            .expect_find_by_id()
+            .returning(|_| Ok(Some(make_ready_quiz(&["a", "b", "c"]))));
+        mock_repo.expect_update().times(1).returning(Ok);
+
+        let service = create_service(mock_repo, mock_job_repo);
+        let update = |correct: bool| UpdateQuizInput {
+            id: "quiz-1".to_string(),
+            title: None,
+            description: None,
+            questions: Some(vec![UpdateQuizQuestionInput {
+                id: "b".to_string(),
+                title: Some("Renamed".to_string()),
+                description: None,
+                options: Some(vec![
+                    UpdateQuizQuestionOptionInput {
+                        id: "b-true".to_string(),
+                        text: None,
+                        correct: None,
+                        explanation: None,
+                    },
+                    UpdateQuizQuestionOptionInput {
+                        id: "b-false".to_string(),
+                        text: None,
+                        correct: Some(correct),
+                        explanation: None,
+                    },
+                ]),
+            }]),
+        };
+
+        let quiz = service
+            .update_quiz_partial(update(false))
+            .await
+            .expect("expected quiz to be updated");
+        assert_eq!(question_ids(&quiz), vec!["a", "b", "c"]);
+        assert_eq!(quiz.questions[1].title, "Renamed");
+
+        // A bool question with two correct options
+        assert!(matches!(
+            service.update_quiz_partial(update(true)).await,
+            Err(AppError::ValidationError(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn reset_failed_generation_only_touches_failed_quizzes() {
+        let mut mock_repo = MockQuizRepo::new();
+        let mock_job_repo = MockAgentJobRepo::new();
+
+        mock_repo.expect_find_by_id().returning(|id| {
+            let mut quiz = make_test_quiz("Quiz", "user-1");
+            if id == "failed-quiz" {
+                quiz.status = QuizStatus::Failed;
+            }
+            Ok(Some(quiz))
+        });
+        mock_repo.expect_update().times(1).returning(|quiz| {
+            assert_eq!(quiz.status, QuizStatus::Draft);
+            Ok(quiz)
+        });
+
+        let service = create_service(mock_repo, mock_job_repo);
+
+        service
+            .reset_failed_generation("failed-quiz")
+            .await
+            .expect("expected failed quiz to be reset");
+        service
+            .reset_failed_generation("draft-quiz")
+            .await
+            .expect("expected draft quiz to be left alone");
+    }
+
+    #[tokio::test]
+    async fn approve_quiz_publishes_only_quizzes_waiting_for_review() {
+        let mut mock_repo = MockQuizRepo::new();
+        let mock_job_repo = MockAgentJobRepo::new();
+
+        mock_repo.expect_find_by_id().returning(|id| {
+            let mut quiz = make_test_quiz("Quiz", "user-1");
+            if id == "flagged-quiz" {
+                quiz.status = QuizStatus::NeedsReview;
+            }
+            Ok(Some(quiz))
+        });
+        mock_repo.expect_update().times(1).returning(|quiz| {
+            assert_eq!(quiz.status, QuizStatus::Ready);
+            Ok(quiz)
+        });
+
+        let service = create_service(mock_repo, mock_job_repo);
+
+        let approved = service
+            .approve_quiz("flagged-quiz")
+            .await
+            .expect("expected flagged quiz to be approved");
+        assert_eq!(approved.status, QuizStatus::Ready);
+        assert!(matches!(
+            service.approve_quiz("draft-quiz").await,
+            Err(AppError::ValidationError(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn saving_a_published_quiz_adds_a_version_only_when_its_content_changes() {
+        let mut mock_repo = MockQuizRepo::new();
+        let mut mock_version_repo = MockQuizVersionRepo::new();
+
+        let mut published = make_ready_quiz(&["a", "b"]);
+        published.version = Some(1);
+        let quiz_id = published.id.clone();
+        let first_version = QuizVersion::new(&published, 1, None);
+
+        mock_repo
+            .expect_find_by_id()
+            .returning(move |_| Ok(Some(published.clone())));
+        mock_repo.expect_update().returning(Ok);
+        mock_version_repo
+            .expect_find_latest()
+            .returning(move |_| Ok(Some(first_version.clone())));
+        mock_version_repo
+            .expect_create()
+            .times(1)
+            .returning(|version| {
+                assert_eq!(version.version, 2);
+                assert_eq!(version.quiz.title.as_deref(), Some("Renamed"));
+                Ok(version)
+            });
+
+        let service = create_service(mock_repo, MockAgentJobRepo::new())
+            .with_versions(Arc::new(mock_version_repo));
+        let update = |title: Option<&str>| UpdateQuizInput {
+            id: quiz_id.clone(),
+            title: title.map(str::to_string),
+            description: None,
+            questions: None,
+        };
+
+        let unchanged = service
+            .update_quiz_partial(update(None))
+            .await
+            .expect("expected unchanged quiz to save");
+        assert_eq!(unchanged.version, Some(1));
+
+        let renamed = service
+            .update_quiz_partial(update(Some("Renamed")))
+            .await
+            .expect("expected renamed quiz to save");
+        assert_eq!(renamed.version, Some(2));
+    }
+
+    #[tokio::test]
+    async fn rollback_publishes_an_earlier_version_as_the_newest() {
+        let mut mock_repo = MockQuizRepo::new();
+        let mut mock_version_repo = MockQuizVersionRepo::new();
+
+        let mut current = make_ready_quiz(&["a", "b"]);
+        current.version = Some(2);
+        let quiz_id = current.id.clone();
+        let mut original = current.clone();
+        original.title = Some("Original".to_string());
+        original.questions = Some(vec![make_question("a")]);
+        original.question_count = 1;
+        let original_version = QuizVersion::new(&original, 1, None);
+        let latest_version = QuizVersion::new(&current, 2, None);
+
+        mock_repo
+            .expect_find_by_id()
+            .returning(move |_| Ok(Some(current.clone())));
+        mock_repo.expect_update().times(1).returning(Ok);
+        mock_version_repo
+            .expect_find_version()
+            .returning(move |_, version| {
+                assert_eq!(version, 1);
+                Ok(Some(original_version.clone()))
+            });
+        mock_version_repo
+            .expect_find_latest()
+            .returning(move |_| Ok(Some(latest_version.clone())));
+        mock_version_repo
+            .expect_create()
+            .times(1)
+            .returning(|version| {
+                assert_eq!(version.version, 3);
+                assert_eq!(version.rolled_back_from, Some(1));
+                Ok(version)
+            });
+
+        let service = create_service(mock_repo, MockAgentJobRepo::new())
+            .with_versions(Arc::new(mock_version_repo));
+
+        let restored = service
+            .rollback_to_version(&quiz_id, 1)
+            .await
+            .expect("expected rollback to succeed");
+
+        assert_eq!(restored.version, Some(3));
+        assert_eq!(restored.title, "Original");
+        assert_eq!(restored.status, QuizStatus::Ready);
+        assert_eq!(question_ids(&restored), vec!["a"]);
+    }
+
+    #[tokio::test]
+    async fn publishing_retries_a_version_number_taken_concurrently() {
+        let mut mock_repo = MockQuizRepo::new();
+        let mut mock_version_repo = MockQuizVersionRepo::new();
+        let mut sequence = mockall::Sequence::new();
+
+        let quiz = make_ready_quiz(&["a", "b"]);
+        let mut other = quiz.clone();
+        other.title = Some("Published elsewhere".to_string());
+        let other_version = QuizVersion::new(&other, 1, None);
+
+        mock_repo
+            .expect_find_by_id()
+            .returning(move |_| Ok(Some(quiz.clone())));
+        mock_repo.expect_update().times(1).returning(|quiz| {
+            assert_eq!(quiz.version, Some(2));
+            Ok(quiz)
+        });
+        mock_version_repo
+            .expect_find_latest()
+            .times(1)
+            .in_sequence(&mut sequence)
+            .returning(|_| Ok(None));
+        mock_version_repo
+            .expect_create()
+            .times(1)
+            .in_sequence(&mut sequence)
+            .returning(|_| Err(AppError::AlreadyExists("Version 1".to_string())));
+        mock_version_repo
+            .expect_find_latest()
+            .times(1)
+            .in_sequence(&mut sequence)
+            .returning(move |_| Ok(Some(other_version.clone())));
+        mock_version_repo
+            .expect_create()
+            .times(1)
+            .in_sequence(&mut sequence)
+            .returning(Ok);
+
+        let service = create_service(mock_repo, MockAgentJobRepo::new())
+            .with_versions(Arc::new(mock_version_repo));
+
+        let version = service
+            .current_version("quiz-1")
+            .await
+            .expect("expected the quiz to be published")
+            .expect("expected a version");
+
+        assert_eq!(version.version, 2);
     }
 }
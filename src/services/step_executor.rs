@@ -2,75 +2,69 @@ use crate::{
     app_state::AppState,
     models::{
         domain::{
-            quiz::QuizStatus,
-            quiz_question::{QuizQuestionOption, QuizQuestionType},
+            quiz::{QuizSource, QuizStatus},
+            quiz_question::{QuestionGrounding, QuizQuestionOption, QuizQuestionType},
             summary_document::SummaryDocument,
             Quiz,
         },
         dto::{
-            quiz_dto::QuizQuestionDto,
-            request::{GenerateQuizRequestDto, QuizRequestDto, SummaryDocumentRequestDto},
+            quiz_dto::{QuizDto, QuizQuestionDto},
+            request::{
+                GenerateQuizQuestionRequestDto, GenerateQuizRequestDto, QuizRequestDto,
+                SummaryDocumentRequestDto,
+            },
+        },
+    },
+    services::{
+        agent_orchestrator_service::{AgentJob, JobStep},
+        content_extractor::ExtractedPage,
+        job_events::{JobProgressEvent, JobProgressEventKind},
+        job_schedule::SCHEDULE_NAME_KEY,
+        model_service::LlmCallContext,
+        question_validator::{
+            parse_correct, parse_question_type, question_grounding, review_questions,
+            review_replacements, unchecked_grounding,
         },
     },
-    services::agent_orchestrator_service::{AgentJob, JobStep},
 };
-use chrono::Utc;
+use async_trait::async_trait;
+use chrono::{Duration, Utc};
+use futures::{StreamExt, TryStreamExt};
 use serde_json::json;
+use sha2::{Digest, Sha256};
 use uuid::Uuid;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub enum JobStepType {
-    CreateQuizDraft,
-    CreateSummaryDocument,
-    CreateQuizQuestions,
-    FinalizeQuiz,
-}
-
-impl JobStepType {
-    pub fn from_step_name(name: &str) -> Option<Self> {
-        match name {
-            "create_quiz_draft" => Some(JobStepType::CreateQuizDraft),
-            "create_summary_document" => Some(JobStepType::CreateSummaryDocument),
-            "create_quiz_questions" => Some(JobStepType::CreateQuizQuestions),
-            "finalize_quiz" => Some(JobStepType::FinalizeQuiz),
-            _ => None,
-        }
-    }
-}
-
+/// A unit of work that can run as a job step. Implementations are registered
+/// by name in a [`StepRegistry`](crate::services::step_registry::StepRegistry)
+/// and looked up when a job reaches a step with that name.
+#[async_trait]
 pub trait StepExecutor: Send + Sync {
-    fn execute_step(
+    /// Name that `JobStep::name` must match for this executor to run it
+    fn name(&self) -> &'static str;
+
+    async fn execute(
         &self,
-        step_type: JobStepType,
         step: &JobStep,
         job: &AgentJob,
-    ) -> impl std::future::Future<Output = Result<serde_json::Value, String>> + Send;
+        app_state: &AppState,
+    ) -> Result<serde_json::Value, String>;
 }
 
-pub struct StepHandler;
+/// Confirms the draft quiz created by the request is recorded on the job
+pub struct CreateQuizDraftStep;
 
-impl StepHandler {
-    pub async fn execute(
-        step_type: JobStepType,
-        step: &JobStep,
-        job: &AgentJob,
-        app_state: &AppState,
-    ) -> Result<serde_json::Value, String> {
-        match step_type {
-            JobStepType::CreateQuizDraft => {
-                Self::handle_create_quiz_draft(step, job, app_state).await
-            }
-            JobStepType::CreateSummaryDocument => {
-                Self::handle_create_summary_document(step, job, app_state).await
-            }
-            JobStepType::CreateQuizQuestions => {
-                Self::handle_create_quiz_questions(step, job, app_state).await
-            }
-            JobStepType::FinalizeQuiz => Self::handle_finalize_quiz(step, job, app_state).await,
-        }
+impl CreateQuizDraftStep {
+    pub const NAME: &'static str = "create_quiz_draft";
+}
+
+#[async_trait]
+impl StepExecutor for CreateQuizDraftStep {
+    fn name(&self) -> &'static str {
+        Self::NAME
     }
 
-    async fn handle_create_quiz_draft(
+    async fn execute(
+        &self,
         _step: &JobStep,
         job: &AgentJob,
         _app_state: &AppState,
@@ -87,8 +81,28 @@ impl StepHandler {
             "quiz_id": quiz_id
         }))
     }
+}
+
+/// Summarises each source of the quiz into its own summary document. Web
+/// pages are fetched; uploaded documents are summarised from the text stored
+/// when they were uploaded.
+pub struct CreateSummaryDocumentStep;
 
-    async fn handle_create_summary_document(
+impl CreateSummaryDocumentStep {
+    pub const NAME: &'static str = "create_summary_document";
+}
+
+/// Sources summarised at once
+const SOURCE_SUMMARY_CONCURRENCY: usize = 2;
+
+#[async_trait]
+impl StepExecutor for CreateSummaryDocumentStep {
+    fn name(&self) -> &'static str {
+        Self::NAME
+    }
+
+    async fn execute(
+        &self,
         _step: &JobStep,
         job: &AgentJob,
         app_state: &AppState,
@@ -115,37 +129,128 @@ impl StepHandler {
             .try_into()
             .map_err(|e| format!("Failed to parse quiz: {}", e))?;
 
-        match app_state.model_service.website_summariser(&quiz.url, Some(quiz.question_count)).await {
-            Ok(summary_dto) => {
-                log::info!(
-                    "Successfully created summary document for job {}",
-                    job.job_id
-                );
+        let existing = app_state
+            .summary_document_service
+            .get_summary_documents_for_quiz(&quiz.id)
+            .await
+            .map_err(|e| format!("Failed to fetch summary documents: {}", e))?;
 
-                let now = Utc::now().to_rfc3339();
-                let summary_request = SummaryDocumentRequestDto {
-                    id: Uuid::new_v4().to_string(),
-                    quiz_id: quiz.id.clone(),
-                    url: quiz.url.clone(),
-                    content: summary_dto,
-                    created_at: now.clone(),
-                    modified_at: now,
-                };
-                let new_doc: SummaryDocument = summary_request
-                    .try_into()
-                    .map_err(|e| format!("Failed to parse summary document: {}", e))?;
-                app_state
-                    .summary_document_service
-                    .create_summary_document(new_doc.clone())
-                    .await
-                    .map_err(|e| format!("Failed to save summary document: {}", e))?;
-                Ok(json!({ "summary_id": new_doc.id }))
-            }
-            Err(e) => Err(format!("Failed to create summary: {}", e)),
+        let sources = quiz.source_list();
+        // Each source only needs to support its share of the questions
+        let question_count = (quiz.question_count as usize).div_ceil(sources.len()) as i16;
+        let context = LlmCallContext::for_job(&job.job_id, &quiz.id, &quiz.created_by_user_id);
+
+        let requests: Vec<_> = sources
+            .into_iter()
+            .map(|source| {
+                let document = existing
+                    .iter()
+                    .find(|document| document.source_id.as_deref() == Some(source.id.as_str()))
+                    .cloned();
+                summarise_source(
+                    app_state,
+                    &context,
+                    &quiz.id,
+                    source,
+                    document,
+                    question_count,
+                )
+            })
+            .collect();
+        let summaries = futures::stream::iter(requests)
+            .buffered(SOURCE_SUMMARY_CONCURRENCY)
+            .try_collect::<Vec<_>>()
+            .await?;
+
+        log::info!(
+            "Successfully created {} summary documents for job {}",
+            summaries.len(),
+            job.job_id
+        );
+
+        let summary_ids: Vec<_> = summaries
+            .iter()
+            .map(|summary| summary["summary_id"].clone())
+            .collect();
+        Ok(json!({ "summary_ids": summary_ids, "sources": summaries }))
+    }
+}
+
+/// Summarise one quiz source, replacing the summary from any previous run
+async fn summarise_source(
+    app_state: &AppState,
+    context: &LlmCallContext,
+    quiz_id: &str,
+    source: QuizSource,
+    document: Option<SummaryDocument>,
+    question_count: i16,
+) -> Result<serde_json::Value, String> {
+    let summary = if source.is_uploaded() {
+        let text = document
+            .as_ref()
+            .and_then(|document| document.source.clone())
+            .ok_or_else(|| format!("No uploaded text stored for {}", source.url))?;
+        let page = ExtractedPage {
+            content: text,
+            ..Default::default()
+        };
+        app_state
+            .model_service
+            .summarise_page(context, &source.url, &page, Some(question_count))
+            .await
+    } else {
+        app_state
+            .model_service
+            .website_summariser(context, &source.url, Some(question_count))
+            .await
+    }
+    .map_err(|e| format!("Failed to create summary of {}: {}", source.url, e))?;
+
+    let saved = match document {
+        Some(document) => {
+            app_state
+                .summary_document_service
+                .update_summary_content(document, summary.value.content)
+                .await
+        }
+        None => {
+            app_state
+                .summary_document_service
+                .create_summary_document(SummaryDocument::for_source(
+                    quiz_id,
+                    &source,
+                    &summary.value.content,
+                ))
+                .await
         }
     }
+    .map_err(|e| format!("Failed to save summary document: {}", e))?;
 
-    async fn handle_create_quiz_questions(
+    Ok(json!({
+        "source_id": source.id,
+        "summary_id": saved.id,
+        "summary_model": summary.model,
+        "summary_chunks": summary.value.chunks,
+        "sections_covered": summary.value.sections_covered,
+        "sections_skipped": summary.value.sections_skipped,
+    }))
+}
+
+/// Generates quiz questions from the summary documents of every source
+pub struct CreateQuizQuestionsStep;
+
+impl CreateQuizQuestionsStep {
+    pub const NAME: &'static str = "create_quiz_questions";
+}
+
+#[async_trait]
+impl StepExecutor for CreateQuizQuestionsStep {
+    fn name(&self) -> &'static str {
+        Self::NAME
+    }
+
+    async fn execute(
+        &self,
         _step: &JobStep,
         job: &AgentJob,
         app_state: &AppState,
@@ -162,48 +267,329 @@ impl StepHandler {
             .ok_or_else(|| "Invalid or missing quiz_id in job results".to_string())?
             .to_string();
 
-        let summary_id = job
-            .results
-            .get("summary_id")
-            .and_then(|v| v.as_str())
-            .ok_or_else(|| "Invalid or missing summary_id in job results".to_string())?
-            .to_string();
-
         let quiz_dto = app_state
             .quiz_service
             .get_quiz(&quiz_id)
             .await
             .map_err(|e| format!("Failed to fetch quiz: {}", e))?;
+        let summaries = load_summaries(job, app_state).await?;
 
-        let summary_document = app_state
-            .summary_document_service
-            .get_summary_document(&summary_id)
-            .await
-            .map_err(|e| format!("Failed to fetch summary document: {}", e))?;
-
+        let context = LlmCallContext::for_job(&job.job_id, &quiz_id, &quiz_dto.created_by_user_id);
         let quiz_request_dto = QuizRequestDto::from(quiz_dto);
-        let summary_dto = SummaryDocumentRequestDto::from(summary_document);
 
         match app_state
             .model_service
-            .structured_quiz_generator(quiz_request_dto, summary_dto)
+            .structured_quiz_generator(&context, quiz_request_dto, summaries)
             .await
         {
-            Ok(response) => {
+            Ok(generated) => {
                 log::info!(
-                    "Successfully generated quiz questions for job {}",
-                    job.job_id
+                    "Successfully generated quiz questions for job {} using {}",
+                    job.job_id,
+                    generated.model
                 );
                 Ok(json!({
                     "status": "quiz_fields_generated",
-                    "response": response
+                    "response": generated.value,
+                    "questions_model": generated.model
                 }))
             }
             Err(e) => Err(format!("Failed to generate quiz questions: {}", e)),
         }
     }
+}
+
+/// Summary documents written by the summary step of `job`
+async fn load_summaries(
+    job: &AgentJob,
+    app_state: &AppState,
+) -> Result<Vec<SummaryDocumentRequestDto>, String> {
+    // Jobs started before quizzes had several sources record one summary_id
+    let summary_ids: Vec<String> = match job.results.get("summary_ids") {
+        Some(ids) => serde_json::from_value(ids.clone())
+            .map_err(|e| format!("Invalid summary_ids in job results: {}", e))?,
+        None => vec![job
+            .results
+            .get("summary_id")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| "Invalid or missing summary_ids in job results".to_string())?
+            .to_string()],
+    };
+
+    let mut summaries = Vec::with_capacity(summary_ids.len());
+    for summary_id in &summary_ids {
+        let summary_document = app_state
+            .summary_document_service
+            .get_summary_document(summary_id)
+            .await
+            .map_err(|e| format!("Failed to fetch summary document: {}", e))?;
+        summaries.push(SummaryDocumentRequestDto::from(summary_document));
+    }
+    Ok(summaries)
+}
+
+/// Rounds of regeneration for questions that fail validation
+const QUESTION_REPAIR_ROUNDS: usize = 2;
+
+/// Checks the generated questions against the rules for their type, drops
+/// repeats and asks the validation model to replace invalid or missing ones.
+/// Problems left after the repair rounds are recorded on the job.
+pub struct ValidateQuizQuestionsStep;
+
+impl ValidateQuizQuestionsStep {
+    pub const NAME: &'static str = "validate_quiz_questions";
+}
+
+#[async_trait]
+impl StepExecutor for ValidateQuizQuestionsStep {
+    fn name(&self) -> &'static str {
+        Self::NAME
+    }
+
+    async fn execute(
+        &self,
+        _step: &JobStep,
+        job: &AgentJob,
+        app_state: &AppState,
+    ) -> Result<serde_json::Value, String> {
+        log::info!(
+            "Executing validate_quiz_questions step for job {}",
+            job.job_id
+        );
+
+        let quiz_id = job
+            .quiz_id()
+            .ok_or_else(|| "Invalid or missing quiz_id in job results".to_string())?
+            .to_string();
+        let mut generated: GenerateQuizRequestDto = job
+            .results
+            .get("response")
+            .cloned()
+            .ok_or_else(|| "Missing generated quiz in job results".to_string())
+            .and_then(|response| {
+                serde_json::from_value(response)
+                    .map_err(|e| format!("Failed to parse quiz from job results: {}", e))
+            })?;
+
+        let quiz = app_state
+            .quiz_service
+            .get_quiz(&quiz_id)
+            .await
+            .map_err(|e| format!("Failed to fetch quiz: {}", e))?;
+        let expected = quiz.question_count.max(1) as usize;
+
+        let mut review = review_questions(std::mem::take(&mut generated.quiz_questions), expected);
+        let initial_problems = review.rejected.len();
+        let mut regenerated = 0;
+        let mut rounds = 0;
+
+        if !review.is_complete() {
+            let summaries = load_summaries(job, app_state).await?;
+            let context = LlmCallContext::for_job(&job.job_id, &quiz_id, &quiz.created_by_user_id);
+
+            while !review.is_complete() && rounds < QUESTION_REPAIR_ROUNDS {
+                rounds += 1;
+                let replacements = match app_state
+                    .model_service
+                    .regenerate_questions(
+                        &context,
+                        &summaries,
+                        &review.valid,
+                        &review.rejected,
+                        review.missing,
+                    )
+                    .await
+                {
+                    Ok(output) => output.value.quiz_questions,
+                    Err(e) => {
+                        log::warn!(
+                            "Failed to regenerate questions for job {}: {}",
+                            job.job_id,
+                            e
+                        );
+                        break;
+                    }
+                };
+                regenerated += replacements.len();
+
+                let duplicates = review.duplicates;
+                let mut questions = std::mem::take(&mut review.valid);
+                questions.extend(replacements);
+                review = review_questions(questions, expected);
+                review.duplicates += duplicates;
+            }
+        }
+
+        if review.valid.is_empty() {
+            return Err(format!(
+                "No valid questions were generated: {}",
+                serde_json::to_string(&review.rejected).unwrap_or_default()
+            ));
+        }
+        if !review.rejected.is_empty() || !review.is_complete() {
+            log::warn!(
+                "Quiz {} has {} of {} questions after validation for job {}",
+                quiz_id,
+                review.valid.len(),
+                expected,
+                job.job_id
+            );
+        }
+
+        let question_count = review.valid.len();
+        generated.quiz_questions = review.valid;
+        Ok(json!({
+            "response": generated,
+            "validation": {
+                "question_count": question_count,
+                "requested_count": expected,
+                "initial_problems": initial_problems,
+                "duplicates_removed": review.duplicates,
+                "repair_rounds": rounds,
+                "regenerated": regenerated,
+                "remaining_problems": review.rejected,
+                "missing": review.missing,
+            }
+        }))
+    }
+}
+
+/// Questions checked at once
+const GROUNDING_CONCURRENCY: usize = 4;
+
+/// Asks the validation model to cite the summary passage supporting each
+/// correct option of the generated quiz, or of the replacement questions on a
+/// regeneration job. Does nothing unless `QUIZ_GROUNDING_CHECK` is enabled.
+pub struct CheckQuestionGroundingStep;
+
+impl CheckQuestionGroundingStep {
+    pub const NAME: &'static str = "check_question_grounding";
+}
 
-    async fn handle_finalize_quiz(
+#[async_trait]
+impl StepExecutor for CheckQuestionGroundingStep {
+    fn name(&self) -> &'static str {
+        Self::NAME
+    }
+
+    async fn execute(
+        &self,
+        _step: &JobStep,
+        job: &AgentJob,
+        app_state: &AppState,
+    ) -> Result<serde_json::Value, String> {
+        if !app_state.config.quiz_grounding_check {
+            return Ok(json!({ "grounding_check": "disabled" }));
+        }
+        log::info!(
+            "Executing check_question_grounding step for job {}",
+            job.job_id
+        );
+
+        let quiz_id = job
+            .quiz_id()
+            .ok_or_else(|| "Invalid or missing quiz_id in job results".to_string())?
+            .to_string();
+        let questions: Vec<GenerateQuizQuestionRequestDto> = match job.results.get("replacements") {
+            Some(replacements) => serde_json::from_value(replacements.clone())
+                .map_err(|e| format!("Failed to parse replacement questions: {}", e))?,
+            None => {
+                let generated: GenerateQuizRequestDto = job
+                    .results
+                    .get("response")
+                    .cloned()
+                    .ok_or_else(|| "Missing generated quiz in job results".to_string())
+                    .and_then(|response| {
+                        serde_json::from_value(response)
+                            .map_err(|e| format!("Failed to parse quiz from job results: {}", e))
+                    })?;
+                generated.quiz_questions
+            }
+        };
+        let quiz = app_state
+            .quiz_service
+            .get_quiz(&quiz_id)
+            .await
+            .map_err(|e| format!("Failed to fetch quiz: {}", e))?;
+        let summaries = load_summaries(job, app_state).await?;
+        let all_summaries = summaries
+            .iter()
+            .map(|summary| summary.content.as_str())
+            .collect::<Vec<_>>()
+            .join("\n\n");
+
+        let context = LlmCallContext::for_job(&job.job_id, &quiz_id, &quiz.created_by_user_id);
+        let min_confidence = app_state.config.quiz_grounding_min_confidence;
+        let checks: Vec<_> = questions
+            .iter()
+            .map(|question| {
+                // Questions are checked against their own source when known
+                let source = summaries
+                    .iter()
+                    .find(|summary| {
+                        question.source_id.is_some() && summary.source_id == question.source_id
+                    })
+                    .map(|summary| summary.content.as_str())
+                    .unwrap_or(&all_summaries);
+                let context = &context;
+                async move {
+                    match app_state
+                        .model_service
+                        .check_grounding(context, source, question)
+                        .await
+                    {
+                        Ok(judgement) => {
+                            question_grounding(question, &judgement.value, source, min_confidence)
+                        }
+                        Err(e) => {
+                            log::warn!(
+                                "Could not check grounding of {:?} for job {}: {}",
+                                question.question_title,
+                                job.job_id,
+                                e
+                            );
+                            unchecked_grounding()
+                        }
+                    }
+                }
+            })
+            .collect();
+        let grounding: Vec<QuestionGrounding> = futures::stream::iter(checks)
+            .buffered(GROUNDING_CONCURRENCY)
+            .collect()
+            .await;
+
+        let flagged = grounding.iter().filter(|g| g.needs_review).count();
+        log::info!(
+            "Grounding check flagged {} of {} questions for job {}",
+            flagged,
+            grounding.len(),
+            job.job_id
+        );
+
+        Ok(json!({
+            "grounding": grounding,
+            "grounding_flagged": flagged,
+        }))
+    }
+}
+
+/// Writes the generated questions to the quiz and marks it ready, or holds it
+/// for review when the grounding check flagged any of its answers
+pub struct FinalizeQuizStep;
+
+impl FinalizeQuizStep {
+    pub const NAME: &'static str = "finalize_quiz";
+}
+
+#[async_trait]
+impl StepExecutor for FinalizeQuizStep {
+    fn name(&self) -> &'static str {
+        Self::NAME
+    }
+
+    async fn execute(
+        &self,
         _step: &JobStep,
         job: &AgentJob,
         app_state: &AppState,
@@ -232,51 +618,37 @@ impl StepHandler {
             .await
             .map_err(|e| format!("Failed to fetch quiz: {}", e))?;
 
+        let source_ids: Vec<String> = quiz_dto.sources.iter().map(|s| s.id.clone()).collect();
+        // Absent when the grounding check is disabled
+        let grounding: Vec<QuestionGrounding> = job
+            .results
+            .get("grounding")
+            .and_then(|v| serde_json::from_value(v.clone()).ok())
+            .unwrap_or_default();
         let generated_questions: Vec<QuizQuestionDto> = generate_quiz_request_dto
             .quiz_questions
             .into_iter()
-            .map(|question| {
-                let question_type = match question.question_type.to_lowercase().as_str() {
-                    "single" => QuizQuestionType::Single,
-                    "multi" => QuizQuestionType::Multi,
-                    "bool" | "boolean" => QuizQuestionType::Bool,
-                    _ => QuizQuestionType::Single,
-                };
-                let options: Vec<QuizQuestionOption> = question
-                    .question_options
-                    .into_iter()
-                    .map(|option| QuizQuestionOption {
-                        id: Uuid::new_v4().to_string(),
-                        text: option.option_text,
-                        correct: option.option_correct.trim().eq_ignore_ascii_case("true"),
-                        explanation: option.option_explanation,
-                    })
-                    .collect();
-                let option_count = options.len() as i16;
-                let now = Utc::now();
-
-                QuizQuestionDto {
-                    id: Uuid::new_v4().to_string(),
-                    title: question.question_title,
-                    description: question.question_description,
-                    question_type,
-                    options,
-                    option_count,
-                    order: 0,
-                    attempt_limit: quiz_dto.attempt_limit,
-                    topic: quiz_dto.topic.clone(),
-                    created_at: now,
-                    modified_at: now,
-                }
+            .enumerate()
+            .map(|(index, question)| QuizQuestionDto {
+                order: index as i16,
+                grounding: grounding.get(index).cloned(),
+                ..generated_question(question, &quiz_dto, &source_ids)
             })
             .collect();
 
+        let status = if grounding.iter().any(|g| g.needs_review) {
+            QuizStatus::NeedsReview
+        } else {
+            QuizStatus::Ready
+        };
         let mut quiz_dto = quiz_dto;
+        // Validation may have left fewer questions than were asked for
+        quiz_dto.question_count = generated_questions.len() as i16;
         quiz_dto.questions = generated_questions;
         quiz_dto.title = generate_quiz_request_dto.quiz_title;
         quiz_dto.description = generate_quiz_request_dto.quiz_description;
         quiz_dto.topic = generate_quiz_request_dto.quiz_topic;
-        quiz_dto.status = QuizStatus::Ready;
+        quiz_dto.status = status;
         quiz_dto.modified_at = Utc::now();
 
         app_state
@@ -291,9 +663,524 @@ impl StepHandler {
             job.job_id
         );
 
+        app_state.agent_orchestrator.events().publish(
+            JobProgressEvent::new(job, JobProgressEventKind::QuizReady).with_quiz_status(status),
+        );
+
         Ok(json!({
             "status": "quiz_finalized",
-            "quiz_status": "ready"
+            "quiz_status": format!("{:?}", status).to_lowercase()
+        }))
+    }
+}
+
+/// A new question for `quiz` from one the model wrote
+fn generated_question(
+    question: GenerateQuizQuestionRequestDto,
+    quiz: &QuizDto,
+    source_ids: &[String],
+) -> QuizQuestionDto {
+    // Checked by ValidateQuizQuestionsStep; the defaults only apply to jobs
+    // started before it was added
+    let question_type =
+        parse_question_type(&question.question_type).unwrap_or(QuizQuestionType::Single);
+    let options: Vec<QuizQuestionOption> = question
+        .question_options
+        .into_iter()
+        .map(|option| QuizQuestionOption {
+            id: Uuid::new_v4().to_string(),
+            text: option.option_text,
+            correct: parse_correct(&option.option_correct).unwrap_or(false),
+            explanation: option.option_explanation,
+        })
+        .collect();
+    let option_count = options.len() as i16;
+    let now = Utc::now();
+    // Single-source quizzes don't ask the model to attribute questions
+    let source_id = match source_ids {
+        [only] => Some(only.clone()),
+        _ => question.source_id.filter(|id| source_ids.contains(id)),
+    };
+
+    QuizQuestionDto {
+        id: Uuid::new_v4().to_string(),
+        title: question.question_title,
+        description: question.question_description,
+        question_type,
+        options,
+        option_count,
+        order: 0,
+        attempt_limit: quiz.attempt_limit,
+        topic: quiz.topic.clone(),
+        source_id,
+        grounding: None,
+        created_at: now,
+        modified_at: now,
+    }
+}
+
+/// Job results key listing the questions a regeneration job replaces
+pub const QUESTION_IDS_KEY: &str = "question_ids";
+/// Job results key holding the quiz creator's instructions for the replacements
+pub const REGENERATION_INSTRUCTIONS_KEY: &str = "instructions";
+
+/// Writes new questions to replace the ones picked by the quiz creator, from
+/// the quiz's stored summaries and without repeating the questions being kept
+pub struct GenerateReplacementQuestionsStep;
+
+impl GenerateReplacementQuestionsStep {
+    pub const NAME: &'static str = "generate_replacement_questions";
+}
+
+#[async_trait]
+impl StepExecutor for GenerateReplacementQuestionsStep {
+    fn name(&self) -> &'static str {
+        Self::NAME
+    }
+
+    async fn execute(
+        &self,
+        _step: &JobStep,
+        job: &AgentJob,
+        app_state: &AppState,
+    ) -> Result<serde_json::Value, String> {
+        log::info!(
+            "Executing generate_replacement_questions step for job {}",
+            job.job_id
+        );
+
+        let quiz_id = job
+            .quiz_id()
+            .ok_or_else(|| "Invalid or missing quiz_id in job results".to_string())?
+            .to_string();
+        let question_ids: Vec<String> = job
+            .results
+            .get(QUESTION_IDS_KEY)
+            .cloned()
+            .ok_or_else(|| "Missing question_ids in job results".to_string())
+            .and_then(|ids| {
+                serde_json::from_value(ids)
+                    .map_err(|e| format!("Invalid question_ids in job results: {}", e))
+            })?;
+        let instructions = job
+            .results
+            .get(REGENERATION_INSTRUCTIONS_KEY)
+            .and_then(|v| v.as_str());
+
+        let quiz = app_state
+            .quiz_service
+            .get_quiz(&quiz_id)
+            .await
+            .map_err(|e| format!("Failed to fetch quiz: {}", e))?;
+        let (replaced, kept): (Vec<QuizQuestionDto>, Vec<QuizQuestionDto>) = quiz
+            .questions
+            .iter()
+            .cloned()
+            .partition(|question| question_ids.contains(&question.id));
+        if replaced.is_empty() {
+            return Err(format!(
+                "None of the questions to regenerate are on quiz {}",
+                quiz_id
+            ));
+        }
+
+        let documents: Vec<SummaryDocument> = app_state
+            .summary_document_service
+            .get_summary_documents_for_quiz(&quiz_id)
+            .await
+            .map_err(|e| format!("Failed to fetch summary documents: {}", e))?
+            .into_iter()
+            .filter(|document| !document.content.trim().is_empty())
+            .collect();
+        // Recorded so the grounding check reads the same summaries
+        let summary_ids: Vec<String> = documents.iter().map(|d| d.id.clone()).collect();
+        let summaries: Vec<SummaryDocumentRequestDto> = documents
+            .into_iter()
+            .map(SummaryDocumentRequestDto::from)
+            .collect();
+        if summaries.is_empty() {
+            return Err(format!(
+                "Quiz {} has no summary to regenerate questions from",
+                quiz_id
+            ));
+        }
+
+        let context = LlmCallContext::for_job(&job.job_id, &quiz_id, &quiz.created_by_user_id);
+        let kept: Vec<GenerateQuizQuestionRequestDto> = kept.into_iter().map(Into::into).collect();
+        let kept_titles: Vec<&str> = kept.iter().map(|q| q.question_title.as_str()).collect();
+        let to_replace: Vec<GenerateQuizQuestionRequestDto> =
+            replaced.iter().cloned().map(Into::into).collect();
+        let expected = replaced.len();
+
+        let mut review = review_replacements(Vec::new(), &kept_titles, expected);
+        let mut rounds = 0;
+        // One attempt plus the same repair rounds as generation
+        while !review.is_complete() && rounds <= QUESTION_REPAIR_ROUNDS {
+            rounds += 1;
+            let mut avoid = kept.clone();
+            avoid.extend(review.valid.iter().cloned());
+            let replacements = match app_state
+                .model_service
+                .replace_questions(
+                    &context,
+                    &summaries,
+                    &avoid,
+                    &to_replace[review.valid.len()..],
+                    review.missing,
+                    instructions,
+                )
+                .await
+            {
+                Ok(output) => output.value.quiz_questions,
+                Err(e) if review.valid.is_empty() => {
+                    return Err(format!("Failed to regenerate questions: {}", e))
+                }
+                Err(e) => {
+                    log::warn!(
+                        "Failed to regenerate questions for job {}: {}",
+                        job.job_id,
+                        e
+                    );
+                    break;
+                }
+            };
+
+            let duplicates = review.duplicates;
+            let mut questions = std::mem::take(&mut review.valid);
+            questions.extend(replacements);
+            review = review_replacements(questions, &kept_titles, expected);
+            review.duplicates += duplicates;
+        }
+
+        if review.valid.is_empty() {
+            return Err(format!(
+                "No valid replacement questions were generated: {}",
+                serde_json::to_string(&review.rejected).unwrap_or_default()
+            ));
+        }
+        if !review.is_complete() {
+            log::warn!(
+                "Only {} of {} questions on quiz {} could be regenerated for job {}",
+                review.valid.len(),
+                expected,
+                quiz_id,
+                job.job_id
+            );
+        }
+
+        // Questions without a valid replacement are left as they are
+        let replaced_ids: Vec<&str> = replaced
+            .iter()
+            .take(review.valid.len())
+            .map(|question| question.id.as_str())
+            .collect();
+        Ok(json!({
+            "replacements": review.valid,
+            "replaced_question_ids": replaced_ids,
+            "summary_ids": summary_ids,
+            "regeneration": {
+                "requested": expected,
+                "rounds": rounds,
+                "duplicates_removed": review.duplicates,
+                "remaining_problems": review.rejected,
+                "missing": review.missing,
+            }
+        }))
+    }
+}
+
+/// Swaps the replacement questions into the quiz, each taking the place and
+/// order of the question it replaces. Holds the quiz for review when the
+/// grounding check flagged any of the replacements.
+pub struct ReplaceQuizQuestionsStep;
+
+impl ReplaceQuizQuestionsStep {
+    pub const NAME: &'static str = "replace_quiz_questions";
+}
+
+#[async_trait]
+impl StepExecutor for ReplaceQuizQuestionsStep {
+    fn name(&self) -> &'static str {
+        Self::NAME
+    }
+
+    async fn execute(
+        &self,
+        _step: &JobStep,
+        job: &AgentJob,
+        app_state: &AppState,
+    ) -> Result<serde_json::Value, String> {
+        log::info!(
+            "Executing replace_quiz_questions step for job {}",
+            job.job_id
+        );
+
+        let quiz_id = job
+            .quiz_id()
+            .ok_or_else(|| "Invalid or missing quiz_id in job results".to_string())?
+            .to_string();
+        let replaced_ids: Vec<String> = job
+            .results
+            .get("replaced_question_ids")
+            .and_then(|v| serde_json::from_value(v.clone()).ok())
+            .ok_or_else(|| "Invalid or missing replaced_question_ids in job results".to_string())?;
+        let replacements: Vec<GenerateQuizQuestionRequestDto> = job
+            .results
+            .get("replacements")
+            .cloned()
+            .ok_or_else(|| "Missing replacement questions in job results".to_string())
+            .and_then(|replacements| {
+                serde_json::from_value(replacements)
+                    .map_err(|e| format!("Failed to parse replacement questions: {}", e))
+            })?;
+
+        let mut quiz_dto = app_state
+            .quiz_service
+            .get_quiz(&quiz_id)
+            .await
+            .map_err(|e| format!("Failed to fetch quiz: {}", e))?;
+        let source_ids: Vec<String> = quiz_dto.sources.iter().map(|s| s.id.clone()).collect();
+        // Absent when the grounding check is disabled
+        let grounding: Vec<QuestionGrounding> = job
+            .results
+            .get("grounding")
+            .and_then(|v| serde_json::from_value(v.clone()).ok())
+            .unwrap_or_default();
+
+        let mut new_ids = Vec::new();
+        let mut flagged = 0;
+        for (replacement_index, (id, replacement)) in
+            replaced_ids.iter().zip(replacements).enumerate()
+        {
+            // The quiz may have been edited while the job ran
+            let Some(index) = quiz_dto.questions.iter().position(|q| &q.id == id) else {
+                log::warn!(
+                    "Question {} is no longer on quiz {}; dropping its replacement",
+                    id,
+                    quiz_id
+                );
+                continue;
+            };
+            let mut question = generated_question(replacement, &quiz_dto, &source_ids);
+            let previous = &quiz_dto.questions[index];
+            question.order = previous.order;
+            question.source_id = question.source_id.or_else(|| previous.source_id.clone());
+            question.grounding = grounding.get(replacement_index).cloned();
+            if question.grounding.as_ref().is_some_and(|g| g.needs_review) {
+                flagged += 1;
+            }
+            new_ids.push(question.id.clone());
+            quiz_dto.questions[index] = question;
+        }
+        if flagged > 0 {
+            quiz_dto.status = QuizStatus::NeedsReview;
+        }
+        let status = quiz_dto.status;
+        quiz_dto.modified_at = Utc::now();
+
+        app_state
+            .quiz_service
+            .update_quiz(quiz_dto)
+            .await
+            .map_err(|e| format!("Failed to update quiz: {}", e))?;
+
+        log::info!(
+            "Replaced {} questions on quiz {} for job {}",
+            new_ids.len(),
+            quiz_id,
+            job.job_id
+        );
+
+        Ok(json!({
+            "status": "questions_replaced",
+            "new_question_ids": new_ids,
+            "quiz_status": format!("{:?}", status).to_lowercase(),
+        }))
+    }
+}
+
+/// Job results key holding the source page hash seen on the previous run
+pub const SOURCE_HASH_KEY: &str = "source_hash";
+/// Job results key overriding how long completed jobs are kept
+pub const RETENTION_DAYS_KEY: &str = "retention_days";
+
+const DEFAULT_JOB_RETENTION_DAYS: i64 = 30;
+
+/// Deletes refresh tokens past their expiry
+pub struct PurgeExpiredRefreshTokensStep;
+
+impl PurgeExpiredRefreshTokensStep {
+    pub const NAME: &'static str = "purge_expired_refresh_tokens";
+}
+
+#[async_trait]
+impl StepExecutor for PurgeExpiredRefreshTokensStep {
+    fn name(&self) -> &'static str {
+        Self::NAME
+    }
+
+    async fn execute(
+        &self,
+        _step: &JobStep,
+        job: &AgentJob,
+        app_state: &AppState,
+    ) -> Result<serde_json::Value, String> {
+        log::info!(
+            "Executing purge_expired_refresh_tokens step for job {}",
+            job.job_id
+        );
+
+        let deleted = app_state
+            .refresh_token_repository
+            .delete_expired()
+            .await
+            .map_err(|e| format!("Failed to delete expired refresh tokens: {}", e))?;
+
+        log::info!("Purged {} expired refresh tokens", deleted);
+
+        Ok(json!({ "deleted_refresh_tokens": deleted }))
+    }
+}
+
+/// Deletes completed jobs older than the retention period
+pub struct PurgeCompletedJobsStep;
+
+impl PurgeCompletedJobsStep {
+    pub const NAME: &'static str = "purge_completed_jobs";
+}
+
+#[async_trait]
+impl StepExecutor for PurgeCompletedJobsStep {
+    fn name(&self) -> &'static str {
+        Self::NAME
+    }
+
+    async fn execute(
+        &self,
+        _step: &JobStep,
+        job: &AgentJob,
+        app_state: &AppState,
+    ) -> Result<serde_json::Value, String> {
+        log::info!("Executing purge_completed_jobs step for job {}", job.job_id);
+
+        let retention_days = job
+            .results
+            .get(RETENTION_DAYS_KEY)
+            .and_then(|v| v.as_i64())
+            .unwrap_or(DEFAULT_JOB_RETENTION_DAYS)
+            .max(1);
+        let cutoff = Utc::now() - Duration::days(retention_days);
+
+        let deleted = app_state
+            .agent_orchestrator
+            .delete_completed_jobs_before(cutoff)
+            .await?;
+
+        log::info!(
+            "Purged {} jobs completed more than {} days ago",
+            deleted,
+            retention_days
+        );
+
+        Ok(json!({ "deleted_jobs": deleted }))
+    }
+}
+
+/// Regenerates a quiz when the text of its source pages has changed since the
+/// previous run. The hash of the pages is kept in the metadata of the schedule
+/// that enqueued the job, so the first run only records a baseline.
+pub struct RefreshQuizFromSourceStep;
+
+impl RefreshQuizFromSourceStep {
+    pub const NAME: &'static str = "refresh_quiz_from_source";
+}
+
+#[async_trait]
+impl StepExecutor for RefreshQuizFromSourceStep {
+    fn name(&self) -> &'static str {
+        Self::NAME
+    }
+
+    async fn execute(
+        &self,
+        _step: &JobStep,
+        job: &AgentJob,
+        app_state: &AppState,
+    ) -> Result<serde_json::Value, String> {
+        log::info!(
+            "Executing refresh_quiz_from_source step for job {}",
+            job.job_id
+        );
+
+        let quiz_id = job
+            .quiz_id()
+            .ok_or_else(|| "Invalid or missing quiz_id in job results".to_string())?
+            .to_string();
+
+        let quiz = app_state
+            .quiz_service
+            .get_quiz(&quiz_id)
+            .await
+            .map_err(|e| format!("Failed to fetch quiz: {}", e))?;
+
+        if quiz.status == QuizStatus::Draft {
+            return Ok(json!({
+                "source_changed": false,
+                "status": "generation_in_progress"
+            }));
+        }
+        // Uploaded documents can't change, so only web sources are checked
+        let urls: Vec<&str> = quiz
+            .sources
+            .iter()
+            .filter(|source| !source.is_uploaded())
+            .map(|source| source.url.as_str())
+            .collect();
+        if urls.is_empty() {
+            return Ok(json!({
+                "source_changed": false,
+                "status": "uploaded_document"
+            }));
+        }
+
+        let mut text = String::new();
+        for url in urls {
+            text.push_str(
+                &app_state
+                    .model_service
+                    .fetch_page_text(url)
+                    .await
+                    .map_err(|e| format!("Failed to fetch quiz source {}: {}", url, e))?,
+            );
+        }
+        let source_hash = format!("{:x}", Sha256::digest(text.as_bytes()));
+
+        let previous_hash = job.results.get(SOURCE_HASH_KEY).and_then(|v| v.as_str());
+        if previous_hash == Some(source_hash.as_str()) {
+            return Ok(json!({ "source_changed": false }));
+        }
+
+        let regeneration_job_id = match previous_hash {
+            Some(_) => Some(
+                app_state
+                    .quiz_service
+                    .start_generation_job(&quiz_id)
+                    .await
+                    .map_err(|e| format!("Failed to start quiz regeneration: {}", e))?,
+            ),
+            None => None,
+        };
+
+        if let Some(schedule_name) = job.results.get(SCHEDULE_NAME_KEY).and_then(|v| v.as_str()) {
+            app_state
+                .agent_orchestrator
+                .set_schedule_metadata(schedule_name, SOURCE_HASH_KEY, json!(source_hash))
+                .await?;
+        }
+
+        Ok(json!({
+            "source_changed": regeneration_job_id.is_some(),
+            "regeneration_job_id": regeneration_job_id
         }))
     }
 }
@@ -0,0 +1,197 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::services::agent_orchestrator_service::JobStep;
+use crate::services::step_executor::StepExecutor;
+
+/// Declarative description of a step within a pipeline. A fresh [`JobStep`]
+/// is built from it for every job so step ids are never shared.
+#[derive(Debug, Clone)]
+pub struct StepDefinition {
+    pub name: String,
+    pub description: Option<String>,
+    pub max_retries: u32,
+    pub timeout_seconds: Option<u64>,
+}
+
+impl StepDefinition {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            description: None,
+            max_retries: 3,
+            timeout_seconds: None,
+        }
+    }
+
+    pub fn with_description(mut self, description: impl Into<String>) -> Self {
+        self.description = Some(description.into());
+        self
+    }
+
+    pub fn with_max_retries(mut self, max_retries: u32) -> Self {
+        self.max_retries = max_retries;
+        self
+    }
+
+    pub fn with_timeout(mut self, seconds: u64) -> Self {
+        self.timeout_seconds = Some(seconds);
+        self
+    }
+
+    pub fn to_job_step(&self) -> JobStep {
+        let mut step = JobStep::new(&self.name).with_max_retries(self.max_retries);
+        if let Some(description) = &self.description {
+            step = step.with_description(description);
+        }
+        if let Some(seconds) = self.timeout_seconds {
+            step = step.with_timeout(seconds);
+        }
+        step
+    }
+}
+
+/// A named, ordered list of steps that together make up a workflow
+#[derive(Debug, Clone)]
+pub struct PipelineDefinition {
+    pub name: String,
+    pub steps: Vec<StepDefinition>,
+}
+
+impl PipelineDefinition {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            steps: Vec::new(),
+        }
+    }
+
+    pub fn with_step(mut self, step: StepDefinition) -> Self {
+        self.steps.push(step);
+        self
+    }
+
+    pub fn build_steps(&self) -> Vec<JobStep> {
+        self.steps.iter().map(StepDefinition::to_job_step).collect()
+    }
+}
+
+/// Step executors and pipelines known to the orchestrator, keyed by name
+#[derive(Default)]
+pub struct StepRegistry {
+    executors: HashMap<String, Arc<dyn StepExecutor>>,
+    pipelines: HashMap<String, PipelineDefinition>,
+}
+
+impl StepRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn register_step(&mut self, executor: impl StepExecutor + 'static) -> &mut Self {
+        let name = executor.name().to_string();
+        if self
+            .executors
+            .insert(name.clone(), Arc::new(executor))
+            .is_some()
+        {
+            log::warn!("Step executor '{}' registered twice; replacing", name);
+        }
+        self
+    }
+
+    pub fn register_pipeline(&mut self, pipeline: PipelineDefinition) -> &mut Self {
+        let name = pipeline.name.clone();
+        if self.pipelines.insert(name.clone(), pipeline).is_some() {
+            log::warn!("Pipeline '{}' registered twice; replacing", name);
+        }
+        self
+    }
+
+    pub fn get_step(&self, name: &str) -> Option<Arc<dyn StepExecutor>> {
+        self.executors.get(name).cloned()
+    }
+
+    pub fn get_pipeline(&self, name: &str) -> Option<&PipelineDefinition> {
+        self.pipelines.get(name)
+    }
+
+    /// Build the job steps for a pipeline, checking every step has an executor
+    pub fn build_pipeline_steps(&self, name: &str) -> Result<Vec<JobStep>, String> {
+        let pipeline = self
+            .get_pipeline(name)
+            .ok_or_else(|| format!("Unknown pipeline: {}", name))?;
+
+        if let Some(missing) = pipeline
+            .steps
+            .iter()
+            .find(|step| !self.executors.contains_key(&step.name))
+        {
+            return Err(format!(
+                "Pipeline {} uses unregistered step: {}",
+                name, missing.name
+            ));
+        }
+
+        Ok(pipeline.build_steps())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use async_trait::async_trait;
+
+    use super::*;
+    use crate::app_state::AppState;
+    use crate::services::agent_orchestrator_service::AgentJob;
+
+    struct EchoStep;
+
+    #[async_trait]
+    impl StepExecutor for EchoStep {
+        fn name(&self) -> &'static str {
+            "echo"
+        }
+
+        async fn execute(
+            &self,
+            step: &JobStep,
+            _job: &AgentJob,
+            _app_state: &AppState,
+        ) -> Result<serde_json::Value, String> {
+            Ok(serde_json::json!({ "echo": step.name }))
+        }
+    }
+
+    #[test]
+    fn build_pipeline_steps_creates_fresh_steps_from_definition() {
+        let mut registry = StepRegistry::new();
+        registry.register_step(EchoStep).register_pipeline(
+            PipelineDefinition::new("echo_twice")
+                .with_step(StepDefinition::new("echo").with_timeout(5))
+                .with_step(StepDefinition::new("echo").with_max_retries(1)),
+        );
+
+        let first = registry.build_pipeline_steps("echo_twice").unwrap();
+        let second = registry.build_pipeline_steps("echo_twice").unwrap();
+
+        assert_eq!(first.len(), 2);
+        assert_eq!(first[0].timeout_seconds, Some(5));
+        assert_eq!(first[1].max_retries, 1);
+        assert_ne!(first[0].id, second[0].id);
+        assert!(registry.get_step("echo").is_some());
+    }
+
+    #[test]
+    fn build_pipeline_steps_rejects_unknown_pipeline_or_step() {
+        let mut registry = StepRegistry::new();
+        registry.register_pipeline(
+            PipelineDefinition::new("translate").with_step(StepDefinition::new("translate_quiz")),
+        );
+
+        assert!(registry.build_pipeline_steps("missing").is_err());
+
+        let error = registry.build_pipeline_steps("translate").unwrap_err();
+        assert!(error.contains("translate_quiz"));
+    }
+}
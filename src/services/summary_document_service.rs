@@ -4,7 +4,7 @@ use chrono::Utc;
 
 use crate::{
     errors::{AppError, AppResult},
-    models::domain::summary_document::SummaryDocument,
+    models::domain::{quiz::QuizSource, summary_document::SummaryDocument},
     repositories::SummaryDocumentRepository,
 };
 
@@ -25,6 +25,14 @@ impl SummaryDocumentService {
         Ok(document)
     }
 
+    /// Summary documents of every source of the quiz
+    pub async fn get_summary_documents_for_quiz(
+        &self,
+        quiz_id: &str,
+    ) -> AppResult<Vec<SummaryDocument>> {
+        self.repository.find_by_quiz_id(quiz_id).await
+    }
+
     pub async fn create_summary_document(
         &self,
         mut document: SummaryDocument,
@@ -46,6 +54,40 @@ impl SummaryDocumentService {
         let created_document = self.repository.create(document).await?;
         Ok(created_document)
     }
+
+    /// Store the text of an uploaded document for the quiz; the summary
+    /// itself is filled in by the generation pipeline
+    pub async fn create_source_document(
+        &self,
+        quiz_id: &str,
+        source: &QuizSource,
+        text: String,
+    ) -> AppResult<SummaryDocument> {
+        if text.trim().is_empty() {
+            return Err(AppError::ValidationError(
+                "Source document cannot be empty".to_string(),
+            ));
+        }
+
+        let document = SummaryDocument::new_source_document(quiz_id, source, text);
+        self.repository.create(document).await
+    }
+
+    pub async fn update_summary_content(
+        &self,
+        mut document: SummaryDocument,
+        content: String,
+    ) -> AppResult<SummaryDocument> {
+        if content.trim().is_empty() {
+            return Err(AppError::ValidationError(
+                "Summary document content cannot be empty".to_string(),
+            ));
+        }
+
+        document.content = content;
+        document.modified_at = Some(Utc::now());
+        self.repository.update(document).await
+    }
 }
 
 #[cfg(test)]
@@ -64,7 +106,9 @@ mod tests {
         #[async_trait]
         impl SummaryDocumentRepository for SummaryRepo {
             async fn find_by_id(&self, id: &str) -> AppResult<Option<SummaryDocument>>;
+            async fn find_by_quiz_id(&self, quiz_id: &str) -> AppResult<Vec<SummaryDocument>>;
             async fn create(&self, document: SummaryDocument) -> AppResult<SummaryDocument>;
+            async fn update(&self, document: SummaryDocument) -> AppResult<SummaryDocument>;
         }
     }
 
@@ -74,6 +118,8 @@ mod tests {
             quiz_id: "quiz-1".to_string(),
             url: "https://example.com".to_string(),
             content: content.to_string(),
+            source_id: None,
+            source: None,
             created_at: None,
             modified_at: None,
         }
@@ -158,4 +204,27 @@ mod tests {
         assert_eq!(result.created_at, Some(created));
         assert_eq!(result.modified_at, Some(modified));
     }
+
+    #[tokio::test]
+    async fn update_summary_content_keeps_source_and_sets_modified_at() {
+        let mut mock_repo = MockSummaryRepo::new();
+        mock_repo.expect_update().returning(Ok);
+
+        let service = SummaryDocumentService::new(Arc::new(mock_repo));
+        let source = QuizSource {
+            id: "source-1".to_string(),
+            url: "upload:guide.pdf".to_string(),
+        };
+        let mut document = SummaryDocument::new_source_document("quiz-1", &source, "Text".into());
+        document.modified_at = None;
+
+        let result = service
+            .update_summary_content(document, "Summary".to_string())
+            .await
+            .expect("expected update success");
+
+        assert_eq!(result.content, "Summary");
+        assert_eq!(result.source.as_deref(), Some("Text"));
+        assert!(result.modified_at.is_some());
+    }
 }
@@ -0,0 +1,208 @@
+use crate::services::content_extractor::truncate_at_char_boundary;
+
+/// Rough characters per token for English prose; close enough to budget
+/// prompts without a model-specific tokenizer
+const CHARS_PER_TOKEN: usize = 4;
+
+/// A slice of a markdown document small enough for one LLM call
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TextChunk {
+    /// Headings of the sections this chunk holds all or part of, in order
+    pub sections: Vec<String>,
+    pub text: String,
+}
+
+pub fn estimate_tokens(text: &str) -> usize {
+    text.chars().count().div_ceil(CHARS_PER_TOKEN)
+}
+
+/// Split markdown into chunks of at most `max_tokens`, keeping sections
+/// together where they fit and otherwise breaking on paragraphs.
+///
+/// Text before the first heading is attributed to `untitled_section`.
+pub fn chunk_markdown(markdown: &str, max_tokens: usize, untitled_section: &str) -> Vec<TextChunk> {
+    let max_tokens = max_tokens.max(1);
+    let mut chunks: Vec<TextChunk> = Vec::new();
+    let mut current = TextChunk {
+        sections: Vec::new(),
+        text: String::new(),
+    };
+
+    for (title, piece) in split_sections(markdown, untitled_section)
+        .into_iter()
+        .flat_map(|(title, text)| {
+            split_to_budget(&text, max_tokens)
+                .into_iter()
+                .map(move |piece| (title.clone(), piece))
+        })
+    {
+        let separator = if current.text.is_empty() { "" } else { "\n\n" };
+        if !current.text.is_empty()
+            && estimate_tokens(&current.text) + estimate_tokens(separator) + estimate_tokens(&piece)
+                > max_tokens
+        {
+            chunks.push(std::mem::replace(
+                &mut current,
+                TextChunk {
+                    sections: Vec::new(),
+                    text: String::new(),
+                },
+            ));
+        }
+
+        if !current.text.is_empty() {
+            current.text.push_str("\n\n");
+        }
+        current.text.push_str(&piece);
+        if current.sections.last() != Some(&title) {
+            current.sections.push(title);
+        }
+    }
+
+    if !current.text.is_empty() {
+        chunks.push(current);
+    }
+    chunks
+}
+
+/// `(heading, text)` pairs, where each text starts with its heading line
+fn split_sections(markdown: &str, untitled_section: &str) -> Vec<(String, String)> {
+    let mut sections: Vec<(String, String)> = Vec::new();
+    let mut title = untitled_section.to_string();
+    let mut text = String::new();
+    let mut in_code_block = false;
+
+    for line in markdown.lines() {
+        if line.trim_start().starts_with("```") {
+            in_code_block = !in_code_block;
+        }
+
+        if let Some(heading) = heading_text(line).filter(|_| !in_code_block) {
+            if !text.trim().is_empty() {
+                sections.push((title, text.trim().to_string()));
+            }
+            title = heading;
+            text = String::new();
+        }
+
+        text.push_str(line);
+        text.push('\n');
+    }
+
+    if !text.trim().is_empty() {
+        sections.push((title, text.trim().to_string()));
+    }
+    sections
+}
+
+fn heading_text(line: &str) -> Option<String> {
+    let hashes = line.chars().take_while(|ch| *ch == '#').count();
+    if !(1..=6).contains(&hashes) {
+        return None;
+    }
+    let rest = &line[hashes..];
+    if !rest.starts_with(' ') {
+        return None;
+    }
+    Some(rest.trim().to_string()).filter(|heading| !heading.is_empty())
+}
+
+/// Break text that is over budget into paragraph-aligned pieces, cutting
+/// single paragraphs that are still too long
+fn split_to_budget(text: &str, max_tokens: usize) -> Vec<String> {
+    if estimate_tokens(text) <= max_tokens {
+        return vec![text.to_string()];
+    }
+
+    let mut pieces: Vec<String> = Vec::new();
+    let mut current = String::new();
+    for paragraph in text.split("\n\n").filter(|p| !p.trim().is_empty()) {
+        for part in split_paragraph(paragraph, max_tokens) {
+            if !current.is_empty()
+                && estimate_tokens(&current) + estimate_tokens(&part) + 1 > max_tokens
+            {
+                pieces.push(std::mem::take(&mut current));
+            }
+            if !current.is_empty() {
+                current.push_str("\n\n");
+            }
+            current.push_str(&part);
+        }
+    }
+    if !current.is_empty() {
+        pieces.push(current);
+    }
+    pieces
+}
+
+fn split_paragraph(paragraph: &str, max_tokens: usize) -> Vec<String> {
+    let max_chars = max_tokens * CHARS_PER_TOKEN;
+    let mut parts = Vec::new();
+    let mut rest = paragraph.trim();
+
+    while estimate_tokens(rest) > max_tokens {
+        let mut head = rest.chars().take(max_chars).collect::<String>();
+        // Prefer to break between words
+        if let Some(space) = head.rfind(char::is_whitespace).filter(|i| *i > 0) {
+            truncate_at_char_boundary(&mut head, space);
+        }
+        rest = rest[head.len()..].trim_start();
+        parts.push(head.trim_end().to_string());
+    }
+    if !rest.is_empty() {
+        parts.push(rest.to_string());
+    }
+    parts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_small_documents_in_one_chunk() {
+        let markdown = "Intro text.\n\n## Setup\n\nInstall it.\n\n## Usage\n\nRun it.";
+
+        let chunks = chunk_markdown(markdown, 1000, "Overview");
+
+        assert_eq!(chunks.len(), 1);
+        assert_eq!(chunks[0].sections, vec!["Overview", "Setup", "Usage"]);
+        assert_eq!(chunks[0].text, markdown);
+    }
+
+    #[test]
+    fn packs_sections_into_budgeted_chunks() {
+        let section = |title: &str| format!("## {}\n\n{}", title, "word ".repeat(30).trim());
+        let markdown = [section("One"), section("Two"), section("Three")].join("\n\n");
+
+        let chunks = chunk_markdown(&markdown, 90, "Overview");
+
+        assert_eq!(chunks.len(), 2);
+        assert_eq!(chunks[0].sections, vec!["One", "Two"]);
+        assert_eq!(chunks[1].sections, vec!["Three"]);
+        assert!(chunks
+            .iter()
+            .all(|chunk| estimate_tokens(&chunk.text) <= 90));
+    }
+
+    #[test]
+    fn splits_oversized_sections_on_paragraphs_and_words() {
+        let paragraph = "alpha beta gamma delta ".repeat(10);
+        let markdown = format!(
+            "## Long\n\n{}\n\n{}\n\n```\n# not a heading\n```",
+            paragraph.trim(),
+            paragraph.repeat(3).trim()
+        );
+
+        let chunks = chunk_markdown(&markdown, 60, "Overview");
+
+        assert!(chunks.len() > 2);
+        assert!(chunks.iter().all(|chunk| chunk.sections == vec!["Long"]));
+        assert!(chunks
+            .iter()
+            .all(|chunk| estimate_tokens(&chunk.text) <= 60));
+        assert!(chunks
+            .iter()
+            .all(|chunk| !chunk.text.starts_with(' ') && !chunk.text.contains("alph ")));
+    }
+}
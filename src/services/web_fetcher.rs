@@ -0,0 +1,446 @@
+use std::error::Error as StdError;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::sync::Arc;
+use std::time::Duration;
+
+use hyper::client::connect::dns::Name;
+use reqwest::{
+    dns::{Addrs, Resolve, Resolving},
+    header, redirect, StatusCode, Url,
+};
+use thiserror::Error;
+
+use crate::config::Config;
+
+const USER_AGENT: &str = "tento-server/1.0";
+
+#[derive(Debug, Error)]
+pub enum WebFetchError {
+    #[error("Invalid URL '{0}'")]
+    InvalidUrl(String),
+    #[error("Blocked URL: {0}")]
+    Blocked(String),
+    #[error("Stopped after {0} redirects")]
+    TooManyRedirects(u32),
+    #[error("Timed out reading {0}")]
+    Timeout(Url),
+    #[error("Request failed: {0}")]
+    Request(#[from] reqwest::Error),
+}
+
+/// Final response of a fetch, after following redirects
+#[derive(Debug, Clone)]
+pub struct FetchedPage {
+    pub url: Url,
+    pub status: StatusCode,
+    pub content_type: Option<String>,
+    pub body: String,
+    /// The body was cut off at the configured size limit
+    pub truncated: bool,
+}
+
+/// Fetches pages on behalf of users and the LLM without exposing internal
+/// services.
+///
+/// Only http and https are allowed, every host must pass the domain allow and
+/// deny lists, and every address a host resolves to must be public. Redirects
+/// are followed by hand so each hop is checked again, and DNS is resolved by
+/// [`PublicOnlyResolver`] at connect time so a re-resolved name cannot point
+/// somewhere else than what was checked.
+pub struct WebFetcher {
+    client: reqwest::Client,
+    read_timeout: Duration,
+    total_timeout: Duration,
+    max_body_bytes: usize,
+    max_redirects: u32,
+    allowed_domains: Vec<String>,
+    denied_domains: Vec<String>,
+}
+
+impl WebFetcher {
+    pub fn new(config: &Config) -> Self {
+        let client = reqwest::Client::builder()
+            .user_agent(USER_AGENT)
+            .redirect(redirect::Policy::none())
+            .no_proxy()
+            .connect_timeout(Duration::from_secs(
+                config.web_fetch_connect_timeout_seconds,
+            ))
+            .dns_resolver(Arc::new(PublicOnlyResolver))
+            .build()
+            .expect("Failed to build web fetch client");
+
+        Self {
+            client,
+            read_timeout: Duration::from_secs(config.web_fetch_read_timeout_seconds),
+            total_timeout: Duration::from_secs(config.web_fetch_total_timeout_seconds),
+            max_body_bytes: config.web_fetch_max_body_bytes,
+            max_redirects: config.web_fetch_max_redirects,
+            allowed_domains: normalise_domains(&config.web_fetch_allowed_domains),
+            denied_domains: normalise_domains(&config.web_fetch_denied_domains),
+        }
+    }
+
+    pub async fn fetch(&self, url: &str) -> Result<FetchedPage, WebFetchError> {
+        let url = Url::parse(url).map_err(|_| WebFetchError::InvalidUrl(url.to_string()))?;
+
+        // Reads are timed one at a time, so a server trickling bytes would
+        // otherwise hold the fetch open indefinitely
+        tokio::time::timeout(self.total_timeout, self.fetch_url(url.clone()))
+            .await
+            .map_err(|_| WebFetchError::Timeout(url))?
+    }
+
+    async fn fetch_url(&self, mut url: Url) -> Result<FetchedPage, WebFetchError> {
+        let mut redirects = 0;
+
+        loop {
+            self.check_url(&url)?;
+
+            let mut response = self
+                .with_read_timeout(&url, self.client.get(url.clone()).send())
+                .await?;
+
+            if response.status().is_redirection() {
+                let Some(location) = response
+                    .headers()
+                    .get(header::LOCATION)
+                    .and_then(|value| value.to_str().ok())
+                else {
+                    return Err(WebFetchError::InvalidUrl(format!(
+                        "redirect from {} without a Location header",
+                        url
+                    )));
+                };
+
+                redirects += 1;
+                if redirects > self.max_redirects {
+                    return Err(WebFetchError::TooManyRedirects(self.max_redirects));
+                }
+                url = url
+                    .join(location)
+                    .map_err(|_| WebFetchError::InvalidUrl(location.to_string()))?;
+                continue;
+            }
+
+            let status = response.status();
+            let content_type = response
+                .headers()
+                .get(header::CONTENT_TYPE)
+                .and_then(|value| value.to_str().ok())
+                .map(str::to_string);
+            let mut body = Vec::new();
+            let mut truncated = false;
+            while let Some(chunk) = self.with_read_timeout(&url, response.chunk()).await? {
+                let remaining = self.max_body_bytes - body.len();
+                if chunk.len() > remaining {
+                    body.extend_from_slice(&chunk[..remaining]);
+                    truncated = true;
+                    break;
+                }
+                body.extend_from_slice(&chunk);
+            }
+
+            return Ok(FetchedPage {
+                url,
+                status,
+                content_type,
+                body: String::from_utf8_lossy(&body).into_owned(),
+                truncated,
+            });
+        }
+    }
+
+    /// Scheme, literal address and domain list checks for one hop
+    fn check_url(&self, url: &Url) -> Result<(), WebFetchError> {
+        if !matches!(url.scheme(), "http" | "https") {
+            return Err(WebFetchError::Blocked(format!(
+                "scheme '{}' is not allowed",
+                url.scheme()
+            )));
+        }
+
+        let host = url
+            .host_str()
+            .ok_or_else(|| WebFetchError::InvalidUrl(url.to_string()))?;
+        if let Ok(ip) = parse_ip(host) {
+            check_ip(ip)?;
+            // An allow list of domains doesn't let any address through
+            let listed = self
+                .allowed_domains
+                .iter()
+                .any(|entry| parse_ip(entry) == Ok(ip));
+            if !self.allowed_domains.is_empty() && !listed {
+                return Err(WebFetchError::Blocked(format!(
+                    "address {} is not on the allow list",
+                    ip
+                )));
+            }
+            return Ok(());
+        }
+
+        let domain = host.trim_end_matches('.').to_ascii_lowercase();
+        if domain_matches(&domain, &self.denied_domains) {
+            return Err(WebFetchError::Blocked(format!(
+                "domain '{}' is denied",
+                domain
+            )));
+        }
+        if !self.allowed_domains.is_empty() && !domain_matches(&domain, &self.allowed_domains) {
+            return Err(WebFetchError::Blocked(format!(
+                "domain '{}' is not on the allow list",
+                domain
+            )));
+        }
+
+        Ok(())
+    }
+
+    async fn with_read_timeout<T>(
+        &self,
+        url: &Url,
+        future: impl std::future::Future<Output = reqwest::Result<T>>,
+    ) -> Result<T, WebFetchError> {
+        match tokio::time::timeout(self.read_timeout, future).await {
+            Ok(Ok(value)) => Ok(value),
+            Ok(Err(e)) => Err(match blocked_address(&e) {
+                Some(blocked) => WebFetchError::Blocked(blocked.to_string()),
+                None => WebFetchError::Request(e),
+            }),
+            Err(_) => Err(WebFetchError::Timeout(url.clone())),
+        }
+    }
+}
+
+/// Host name that resolved to an address we refuse to connect to
+#[derive(Debug, Error)]
+#[error("'{host}' resolves to non-public address {ip}")]
+struct BlockedAddress {
+    host: String,
+    ip: IpAddr,
+}
+
+/// Resolves with the system resolver and fails if any address is not public
+struct PublicOnlyResolver;
+
+impl Resolve for PublicOnlyResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        Box::pin(async move {
+            let host = name.as_str().to_string();
+            let addrs: Vec<SocketAddr> =
+                tokio::net::lookup_host((host.as_str(), 0)).await?.collect();
+
+            if let Some(addr) = addrs.iter().find(|addr| is_blocked_ip(addr.ip())) {
+                return Err(Box::new(BlockedAddress {
+                    host,
+                    ip: addr.ip(),
+                }) as Box<dyn StdError + Send + Sync>);
+            }
+
+            Ok(Box::new(addrs.into_iter()) as Addrs)
+        })
+    }
+}
+
+fn blocked_address(error: &reqwest::Error) -> Option<&BlockedAddress> {
+    let mut source = error.source();
+    while let Some(error) = source {
+        if let Some(blocked) = error.downcast_ref::<BlockedAddress>() {
+            return Some(blocked);
+        }
+        source = error.source();
+    }
+    None
+}
+
+/// IP address of a URL host, which is bracketed for IPv6
+fn parse_ip(host: &str) -> Result<IpAddr, std::net::AddrParseError> {
+    host.trim_start_matches('[').trim_end_matches(']').parse()
+}
+
+fn check_ip(ip: IpAddr) -> Result<(), WebFetchError> {
+    if is_blocked_ip(ip) {
+        return Err(WebFetchError::Blocked(format!(
+            "address {} is not public",
+            ip
+        )));
+    }
+    Ok(())
+}
+
+/// Loopback, private, link-local and other addresses that are not reachable
+/// on the public internet
+pub fn is_blocked_ip(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => is_blocked_ipv4(ip),
+        IpAddr::V6(ip) => is_blocked_ipv6(ip),
+    }
+}
+
+fn is_blocked_ipv4(ip: Ipv4Addr) -> bool {
+    let [a, b, c, _] = ip.octets();
+    ip.is_unspecified()
+        || ip.is_loopback()
+        || ip.is_private()
+        || ip.is_link_local()
+        || ip.is_broadcast()
+        || ip.is_documentation()
+        || ip.is_multicast()
+        // "this network", 0.0.0.0/8
+        || a == 0
+        // Carrier-grade NAT, 100.64.0.0/10
+        || (a == 100 && (64..128).contains(&b))
+        // IETF protocol assignments, 192.0.0.0/24
+        || (a == 192 && b == 0 && c == 0)
+        // Benchmarking, 198.18.0.0/15
+        || (a == 198 && (b == 18 || b == 19))
+        // Reserved, 240.0.0.0/4
+        || a >= 240
+}
+
+fn is_blocked_ipv6(ip: Ipv6Addr) -> bool {
+    if let Some(v4) = ip.to_ipv4_mapped() {
+        return is_blocked_ipv4(v4);
+    }
+
+    let segments = ip.segments();
+    ip.is_unspecified()
+        || ip.is_loopback()
+        || ip.is_multicast()
+        // IPv4-compatible, ::/96
+        || segments[..6].iter().all(|segment| *segment == 0)
+        // NAT64, 64:ff9b::/96, can reach any IPv4 address
+        || (segments[0] == 0x64 && segments[1] == 0xff9b)
+        // Unique local, fc00::/7
+        || (segments[0] & 0xfe00) == 0xfc00
+        // Link-local, fe80::/10
+        || (segments[0] & 0xffc0) == 0xfe80
+        // Documentation, 2001:db8::/32
+        || (segments[0] == 0x2001 && segments[1] == 0x0db8)
+}
+
+fn normalise_domains(domains: &[String]) -> Vec<String> {
+    domains
+        .iter()
+        .map(|domain| {
+            domain
+                .trim()
+                .trim_start_matches("*.")
+                .trim_end_matches('.')
+                .to_ascii_lowercase()
+        })
+        .filter(|domain| !domain.is_empty())
+        .collect()
+}
+
+/// `example.com` in the list matches `example.com` and any of its subdomains
+fn domain_matches(domain: &str, list: &[String]) -> bool {
+    list.iter().any(|entry| {
+        domain == entry
+            || domain
+                .strip_suffix(entry.as_str())
+                .is_some_and(|prefix| prefix.ends_with('.'))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fetcher(allowed: &[&str], denied: &[&str]) -> WebFetcher {
+        let mut config = Config::test_config();
+        config.web_fetch_allowed_domains = allowed.iter().map(|d| d.to_string()).collect();
+        config.web_fetch_denied_domains = denied.iter().map(|d| d.to_string()).collect();
+        WebFetcher::new(&config)
+    }
+
+    #[test]
+    fn blocks_internal_address_ranges() {
+        for ip in [
+            "127.0.0.1",
+            "10.1.2.3",
+            "172.16.0.1",
+            "192.168.1.1",
+            "169.254.169.254",
+            "100.64.0.1",
+            "0.0.0.0",
+            "::1",
+            "fd00::1",
+            "fe80::1",
+            "::ffff:127.0.0.1",
+            "64:ff9b::a9fe:a9fe",
+        ] {
+            assert!(
+                is_blocked_ip(ip.parse().unwrap()),
+                "{} should be blocked",
+                ip
+            );
+        }
+
+        for ip in ["93.184.215.14", "1.1.1.1", "2606:4700:4700::1111"] {
+            assert!(
+                !is_blocked_ip(ip.parse().unwrap()),
+                "{} should be allowed",
+                ip
+            );
+        }
+    }
+
+    #[test]
+    fn checks_scheme_and_domain_lists() {
+        let fetcher = fetcher(&["example.com"], &["internal.example.com"]);
+        let check = |url: &str| fetcher.check_url(&Url::parse(url).unwrap());
+
+        assert!(check("https://example.com/page").is_ok());
+        assert!(check("http://docs.example.com/page").is_ok());
+        assert!(matches!(
+            check("ftp://example.com/file"),
+            Err(WebFetchError::Blocked(_))
+        ));
+        assert!(matches!(
+            check("https://notexample.com"),
+            Err(WebFetchError::Blocked(_))
+        ));
+        assert!(matches!(
+            check("https://api.internal.example.com"),
+            Err(WebFetchError::Blocked(_))
+        ));
+        assert!(matches!(
+            check("http://169.254.169.254/latest"),
+            Err(WebFetchError::Blocked(_))
+        ));
+        assert!(matches!(
+            check("http://[::1]:27017"),
+            Err(WebFetchError::Blocked(_))
+        ));
+    }
+
+    #[test]
+    fn allow_list_only_lets_listed_addresses_through() {
+        let unrestricted = fetcher(&[], &[]);
+        let restricted = fetcher(&["example.com", "1.1.1.1", "2606:4700:4700::1111"], &[]);
+        let check = |url: &str| restricted.check_url(&Url::parse(url).unwrap());
+
+        assert!(check("http://1.1.1.1/page").is_ok());
+        assert!(check("http://[2606:4700:4700::1111]/page").is_ok());
+        assert!(matches!(
+            check("http://93.184.215.14/page"),
+            Err(WebFetchError::Blocked(_))
+        ));
+        assert!(unrestricted
+            .check_url(&Url::parse("http://93.184.215.14/page").unwrap())
+            .is_ok());
+    }
+
+    #[tokio::test]
+    async fn refuses_hosts_that_resolve_to_loopback() {
+        let fetcher = fetcher(&[], &[]);
+
+        let result = fetcher.fetch("http://localhost:27017/").await;
+
+        match result {
+            Err(WebFetchError::Blocked(reason)) => assert!(reason.contains("localhost")),
+            other => panic!("expected Blocked, got {:?}", other),
+        }
+    }
+}
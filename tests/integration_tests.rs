@@ -37,6 +37,7 @@ fn test_quiz_draft_dto() {
         required_score: 70,
         attempt_limit: 3,
         url: "https://example.com/article".to_string(),
+        sources: vec!["https://example.com/follow-up".to_string()],
     };
 
     let json = serde_json::to_string(&draft).unwrap();
@@ -44,6 +45,7 @@ fn test_quiz_draft_dto() {
 
     assert_eq!(draft.name, deserialized.name);
     assert_eq!(draft.question_count, deserialized.question_count);
+    assert_eq!(draft.sources, deserialized.sources);
 }
 
 #[test]
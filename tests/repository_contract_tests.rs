@@ -1,7 +1,7 @@
 use std::{collections::HashMap, sync::Arc};
 
 use async_trait::async_trait;
-use chrono::Utc;
+use chrono::{DateTime, Duration, Utc};
 use mongodb::bson::{doc, oid::ObjectId, Bson, Document};
 use tokio::sync::RwLock;
 
@@ -98,6 +98,8 @@ impl QuizRepository for InMemoryQuizRepository {
             QuizStatus::Pending => "pending",
             QuizStatus::Ready => "ready",
             QuizStatus::Complete => "complete",
+            QuizStatus::Failed => "failed",
+            QuizStatus::NeedsReview => "needs_review",
         };
 
         if quiz_status == status {
@@ -138,6 +140,31 @@ impl QuizRepository for InMemoryQuizRepository {
         quizzes.insert(quiz.id.clone(), quiz.clone());
         Ok(quiz)
     }
+
+    async fn count_created_by_user_since(
+        &self,
+        user_id: &str,
+        since: DateTime<Utc>,
+    ) -> AppResult<i64> {
+        let quizzes = self.quizzes.read().await;
+        Ok(quizzes
+            .values()
+            .filter(|q| q.created_by_user_id == user_id)
+            .filter(|q| q.created_at.is_some_and(|created_at| created_at >= since))
+            .count() as i64)
+    }
+
+    async fn count_by_user_with_status(
+        &self,
+        user_id: &str,
+        statuses: &[QuizStatus],
+    ) -> AppResult<i64> {
+        let quizzes = self.quizzes.read().await;
+        Ok(quizzes
+            .values()
+            .filter(|q| q.created_by_user_id == user_id && statuses.contains(&q.status))
+            .count() as i64)
+    }
 }
 
 struct InMemoryQuizAttemptRepository {
@@ -178,7 +205,7 @@ impl QuizAttemptRepository for InMemoryQuizAttemptRepository {
             .filter(|a| a.user_id == user_id && a.quiz_id == quiz_id)
             .cloned()
             .collect();
-        items.sort_by(|a, b| b.submitted_at.cmp(&a.submitted_at));
+        items.sort_by_key(|a| std::cmp::Reverse(a.submitted_at));
         Ok(items)
     }
 
@@ -211,7 +238,7 @@ impl QuizAttemptRepository for InMemoryQuizAttemptRepository {
             .cloned()
             .collect();
 
-        items.sort_by(|a, b| b.submitted_at.cmp(&a.submitted_at));
+        items.sort_by_key(|a| std::cmp::Reverse(a.submitted_at));
 
         let total = items.len() as i64;
         let start = offset.max(0) as usize;
@@ -383,6 +410,7 @@ fn make_attempt(id: &str, user_id: &str, quiz_id: &str, attempt_number: i16) ->
         id: id.to_string(),
         user_id: user_id.to_string(),
         quiz_id: quiz_id.to_string(),
+        quiz_version: None,
         points_earned: 1,
         required_score: 1,
         total_possible: 1,
@@ -462,6 +490,24 @@ async fn quiz_repository_crud_and_error_paths() {
 
     let missing_update = repo.update(make_quiz("quiz-missing", "Missing", "user-z")).await;
     assert!(matches!(missing_update, Err(AppError::NotFound(_))));
+
+    let created_today = repo
+        .count_created_by_user_since("user-a", Utc::now() - Duration::hours(1))
+        .await
+        .expect("count should work");
+    assert_eq!(created_today, 2);
+
+    let created_later = repo
+        .count_created_by_user_since("user-a", Utc::now() + Duration::hours(1))
+        .await
+        .expect("count should work");
+    assert_eq!(created_later, 0);
+
+    let generating = repo
+        .count_by_user_with_status("user-a", &[QuizStatus::Draft, QuizStatus::Pending])
+        .await
+        .expect("count should work");
+    assert_eq!(generating, 2);
 }
 
 #[tokio::test]